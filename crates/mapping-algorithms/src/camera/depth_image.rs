@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point2, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{camera::CameraIntrinsics, point_clouds::PointCloud, Vec};
+
+/// A row-major depth image buffer, as produced by RGB-D cameras.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug)]
+pub enum DepthBuffer<'a, T> {
+    /// Integer depths, where zero marks a missing measurement.
+    U16 {
+        /// The raw depth of each pixel.
+        depths: &'a [u16],
+        /// The amount of raw units per metre, usually 1000 for millimetres, or 5000 for the TUM RGB-D datasets.
+        depth_scale: T,
+    },
+    /// Depths in metres, where non-finite and non-positive values mark a missing measurement.
+    Float(&'a [T]),
+}
+
+impl<T> DepthBuffer<'_, T> {
+    fn len(&self) -> usize {
+        match self {
+            Self::U16 { depths, .. } => depths.len(),
+            Self::Float(depths) => depths.len(),
+        }
+    }
+}
+
+/// An error type containing the errors that might arise when converting a depth image, when compiling with the `std` feature, it will also derive [`thiserror::Error`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum DepthImageError {
+    /// The amount of values in the depth or colour buffer differs from the amount of pixels in the image.
+    #[cfg_attr(
+        feature = "std",
+        error("The buffer has {actual} values, but the image has {expected} pixels")
+    )]
+    BufferSizeMismatch {
+        /// The amount of pixels, according to the camera's intrinsics.
+        expected: usize,
+        /// The amount of values in the buffer.
+        actual: usize,
+    },
+}
+
+/// Converts a depth image, with an optional registered colour image, into a point cloud in the camera's optical frame.
+///
+/// Each pixel with a valid depth is unprojected at its integer column and row, using the camera's distortion model,
+/// so that its point lies at the measured depth along the camera's Z axis.
+/// This follows the OpenCV convention of pixel centres at integer coordinates, where the principal point of
+/// a 640 pixel wide image is at 319.5.
+///
+/// # Arguments
+/// * `depth`: a [`DepthBuffer`], the depth of each pixel in row-major order.
+/// * `colours`: an optional slice of RGB colours, registered to the depth image, in row-major order.
+/// * `intrinsics`: the [`CameraIntrinsics`] of the depth camera, whose width and height determine the buffers' expected size.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`PointCloud`] containing a point for each valid pixel in row-major order, along with its colour if given,
+/// or a [`DepthImageError`] if a buffer's size does not match the image.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Depth Image To Point Cloud", skip_all, level = "info")
+)]
+pub fn depth_image_to_point_cloud<T>(
+    depth: DepthBuffer<T>,
+    colours: Option<&[[u8; 3]]>,
+    intrinsics: &CameraIntrinsics<T>,
+) -> Result<PointCloud<T, 3>, DepthImageError>
+where
+    T: Copy + RealField,
+    u16: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    let num_pixels = intrinsics.width * intrinsics.height;
+    for buffer_len in [Some(depth.len()), colours.map(<[[u8; 3]]>::len)]
+        .into_iter()
+        .flatten()
+    {
+        if buffer_len != num_pixels {
+            return Err(DepthImageError::BufferSizeMismatch {
+                expected: num_pixels,
+                actual: buffer_len,
+            });
+        }
+    }
+
+    let mut points = Vec::with_capacity(num_pixels);
+    let mut point_colours = Vec::new();
+    for pixel_idx in 0..num_pixels {
+        let depth = match depth {
+            DepthBuffer::U16 {
+                depths,
+                depth_scale,
+            } => (depths[pixel_idx] > 0).then(|| depths[pixel_idx].as_() / depth_scale),
+            DepthBuffer::Float(depths) => Some(depths[pixel_idx]),
+        };
+        let Some(depth) = depth.filter(|depth| depth.is_finite() && *depth > T::zero()) else {
+            continue;
+        };
+
+        let pixel = Point2::new(
+            (pixel_idx % intrinsics.width).as_(),
+            (pixel_idx / intrinsics.width).as_(),
+        );
+        let Some(point) = intrinsics.unproject_with_depth(&pixel, depth) else {
+            continue;
+        };
+        points.push(point);
+        if let Some(colours) = colours {
+            point_colours.push(colours[pixel_idx]);
+        }
+    }
+
+    let cloud = PointCloud::new(points);
+    Ok(match colours {
+        Some(_) => cloud
+            .with_colours(point_colours)
+            .expect("Channel length matches"),
+        None => cloud,
+    })
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_depth_image {
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use crate::{camera::CameraIntrinsics, point_clouds::PointCloud};
+                use super::{DepthBuffer, DepthImageError};
+
+                #[doc = "A premade variant of the depth image conversion function, in " $doc "-precision floats."]
+                pub fn depth_image_to_point_cloud(depth: DepthBuffer<$precision>, colours: Option<&[[u8; 3]]>, intrinsics: &CameraIntrinsics<$precision>) -> Result<PointCloud<$precision, 3>, DepthImageError> {
+                    super::depth_image_to_point_cloud(depth, colours, intrinsics)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_depth_image!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_depth_image!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Isometry3, Point3, Vector3};
+
+    use crate::{
+        camera::DistortionModel,
+        point_clouds::{downsample_point_cloud_voxel, icp, ICPConfiguration},
+    };
+
+    use super::*;
+
+    // A camera looking at a wall 2 metres away, with a box protruding from its centre
+    fn generate_depths(intrinsics: &CameraIntrinsics<f64>) -> Vec<u16> {
+        (0..intrinsics.width * intrinsics.height)
+            .map(|pixel_idx| {
+                let (column, row) = (pixel_idx % intrinsics.width, pixel_idx / intrinsics.width);
+                match (column, row) {
+                    (0, _) => 0,
+                    (24..=40, 16..=32) => 1500,
+                    _ => 2000,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_depth_image_to_point_cloud() {
+        let intrinsics = CameraIntrinsics::pinhole(60.0, 60.0, 31.5, 23.5, 64, 48);
+        let depths = generate_depths(&intrinsics);
+        let colours = (0..depths.len())
+            .map(|pixel_idx| [(pixel_idx % 256) as u8, 0, 255])
+            .collect::<Vec<_>>();
+
+        let cloud = depth_image_to_point_cloud(
+            DepthBuffer::U16 {
+                depths: &depths,
+                depth_scale: 1000.0,
+            },
+            Some(&colours),
+            &intrinsics,
+        )
+        .unwrap();
+        // The first column has no measurements
+        assert_eq!(cloud.len(), 63 * 48);
+        assert_eq!(cloud.colours().unwrap()[0], colours[1]);
+        assert!(
+            (cloud[0] - Point3::new(-30.5 / 60.0 * 2.0, -23.5 / 60.0 * 2.0, 2.0)).norm() < 1e-12
+        );
+        assert!(cloud.iter().all(|point| point.z == 1.5 || point.z == 2.0));
+
+        // The same depths as floats, in metres, give the same points
+        let float_depths = depths
+            .iter()
+            .map(|&depth| {
+                if depth == 0 {
+                    f64::NAN
+                } else {
+                    depth as f64 / 1000.0
+                }
+            })
+            .collect::<Vec<_>>();
+        let float_cloud =
+            depth_image_to_point_cloud(DepthBuffer::Float(&float_depths), None, &intrinsics)
+                .unwrap();
+        assert_eq!(float_cloud.points(), cloud.points());
+        assert_eq!(float_cloud.colours(), None);
+
+        assert_eq!(
+            depth_image_to_point_cloud(DepthBuffer::Float(&float_depths[1..]), None, &intrinsics)
+                .err(),
+            Some(DepthImageError::BufferSizeMismatch {
+                expected: 64 * 48,
+                actual: 64 * 48 - 1
+            })
+        );
+        assert!(depth_image_to_point_cloud(
+            DepthBuffer::Float(&float_depths),
+            Some(&colours[1..]),
+            &intrinsics
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_distorted_depth_image_feeds_icp() {
+        let intrinsics = CameraIntrinsics::pinhole(60.0, 60.0, 31.5, 23.5, 64, 48).with_distortion(
+            DistortionModel::BrownConrady {
+                k1: -0.1,
+                k2: 0.01,
+                k3: 0.0,
+                p1: 0.0,
+                p2: 0.0,
+            },
+        );
+        let depths = generate_depths(&intrinsics);
+        let cloud = depth_image_to_point_cloud(
+            DepthBuffer::U16 {
+                depths: &depths,
+                depth_scale: 1000.0,
+            },
+            None,
+            &intrinsics,
+        )
+        .unwrap();
+        assert!(cloud.iter().all(|point| point.z == 1.5 || point.z == 2.0));
+
+        // Reprojecting each point lands it back at its pixel's column and row
+        let first = intrinsics.project(&cloud[0]).unwrap();
+        assert!((first - Point2::new(1.0, 0.0)).norm() < 1e-9);
+
+        let downsampled = downsample_point_cloud_voxel(&cloud, 0.05);
+        // The wall only constrains motion along the camera's axis
+        let transform = Isometry3::translation(0.0, 0.0, 0.03);
+        let moved = downsampled
+            .iter()
+            .map(|point| transform * point)
+            .collect::<Vec<_>>();
+        let result = icp(
+            &downsampled,
+            &moved,
+            ICPConfiguration::builder()
+                .with_kd_tree(true)
+                .with_max_iterations(50)
+                .with_mse_interval_threshold(1e-9)
+                .build(),
+        )
+        .unwrap();
+        assert!((result.transform.translation.vector - Vector3::new(0.0, 0.0, 0.03)).norm() < 1e-3);
+    }
+}
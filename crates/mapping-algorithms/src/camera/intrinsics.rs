@@ -0,0 +1,439 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Matrix2, Point2, Point3, RealField, Vector2, Vector3};
+
+// Undistortion is solved iteratively, converging within a few iterations for realistic lenses
+const MAX_UNDISTORTION_ITERATIONS: usize = 20;
+
+// Newton's method converges quadratically, so a step below the square root of the epsilon leaves an error near the epsilon,
+// while a tolerance of the epsilon itself is never reached in single precision due to rounding
+fn convergence_tolerance<T: RealField + Copy>(magnitude: T) -> T {
+    T::default_epsilon().sqrt() * (T::one() + magnitude)
+}
+
+/// The lens distortion of a camera, applied to normalised image coordinates.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistortionModel<T> {
+    /// An ideal pinhole camera, without any distortion.
+    None,
+    /// The Brown-Conrady model, with three radial and two tangential coefficients, as used by OpenCV's `k1, k2, p1, p2, k3`.
+    BrownConrady {
+        /// The first radial coefficient.
+        k1: T,
+        /// The second radial coefficient.
+        k2: T,
+        /// The third radial coefficient.
+        k3: T,
+        /// The first tangential coefficient.
+        p1: T,
+        /// The second tangential coefficient.
+        p2: T,
+    },
+    /// The equidistant fisheye model, as used by OpenCV's fisheye module, which also covers fields of view beyond 180 degrees.
+    EquidistantFisheye {
+        /// The first coefficient of the angle polynomial.
+        k1: T,
+        /// The second coefficient of the angle polynomial.
+        k2: T,
+        /// The third coefficient of the angle polynomial.
+        k3: T,
+        /// The fourth coefficient of the angle polynomial.
+        k4: T,
+    },
+}
+
+/// The intrinsic parameters of a camera, in the optical frame, where Z points forward, X right and Y down.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraIntrinsics<T> {
+    /// The focal length along the X axis, in pixels.
+    pub fx: T,
+    /// The focal length along the Y axis, in pixels.
+    pub fy: T,
+    /// The X coordinate of the principal point, in pixels.
+    pub cx: T,
+    /// The Y coordinate of the principal point, in pixels.
+    pub cy: T,
+    /// The width of the image, in pixels.
+    pub width: usize,
+    /// The height of the image, in pixels.
+    pub height: usize,
+    /// The lens distortion.
+    pub distortion: DistortionModel<T>,
+}
+
+impl<T: Copy + RealField> CameraIntrinsics<T> {
+    /// Constructs the intrinsics of an ideal pinhole camera.
+    ///
+    /// # Arguments
+    /// * `fx`: the focal length along the X axis, in pixels.
+    /// * `fy`: the focal length along the Y axis, in pixels.
+    /// * `cx`: the X coordinate of the principal point, in pixels.
+    /// * `cy`: the Y coordinate of the principal point, in pixels.
+    /// * `width`: the width of the image, in pixels.
+    /// * `height`: the height of the image, in pixels.
+    ///
+    /// # Returns
+    /// A [`CameraIntrinsics`] without distortion.
+    pub fn pinhole(fx: T, fy: T, cx: T, cy: T, width: usize, height: usize) -> Self {
+        Self {
+            fx,
+            fy,
+            cx,
+            cy,
+            width,
+            height,
+            distortion: DistortionModel::None,
+        }
+    }
+
+    /// Returns the same intrinsics with a different lens distortion.
+    pub fn with_distortion(self, distortion: DistortionModel<T>) -> Self {
+        Self { distortion, ..self }
+    }
+
+    /// Returns whether a pixel lies within the image's bounds.
+    pub fn contains(&self, pixel: &Point2<T>) -> bool {
+        pixel.x >= T::zero()
+            && pixel.y >= T::zero()
+            && pixel.x < nalgebra::convert(self.width as f64)
+            && pixel.y < nalgebra::convert(self.height as f64)
+    }
+
+    /// Projects a point in the camera's frame onto the image plane.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point3`], the point in the camera's optical frame.
+    ///
+    /// # Returns
+    /// [`Some`] containing the pixel coordinates, which may lie outside the image's bounds,
+    /// or [`None`] if the point is behind the camera, or for fisheye lenses, at the camera's centre.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Project Point To Image", skip_all, level = "trace")
+    )]
+    pub fn project(&self, point: &Point3<T>) -> Option<Point2<T>> {
+        let normalised = match self.distortion {
+            DistortionModel::None => (point.z > T::zero()).then(|| point.xy().coords / point.z)?,
+            DistortionModel::BrownConrady { .. } => {
+                if point.z <= T::zero() {
+                    return None;
+                }
+                self.distort(&(point.xy().coords / point.z))
+            }
+            DistortionModel::EquidistantFisheye { k1, k2, k3, k4 } => {
+                let radius = point.xy().coords.norm();
+                if radius <= T::zero() {
+                    return (point.z > T::zero()).then_some(Point2::new(self.cx, self.cy));
+                }
+                let theta = radius.atan2(point.z);
+                let theta_squared = theta * theta;
+                let distorted_theta = theta
+                    * (T::one()
+                        + theta_squared
+                            * (k1
+                                + theta_squared
+                                    * (k2 + theta_squared * (k3 + theta_squared * k4))));
+                point.xy().coords * (distorted_theta / radius)
+            }
+        };
+
+        Some(Point2::new(
+            self.fx * normalised.x + self.cx,
+            self.fy * normalised.y + self.cy,
+        ))
+    }
+
+    /// Unprojects a pixel into the direction of its ray, in the camera's frame.
+    ///
+    /// # Arguments
+    /// * `pixel`: a [`Point2`], the pixel coordinates.
+    ///
+    /// # Returns
+    /// [`Some`] containing the ray's unit direction, or [`None`] if undistortion did not converge.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Unproject Pixel", skip_all, level = "trace")
+    )]
+    pub fn unproject(&self, pixel: &Point2<T>) -> Option<Vector3<T>> {
+        let distorted = Vector2::new((pixel.x - self.cx) / self.fx, (pixel.y - self.cy) / self.fy);
+        match self.distortion {
+            DistortionModel::None => Some(distorted.push(T::one()).normalize()),
+            DistortionModel::BrownConrady { .. } => {
+                Some(self.undistort(&distorted)?.push(T::one()).normalize())
+            }
+            DistortionModel::EquidistantFisheye { k1, k2, k3, k4 } => {
+                let distorted_theta = distorted.norm();
+                if distorted_theta <= T::zero() {
+                    return Some(Vector3::z());
+                }
+
+                // Newton's method on the angle polynomial, starting from the undistorted angle
+                let mut theta = distorted_theta;
+                let mut converged = false;
+                for _ in 0..MAX_UNDISTORTION_ITERATIONS {
+                    let theta_squared = theta * theta;
+                    let value = theta
+                        * (T::one()
+                            + theta_squared
+                                * (k1
+                                    + theta_squared
+                                        * (k2 + theta_squared * (k3 + theta_squared * k4))))
+                        - distorted_theta;
+                    let derivative = T::one()
+                        + theta_squared
+                            * (nalgebra::convert::<f64, T>(3.0) * k1
+                                + theta_squared
+                                    * (nalgebra::convert::<f64, T>(5.0) * k2
+                                        + theta_squared
+                                            * (nalgebra::convert::<f64, T>(7.0) * k3
+                                                + theta_squared
+                                                    * nalgebra::convert::<f64, T>(9.0)
+                                                    * k4)));
+                    let step = value / derivative;
+                    theta -= step;
+                    if step.abs() <= convergence_tolerance(theta.abs()) {
+                        converged = true;
+                        break;
+                    }
+                }
+                if !converged || !theta.is_finite() {
+                    return None;
+                }
+
+                let planar_direction = distorted / distorted_theta * theta.sin();
+                Some(planar_direction.push(theta.cos()))
+            }
+        }
+    }
+
+    /// Unprojects a pixel with a known depth into a point, as measured by depth cameras.
+    ///
+    /// # Arguments
+    /// * `pixel`: a [`Point2`], the pixel coordinates.
+    /// * `depth`: the depth of the point, its distance along the camera's Z axis.
+    ///
+    /// # Returns
+    /// [`Some`] containing the [`Point3`] in the camera's frame, or [`None`] if the pixel's ray does not point forward, or undistortion did not converge.
+    pub fn unproject_with_depth(&self, pixel: &Point2<T>, depth: T) -> Option<Point3<T>> {
+        let distorted = Vector2::new((pixel.x - self.cx) / self.fx, (pixel.y - self.cy) / self.fy);
+        let normalised = match self.distortion {
+            DistortionModel::None => distorted,
+            DistortionModel::BrownConrady { .. } => self.undistort(&distorted)?,
+            DistortionModel::EquidistantFisheye { .. } => {
+                let direction = self.unproject(pixel)?;
+                (direction.z > T::zero()).then(|| direction.xy() / direction.z)?
+            }
+        };
+
+        Some(Point3::new(
+            normalised.x * depth,
+            normalised.y * depth,
+            depth,
+        ))
+    }
+
+    // Applies the Brown-Conrady distortion to normalised image coordinates, along with the distortion's Jacobian
+    fn distort_with_jacobian(&self, undistorted: &Vector2<T>) -> (Vector2<T>, Matrix2<T>) {
+        let DistortionModel::BrownConrady { k1, k2, k3, p1, p2 } = self.distortion else {
+            return (*undistorted, Matrix2::identity());
+        };
+        let two: T = nalgebra::convert(2.0);
+        let (x, y) = (undistorted.x, undistorted.y);
+        let radius_squared = x * x + y * y;
+        let radial = T::one() + radius_squared * (k1 + radius_squared * (k2 + radius_squared * k3));
+        let radial_derivative = two
+            * (k1
+                + radius_squared
+                    * (two * k2 + nalgebra::convert::<f64, T>(3.0) * radius_squared * k3));
+
+        let distorted = Vector2::new(
+            x * radial + two * p1 * x * y + p2 * (radius_squared + two * x * x),
+            y * radial + p1 * (radius_squared + two * y * y) + two * p2 * x * y,
+        );
+        let six: T = nalgebra::convert(6.0);
+        let jacobian = Matrix2::new(
+            radial + x * x * radial_derivative + two * p1 * y + six * p2 * x,
+            x * y * radial_derivative + two * p1 * x + two * p2 * y,
+            x * y * radial_derivative + two * p1 * x + two * p2 * y,
+            radial + y * y * radial_derivative + six * p1 * y + two * p2 * x,
+        );
+        (distorted, jacobian)
+    }
+
+    fn distort(&self, undistorted: &Vector2<T>) -> Vector2<T> {
+        self.distort_with_jacobian(undistorted).0
+    }
+
+    // Inverts the Brown-Conrady distortion using Newton's method, starting from the distorted coordinates
+    fn undistort(&self, distorted: &Vector2<T>) -> Option<Vector2<T>> {
+        let mut undistorted = *distorted;
+        for _ in 0..MAX_UNDISTORTION_ITERATIONS {
+            let (estimate, jacobian) = self.distort_with_jacobian(&undistorted);
+            let step = jacobian.try_inverse()? * (estimate - distorted);
+            undistorted -= step;
+            if step.norm() <= convergence_tolerance(undistorted.norm()) {
+                return undistorted
+                    .iter()
+                    .all(|value| value.is_finite())
+                    .then_some(undistorted);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pinhole() -> CameraIntrinsics<f64> {
+        CameraIntrinsics::pinhole(525.0, 520.0, 319.5, 239.5, 640, 480)
+    }
+
+    fn assert_round_trip(intrinsics: &CameraIntrinsics<f64>, point: Point3<f64>) {
+        let pixel = intrinsics.project(&point).unwrap();
+        let direction = intrinsics.unproject(&pixel).unwrap();
+        assert!(
+            (direction - point.coords.normalize()).norm() < 1e-9,
+            "{direction} {point}"
+        );
+        if point.z > 0.0 {
+            let unprojected = intrinsics.unproject_with_depth(&pixel, point.z).unwrap();
+            assert!((unprojected - point).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_pinhole() {
+        let intrinsics = pinhole();
+        assert_eq!(
+            intrinsics.project(&Point3::new(0.0, 0.0, 2.0)),
+            Some(Point2::new(319.5, 239.5))
+        );
+        assert_eq!(
+            intrinsics.project(&Point3::new(1.0, -0.5, 2.0)),
+            Some(Point2::new(319.5 + 262.5, 239.5 - 130.0))
+        );
+        assert_eq!(intrinsics.project(&Point3::new(1.0, 0.0, -1.0)), None);
+        assert!(intrinsics.contains(&Point2::new(639.9, 0.0)));
+        assert!(!intrinsics.contains(&Point2::new(640.0, 0.0)));
+        assert_round_trip(&intrinsics, Point3::new(0.3, -0.2, 1.5));
+    }
+
+    #[test]
+    fn test_brown_conrady() {
+        let intrinsics = pinhole().with_distortion(DistortionModel::BrownConrady {
+            k1: -0.28,
+            k2: 0.07,
+            k3: -0.01,
+            p1: 0.001,
+            p2: -0.0005,
+        });
+        // Barrel distortion pulls points towards the principal point
+        let distorted = intrinsics.project(&Point3::new(0.5, 0.4, 1.0)).unwrap();
+        let undistorted = pinhole().project(&Point3::new(0.5, 0.4, 1.0)).unwrap();
+        assert!(
+            (distorted - Point2::new(319.5, 239.5)).norm()
+                < (undistorted - Point2::new(319.5, 239.5)).norm()
+        );
+
+        for point in [
+            Point3::new(0.0, 0.0, 1.0),
+            Point3::new(0.5, 0.4, 1.0),
+            Point3::new(-1.2, 0.8, 3.0),
+        ] {
+            assert_round_trip(&intrinsics, point);
+        }
+    }
+
+    #[test]
+    fn test_equidistant_fisheye() {
+        let intrinsics = CameraIntrinsics::pinhole(300.0, 300.0, 640.0, 640.0, 1280, 1280)
+            .with_distortion(DistortionModel::EquidistantFisheye {
+                k1: 0.02,
+                k2: -0.005,
+                k3: 0.001,
+                k4: 0.0,
+            });
+        assert_eq!(
+            intrinsics.project(&Point3::new(0.0, 0.0, 1.0)),
+            Some(Point2::new(640.0, 640.0))
+        );
+        assert_eq!(intrinsics.project(&Point3::new(0.0, 0.0, -1.0)), None);
+
+        // Points beside and slightly behind the camera are still visible
+        for point in [
+            Point3::new(0.2, -0.1, 1.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(-0.5, 1.0, -0.2),
+        ] {
+            assert_round_trip(&intrinsics, point);
+        }
+        let sideways = intrinsics.project(&Point3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!(sideways.y > 640.0 + 300.0 * core::f64::consts::FRAC_PI_2);
+        assert_eq!(intrinsics.unproject_with_depth(&sideways, 1.0), None);
+    }
+
+    #[test]
+    fn test_single_precision_round_trip() {
+        let brown_conrady = CameraIntrinsics::<f32>::pinhole(525.0, 520.0, 319.5, 239.5, 640, 480)
+            .with_distortion(DistortionModel::BrownConrady {
+                k1: -0.28,
+                k2: 0.07,
+                k3: -0.01,
+                p1: 0.001,
+                p2: -0.0005,
+            });
+        let fisheye = CameraIntrinsics::<f32>::pinhole(300.0, 300.0, 640.0, 640.0, 1280, 1280)
+            .with_distortion(DistortionModel::EquidistantFisheye {
+                k1: 0.02,
+                k2: -0.005,
+                k3: 0.001,
+                k4: 0.0,
+            });
+
+        for (intrinsics, point) in [
+            (brown_conrady, Point3::new(0.5f32, 0.4, 1.0)),
+            (brown_conrady, Point3::new(-1.2, 0.8, 3.0)),
+            (brown_conrady, Point3::new(-1.4, -0.7, 1.0)),
+            (fisheye, Point3::new(0.2, -0.1, 1.0)),
+            (fisheye, Point3::new(1.0, 0.0, 0.0)),
+            (fisheye, Point3::new(-0.5, 1.0, -0.2)),
+        ] {
+            let pixel = intrinsics.project(&point).unwrap();
+            let direction = intrinsics.unproject(&pixel).unwrap();
+            assert!(
+                (direction - point.coords.normalize()).norm() < 1e-4,
+                "{direction} {point}"
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+pub use depth_image::{depth_image_to_point_cloud, DepthBuffer, DepthImageError};
+pub use intrinsics::{CameraIntrinsics, DistortionModel};
+
+mod depth_image;
+mod intrinsics;
+
+#[cfg(feature = "pregenerated")]
+#[doc = "A module containing pregenerated functions for single-precision camera algorithms."]
+pub mod single_precision {
+    pub use super::depth_image::single_precision::*;
+}
+
+#[cfg(feature = "pregenerated")]
+#[doc = "A module containing pregenerated functions for double-precision camera algorithms."]
+pub mod double_precision {
+    pub use super::depth_image::double_precision::*;
+}
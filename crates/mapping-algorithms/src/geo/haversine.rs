@@ -35,7 +35,7 @@ fn half_angle_sine_squared<T>(input: T) -> T
 /// Calculates the Haversine distance between two points on a sphere using floating-point arithmetic.
 ///
 /// # Arguments
-/// * `point_a`: A [`Point2'] representing the first geographical point.
+/// * `point_a`: A [`Point2`] representing the first geographical point.
 /// * `point_b`: A [`Point2`] representing the second geographical point.
 /// * `sphere_radius`: A `T` representing the radius of the sphere, typically the Earth's radius in kilometers or miles.
 ///
@@ -0,0 +1,595 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use nalgebra::{Isometry3, Matrix3, Matrix3x4, Point3, Rotation3, Translation3, UnitQuaternion};
+
+use crate::point_clouds::PointCloud;
+
+// Each point is stored as 4 little endian floats, x, y, z and intensity
+const SCAN_POINT_SIZE: usize = 4 * core::mem::size_of::<f32>();
+const PROJECTION_NAMES: [&str; 4] = ["P0", "P1", "P2", "P3"];
+const VELODYNE_TO_CAMERA_NAMES: [&str; 2] = ["Tr", "Tr_velo_to_cam"];
+
+/// An error type containing the various errors that might arise when reading KITTI files.
+#[derive(Debug, thiserror::Error)]
+pub enum KittiError {
+    /// The underlying reader failed.
+    #[error("Failed reading KITTI data: {0}")]
+    Io(#[from] io::Error),
+    /// The length of a scan is not a multiple of the 16 bytes of each point.
+    #[error("A scan of {0} bytes does not consist of whole points")]
+    InvalidScanLength(usize),
+    /// A line of a text file could not be parsed.
+    #[error("Invalid value in line {line}: {message}")]
+    InvalidLine {
+        /// The line's number, starting with 1.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// The calibration file does not contain a required matrix.
+    #[error("The calibration does not contain the {0} matrix")]
+    MissingCalibration(&'static str),
+    /// The amount of poses or timestamps does not match the amount of scans.
+    #[error("The sequence contains {scans} scans but {entries} poses or timestamps")]
+    FrameCountMismatch {
+        /// The amount of scans in the sequence.
+        scans: usize,
+        /// The amount of poses or timestamps read.
+        entries: usize,
+    },
+}
+
+/// The calibration of a KITTI odometry sequence, as stored in its `calib.txt` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KittiCalibration {
+    /// The projection matrices of the four cameras, `P0` to `P3`, after rectification.
+    pub projections: [Matrix3x4<f64>; 4],
+    /// The transformation from the velodyne frame to the frame of the left grey camera, `Tr`.
+    pub velodyne_to_camera: Isometry3<f64>,
+}
+
+/// A single frame of a KITTI sequence.
+#[derive(Clone, Debug)]
+pub struct KittiFrame {
+    /// The index of the frame in its sequence.
+    pub index: usize,
+    /// The time of the frame in seconds since the start of the sequence, if the sequence contains a `times.txt` file.
+    pub timestamp: Option<f64>,
+    /// The scan's points, with the reflectance of each point as its intensity.
+    pub cloud: PointCloud<f32, 3>,
+    /// The ground truth pose of the velodyne relative to its pose in the first frame, if the sequence has poses.
+    pub ground_truth: Option<Isometry3<f64>>,
+}
+
+// Parses a row-major 3x4 matrix, as used by both pose and calibration files
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Parse KITTI Matrix", skip_all, level = "trace")
+)]
+fn parse_matrix(tokens: &[&str], line: usize) -> Result<Matrix3x4<f64>, KittiError> {
+    if tokens.len() != 12 {
+        return Err(KittiError::InvalidLine {
+            line,
+            message: format!("Expected 12 values, found {}", tokens.len()),
+        });
+    }
+    let values = tokens
+        .iter()
+        .map(|token| {
+            token.parse::<f64>().map_err(|_| KittiError::InvalidLine {
+                line,
+                message: format!("Could not parse {token}"),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Matrix3x4::from_row_slice(&values))
+}
+
+// The rotations in KITTI files are only orthonormal up to their printed precision, so they are projected onto the closest rotation
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("KITTI Matrix To Isometry", skip_all, level = "trace")
+)]
+fn matrix_to_isometry(matrix: &Matrix3x4<f64>) -> Isometry3<f64> {
+    let rotation = Rotation3::from_matrix_eps(
+        &Matrix3::from(matrix.fixed_view::<3, 3>(0, 0)),
+        f64::EPSILON,
+        100,
+        Rotation3::identity(),
+    );
+    Isometry3::from_parts(
+        Translation3::from(matrix.column(3).into_owned()),
+        UnitQuaternion::from_rotation_matrix(&rotation),
+    )
+}
+
+/// Reads a KITTI velodyne scan, consisting of packed little endian `f32` x, y, z and reflectance values.
+///
+/// # Arguments
+/// * `reader`: a [`Read`], containing the scan's bytes.
+///
+/// # Returns
+/// A [`PointCloud`] with the reflectance of each point as its intensity, or a [`KittiError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read KITTI Scan", skip_all, level = "debug")
+)]
+pub fn read_kitti_scan<R: Read>(mut reader: R) -> Result<PointCloud<f32, 3>, KittiError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() % SCAN_POINT_SIZE != 0 {
+        return Err(KittiError::InvalidScanLength(bytes.len()));
+    }
+
+    let (points, intensities) = bytes
+        .chunks_exact(SCAN_POINT_SIZE)
+        .map(|point_bytes| {
+            let [x, y, z, intensity] = [0, 1, 2, 3].map(|value_idx| {
+                f32::from_le_bytes(
+                    point_bytes[value_idx * 4..(value_idx + 1) * 4]
+                        .try_into()
+                        .expect("Slice length matches the type's size"),
+                )
+            });
+            (Point3::new(x, y, z), intensity)
+        })
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+
+    Ok(PointCloud::new(points)
+        .with_intensities(intensities)
+        .expect("Channel length matches"))
+}
+
+/// Reads a KITTI poses file, where each line contains the row-major 3x4 pose matrix of a single frame.
+///
+/// # Arguments
+/// * `reader`: a [`BufRead`], containing the poses file.
+///
+/// # Returns
+/// A [`Vec`] of [`Isometry3`], the pose of each frame in the frame of the first pose's sensor, or a [`KittiError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read KITTI Poses", skip_all, level = "debug")
+)]
+pub fn read_kitti_poses<R: BufRead>(reader: R) -> Result<Vec<Isometry3<f64>>, KittiError> {
+    let mut poses = Vec::new();
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if !tokens.is_empty() {
+            poses.push(matrix_to_isometry(&parse_matrix(&tokens, line_idx + 1)?));
+        }
+    }
+
+    Ok(poses)
+}
+
+/// Reads a KITTI odometry calibration file, containing the `P0` to `P3` projection matrices and the `Tr` velodyne to camera transformation.
+///
+/// # Arguments
+/// * `reader`: a [`BufRead`], containing the calibration file.
+///
+/// # Returns
+/// A [`KittiCalibration`], or a [`KittiError`] if one of the matrices is missing or malformed.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read KITTI Calibration", skip_all, level = "debug")
+)]
+pub fn read_kitti_calibration<R: BufRead>(reader: R) -> Result<KittiCalibration, KittiError> {
+    let mut projections = [None; 4];
+    let mut velodyne_to_camera = None;
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let Some((name, values)) = line.split_once(':') else {
+            continue;
+        };
+        let tokens = values.split_whitespace().collect::<Vec<_>>();
+        let name = name.trim();
+        if let Some(camera_idx) = PROJECTION_NAMES.iter().position(|&known| known == name) {
+            projections[camera_idx] = Some(parse_matrix(&tokens, line_idx + 1)?);
+        } else if VELODYNE_TO_CAMERA_NAMES.contains(&name) {
+            velodyne_to_camera = Some(matrix_to_isometry(&parse_matrix(&tokens, line_idx + 1)?));
+        }
+    }
+
+    let mut missing_projection = None;
+    let projections = [0, 1, 2, 3].map(|camera_idx| {
+        projections[camera_idx].unwrap_or_else(|| {
+            missing_projection.get_or_insert(PROJECTION_NAMES[camera_idx]);
+            Matrix3x4::zeros()
+        })
+    });
+    if let Some(name) = missing_projection {
+        return Err(KittiError::MissingCalibration(name));
+    }
+
+    Ok(KittiCalibration {
+        projections,
+        velodyne_to_camera: velodyne_to_camera
+            .ok_or(KittiError::MissingCalibration(VELODYNE_TO_CAMERA_NAMES[0]))?,
+    })
+}
+
+/// A KITTI odometry sequence on disk, which reads its scans lazily, one frame at a time.
+///
+/// The sequence directory is expected to contain a `velodyne` directory of `.bin` scans, which are ordered by their file names,
+/// and may contain `calib.txt` and `times.txt` files. Ground truth poses are stored separately, see [`Self::with_poses_file`].
+#[derive(Clone, Debug)]
+pub struct KittiSequence {
+    scan_paths: Vec<PathBuf>,
+    timestamps: Option<Vec<f64>>,
+    calibration: Option<KittiCalibration>,
+    poses: Option<Vec<Isometry3<f64>>>,
+}
+
+impl KittiSequence {
+    /// Opens a sequence directory, listing its scans and reading its calibration and timestamps, if present.
+    ///
+    /// # Arguments
+    /// * `sequence_dir`: the path of the sequence's directory, such as `dataset/sequences/00`.
+    ///
+    /// # Returns
+    /// A [`KittiSequence`], or a [`KittiError`] if the directory or one of its text files could not be read.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Open KITTI Sequence", skip_all, level = "info")
+    )]
+    pub fn open<P: AsRef<Path>>(sequence_dir: P) -> Result<Self, KittiError> {
+        let sequence_dir = sequence_dir.as_ref();
+        let mut scan_paths = fs::read_dir(sequence_dir.join("velodyne"))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .filter(|path| {
+                path.as_ref().map_or(true, |path| {
+                    path.extension().is_some_and(|extension| extension == "bin")
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        scan_paths.sort_unstable();
+
+        let calibration_path = sequence_dir.join("calib.txt");
+        let calibration = calibration_path
+            .is_file()
+            .then(|| read_kitti_calibration(BufReader::new(File::open(&calibration_path)?)))
+            .transpose()?;
+
+        let times_path = sequence_dir.join("times.txt");
+        let timestamps = times_path
+            .is_file()
+            .then(|| -> Result<Vec<f64>, KittiError> {
+                let mut timestamps = Vec::with_capacity(scan_paths.len());
+                for (line_idx, line) in BufReader::new(File::open(&times_path)?).lines().enumerate()
+                {
+                    let line = line?;
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    timestamps.push(line.parse().map_err(|_| KittiError::InvalidLine {
+                        line: line_idx + 1,
+                        message: format!("Could not parse {line}"),
+                    })?);
+                }
+                Ok(timestamps)
+            })
+            .transpose()?;
+        if let Some(timestamps) = timestamps.as_ref() {
+            if timestamps.len() != scan_paths.len() {
+                return Err(KittiError::FrameCountMismatch {
+                    scans: scan_paths.len(),
+                    entries: timestamps.len(),
+                });
+            }
+        }
+
+        Ok(Self {
+            scan_paths,
+            timestamps,
+            calibration,
+            poses: None,
+        })
+    }
+
+    /// Reads the ground truth poses of the sequence, such as `dataset/poses/00.txt`.
+    ///
+    /// KITTI poses describe the left grey camera, if the sequence has a calibration they are converted to describe the velodyne,
+    /// so they can be compared directly with odometry estimated from the scans.
+    ///
+    /// # Arguments
+    /// * `poses_path`: the path of the poses file, containing a pose for each scan.
+    ///
+    /// # Returns
+    /// The sequence with its ground truth, or a [`KittiError`] if the file could not be read, or does not contain a pose for each scan.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read KITTI Sequence Poses", skip_all, level = "info")
+    )]
+    pub fn with_poses_file<P: AsRef<Path>>(mut self, poses_path: P) -> Result<Self, KittiError> {
+        let mut poses = read_kitti_poses(BufReader::new(File::open(poses_path)?))?;
+        if poses.len() != self.scan_paths.len() {
+            return Err(KittiError::FrameCountMismatch {
+                scans: self.scan_paths.len(),
+                entries: poses.len(),
+            });
+        }
+
+        if let Some(calibration) = self.calibration.as_ref() {
+            let velodyne_to_camera = calibration.velodyne_to_camera;
+            poses.iter_mut().for_each(|pose| {
+                *pose = velodyne_to_camera.inverse() * *pose * velodyne_to_camera;
+            });
+        }
+        self.poses = Some(poses);
+        Ok(self)
+    }
+
+    /// Returns the amount of frames in the sequence.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Get KITTI Sequence Length", skip_all, level = "debug")
+    )]
+    pub fn len(&self) -> usize {
+        self.scan_paths.len()
+    }
+
+    /// Returns whether the sequence contains no frames.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Check If KITTI Sequence Is Empty", skip_all, level = "debug")
+    )]
+    pub fn is_empty(&self) -> bool {
+        self.scan_paths.is_empty()
+    }
+
+    /// Returns the calibration of the sequence, if it contains a `calib.txt` file.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Get KITTI Sequence Calibration", skip_all, level = "debug")
+    )]
+    pub fn calibration(&self) -> Option<&KittiCalibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Returns the ground truth poses of the velodyne in all frames, if they were read.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Get KITTI Sequence Poses", skip_all, level = "debug")
+    )]
+    pub fn poses(&self) -> Option<&[Isometry3<f64>]> {
+        self.poses.as_deref()
+    }
+
+    /// Reads a single frame of the sequence.
+    ///
+    /// # Arguments
+    /// * `index`: the index of the frame.
+    ///
+    /// # Returns
+    /// [`Some`] containing the [`KittiFrame`] or a [`KittiError`] if its scan could not be read, or [`None`] if the index is out of bounds.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read KITTI Frame", skip_all, level = "debug")
+    )]
+    pub fn frame(&self, index: usize) -> Option<Result<KittiFrame, KittiError>> {
+        let scan_path = self.scan_paths.get(index)?;
+        Some(
+            File::open(scan_path)
+                .map_err(KittiError::from)
+                .and_then(|file| read_kitti_scan(BufReader::new(file)))
+                .map(|cloud| KittiFrame {
+                    index,
+                    timestamp: self.timestamps.as_ref().map(|timestamps| timestamps[index]),
+                    cloud,
+                    ground_truth: self.poses.as_ref().map(|poses| poses[index]),
+                }),
+        )
+    }
+
+    /// Returns an iterator over the frames of the sequence, reading each scan as it is reached.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Iterate KITTI Frames", skip_all, level = "debug")
+    )]
+    pub fn iter(&self) -> impl Iterator<Item = Result<KittiFrame, KittiError>> + '_ {
+        (0..self.len()).filter_map(|index| self.frame(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    const CALIBRATION: &str = "P0: 718.856 0 607.1928 0 0 718.856 185.2157 0 0 0 1 0\n\
+        P1: 718.856 0 607.1928 -386.1448 0 718.856 185.2157 0 0 0 1 0\n\
+        P2: 718.856 0 607.1928 45.38225 0 718.856 185.2157 -0.1130887 0 0 1 0.003779761\n\
+        P3: 718.856 0 607.1928 -337.2877 0 718.856 185.2157 2.369057 0 0 1 0.004915215\n\
+        Tr: 0 -1 0 0.1 0 0 -1 -0.2 1 0 0 -0.3\n";
+
+    fn scan_bytes(points: &[[f32; 4]]) -> Vec<u8> {
+        points
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_matrix() {
+        let tokens: Vec<&str> = "1 2 3 4 5 6 7 8 9 10 11 12".split_whitespace().collect();
+        let matrix = parse_matrix(&tokens, 1).unwrap();
+        assert_eq!(matrix[(0, 3)], 4.0);
+        assert_eq!(matrix[(1, 0)], 5.0);
+        assert_eq!(matrix[(2, 3)], 12.0);
+
+        assert!(matches!(
+            parse_matrix(&tokens[..11], 2),
+            Err(KittiError::InvalidLine { line: 2, .. })
+        ));
+        let mut tokens = tokens;
+        tokens[5] = "six";
+        assert!(matches!(
+            parse_matrix(&tokens, 3),
+            Err(KittiError::InvalidLine { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_matrix_to_isometry() {
+        // A rotation printed with limited precision is projected back onto an orthonormal one
+        let matrix = Matrix3x4::new(
+            0.0, -1.0001, 0.0, 1.0, 1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.9999, 3.0,
+        );
+        let isometry = matrix_to_isometry(&matrix);
+        assert_eq!(isometry.translation.vector, Vector3::new(1.0, 2.0, 3.0));
+        assert!((isometry.rotation * Vector3::x() - Vector3::y()).norm() < 1e-3);
+        let rotation = isometry.rotation.to_rotation_matrix().into_inner();
+        assert!((rotation.transpose() * rotation - Matrix3::identity()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_read_scan() {
+        let cloud = read_kitti_scan(
+            scan_bytes(&[[1.0, 2.0, 3.0, 0.5], [-4.0, 5.5, -0.25, 0.0]]).as_slice(),
+        )
+        .unwrap();
+        assert_eq!(
+            cloud.points(),
+            [Point3::new(1.0, 2.0, 3.0), Point3::new(-4.0, 5.5, -0.25)]
+        );
+        assert_eq!(cloud.intensities(), Some([0.5, 0.0].as_slice()));
+
+        assert!(matches!(
+            read_kitti_scan([0u8; 17].as_slice()),
+            Err(KittiError::InvalidScanLength(17))
+        ));
+    }
+
+    #[test]
+    fn test_read_poses_and_calibration() {
+        let poses = read_kitti_poses(
+            "1 0 0 0 0 1 0 0 0 0 1 0\n\n0 -1 0 1.5 1 0 0 2.5 0 0 1 3.5\n".as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(poses.len(), 2);
+        assert_eq!(poses[0], Isometry3::identity());
+        let expected = Isometry3::new(
+            Vector3::new(1.5, 2.5, 3.5),
+            Vector3::z() * core::f64::consts::FRAC_PI_2,
+        );
+        assert!((poses[1].to_homogeneous() - expected.to_homogeneous()).norm() < 1e-12);
+        assert!(matches!(
+            read_kitti_poses("1 0 0\n".as_bytes()),
+            Err(KittiError::InvalidLine { line: 1, .. })
+        ));
+
+        let calibration = read_kitti_calibration(CALIBRATION.as_bytes()).unwrap();
+        assert_eq!(calibration.projections[1][(0, 3)], -386.1448);
+        // The velodyne's forward X axis is the camera's forward Z axis
+        assert!(
+            (calibration.velodyne_to_camera.rotation * Vector3::x() - Vector3::z()).norm() < 1e-12
+        );
+        assert!(matches!(
+            read_kitti_calibration("P0: 1 0 0 0 0 1 0 0 0 0 1 0\n".as_bytes()),
+            Err(KittiError::MissingCalibration("P1"))
+        ));
+    }
+
+    // Removes the directory when dropped, so it is cleaned up even if the test fails
+    struct TempDir(std::path::PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_sequence() {
+        let temp_dir =
+            TempDir(std::env::temp_dir().join(format!("kitti_sequence_{}", std::process::id())));
+        let sequence_dir = &temp_dir.0;
+        let velodyne_dir = sequence_dir.join("velodyne");
+        fs::create_dir_all(&velodyne_dir).unwrap();
+        fs::write(
+            velodyne_dir.join("000001.bin"),
+            scan_bytes(&[[2.0, 0.0, 0.0, 1.0]]),
+        )
+        .unwrap();
+        fs::write(
+            velodyne_dir.join("000000.bin"),
+            scan_bytes(&[[1.0, 0.0, 0.0, 1.0]]),
+        )
+        .unwrap();
+        fs::write(velodyne_dir.join("notes.txt"), "Not a scan").unwrap();
+        fs::write(sequence_dir.join("calib.txt"), CALIBRATION).unwrap();
+        fs::write(sequence_dir.join("times.txt"), "0.0\n0.1\n").unwrap();
+        // The camera moves one metre forward, along its Z axis
+        let poses_path = sequence_dir.join("poses.txt");
+        fs::write(
+            &poses_path,
+            "1 0 0 0 0 1 0 0 0 0 1 0\n1 0 0 0 0 1 0 0 0 0 1 1\n",
+        )
+        .unwrap();
+
+        let sequence = KittiSequence::open(sequence_dir)
+            .unwrap()
+            .with_poses_file(&poses_path)
+            .unwrap();
+        assert_eq!(sequence.len(), 2);
+        assert!(sequence.calibration().is_some());
+        let frames = sequence.iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(frames[1].index, 1);
+        assert_eq!(frames[1].timestamp, Some(0.1));
+        assert_eq!(frames[1].cloud.points(), [Point3::new(2.0, 0.0, 0.0)]);
+        assert!(
+            (frames[0].ground_truth.unwrap().to_homogeneous()
+                - Isometry3::identity().to_homogeneous())
+            .norm()
+                < 1e-12
+        );
+        // In the velodyne frame, the same motion is along the X axis
+        let ground_truth = frames[1].ground_truth.unwrap();
+        assert!((ground_truth.translation.vector - Vector3::x()).norm() < 1e-12);
+        assert!(sequence.frame(2).is_none());
+
+        fs::write(&poses_path, "1 0 0 0 0 1 0 0 0 0 1 0\n").unwrap();
+        assert!(matches!(
+            KittiSequence::open(sequence_dir)
+                .unwrap()
+                .with_poses_file(&poses_path),
+            Err(KittiError::FrameCountMismatch {
+                scans: 2,
+                entries: 1
+            })
+        ));
+        assert!(!sequence.is_empty());
+    }
+}
@@ -0,0 +1,1267 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use nalgebra::{Point3, Vector3};
+
+use crate::point_clouds::{AttributeChannel, AttributeValues, PointCloud};
+
+const SIGNATURE: &[u8; 4] = b"LASF";
+const VLR_HEADER_SIZE: usize = 54;
+const MAX_POINT_FORMAT: u8 = 10;
+// The minimum record length of each point data format, records may contain extra bytes beyond these
+const RECORD_LENGTHS: [usize; 11] = [20, 28, 26, 34, 57, 63, 30, 36, 38, 59, 67];
+// Scan angles of point formats 6 and above are stored in increments of 0.006 degrees
+const EXTENDED_SCAN_ANGLE_SCALE: f32 = 0.006;
+
+/// The name of the channel containing each point's ASPRS classification, as [`AttributeValues::U8`].
+pub const CLASSIFICATION_CHANNEL: &str = "classification";
+/// The name of the channel containing each point's synthetic, key-point, withheld and overlap flags, in bits 0 to 3, as [`AttributeValues::U8`].
+pub const CLASSIFICATION_FLAGS_CHANNEL: &str = "classification_flags";
+/// The name of the channel containing each point's return number, as [`AttributeValues::U8`].
+pub const RETURN_NUMBER_CHANNEL: &str = "return_number";
+/// The name of the channel containing each point's amount of returns for its pulse, as [`AttributeValues::U8`].
+pub const NUMBER_OF_RETURNS_CHANNEL: &str = "number_of_returns";
+/// The name of the channel containing each point's scan angle in degrees, as [`AttributeValues::F32`].
+pub const SCAN_ANGLE_CHANNEL: &str = "scan_angle";
+/// The name of the channel containing each point's user data, as [`AttributeValues::U8`].
+pub const USER_DATA_CHANNEL: &str = "user_data";
+/// The name of the channel containing each point's source, usually its flight line, as [`AttributeValues::U16`].
+pub const POINT_SOURCE_ID_CHANNEL: &str = "point_source_id";
+/// The name of the channel containing each point's near infrared value, for point formats 8 and 10, as [`AttributeValues::U16`].
+pub const NIR_CHANNEL: &str = "nir";
+
+/// An error type containing the various errors that might arise when reading or writing a LAS file.
+#[derive(Debug, thiserror::Error)]
+pub enum LasError {
+    /// The underlying reader or writer failed.
+    #[error("Failed reading or writing LAS data: {0}")]
+    Io(#[from] io::Error),
+    /// The header is malformed.
+    #[error("Invalid LAS header: {0}")]
+    InvalidHeader(String),
+    /// The file's version is not LAS 1.0 to 1.4 when reading, or 1.2 to 1.4 when writing.
+    #[error("LAS version {0}.{1} is not supported")]
+    UnsupportedVersion(u8, u8),
+    /// The point data format is not one of the uncompressed formats 0 to 10, or cannot be used with the file's version.
+    #[error("Point data format {0} is not supported")]
+    UnsupportedPointFormat(u8),
+    /// A point's scaled and offset coordinates do not fit in the 32 bit integers stored in the file.
+    #[error(
+        "The coordinates of point {0} cannot be represented using the header's scale and offset"
+    )]
+    CoordinateOutOfRange(usize),
+}
+
+/// A variable length record, containing metadata such as the coordinate reference system.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LasVlr {
+    /// The identifier of the organisation that defined the record, at most 16 bytes.
+    pub user_id: String,
+    /// The identifier of the record, defined by `user_id`.
+    pub record_id: u16,
+    /// A textual description of the record, at most 32 bytes.
+    pub description: String,
+    /// The contents of the record, at most 65535 bytes.
+    pub data: Vec<u8>,
+}
+
+/// The header of a LAS file, excluding the fields derived from its points, such as point counts and bounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LasHeader {
+    /// The minor version of the file, the major version is always 1.
+    pub version_minor: u8,
+    /// The point data record format, from 0 to 10, formats 6 and above require version 1.4.
+    pub point_format: u8,
+    /// The scale applied to the stored integer coordinates.
+    pub scale: Vector3<f64>,
+    /// The offset added to the scaled coordinates.
+    pub offset: Vector3<f64>,
+    /// The identifier of the source of the file, such as a flight line.
+    pub file_source_id: u16,
+    /// The global encoding bit field.
+    pub global_encoding: u16,
+    /// The project's GUID.
+    pub project_id: [u8; 16],
+    /// The system that generated the data, at most 32 bytes.
+    pub system_identifier: String,
+    /// The software that generated the file, at most 32 bytes.
+    pub generating_software: String,
+    /// The day of the year the file was created, starting with 1.
+    pub creation_day_of_year: u16,
+    /// The year the file was created.
+    pub creation_year: u16,
+    /// The variable length records following the header, extended variable length records are not read.
+    pub vlrs: Vec<LasVlr>,
+}
+
+impl Default for LasHeader {
+    fn default() -> Self {
+        Self {
+            version_minor: 2,
+            point_format: 0,
+            scale: Vector3::repeat(0.001),
+            offset: Vector3::zeros(),
+            file_source_id: 0,
+            global_encoding: 0,
+            project_id: [0; 16],
+            system_identifier: String::new(),
+            generating_software: "mapping-algorithms".to_owned(),
+            creation_day_of_year: 0,
+            creation_year: 0,
+            vlrs: Vec::new(),
+        }
+    }
+}
+
+/// A point cloud along with the header of the LAS file it was read from or will be written to.
+///
+/// The cloud's intensities and colours are stored in their dedicated channels, and GPS times as its timestamps,
+/// all other point attributes are stored as custom [`AttributeChannel`]s, see [`CLASSIFICATION_CHANNEL`] and its neighbours.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LasPointCloud {
+    /// The points and their attribute channels.
+    pub cloud: PointCloud<f64, 3>,
+    /// The header of the file.
+    pub header: LasHeader,
+}
+
+impl LasPointCloud {
+    /// Returns the ASPRS classification of each point, if present.
+    pub fn classifications(&self) -> Option<&[u8]> {
+        match &self.cloud.channel(CLASSIFICATION_CHANNEL)?.values {
+            AttributeValues::U8(classifications) => Some(classifications),
+            _ => None,
+        }
+    }
+
+    /// Returns the GPS time of each point, if the point format contains it.
+    pub fn gps_times(&self) -> Option<&[f64]> {
+        self.cloud.timestamps()
+    }
+
+    /// Returns the intensity of each point, if present.
+    pub fn intensities(&self) -> Option<&[f64]> {
+        self.cloud.intensities()
+    }
+}
+
+impl From<PointCloud<f64, 3>> for LasPointCloud {
+    /// Wraps a cloud with a default header, using a millimetre scale and an offset at the cloud's minimum corner,
+    /// and the smallest point format that can store the cloud's timestamps and colours.
+    fn from(cloud: PointCloud<f64, 3>) -> Self {
+        let offset = cloud
+            .iter()
+            .fold(None, |minimum: Option<Vector3<f64>>, point| {
+                Some(minimum.map_or(point.coords, |minimum| minimum.inf(&point.coords)))
+            })
+            .map_or_else(Vector3::zeros, |minimum| minimum.map(f64::floor));
+        let point_format = match (cloud.timestamps().is_some(), cloud.colours().is_some()) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+
+        Self {
+            cloud,
+            header: LasHeader {
+                point_format,
+                offset,
+                ..LasHeader::default()
+            },
+        }
+    }
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Has GPS Time", skip_all, level = "trace")
+)]
+fn has_gps_time(point_format: u8) -> bool {
+    !matches!(point_format, 0 | 2)
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Has Colour", skip_all, level = "trace")
+)]
+fn has_colour(point_format: u8) -> bool {
+    matches!(point_format, 2 | 3 | 5 | 7 | 8 | 10)
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Has NIR", skip_all, level = "trace")
+)]
+fn has_nir(point_format: u8) -> bool {
+    matches!(point_format, 8 | 10)
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("LAS Header Size", skip_all, level = "trace")
+)]
+fn header_size(version_minor: u8) -> usize {
+    match version_minor {
+        ..=2 => 227,
+        3 => 235,
+        _ => 375,
+    }
+}
+
+// Reads little endian values from a byte slice, which must be long enough for all values read
+struct ByteCursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteCursor<'a> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Create Byte Cursor", skip_all, level = "trace")
+    )]
+    fn new(bytes: &'a [u8], position: usize) -> Self {
+        Self { bytes, position }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Take Bytes", skip_all, level = "trace")
+    )]
+    fn take<const S: usize>(&mut self) -> [u8; S] {
+        let taken = self.bytes[self.position..self.position + S]
+            .try_into()
+            .expect("Slice length matches the array's size");
+        self.position += S;
+        taken
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read String", skip_all, level = "trace")
+    )]
+    fn string<const S: usize>(&mut self) -> String {
+        let bytes = self.take::<S>();
+        let length = bytes.iter().position(|&byte| byte == 0).unwrap_or(S);
+        String::from_utf8_lossy(&bytes[..length]).into_owned()
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read U8", skip_all, level = "trace")
+    )]
+    fn u8(&mut self) -> u8 {
+        self.take::<1>()[0]
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read U16", skip_all, level = "trace")
+    )]
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read U32", skip_all, level = "trace")
+    )]
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read U64", skip_all, level = "trace")
+    )]
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read I32", skip_all, level = "trace")
+    )]
+    fn i32(&mut self) -> i32 {
+        i32::from_le_bytes(self.take())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read F64", skip_all, level = "trace")
+    )]
+    fn f64(&mut self) -> f64 {
+        f64::from_le_bytes(self.take())
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Push String", skip_all, level = "trace")
+)]
+fn push_string<const S: usize>(
+    value: &str,
+    field: &str,
+    output: &mut Vec<u8>,
+) -> Result<(), LasError> {
+    if value.len() > S {
+        return Err(LasError::InvalidHeader(format!(
+            "The {field} must be at most {S} bytes long"
+        )));
+    }
+    output.extend_from_slice(value.as_bytes());
+    output.resize(output.len() + S - value.len(), 0);
+    Ok(())
+}
+
+// The attributes of all points, as they are stored in the point records
+#[derive(Default)]
+struct LasColumns {
+    points: Vec<Point3<f64>>,
+    intensities: Vec<f64>,
+    return_numbers: Vec<u8>,
+    numbers_of_returns: Vec<u8>,
+    classifications: Vec<u8>,
+    classification_flags: Vec<u8>,
+    scan_angles: Vec<f32>,
+    user_data: Vec<u8>,
+    point_source_ids: Vec<u16>,
+    gps_times: Vec<f64>,
+    colours: Vec<[u16; 3]>,
+    nirs: Vec<u16>,
+}
+
+impl LasColumns {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Decode LAS Record", skip_all, level = "trace")
+    )]
+    fn decode_record(&mut self, record: &[u8], header: &LasHeader) {
+        let mut cursor = ByteCursor::new(record, 0);
+        let integer_coordinates = Vector3::new(cursor.i32(), cursor.i32(), cursor.i32());
+        self.points.push(Point3::from(
+            integer_coordinates
+                .map(f64::from)
+                .component_mul(&header.scale)
+                + header.offset,
+        ));
+        self.intensities.push(cursor.u16().into());
+
+        let point_format = header.point_format;
+        if point_format < 6 {
+            let returns = cursor.u8();
+            self.return_numbers.push(returns & 0b111);
+            self.numbers_of_returns.push(returns >> 3 & 0b111);
+            let classification = cursor.u8();
+            self.classifications.push(classification & 0b1_1111);
+            self.classification_flags.push(classification >> 5);
+            self.scan_angles.push(f32::from(cursor.u8() as i8));
+            self.user_data.push(cursor.u8());
+            self.point_source_ids.push(cursor.u16());
+            if has_gps_time(point_format) {
+                self.gps_times.push(cursor.f64());
+            }
+        } else {
+            let returns = cursor.u8();
+            self.return_numbers.push(returns & 0b1111);
+            self.numbers_of_returns.push(returns >> 4);
+            self.classification_flags.push(cursor.u8() & 0b1111);
+            self.classifications.push(cursor.u8());
+            self.user_data.push(cursor.u8());
+            self.scan_angles
+                .push(f32::from(i16::from_le_bytes(cursor.take())) * EXTENDED_SCAN_ANGLE_SCALE);
+            self.point_source_ids.push(cursor.u16());
+            self.gps_times.push(cursor.f64());
+        }
+
+        if has_colour(point_format) {
+            self.colours
+                .push([cursor.u16(), cursor.u16(), cursor.u16()]);
+        }
+        if has_nir(point_format) {
+            self.nirs.push(cursor.u16());
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Build LAS Point Cloud", skip_all, level = "debug")
+    )]
+    fn into_cloud(self, point_format: u8) -> PointCloud<f64, 3> {
+        // Colours should span the full 16 bits, but some writers store 8 bit values as they are
+        let colour_shift = if self
+            .colours
+            .iter()
+            .flatten()
+            .any(|&component| component > u8::MAX as u16)
+        {
+            8
+        } else {
+            0
+        };
+        let colours = self
+            .colours
+            .iter()
+            .map(|colour| colour.map(|component| (component >> colour_shift) as u8))
+            .collect();
+
+        let channel = |name: &str, values| AttributeChannel {
+            name: name.to_owned(),
+            components: 1,
+            values,
+        };
+        let mut channels = Vec::from([
+            channel(
+                CLASSIFICATION_CHANNEL,
+                AttributeValues::U8(self.classifications),
+            ),
+            channel(
+                CLASSIFICATION_FLAGS_CHANNEL,
+                AttributeValues::U8(self.classification_flags),
+            ),
+            channel(
+                RETURN_NUMBER_CHANNEL,
+                AttributeValues::U8(self.return_numbers),
+            ),
+            channel(
+                NUMBER_OF_RETURNS_CHANNEL,
+                AttributeValues::U8(self.numbers_of_returns),
+            ),
+            channel(SCAN_ANGLE_CHANNEL, AttributeValues::F32(self.scan_angles)),
+            channel(USER_DATA_CHANNEL, AttributeValues::U8(self.user_data)),
+            channel(
+                POINT_SOURCE_ID_CHANNEL,
+                AttributeValues::U16(self.point_source_ids),
+            ),
+        ]);
+        if has_nir(point_format) {
+            channels.push(channel(NIR_CHANNEL, AttributeValues::U16(self.nirs)));
+        }
+
+        // All channels were decoded alongside the points, so their lengths always match
+        let mut cloud = PointCloud::new(self.points)
+            .with_intensities(self.intensities)
+            .expect("Channel length matches");
+        if has_gps_time(point_format) {
+            cloud = cloud
+                .with_timestamps(self.gps_times)
+                .expect("Channel length matches");
+        }
+        if has_colour(point_format) {
+            cloud = cloud.with_colours(colours).expect("Channel length matches");
+        }
+        channels.into_iter().fold(cloud, |cloud, channel| {
+            cloud.with_channel(channel).expect("Channel length matches")
+        })
+    }
+}
+
+/// Reads a point cloud from an uncompressed LAS 1.0 to 1.4 file, using any of the point data formats 0 to 10.
+///
+/// The stored integer coordinates are scaled and offset according to the header, intensities and colours are stored in the cloud's
+/// dedicated channels, GPS times as its timestamps, and the remaining attributes as custom channels.
+/// Colours are reduced to 8 bits, unless all of the file's colour values already fit in 8 bits.
+/// Waveform packets and extra bytes at the end of each record are skipped.
+///
+/// # Arguments
+/// * `reader`: a [`Read`], positioned at the beginning of the LAS file.
+///
+/// # Returns
+/// A [`LasPointCloud`] containing the points and the file's header, or a [`LasError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read LAS Point Cloud", skip_all, level = "info")
+)]
+pub fn read_las<R: Read>(reader: R) -> Result<LasPointCloud, LasError> {
+    let mut reader = BufReader::new(reader);
+    let mut header_bytes = vec![0; header_size(0)];
+    reader.read_exact(&mut header_bytes)?;
+    if &header_bytes[..4] != SIGNATURE {
+        return Err(LasError::InvalidHeader(
+            "The file does not begin with the LASF signature".to_owned(),
+        ));
+    }
+
+    let mut cursor = ByteCursor::new(&header_bytes, 4);
+    let file_source_id = cursor.u16();
+    let global_encoding = cursor.u16();
+    let project_id = cursor.take::<16>();
+    let (version_major, version_minor) = (cursor.u8(), cursor.u8());
+    if version_major != 1 || version_minor > 4 {
+        return Err(LasError::UnsupportedVersion(version_major, version_minor));
+    }
+    let system_identifier = cursor.string::<32>();
+    let generating_software = cursor.string::<32>();
+    let creation_day_of_year = cursor.u16();
+    let creation_year = cursor.u16();
+    let declared_header_size = cursor.u16() as usize;
+    let point_data_offset = cursor.u32() as usize;
+    let vlr_count = cursor.u32();
+    let point_format = cursor.u8();
+    let record_length = cursor.u16() as usize;
+    let legacy_point_count = cursor.u32();
+    cursor.take::<20>();
+    let scale = Vector3::new(cursor.f64(), cursor.f64(), cursor.f64());
+    let offset = Vector3::new(cursor.f64(), cursor.f64(), cursor.f64());
+
+    // Compressed LAZ files set the two most significant bits of the point format
+    if point_format > MAX_POINT_FORMAT || (point_format > 5 && version_minor < 4) {
+        return Err(LasError::UnsupportedPointFormat(point_format));
+    }
+    if record_length < RECORD_LENGTHS[point_format as usize] {
+        return Err(LasError::InvalidHeader(format!(
+            "A record length of {record_length} bytes is too short for point format {point_format}"
+        )));
+    }
+    if declared_header_size < header_bytes.len() {
+        return Err(LasError::InvalidHeader(format!(
+            "A header size of {declared_header_size} bytes is too short"
+        )));
+    }
+
+    header_bytes.resize(declared_header_size, 0);
+    reader.read_exact(&mut header_bytes[header_size(0)..])?;
+    let point_count = if version_minor >= 4 && declared_header_size >= header_size(4) {
+        ByteCursor::new(&header_bytes, 247).u64() as usize
+    } else {
+        legacy_point_count as usize
+    };
+
+    let mut position = declared_header_size;
+    // The records lie between the header and the point data, which bounds how many of them can be present
+    let mut vlrs = Vec::with_capacity(
+        (vlr_count as usize)
+            .min(point_data_offset.saturating_sub(declared_header_size) / VLR_HEADER_SIZE),
+    );
+    for _ in 0..vlr_count {
+        let mut vlr_header = [0; VLR_HEADER_SIZE];
+        reader.read_exact(&mut vlr_header)?;
+        let mut cursor = ByteCursor::new(&vlr_header, 2);
+        let user_id = cursor.string::<16>();
+        let record_id = cursor.u16();
+        let data_length = cursor.u16() as usize;
+        let description = cursor.string::<32>();
+        let mut data = vec![0; data_length];
+        reader.read_exact(&mut data)?;
+        position += VLR_HEADER_SIZE + data_length;
+        vlrs.push(LasVlr {
+            user_id,
+            record_id,
+            description,
+            data,
+        });
+    }
+
+    let padding = point_data_offset.checked_sub(position).ok_or_else(|| {
+        LasError::InvalidHeader(format!(
+            "The point data offset {point_data_offset} points inside the header or its records"
+        ))
+    })?;
+    io::copy(&mut (&mut reader).take(padding as u64), &mut io::sink())?;
+
+    let header = LasHeader {
+        version_minor,
+        point_format,
+        scale,
+        offset,
+        file_source_id,
+        global_encoding,
+        project_id,
+        system_identifier,
+        generating_software,
+        creation_day_of_year,
+        creation_year,
+        vlrs,
+    };
+    let mut columns = LasColumns::default();
+    let mut record = vec![0; record_length];
+    for _ in 0..point_count {
+        reader.read_exact(&mut record)?;
+        columns.decode_record(&record, &header);
+    }
+
+    Ok(LasPointCloud {
+        cloud: columns.into_cloud(point_format),
+        header,
+    })
+}
+
+/// Writes a point cloud as an uncompressed LAS file, using the version, point format, scale and offset of its header.
+///
+/// Point counts and bounds are computed from the points, intensities are rounded to 16 bit integers and colours are expanded to 16 bits.
+/// Attributes stored in custom channels, see [`CLASSIFICATION_CHANNEL`] and its neighbours, are written if present and zeroed otherwise,
+/// as are GPS times and colours required by the point format, waveform packets are always zeroed.
+///
+/// # Arguments
+/// * `writer`: a [`Write`], to which the LAS file is written.
+/// * `cloud`: a [`LasPointCloud`], the point cloud to write along with its header.
+///
+/// # Returns
+/// Nothing on success, or a [`LasError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Write LAS Point Cloud", skip_all, level = "info")
+)]
+pub fn write_las<W: Write>(writer: W, cloud: &LasPointCloud) -> Result<(), LasError> {
+    let header = &cloud.header;
+    let points = &cloud.cloud;
+    let point_format = header.point_format;
+    if !(2..=4).contains(&header.version_minor) {
+        return Err(LasError::UnsupportedVersion(1, header.version_minor));
+    }
+    if point_format > MAX_POINT_FORMAT || (point_format > 5 && header.version_minor < 4) {
+        return Err(LasError::UnsupportedPointFormat(point_format));
+    }
+
+    let channel_value = |name: &str| {
+        let channel = points
+            .channel(name)
+            .filter(|channel| channel.components == 1);
+        move |point_idx: usize| {
+            channel
+                .and_then(|channel| channel.values.get_f64(point_idx))
+                .unwrap_or_default()
+        }
+    };
+    let classification = channel_value(CLASSIFICATION_CHANNEL);
+    let classification_flags = channel_value(CLASSIFICATION_FLAGS_CHANNEL);
+    let return_number = channel_value(RETURN_NUMBER_CHANNEL);
+    let number_of_returns = channel_value(NUMBER_OF_RETURNS_CHANNEL);
+    let scan_angle = channel_value(SCAN_ANGLE_CHANNEL);
+    let user_data = channel_value(USER_DATA_CHANNEL);
+    let point_source_id = channel_value(POINT_SOURCE_ID_CHANNEL);
+    let nir = channel_value(NIR_CHANNEL);
+
+    let record_length = RECORD_LENGTHS[point_format as usize];
+    let mut records = Vec::with_capacity(points.len() * record_length);
+    let mut returns_counts = [0u64; 15];
+    let mut minimum = Vector3::repeat(f64::INFINITY);
+    let mut maximum = Vector3::repeat(f64::NEG_INFINITY);
+    for (point_idx, point) in points.iter().enumerate() {
+        minimum = minimum.inf(&point.coords);
+        maximum = maximum.sup(&point.coords);
+        let integer_coordinates = (point.coords - header.offset).component_div(&header.scale);
+        if integer_coordinates
+            .iter()
+            .any(|coordinate| !(i32::MIN as f64..=i32::MAX as f64).contains(&coordinate.round()))
+        {
+            return Err(LasError::CoordinateOutOfRange(point_idx));
+        }
+        integer_coordinates.iter().for_each(|coordinate| {
+            records.extend_from_slice(&(coordinate.round() as i32).to_le_bytes())
+        });
+        let intensity = points
+            .intensities()
+            .map_or(0.0, |intensities| intensities[point_idx]);
+        records.extend_from_slice(&(intensity.round() as u16).to_le_bytes());
+
+        let return_number = return_number(point_idx) as u8;
+        if (1..=15).contains(&return_number) {
+            returns_counts[return_number as usize - 1] += 1;
+        }
+        let gps_time = points
+            .timestamps()
+            .map_or(0.0, |timestamps| timestamps[point_idx]);
+        if point_format < 6 {
+            records.push(return_number & 0b111 | (number_of_returns(point_idx) as u8 & 0b111) << 3);
+            records.push(
+                classification(point_idx) as u8 & 0b1_1111
+                    | (classification_flags(point_idx) as u8) << 5,
+            );
+            records.push(scan_angle(point_idx).round() as i8 as u8);
+            records.push(user_data(point_idx) as u8);
+            records.extend_from_slice(&(point_source_id(point_idx) as u16).to_le_bytes());
+            if has_gps_time(point_format) {
+                records.extend_from_slice(&gps_time.to_le_bytes());
+            }
+        } else {
+            records.push(return_number & 0b1111 | (number_of_returns(point_idx) as u8) << 4);
+            records.push(classification_flags(point_idx) as u8 & 0b1111);
+            records.push(classification(point_idx) as u8);
+            records.push(user_data(point_idx) as u8);
+            records.extend_from_slice(
+                &((scan_angle(point_idx) as f32 / EXTENDED_SCAN_ANGLE_SCALE).round() as i16)
+                    .to_le_bytes(),
+            );
+            records.extend_from_slice(&(point_source_id(point_idx) as u16).to_le_bytes());
+            records.extend_from_slice(&gps_time.to_le_bytes());
+        }
+
+        if has_colour(point_format) {
+            let colour = points
+                .colours()
+                .map_or([0; 3], |colours| colours[point_idx]);
+            colour.iter().for_each(|&component| {
+                records.extend_from_slice(&(component as u16 * 257).to_le_bytes())
+            });
+        }
+        if has_nir(point_format) {
+            records.extend_from_slice(&(nir(point_idx) as u16).to_le_bytes());
+        }
+        records.resize((point_idx + 1) * record_length, 0);
+    }
+    if points.is_empty() {
+        minimum = Vector3::zeros();
+        maximum = Vector3::zeros();
+    }
+
+    let header_size = header_size(header.version_minor);
+    let mut vlr_bytes = Vec::new();
+    for vlr in header.vlrs.iter() {
+        let data_length = u16::try_from(vlr.data.len()).map_err(|_| {
+            LasError::InvalidHeader(
+                "A variable length record must be at most 65535 bytes long".to_owned(),
+            )
+        })?;
+        vlr_bytes.extend_from_slice(&[0, 0]);
+        push_string::<16>(&vlr.user_id, "record's user ID", &mut vlr_bytes)?;
+        vlr_bytes.extend_from_slice(&vlr.record_id.to_le_bytes());
+        vlr_bytes.extend_from_slice(&data_length.to_le_bytes());
+        push_string::<32>(&vlr.description, "record's description", &mut vlr_bytes)?;
+        vlr_bytes.extend_from_slice(&vlr.data);
+    }
+
+    // Point formats 6 and above must leave the legacy counts zeroed, as must files with too many points
+    let legacy_counts = point_format < 6 && points.len() <= u32::MAX as usize;
+    let legacy_count = |count: u64| if legacy_counts { count as u32 } else { 0 };
+    let mut header_bytes = Vec::with_capacity(header_size);
+    header_bytes.extend_from_slice(SIGNATURE);
+    header_bytes.extend_from_slice(&header.file_source_id.to_le_bytes());
+    header_bytes.extend_from_slice(&header.global_encoding.to_le_bytes());
+    header_bytes.extend_from_slice(&header.project_id);
+    header_bytes.extend_from_slice(&[1, header.version_minor]);
+    push_string::<32>(
+        &header.system_identifier,
+        "system identifier",
+        &mut header_bytes,
+    )?;
+    push_string::<32>(
+        &header.generating_software,
+        "generating software",
+        &mut header_bytes,
+    )?;
+    header_bytes.extend_from_slice(&header.creation_day_of_year.to_le_bytes());
+    header_bytes.extend_from_slice(&header.creation_year.to_le_bytes());
+    header_bytes.extend_from_slice(&(header_size as u16).to_le_bytes());
+    header_bytes.extend_from_slice(&((header_size + vlr_bytes.len()) as u32).to_le_bytes());
+    header_bytes.extend_from_slice(&(header.vlrs.len() as u32).to_le_bytes());
+    header_bytes.push(point_format);
+    header_bytes.extend_from_slice(&(record_length as u16).to_le_bytes());
+    header_bytes.extend_from_slice(&legacy_count(points.len() as u64).to_le_bytes());
+    for count in returns_counts[..5].iter() {
+        header_bytes.extend_from_slice(&legacy_count(*count).to_le_bytes());
+    }
+    for value in header.scale.iter().chain(header.offset.iter()) {
+        header_bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    for axis in 0..3 {
+        header_bytes.extend_from_slice(&maximum[axis].to_le_bytes());
+        header_bytes.extend_from_slice(&minimum[axis].to_le_bytes());
+    }
+    if header.version_minor >= 3 {
+        // No waveform data packet records are written
+        header_bytes.extend_from_slice(&0u64.to_le_bytes());
+    }
+    if header.version_minor >= 4 {
+        // No extended variable length records are written
+        header_bytes.extend_from_slice(&0u64.to_le_bytes());
+        header_bytes.extend_from_slice(&0u32.to_le_bytes());
+        header_bytes.extend_from_slice(&(points.len() as u64).to_le_bytes());
+        for count in returns_counts.iter() {
+            header_bytes.extend_from_slice(&count.to_le_bytes());
+        }
+    }
+
+    let mut writer = BufWriter::new(writer);
+    writer.write_all(&header_bytes)?;
+    writer.write_all(&vlr_bytes)?;
+    writer.write_all(&records)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate_cloud() -> LasPointCloud {
+        let channel = |name: &str, values| AttributeChannel {
+            name: name.to_owned(),
+            components: 1,
+            values,
+        };
+        let cloud = PointCloud::new(Vec::from([
+            Point3::new(500_000.125, 4_100_000.5, 12.25),
+            Point3::new(500_010.0, 4_100_020.75, -3.5),
+            Point3::new(500_005.001, 4_100_001.0, 100.0),
+        ]))
+        .with_intensities(Vec::from([0.0, 1200.0, 65535.0]))
+        .unwrap()
+        .with_timestamps(Vec::from([1e9, 1e9 + 0.5, 1e9 + 1.25]))
+        .unwrap()
+        .with_colours(Vec::from([[0, 0, 0], [255, 128, 1], [10, 20, 30]]))
+        .unwrap();
+        [
+            channel(
+                CLASSIFICATION_CHANNEL,
+                AttributeValues::U8(Vec::from([2, 6, 9])),
+            ),
+            channel(
+                CLASSIFICATION_FLAGS_CHANNEL,
+                AttributeValues::U8(Vec::from([0, 1, 4])),
+            ),
+            channel(
+                RETURN_NUMBER_CHANNEL,
+                AttributeValues::U8(Vec::from([1, 2, 1])),
+            ),
+            channel(
+                NUMBER_OF_RETURNS_CHANNEL,
+                AttributeValues::U8(Vec::from([1, 3, 2])),
+            ),
+            channel(
+                SCAN_ANGLE_CHANNEL,
+                AttributeValues::F32(Vec::from([-12.0, 0.0, 30.0])),
+            ),
+            channel(
+                USER_DATA_CHANNEL,
+                AttributeValues::U8(Vec::from([0, 7, 255])),
+            ),
+            channel(
+                POINT_SOURCE_ID_CHANNEL,
+                AttributeValues::U16(Vec::from([1, 1, 2])),
+            ),
+            channel(
+                NIR_CHANNEL,
+                AttributeValues::U16(Vec::from([0, 1000, 65535])),
+            ),
+        ]
+        .into_iter()
+        .fold(cloud, |cloud, channel| cloud.with_channel(channel).unwrap())
+        .into()
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let mut cloud = generate_cloud();
+        cloud.header.vlrs.push(LasVlr {
+            user_id: "LASF_Projection".to_owned(),
+            record_id: 2112,
+            description: "OGC WKT".to_owned(),
+            data: b"PROJCS[\"WGS 84 / UTM zone 36N\"]\0".to_vec(),
+        });
+        assert_eq!(cloud.header.point_format, 3);
+        assert_eq!(
+            cloud.header.offset,
+            Vector3::new(500_000.0, 4_100_000.0, -4.0)
+        );
+
+        for point_format in 0..=MAX_POINT_FORMAT {
+            for version_minor in 2..=4 {
+                let mut cloud = cloud.clone();
+                cloud.header.point_format = point_format;
+                cloud.header.version_minor = version_minor;
+                let mut bytes = Vec::new();
+                if point_format > 5 && version_minor < 4 {
+                    assert!(matches!(
+                        write_las(&mut bytes, &cloud),
+                        Err(LasError::UnsupportedPointFormat(_))
+                    ));
+                    continue;
+                }
+
+                write_las(&mut bytes, &cloud).unwrap();
+                assert_eq!(
+                    bytes.len(),
+                    header_size(version_minor)
+                        + VLR_HEADER_SIZE
+                        + cloud.header.vlrs[0].data.len()
+                        + 3 * RECORD_LENGTHS[point_format as usize]
+                );
+                let read_cloud = read_las(bytes.as_slice()).unwrap();
+                assert_eq!(read_cloud.header, cloud.header);
+                for (read_point, point) in read_cloud.cloud.iter().zip(cloud.cloud.iter()) {
+                    assert!((read_point - point).norm() < 1e-3);
+                }
+                assert_eq!(read_cloud.intensities(), cloud.intensities());
+                assert_eq!(read_cloud.classifications(), Some([2, 6, 9].as_slice()));
+                assert_eq!(
+                    read_cloud.gps_times(),
+                    has_gps_time(point_format).then_some(cloud.gps_times().unwrap())
+                );
+                assert_eq!(
+                    read_cloud.cloud.colours(),
+                    has_colour(point_format).then_some(cloud.cloud.colours().unwrap())
+                );
+                for name in [
+                    CLASSIFICATION_FLAGS_CHANNEL,
+                    RETURN_NUMBER_CHANNEL,
+                    NUMBER_OF_RETURNS_CHANNEL,
+                    SCAN_ANGLE_CHANNEL,
+                    USER_DATA_CHANNEL,
+                    POINT_SOURCE_ID_CHANNEL,
+                ] {
+                    assert_eq!(read_cloud.cloud.channel(name), cloud.cloud.channel(name));
+                }
+                assert_eq!(
+                    read_cloud.cloud.channel(NIR_CHANNEL).is_some(),
+                    has_nir(point_format)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_handwritten() {
+        // A LAS 1.2 file with point format 1, an extra byte at the end of each record and padding before the points
+        let mut bytes = Vec::from(*SIGNATURE);
+        bytes.resize(24, 0);
+        bytes.extend_from_slice(&[1, 2]);
+        bytes.resize(94, 0);
+        bytes.extend_from_slice(&227u16.to_le_bytes());
+        bytes.extend_from_slice(&231u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.push(1);
+        bytes.extend_from_slice(&29u16.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.resize(131, 0);
+        for value in [0.01f64, 0.01, 0.1, 100.0, 200.0, 0.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.resize(231, 0);
+        for (coordinates, classification, gps_time) in
+            [([1, 2, 3], 2u8, 10.0), ([-100, 0, 5], 0b1000_0110, 11.0)]
+        {
+            for coordinate in coordinates {
+                bytes.extend_from_slice(&i32::to_le_bytes(coordinate));
+            }
+            bytes.extend_from_slice(&7u16.to_le_bytes());
+            bytes.extend_from_slice(&[0b0001_0001, classification, (-5i8) as u8, 0]);
+            bytes.extend_from_slice(&3u16.to_le_bytes());
+            bytes.extend_from_slice(&f64::to_le_bytes(gps_time));
+            bytes.push(0xff);
+        }
+
+        let cloud = read_las(bytes.as_slice()).unwrap();
+        assert_eq!(cloud.header.version_minor, 2);
+        assert!((cloud.cloud[0] - Point3::new(100.01, 200.02, 0.3)).norm() < 1e-9);
+        assert!((cloud.cloud[1] - Point3::new(99.0, 200.0, 0.5)).norm() < 1e-9);
+        assert_eq!(cloud.classifications(), Some([2, 6].as_slice()));
+        assert_eq!(
+            cloud
+                .cloud
+                .channel(CLASSIFICATION_FLAGS_CHANNEL)
+                .unwrap()
+                .values,
+            AttributeValues::U8(Vec::from([0, 4]))
+        );
+        assert_eq!(
+            cloud
+                .cloud
+                .channel(NUMBER_OF_RETURNS_CHANNEL)
+                .unwrap()
+                .values,
+            AttributeValues::U8(Vec::from([2, 2]))
+        );
+        assert_eq!(cloud.gps_times(), Some([10.0, 11.0].as_slice()));
+        assert_eq!(cloud.intensities(), Some([7.0, 7.0].as_slice()));
+
+        // The voxel downsampling keeps the custom channels
+        let downsampled = cloud.cloud.downsample_voxel(1000.0);
+        assert_eq!(downsampled.len(), 1);
+        assert_eq!(
+            downsampled.channel(CLASSIFICATION_CHANNEL).unwrap().values,
+            AttributeValues::U8(Vec::from([2]))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            read_las([0u8; 227].as_slice()),
+            Err(LasError::InvalidHeader(_))
+        ));
+
+        let mut bytes = Vec::new();
+        write_las(&mut bytes, &generate_cloud()).unwrap();
+        // Compressed LAZ point formats are rejected
+        bytes[104] |= 0x80;
+        assert!(matches!(
+            read_las(bytes.as_slice()),
+            Err(LasError::UnsupportedPointFormat(131))
+        ));
+        bytes[25] = 5;
+        assert!(matches!(
+            read_las(bytes.as_slice()),
+            Err(LasError::UnsupportedVersion(1, 5))
+        ));
+
+        // A huge record count fails on the missing records, rather than allocating for them
+        let mut bytes = Vec::new();
+        write_las(&mut bytes, &generate_cloud()).unwrap();
+        bytes[100..104].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(read_las(bytes.as_slice()), Err(LasError::Io(_))));
+
+        let mut cloud = generate_cloud();
+        cloud.header.offset = Vector3::zeros();
+        assert!(matches!(
+            write_las(Vec::new(), &cloud),
+            Err(LasError::CoordinateOutOfRange(0))
+        ));
+        cloud.header.version_minor = 1;
+        assert!(matches!(
+            write_las(Vec::new(), &cloud),
+            Err(LasError::UnsupportedVersion(1, 1))
+        ));
+    }
+
+    #[test]
+    fn test_has_gps_time() {
+        let formats: Vec<u8> = (0..=MAX_POINT_FORMAT)
+            .filter(|&format| has_gps_time(format))
+            .collect();
+        assert_eq!(formats, Vec::from([1, 3, 4, 5, 6, 7, 8, 9, 10]));
+    }
+
+    #[test]
+    fn test_has_colour() {
+        let formats: Vec<u8> = (0..=MAX_POINT_FORMAT)
+            .filter(|&format| has_colour(format))
+            .collect();
+        assert_eq!(formats, Vec::from([2, 3, 5, 7, 8, 10]));
+    }
+
+    #[test]
+    fn test_has_nir() {
+        let formats: Vec<u8> = (0..=MAX_POINT_FORMAT)
+            .filter(|&format| has_nir(format))
+            .collect();
+        assert_eq!(formats, Vec::from([8, 10]));
+    }
+
+    #[test]
+    fn test_header_size() {
+        assert_eq!(header_size(0), 227);
+        assert_eq!(header_size(2), 227);
+        assert_eq!(header_size(3), 235);
+        assert_eq!(header_size(4), 375);
+    }
+
+    #[test]
+    fn test_record_lengths() {
+        for point_format in 0..=MAX_POINT_FORMAT {
+            // Coordinates, intensity and the per format attributes, followed by the optional fields
+            let base_length = if point_format < 6 { 20 } else { 30 };
+            let legacy_gps_time = point_format < 6 && has_gps_time(point_format);
+            let waveform = matches!(point_format, 4 | 5 | 9 | 10);
+            let length = base_length
+                + 8 * usize::from(legacy_gps_time)
+                + 6 * usize::from(has_colour(point_format))
+                + 2 * usize::from(has_nir(point_format))
+                + 29 * usize::from(waveform);
+            assert_eq!(
+                RECORD_LENGTHS[point_format as usize], length,
+                "Point format {point_format}"
+            );
+
+            // A record of exactly the format's length holds all of the decoded fields
+            let header = LasHeader {
+                point_format,
+                ..LasHeader::default()
+            };
+            let mut columns = LasColumns::default();
+            columns.decode_record(&vec![0; length], &header);
+            assert_eq!(columns.points.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_byte_cursor() {
+        let mut bytes = Vec::from(*b"LAS\0ab");
+        bytes.push(0xff);
+        bytes.extend_from_slice(&0x1234u16.to_le_bytes());
+        bytes.extend_from_slice(&0x1234_5678u32.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&(-42i32).to_le_bytes());
+        bytes.extend_from_slice(&1.5f64.to_le_bytes());
+
+        let mut cursor = ByteCursor::new(&bytes, 0);
+        // Strings end at the first NUL byte, but the cursor moves past the whole field
+        assert_eq!(cursor.string::<6>(), "LAS");
+        assert_eq!(cursor.u8(), 0xff);
+        assert_eq!(cursor.u16(), 0x1234);
+        assert_eq!(cursor.u32(), 0x1234_5678);
+        assert_eq!(cursor.u64(), u64::MAX);
+        assert_eq!(cursor.i32(), -42);
+        assert_eq!(cursor.f64(), 1.5);
+        assert_eq!(cursor.position, bytes.len());
+
+        let mut cursor = ByteCursor::new(&bytes, 4);
+        assert_eq!(cursor.take::<2>(), *b"ab");
+        assert_eq!(cursor.string::<1>(), "\u{fffd}");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_byte_cursor_past_end() {
+        let bytes = [0u8; 6];
+        let mut cursor = ByteCursor::new(&bytes, 0);
+        cursor.u32();
+        cursor.u32();
+    }
+
+    #[test]
+    fn test_push_string() {
+        let mut output = Vec::from([1]);
+        push_string::<6>("LAS", "name", &mut output).unwrap();
+        assert_eq!(output, b"\x01LAS\0\0\0");
+
+        push_string::<3>("abc", "name", &mut output).unwrap();
+        assert_eq!(output.len(), 10);
+
+        assert!(matches!(
+            push_string::<2>("abc", "name", &mut output),
+            Err(LasError::InvalidHeader(_))
+        ));
+        assert_eq!(output.len(), 10);
+    }
+
+    #[test]
+    fn test_decode_record() {
+        let header = LasHeader {
+            point_format: 3,
+            scale: Vector3::new(0.01, 0.01, 0.01),
+            offset: Vector3::new(10.0, 20.0, 30.0),
+            ..LasHeader::default()
+        };
+        let mut record = Vec::new();
+        for coordinate in [1000i32, -2000, 3000] {
+            record.extend_from_slice(&coordinate.to_le_bytes());
+        }
+        record.extend_from_slice(&500u16.to_le_bytes());
+        record.extend_from_slice(&[2 | 3 << 3, 6 | 0b101 << 5, (-15i8) as u8, 7]);
+        record.extend_from_slice(&42u16.to_le_bytes());
+        record.extend_from_slice(&12.5f64.to_le_bytes());
+        for component in [256u16, 512, 65535] {
+            record.extend_from_slice(&component.to_le_bytes());
+        }
+
+        let mut columns = LasColumns::default();
+        columns.decode_record(&record, &header);
+        assert!((columns.points[0] - Point3::new(20.0, 0.0, 60.0)).norm() < 1e-9);
+        assert_eq!(columns.intensities, [500.0]);
+        assert_eq!(columns.return_numbers, [2]);
+        assert_eq!(columns.numbers_of_returns, [3]);
+        assert_eq!(columns.classifications, [6]);
+        assert_eq!(columns.classification_flags, [0b101]);
+        assert_eq!(columns.scan_angles, [-15.0]);
+        assert_eq!(columns.user_data, [7]);
+        assert_eq!(columns.point_source_ids, [42]);
+        assert_eq!(columns.gps_times, [12.5]);
+        assert_eq!(columns.colours, [[256, 512, 65535]]);
+        assert!(columns.nirs.is_empty());
+
+        // The extended formats use wider return fields, a separate flags byte and a scaled 16 bit scan angle
+        let header = LasHeader {
+            point_format: 8,
+            ..header
+        };
+        let mut record = record[..14].to_vec();
+        record.extend_from_slice(&[2 | 3 << 4, 0b1010, 45, 7]);
+        record.extend_from_slice(&(-2500i16).to_le_bytes());
+        record.extend_from_slice(&42u16.to_le_bytes());
+        record.extend_from_slice(&12.5f64.to_le_bytes());
+        for component in [1u16, 2, 3, 999] {
+            record.extend_from_slice(&component.to_le_bytes());
+        }
+
+        let mut columns = LasColumns::default();
+        columns.decode_record(&record, &header);
+        assert_eq!(columns.return_numbers, [2]);
+        assert_eq!(columns.numbers_of_returns, [3]);
+        assert_eq!(columns.classifications, [45]);
+        assert_eq!(columns.classification_flags, [0b1010]);
+        assert!((columns.scan_angles[0] + 15.0).abs() < 1e-4);
+        assert_eq!(columns.user_data, [7]);
+        assert_eq!(columns.point_source_ids, [42]);
+        assert_eq!(columns.gps_times, [12.5]);
+        assert_eq!(columns.colours, [[1, 2, 3]]);
+        assert_eq!(columns.nirs, [999]);
+    }
+
+    #[test]
+    fn test_into_cloud() {
+        let columns = |colours: [[u16; 3]; 2]| LasColumns {
+            points: Vec::from([Point3::new(1.0, 2.0, 3.0), Point3::new(4.0, 5.0, 6.0)]),
+            intensities: Vec::from([10.0, 20.0]),
+            return_numbers: Vec::from([1, 2]),
+            numbers_of_returns: Vec::from([2, 2]),
+            classifications: Vec::from([2, 6]),
+            classification_flags: Vec::from([0, 1]),
+            scan_angles: Vec::from([-1.0, 1.0]),
+            user_data: Vec::from([0, 7]),
+            point_source_ids: Vec::from([3, 4]),
+            gps_times: Vec::from([0.5, 1.5]),
+            colours: Vec::from(colours),
+            nirs: Vec::from([100, 200]),
+        };
+
+        // 16 bit colours are reduced to their 8 most significant bits
+        let cloud = columns([[256, 512, 65535], [0, 255, 1024]]).into_cloud(8);
+        assert_eq!(cloud.len(), 2);
+        assert_eq!(cloud.intensities(), Some([10.0, 20.0].as_slice()));
+        assert_eq!(cloud.timestamps(), Some([0.5, 1.5].as_slice()));
+        assert_eq!(cloud.colours(), Some([[1, 2, 255], [0, 0, 4]].as_slice()));
+        assert_eq!(
+            cloud.channel(CLASSIFICATION_CHANNEL).unwrap().values,
+            AttributeValues::U8(Vec::from([2, 6]))
+        );
+        assert_eq!(
+            cloud.channel(SCAN_ANGLE_CHANNEL).unwrap().values,
+            AttributeValues::F32(Vec::from([-1.0, 1.0]))
+        );
+        assert_eq!(
+            cloud.channel(POINT_SOURCE_ID_CHANNEL).unwrap().values,
+            AttributeValues::U16(Vec::from([3, 4]))
+        );
+        assert_eq!(
+            cloud.channel(NIR_CHANNEL).unwrap().values,
+            AttributeValues::U16(Vec::from([100, 200]))
+        );
+
+        // Colours that already fit in 8 bits are kept as they are
+        let cloud = columns([[1, 2, 3], [255, 0, 128]]).into_cloud(3);
+        assert_eq!(cloud.colours(), Some([[1, 2, 3], [255, 0, 128]].as_slice()));
+        assert!(cloud.channel(NIR_CHANNEL).is_none());
+
+        // Formats without GPS times or colours leave those channels empty
+        let cloud = columns([[0; 3]; 2]).into_cloud(0);
+        assert!(cloud.timestamps().is_none());
+        assert!(cloud.colours().is_none());
+    }
+}
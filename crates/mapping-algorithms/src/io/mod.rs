@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+pub use kitti::{
+    read_kitti_calibration, read_kitti_poses, read_kitti_scan, KittiCalibration, KittiError,
+    KittiFrame, KittiSequence,
+};
+pub use las::{
+    read_las, write_las, LasError, LasHeader, LasPointCloud, LasVlr, CLASSIFICATION_CHANNEL,
+    CLASSIFICATION_FLAGS_CHANNEL, NIR_CHANNEL, NUMBER_OF_RETURNS_CHANNEL, POINT_SOURCE_ID_CHANNEL,
+    RETURN_NUMBER_CHANNEL, SCAN_ANGLE_CHANNEL, USER_DATA_CHANNEL,
+};
+pub use pcd::{read_pcd, write_pcd, PcdEncoding, PcdError, PcdPointCloud};
+pub use ply::{read_ply, write_ply, PlyError, PlyFormat};
+
+mod kitti;
+mod las;
+mod pcd;
+mod ply;
+mod scalar;
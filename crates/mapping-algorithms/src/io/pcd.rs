@@ -0,0 +1,1568 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::io::{self, BufRead, BufWriter, Read, Write};
+
+use nalgebra::{Isometry3, Point, Quaternion, RealField, SVector, Translation3, UnitQuaternion};
+use num_traits::AsPrimitive;
+
+use crate::point_clouds::{AttributeChannel, AttributeValues, PointCloud};
+
+const COORDINATE_NAMES: [&str; 3] = ["x", "y", "z"];
+const NORMAL_NAMES: [&str; 3] = ["normal_x", "normal_y", "normal_z"];
+const COLOUR_NAMES: [&str; 2] = ["rgb", "rgba"];
+const INTENSITY_NAME: &str = "intensity";
+const RING_NAME: &str = "ring";
+const TIMESTAMP_NAMES: [&str; 2] = ["timestamp", "time"];
+const PADDING_NAME: &str = "_";
+
+// LZF back references can reach at most 8192 bytes behind, and copy at most 264 bytes
+const LZF_MAX_OFFSET: usize = 1 << 13;
+const LZF_MAX_MATCH: usize = 264;
+const LZF_MAX_LITERAL: usize = 32;
+const LZF_HASH_LOG: usize = 14;
+
+/// The encoding of a PCD file's body.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PcdEncoding {
+    /// Human readable text, one point per line.
+    Ascii,
+    /// Packed little endian values, stored point after point.
+    Binary,
+    /// Packed little endian values, stored field after field and compressed using LZF.
+    BinaryCompressed,
+}
+
+/// An error type containing the various errors that might arise when reading or writing a PCD file.
+#[derive(Debug, thiserror::Error)]
+pub enum PcdError {
+    /// The underlying reader or writer failed.
+    #[error("Failed reading or writing PCD data: {0}")]
+    Io(#[from] io::Error),
+    /// The header is malformed or uses an unsupported feature.
+    #[error("Invalid PCD header: {0}")]
+    InvalidHeader(String),
+    /// A value in the body could not be parsed.
+    #[error("Invalid PCD value: {0}")]
+    InvalidValue(String),
+    /// The fields do not contain one of the point's coordinates.
+    #[error("The PCD file does not contain the {0} field")]
+    MissingCoordinate(&'static str),
+    /// The requested point dimensions cannot be stored in a PCD file, which has at most 3 coordinates.
+    #[error("Points with {0} dimensions are not supported, PCD points have at most 3 coordinates")]
+    UnsupportedDimensions(usize),
+    /// The LZF compressed body of a `binary_compressed` file could not be decompressed.
+    #[error("Invalid compressed PCD data: {0}")]
+    InvalidCompressedData(String),
+    /// The width and height of the cloud do not match its amount of points.
+    #[error("A cloud of {width}x{height} cannot contain {points} points")]
+    OrganizationMismatch {
+        /// The width of the cloud, the amount of points in each row.
+        width: usize,
+        /// The height of the cloud, the amount of rows.
+        height: usize,
+        /// The actual amount of points.
+        points: usize,
+    },
+}
+
+/// A point cloud along with the metadata stored in a PCD file.
+#[derive(Clone, Debug)]
+pub struct PcdPointCloud<T: nalgebra::Scalar, const N: usize> {
+    /// The points and their attribute channels, in row-major order for organised clouds.
+    pub cloud: PointCloud<T, N>,
+    /// The amount of points in each row, equal to the amount of points for unorganised clouds.
+    pub width: usize,
+    /// The amount of rows, `1` for unorganised clouds.
+    pub height: usize,
+    /// The pose of the sensor that acquired the cloud.
+    pub viewpoint: Isometry3<T>,
+}
+
+impl<T: Copy + RealField, const N: usize> PcdPointCloud<T, N> {
+    /// Returns whether the cloud is organised, meaning its points form an image-like grid with multiple rows.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Is PCD Cloud Organised", skip_all, level = "trace")
+    )]
+    pub fn is_organised(&self) -> bool {
+        self.height > 1
+    }
+
+    /// Returns the index of the point in a specific row and column of an organised cloud.
+    ///
+    /// # Arguments
+    /// * `row`: the index of the row, less than `height`.
+    /// * `column`: the index of the column, less than `width`.
+    ///
+    /// # Returns
+    /// [`Some`] containing the index of the point in `cloud`, or [`None`] if the row or column are out of bounds.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Get PCD Point Index", skip_all, level = "trace")
+    )]
+    pub fn point_index(&self, row: usize, column: usize) -> Option<usize> {
+        (row < self.height && column < self.width).then_some(row * self.width + column)
+    }
+}
+
+impl<T: Copy + RealField, const N: usize> From<PointCloud<T, N>> for PcdPointCloud<T, N> {
+    fn from(cloud: PointCloud<T, N>) -> Self {
+        Self {
+            width: cloud.len(),
+            height: 1,
+            viewpoint: Isometry3::identity(),
+            cloud,
+        }
+    }
+}
+
+// Binds the inner vector of any variant along with its element type, for code that is identical for all types
+macro_rules! with_typed_values {
+    ($values:expr, $inner:ident: $ty:ident => $body:expr) => {
+        match $values {
+            AttributeValues::I8($inner) => {
+                type $ty = i8;
+                $body
+            }
+            AttributeValues::U8($inner) => {
+                type $ty = u8;
+                $body
+            }
+            AttributeValues::I16($inner) => {
+                type $ty = i16;
+                $body
+            }
+            AttributeValues::U16($inner) => {
+                type $ty = u16;
+                $body
+            }
+            AttributeValues::I32($inner) => {
+                type $ty = i32;
+                $body
+            }
+            AttributeValues::U32($inner) => {
+                type $ty = u32;
+                $body
+            }
+            AttributeValues::I64($inner) => {
+                type $ty = i64;
+                $body
+            }
+            AttributeValues::U64($inner) => {
+                type $ty = u64;
+                $body
+            }
+            AttributeValues::F32($inner) => {
+                type $ty = f32;
+                $body
+            }
+            AttributeValues::F64($inner) => {
+                type $ty = f64;
+                $body
+            }
+        }
+    };
+}
+
+// The values are not preallocated, as the amount of points declared in the header is not trusted
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Create Empty PCD Field Values", skip_all, level = "trace")
+)]
+fn empty_values(type_name: &str, size: usize) -> Result<AttributeValues, PcdError> {
+    Ok(match (type_name, size) {
+        ("I", 1) => AttributeValues::I8(Vec::new()),
+        ("U", 1) => AttributeValues::U8(Vec::new()),
+        ("I", 2) => AttributeValues::I16(Vec::new()),
+        ("U", 2) => AttributeValues::U16(Vec::new()),
+        ("I", 4) => AttributeValues::I32(Vec::new()),
+        ("U", 4) => AttributeValues::U32(Vec::new()),
+        ("I", 8) => AttributeValues::I64(Vec::new()),
+        ("U", 8) => AttributeValues::U64(Vec::new()),
+        ("F", 4) => AttributeValues::F32(Vec::new()),
+        ("F", 8) => AttributeValues::F64(Vec::new()),
+        _ => {
+            return Err(PcdError::InvalidHeader(format!(
+                "Unsupported field type {type_name} of size {size}"
+            )))
+        }
+    })
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Get PCD Field Type And Size", skip_all, level = "trace")
+)]
+fn type_name_and_size(values: &AttributeValues) -> (&'static str, usize) {
+    match values {
+        AttributeValues::I8(_) => ("I", 1),
+        AttributeValues::U8(_) => ("U", 1),
+        AttributeValues::I16(_) => ("I", 2),
+        AttributeValues::U16(_) => ("U", 2),
+        AttributeValues::I32(_) => ("I", 4),
+        AttributeValues::U32(_) => ("U", 4),
+        AttributeValues::I64(_) => ("I", 8),
+        AttributeValues::U64(_) => ("U", 8),
+        AttributeValues::F32(_) => ("F", 4),
+        AttributeValues::F64(_) => ("F", 8),
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Collect PCD Float Values", skip_all, level = "trace")
+)]
+fn float_values(values: impl Iterator<Item = f64>, double_precision: bool) -> AttributeValues {
+    if double_precision {
+        AttributeValues::F64(values.collect())
+    } else {
+        AttributeValues::F32(values.map(|value| value as f32).collect())
+    }
+}
+
+// Pushes a single value, the length of `bytes` must match the size of the values' type
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Push PCD Little Endian Value", skip_all, level = "trace")
+)]
+fn push_le_bytes(values: &mut AttributeValues, bytes: &[u8]) {
+    with_typed_values!(values, inner: Ty => inner.push(Ty::from_le_bytes(
+        bytes.try_into().expect("Slice length matches the type's size")
+    )))
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Push PCD Text Value", skip_all, level = "trace")
+)]
+fn push_token(values: &mut AttributeValues, token: &str) -> Result<(), PcdError> {
+    let invalid_value = || PcdError::InvalidValue(format!("Could not parse {token}"));
+    with_typed_values!(values, inner: Ty => inner.push(token.parse::<Ty>().map_err(|_| invalid_value())?));
+    Ok(())
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Extend PCD Little Endian Bytes", skip_all, level = "trace")
+)]
+fn extend_le_bytes(values: &AttributeValues, index: usize, output: &mut Vec<u8>) {
+    with_typed_values!(values, inner: Ty => output.extend_from_slice(&Ty::to_le_bytes(inner[index])))
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Format PCD Text Value", skip_all, level = "trace")
+)]
+fn format_value(values: &AttributeValues, index: usize) -> String {
+    let formatted = with_typed_values!(values, inner: Ty => Ty::to_string(&inner[index]));
+    // PCL writes non-finite values in lower case
+    match formatted.as_str() {
+        "NaN" => "nan".to_owned(),
+        _ => formatted,
+    }
+}
+
+/// Decompresses LZF data, as used by the `binary_compressed` encoding.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("LZF Decompress", skip_all, level = "debug")
+)]
+fn lzf_decompress(input: &[u8], output_len: usize) -> Result<Vec<u8>, PcdError> {
+    let truncated = || PcdError::InvalidCompressedData("The data ended unexpectedly".to_owned());
+    // Every two input bytes expand to at most a single match, so the declared length is only trusted up to that bound
+    let mut output = Vec::with_capacity(output_len.min(input.len().saturating_mul(LZF_MAX_MATCH)));
+    let mut input_idx = 0;
+    while input_idx < input.len() {
+        let control = input[input_idx] as usize;
+        input_idx += 1;
+
+        if control < LZF_MAX_LITERAL {
+            let literal = input
+                .get(input_idx..input_idx + control + 1)
+                .ok_or_else(truncated)?;
+            output.extend_from_slice(literal);
+            input_idx += literal.len();
+        } else {
+            let mut match_len = control >> 5;
+            if match_len == 7 {
+                match_len += *input.get(input_idx).ok_or_else(truncated)? as usize;
+                input_idx += 1;
+            }
+            let offset =
+                ((control & 0x1f) << 8) + *input.get(input_idx).ok_or_else(truncated)? as usize + 1;
+            input_idx += 1;
+
+            let start = output.len().checked_sub(offset).ok_or_else(|| {
+                PcdError::InvalidCompressedData(
+                    "A back reference points before the start of the data".to_owned(),
+                )
+            })?;
+            // The referenced range may overlap the bytes being written, so they must be copied one by one
+            for copy_idx in 0..match_len + 2 {
+                output.push(output[start + copy_idx]);
+            }
+        }
+
+        if output.len() > output_len {
+            return Err(PcdError::InvalidCompressedData(format!(
+                "The data decompresses to more than the declared {output_len} bytes"
+            )));
+        }
+    }
+
+    if output.len() != output_len {
+        return Err(PcdError::InvalidCompressedData(format!(
+            "The data decompresses to {} bytes instead of {output_len}",
+            output.len()
+        )));
+    }
+    Ok(output)
+}
+
+/// Compresses data using LZF, as used by the `binary_compressed` encoding.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("LZF Compress", skip_all, level = "debug")
+)]
+fn lzf_compress(input: &[u8]) -> Vec<u8> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Flush LZF Literals", skip_all, level = "trace")
+    )]
+    fn flush_literals(literals: &[u8], output: &mut Vec<u8>) {
+        for chunk in literals.chunks(LZF_MAX_LITERAL) {
+            output.push((chunk.len() - 1) as u8);
+            output.extend_from_slice(chunk);
+        }
+    }
+
+    let mut hash_table = vec![None; 1 << LZF_HASH_LOG];
+    let mut output = Vec::with_capacity(input.len() / 2);
+    let mut literal_start = 0;
+    let mut input_idx = 0;
+    while input_idx + 2 < input.len() {
+        let sequence = &input[input_idx..input_idx + 3];
+        let hash =
+            ((sequence[0] as usize) << 16 | (sequence[1] as usize) << 8 | sequence[2] as usize)
+                .wrapping_mul(2654435761)
+                >> 8
+                & ((1 << LZF_HASH_LOG) - 1);
+        let candidate = hash_table[hash].replace(input_idx);
+
+        let Some(candidate_idx) = candidate.filter(|&candidate_idx| {
+            input_idx - candidate_idx <= LZF_MAX_OFFSET
+                && input[candidate_idx..candidate_idx + 3] == *sequence
+        }) else {
+            input_idx += 1;
+            continue;
+        };
+
+        let max_len = LZF_MAX_MATCH.min(input.len() - input_idx);
+        let mut match_len = 3;
+        while match_len < max_len
+            && input[candidate_idx + match_len] == input[input_idx + match_len]
+        {
+            match_len += 1;
+        }
+
+        flush_literals(&input[literal_start..input_idx], &mut output);
+        let offset = input_idx - candidate_idx - 1;
+        let encoded_len = match_len - 2;
+        if encoded_len < 7 {
+            output.push((encoded_len << 5 | offset >> 8) as u8);
+        } else {
+            output.push((7 << 5 | offset >> 8) as u8);
+            output.push((encoded_len - 7) as u8);
+        }
+        output.push(offset as u8);
+
+        input_idx += match_len;
+        literal_start = input_idx;
+    }
+    flush_literals(&input[literal_start..], &mut output);
+
+    output
+}
+
+struct PcdHeader {
+    fields: Vec<AttributeChannel>,
+    width: usize,
+    height: usize,
+    viewpoint: [f64; 7],
+    points: usize,
+    // The size of a single point's values, in bytes
+    point_size: usize,
+    encoding: PcdEncoding,
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Parse PCD Header Values", skip_all, level = "trace")
+)]
+fn parse_values<V: core::str::FromStr>(key: &str, tokens: &[&str]) -> Result<Vec<V>, PcdError> {
+    tokens
+        .iter()
+        .map(|token| {
+            token
+                .parse()
+                .map_err(|_| PcdError::InvalidHeader(format!("Invalid {key} value {token}")))
+        })
+        .collect()
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Parse PCD Header Value", skip_all, level = "trace")
+)]
+fn parse_single<V: core::str::FromStr>(key: &str, tokens: &[&str]) -> Result<V, PcdError> {
+    match tokens {
+        [token] => parse_values(key, &[token]).map(|mut values| values.remove(0)),
+        _ => Err(PcdError::InvalidHeader(format!(
+            "{key} must contain a single value"
+        ))),
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PCD Header", skip_all, level = "debug")
+)]
+fn read_header<R: BufRead>(reader: &mut R) -> Result<PcdHeader, PcdError> {
+    let mut names = None;
+    let mut sizes = None;
+    let mut type_names = None;
+    let mut counts = None;
+    let mut width: Option<usize> = None;
+    let mut height = 1;
+    let mut viewpoint = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+    let mut points = None;
+
+    let mut line = String::new();
+    let encoding = loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(PcdError::InvalidHeader(
+                "The header ended before the DATA line".to_owned(),
+            ));
+        }
+
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let Some((key, values)) = tokens.split_first() else {
+            continue;
+        };
+        match *key {
+            _ if key.starts_with('#') => {}
+            "VERSION" => {}
+            "FIELDS" | "COLUMNS" => {
+                names = Some(
+                    values
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect::<Vec<_>>(),
+                )
+            }
+            "SIZE" => sizes = Some(parse_values::<usize>(key, values)?),
+            "TYPE" => {
+                type_names = Some(
+                    values
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect::<Vec<_>>(),
+                )
+            }
+            "COUNT" => counts = Some(parse_values::<usize>(key, values)?),
+            "WIDTH" => width = Some(parse_single(key, values)?),
+            "HEIGHT" => height = parse_single(key, values)?,
+            "POINTS" => points = Some(parse_single(key, values)?),
+            "VIEWPOINT" => {
+                viewpoint = parse_values::<f64>(key, values)?.try_into().map_err(|_| {
+                    PcdError::InvalidHeader("VIEWPOINT must contain 7 values".to_owned())
+                })?
+            }
+            "DATA" => {
+                break match values {
+                    ["ascii"] => PcdEncoding::Ascii,
+                    ["binary"] => PcdEncoding::Binary,
+                    ["binary_compressed"] => PcdEncoding::BinaryCompressed,
+                    _ => {
+                        return Err(PcdError::InvalidHeader(format!(
+                            "Unknown data encoding {}",
+                            values.join(" ")
+                        )))
+                    }
+                }
+            }
+            _ => {
+                return Err(PcdError::InvalidHeader(format!(
+                    "Unexpected header line {}",
+                    line.trim_end()
+                )))
+            }
+        }
+    };
+
+    let names = names.ok_or_else(|| PcdError::InvalidHeader("Missing FIELDS line".to_owned()))?;
+    let sizes = sizes.ok_or_else(|| PcdError::InvalidHeader("Missing SIZE line".to_owned()))?;
+    let type_names =
+        type_names.ok_or_else(|| PcdError::InvalidHeader("Missing TYPE line".to_owned()))?;
+    let counts = counts.unwrap_or_else(|| vec![1; names.len()]);
+    if sizes.len() != names.len() || type_names.len() != names.len() || counts.len() != names.len()
+    {
+        return Err(PcdError::InvalidHeader(
+            "FIELDS, SIZE, TYPE and COUNT must have the same amount of values".to_owned(),
+        ));
+    }
+
+    let width = width.ok_or_else(|| PcdError::InvalidHeader("Missing WIDTH line".to_owned()))?;
+    let organized_points = width.checked_mul(height).ok_or_else(|| {
+        PcdError::InvalidHeader(format!("A cloud of {width}x{height} is too large"))
+    })?;
+    let points = points.unwrap_or(organized_points);
+    if organized_points != points {
+        return Err(PcdError::OrganizationMismatch {
+            width,
+            height,
+            points,
+        });
+    }
+
+    let too_large = || PcdError::InvalidHeader("The declared points are too large".to_owned());
+    let point_size = sizes
+        .iter()
+        .zip(counts.iter())
+        .try_fold(0usize, |acc, (size, count)| {
+            size.checked_mul(*count)
+                .and_then(|field_size| acc.checked_add(field_size))
+        })
+        .ok_or_else(too_large)?;
+    points.checked_mul(point_size).ok_or_else(too_large)?;
+
+    let fields = names
+        .into_iter()
+        .zip(sizes)
+        .zip(type_names)
+        .zip(counts)
+        .map(|(((name, size), type_name), count)| {
+            Ok(AttributeChannel {
+                name,
+                components: count,
+                values: empty_values(&type_name, size)?,
+            })
+        })
+        .collect::<Result<Vec<_>, PcdError>>()?;
+
+    Ok(PcdHeader {
+        fields,
+        width,
+        height,
+        viewpoint,
+        points,
+        point_size,
+        encoding,
+    })
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PCD Body", skip_all, level = "debug")
+)]
+fn read_body<R: BufRead>(reader: &mut R, header: &mut PcdHeader) -> Result<(), PcdError> {
+    let field_sizes = header
+        .fields
+        .iter()
+        .map(|field| type_name_and_size(&field.values).1)
+        .collect::<Vec<_>>();
+    // Verified not to overflow when reading the header
+    let data_size = header.points * header.point_size;
+
+    match header.encoding {
+        PcdEncoding::Ascii => {
+            let mut line = String::new();
+            let mut points_read = 0;
+            while points_read < header.points {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Err(PcdError::InvalidValue(format!(
+                        "The file ended after {points_read} of {} points",
+                        header.points
+                    )));
+                }
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let mut tokens = line.split_whitespace();
+                for field in header.fields.iter_mut() {
+                    for _ in 0..field.components {
+                        let token = tokens.next().ok_or_else(|| {
+                            PcdError::InvalidValue(format!(
+                                "Point {points_read} has too few values"
+                            ))
+                        })?;
+                        // PCL writes packed colours stored as floats as their integer bit pattern
+                        match (&mut field.values, token.parse::<u32>()) {
+                            (AttributeValues::F32(values), Ok(bits))
+                                if COLOUR_NAMES.contains(&field.name.as_str()) =>
+                            {
+                                values.push(f32::from_bits(bits))
+                            }
+                            (values, _) => push_token(values, token)?,
+                        }
+                    }
+                }
+                points_read += 1;
+            }
+        }
+        PcdEncoding::Binary => {
+            // Values are read one by one, so a truncated file fails before allocating for all declared points
+            let mut buffer = [0; 8];
+            for _ in 0..header.points {
+                for (field, &size) in header.fields.iter_mut().zip(field_sizes.iter()) {
+                    for _ in 0..field.components {
+                        reader.read_exact(&mut buffer[..size])?;
+                        push_le_bytes(&mut field.values, &buffer[..size]);
+                    }
+                }
+            }
+        }
+        PcdEncoding::BinaryCompressed => {
+            let mut sizes = [0; 8];
+            reader.read_exact(&mut sizes)?;
+            let compressed_size = u32::from_le_bytes(sizes[..4].try_into().expect("4 bytes"));
+            let uncompressed_size = u32::from_le_bytes(sizes[4..].try_into().expect("4 bytes"));
+            if uncompressed_size as usize != data_size {
+                return Err(PcdError::InvalidCompressedData(format!(
+                    "The declared size of {uncompressed_size} bytes does not match {} points of {} bytes",
+                    header.points, header.point_size
+                )));
+            }
+
+            // Read up to the declared size, rather than allocating it upfront
+            let mut compressed = Vec::new();
+            Read::take(&mut *reader, compressed_size as u64).read_to_end(&mut compressed)?;
+            if compressed.len() != compressed_size as usize {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            let data = lzf_decompress(&compressed, data_size)?;
+
+            // Compressed data is stored field after field, rather than point after point
+            let mut offset = 0;
+            for (field, size) in header.fields.iter_mut().zip(field_sizes.iter()) {
+                for _ in 0..header.points * field.components {
+                    push_le_bytes(&mut field.values, &data[offset..offset + size]);
+                    offset += size;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Removes a field with a specific name and amount of components from the fields, if present
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Take PCD Field", skip_all, level = "trace")
+)]
+fn take_field(
+    fields: &mut Vec<AttributeChannel>,
+    name: &str,
+    components: usize,
+) -> Option<AttributeChannel> {
+    let field_idx = fields
+        .iter()
+        .position(|field| field.name == name && field.components == components)?;
+    Some(fields.remove(field_idx))
+}
+
+/// Reads a point cloud from a PCD file, in any of the [`PcdEncoding`] encodings.
+///
+/// The points are read from the `x`, `y` and `z` fields, `normal_x`, `normal_y` and `normal_z` are read as normals,
+/// the packed `rgb` or `rgba` field as colours, `intensity` as intensities, `ring` as rings and `timestamp` or `time` as timestamps.
+/// All other fields are kept in their original type as custom [`AttributeChannel`]s, except for `_` padding fields.
+/// Points are kept as they are, including NaN points, which mark invalid pixels in organised clouds.
+///
+/// # Arguments
+/// * `reader`: a [`BufRead`], positioned at the beginning of the PCD file.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points, at most 3.
+///
+/// # Returns
+/// A [`PcdPointCloud`] containing the cloud with the channels found in the file, along with its organisation and viewpoint, or a [`PcdError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PCD Point Cloud", skip_all, level = "info")
+)]
+pub fn read_pcd<T, const N: usize, R>(mut reader: R) -> Result<PcdPointCloud<T, N>, PcdError>
+where
+    T: Copy + RealField,
+    f64: AsPrimitive<T>,
+    R: BufRead,
+{
+    if N > COORDINATE_NAMES.len() {
+        return Err(PcdError::UnsupportedDimensions(N));
+    }
+
+    let mut header = read_header(&mut reader)?;
+    read_body(&mut reader, &mut header)?;
+    let mut fields = header.fields;
+    let get_value = |field: &AttributeChannel, index: usize| {
+        field
+            .values
+            .get_f64(index)
+            .expect("Every field contains a value for each point")
+    };
+
+    let mut coordinates = Vec::with_capacity(N);
+    for name in COORDINATE_NAMES[..N].iter() {
+        coordinates
+            .push(take_field(&mut fields, name, 1).ok_or(PcdError::MissingCoordinate(name))?);
+    }
+    let points = (0..header.points)
+        .map(|point_idx| {
+            Point::from(SVector::<T, N>::from_fn(|idx, _| {
+                get_value(&coordinates[idx], point_idx).as_()
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    // All channels are read alongside the points, so their lengths always match
+    let mut cloud = PointCloud::new(points);
+    if NORMAL_NAMES[..N].iter().all(|name| {
+        fields
+            .iter()
+            .any(|field| field.name == *name && field.components == 1)
+    }) {
+        let normal_fields = NORMAL_NAMES[..N]
+            .iter()
+            .filter_map(|name| take_field(&mut fields, name, 1))
+            .collect::<Vec<_>>();
+        let normals = (0..header.points)
+            .map(|point_idx| {
+                SVector::<T, N>::from_fn(|idx, _| get_value(&normal_fields[idx], point_idx).as_())
+            })
+            .collect();
+        cloud = cloud.with_normals(normals).expect("Channel length matches");
+    }
+    if let Some(colour_field) = COLOUR_NAMES
+        .iter()
+        .find_map(|name| take_field(&mut fields, name, 1))
+    {
+        let packed_colours = match colour_field.values {
+            AttributeValues::F32(values) => values.into_iter().map(f32::to_bits).collect(),
+            values => (0..header.points)
+                .map(|point_idx| values.get_f64(point_idx).unwrap_or_default() as u32)
+                .collect::<Vec<_>>(),
+        };
+        let colours = packed_colours
+            .into_iter()
+            .map(|packed| [16, 8, 0].map(|shift| (packed >> shift) as u8))
+            .collect();
+        cloud = cloud.with_colours(colours).expect("Channel length matches");
+    }
+    if let Some(intensity_field) = take_field(&mut fields, INTENSITY_NAME, 1) {
+        let intensities = (0..header.points)
+            .map(|point_idx| get_value(&intensity_field, point_idx).as_())
+            .collect();
+        cloud = cloud
+            .with_intensities(intensities)
+            .expect("Channel length matches");
+    }
+    if let Some(ring_field) = take_field(&mut fields, RING_NAME, 1) {
+        let rings = (0..header.points)
+            .map(|point_idx| get_value(&ring_field, point_idx) as u16)
+            .collect();
+        cloud = cloud.with_rings(rings).expect("Channel length matches");
+    }
+    if let Some(timestamp_field) = TIMESTAMP_NAMES
+        .iter()
+        .find_map(|name| take_field(&mut fields, name, 1))
+    {
+        let timestamps = (0..header.points)
+            .map(|point_idx| get_value(&timestamp_field, point_idx).as_())
+            .collect();
+        cloud = cloud
+            .with_timestamps(timestamps)
+            .expect("Channel length matches");
+    }
+    for field in fields
+        .into_iter()
+        .filter(|field| field.name != PADDING_NAME)
+    {
+        cloud = cloud.with_channel(field).expect("Channel length matches");
+    }
+
+    let [x, y, z, qw, qx, qy, qz] = header.viewpoint.map(|value| value.as_());
+    Ok(PcdPointCloud {
+        cloud,
+        width: header.width,
+        height: header.height,
+        viewpoint: Isometry3::from_parts(
+            Translation3::new(x, y, z),
+            UnitQuaternion::from_quaternion(Quaternion::new(qw, qx, qy, qz)),
+        ),
+    })
+}
+
+/// Writes a point cloud as a PCD file, including all of its attribute channels.
+///
+/// Coordinates, normals, intensities and timestamps are written as `F` fields of the same size as `T`,
+/// colours are packed into a single `rgb` field of type `U 4`, rings are written as `U 2`,
+/// and custom channels keep their type and amount of components.
+/// Custom channels sharing a name with one of these fields are skipped.
+///
+/// # Arguments
+/// * `writer`: a [`Write`], to which the PCD file is written.
+/// * `cloud`: a [`PcdPointCloud`], the point cloud to write, along with its organisation and viewpoint.
+/// * `encoding`: a [`PcdEncoding`], the encoding of the file's body.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points, at most 3.
+///
+/// # Returns
+/// Nothing on success, or a [`PcdError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Write PCD Point Cloud", skip_all, level = "info")
+)]
+pub fn write_pcd<T, const N: usize, W>(
+    writer: W,
+    cloud: &PcdPointCloud<T, N>,
+    encoding: PcdEncoding,
+) -> Result<(), PcdError>
+where
+    T: AsPrimitive<f64> + Copy + RealField,
+    W: Write,
+{
+    if N > COORDINATE_NAMES.len() {
+        return Err(PcdError::UnsupportedDimensions(N));
+    }
+    let points = &cloud.cloud;
+    if cloud.width * cloud.height != points.len() {
+        return Err(PcdError::OrganizationMismatch {
+            width: cloud.width,
+            height: cloud.height,
+            points: points.len(),
+        });
+    }
+
+    let double_precision = core::mem::size_of::<T>() == 8;
+    let single_field = |name: &str, values: AttributeValues| AttributeChannel {
+        name: name.to_owned(),
+        components: 1,
+        values,
+    };
+    let mut fields = COORDINATE_NAMES[..N]
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            single_field(
+                name,
+                float_values(
+                    points.iter().map(|point| point[idx].as_()),
+                    double_precision,
+                ),
+            )
+        })
+        .collect::<Vec<_>>();
+    if let Some(normals) = points.normals() {
+        fields.extend(NORMAL_NAMES[..N].iter().enumerate().map(|(idx, name)| {
+            single_field(
+                name,
+                float_values(
+                    normals.iter().map(|normal| normal[idx].as_()),
+                    double_precision,
+                ),
+            )
+        }));
+    }
+    if let Some(colours) = points.colours() {
+        fields.push(single_field(
+            COLOUR_NAMES[0],
+            AttributeValues::U32(
+                colours
+                    .iter()
+                    .map(|&[red, green, blue]| u32::from_be_bytes([0, red, green, blue]))
+                    .collect(),
+            ),
+        ));
+    }
+    if let Some(intensities) = points.intensities() {
+        fields.push(single_field(
+            INTENSITY_NAME,
+            float_values(
+                intensities.iter().map(|intensity| intensity.as_()),
+                double_precision,
+            ),
+        ));
+    }
+    if let Some(rings) = points.rings() {
+        fields.push(single_field(
+            RING_NAME,
+            AttributeValues::U16(rings.to_vec()),
+        ));
+    }
+    if let Some(timestamps) = points.timestamps() {
+        fields.push(single_field(
+            TIMESTAMP_NAMES[0],
+            float_values(
+                timestamps.iter().map(|timestamp| timestamp.as_()),
+                double_precision,
+            ),
+        ));
+    }
+    for channel in points.channels() {
+        if fields.iter().all(|field| field.name != channel.name) {
+            fields.push(channel.clone());
+        }
+    }
+
+    let mut writer = BufWriter::new(writer);
+    writeln!(
+        writer,
+        "# .PCD v0.7 - Point Cloud Data file format\nVERSION 0.7"
+    )?;
+    let header_line = |key: &str, value: &dyn Fn(&AttributeChannel) -> String| {
+        let values = fields.iter().map(value).collect::<Vec<_>>();
+        format!("{key} {}", values.join(" "))
+    };
+    writeln!(
+        writer,
+        "{}",
+        header_line("FIELDS", &|field| field.name.clone())
+    )?;
+    writeln!(
+        writer,
+        "{}",
+        header_line("SIZE", &|field| type_name_and_size(&field.values)
+            .1
+            .to_string())
+    )?;
+    writeln!(
+        writer,
+        "{}",
+        header_line("TYPE", &|field| type_name_and_size(&field.values)
+            .0
+            .to_owned())
+    )?;
+    writeln!(
+        writer,
+        "{}",
+        header_line("COUNT", &|field| field.components.to_string())
+    )?;
+    let translation = cloud.viewpoint.translation.vector;
+    let rotation = cloud.viewpoint.rotation.quaternion();
+    writeln!(
+        writer,
+        "WIDTH {}\nHEIGHT {}\nVIEWPOINT {} {} {} {} {} {} {}\nPOINTS {}",
+        cloud.width,
+        cloud.height,
+        translation.x,
+        translation.y,
+        translation.z,
+        rotation.w,
+        rotation.i,
+        rotation.j,
+        rotation.k,
+        points.len()
+    )?;
+
+    match encoding {
+        PcdEncoding::Ascii => {
+            writeln!(writer, "DATA ascii")?;
+            let mut tokens = Vec::new();
+            for point_idx in 0..points.len() {
+                tokens.clear();
+                for field in fields.iter() {
+                    for component_idx in 0..field.components {
+                        tokens.push(format_value(
+                            &field.values,
+                            point_idx * field.components + component_idx,
+                        ));
+                    }
+                }
+                writeln!(writer, "{}", tokens.join(" "))?;
+            }
+        }
+        PcdEncoding::Binary => {
+            writeln!(writer, "DATA binary")?;
+            let mut encoded = Vec::new();
+            for point_idx in 0..points.len() {
+                encoded.clear();
+                for field in fields.iter() {
+                    for component_idx in 0..field.components {
+                        extend_le_bytes(
+                            &field.values,
+                            point_idx * field.components + component_idx,
+                            &mut encoded,
+                        );
+                    }
+                }
+                writer.write_all(&encoded)?;
+            }
+        }
+        PcdEncoding::BinaryCompressed => {
+            writeln!(writer, "DATA binary_compressed")?;
+            // Compressed data is stored field after field, which is also the layout of each field's values
+            let mut data = Vec::new();
+            for field in fields.iter() {
+                for value_idx in 0..field.values.len() {
+                    extend_le_bytes(&field.values, value_idx, &mut data);
+                }
+            }
+            let compressed = lzf_compress(&data);
+            writer.write_all(&(compressed.len() as u32).to_le_bytes())?;
+            writer.write_all(&(data.len() as u32).to_le_bytes())?;
+            writer.write_all(&compressed)?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector3};
+
+    use super::*;
+
+    fn generate_cloud() -> PointCloud<f32, 3> {
+        PointCloud::new(Vec::from([
+            Point3::new(0.1, -2.5, 3.0),
+            Point3::new(1e-3, 4.25, -1e6),
+            Point3::new(f32::NAN, f32::NAN, f32::NAN),
+            Point3::new(7.0, 8.0, 9.0),
+        ]))
+        .with_normals(Vec::from([
+            Vector3::x(),
+            Vector3::new(0.0, 0.6, 0.8),
+            Vector3::z(),
+            Vector3::y(),
+        ]))
+        .unwrap()
+        .with_colours(Vec::from([
+            [1, 2, 3],
+            [250, 128, 0],
+            [0, 0, 0],
+            [255, 255, 255],
+        ]))
+        .unwrap()
+        .with_intensities(Vec::from([0.5, 100.0, 0.0, 3.0]))
+        .unwrap()
+        .with_rings(Vec::from([0, 1, 2, 31]))
+        .unwrap()
+        .with_timestamps(Vec::from([0.0, 0.025, 0.05, 0.1]))
+        .unwrap()
+        .with_channel(AttributeChannel {
+            name: "label".to_owned(),
+            components: 1,
+            values: AttributeValues::U64(Vec::from([u64::MAX, 0, 1, 2])),
+        })
+        .unwrap()
+        .with_channel(AttributeChannel {
+            name: "fpfh".to_owned(),
+            components: 2,
+            values: AttributeValues::I16(Vec::from([-1, 1, -2, 2, -3, 3, -4, 4])),
+        })
+        .unwrap()
+    }
+
+    // NaN coordinates never compare equal, so points are compared through their bit patterns
+    fn assert_clouds_eq(first: &PointCloud<f32, 3>, second: &PointCloud<f32, 3>) {
+        let to_bits = |cloud: &PointCloud<f32, 3>| {
+            cloud
+                .iter()
+                .map(|point| point.coords.map(f32::to_bits))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(to_bits(first), to_bits(second));
+        assert_eq!(first.normals(), second.normals());
+        assert_eq!(first.colours(), second.colours());
+        assert_eq!(first.intensities(), second.intensities());
+        assert_eq!(first.rings(), second.rings());
+        assert_eq!(first.timestamps(), second.timestamps());
+        assert_eq!(first.channels(), second.channels());
+    }
+
+    #[test]
+    fn test_empty_values() {
+        for (type_name, size) in [
+            ("I", 1),
+            ("U", 1),
+            ("I", 2),
+            ("U", 2),
+            ("I", 4),
+            ("U", 4),
+            ("I", 8),
+            ("U", 8),
+            ("F", 4),
+            ("F", 8),
+        ] {
+            let values = empty_values(type_name, size).unwrap();
+            assert!(values.is_empty());
+            assert_eq!(type_name_and_size(&values), (type_name, size));
+        }
+
+        for (type_name, size) in [("F", 2), ("U", 3), ("X", 4)] {
+            assert!(matches!(
+                empty_values(type_name, size),
+                Err(PcdError::InvalidHeader(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_type_name_and_size() {
+        assert_eq!(
+            type_name_and_size(&AttributeValues::I8(Vec::new())),
+            ("I", 1)
+        );
+        assert_eq!(
+            type_name_and_size(&AttributeValues::U16(Vec::from([1]))),
+            ("U", 2)
+        );
+        assert_eq!(
+            type_name_and_size(&AttributeValues::I32(Vec::new())),
+            ("I", 4)
+        );
+        assert_eq!(
+            type_name_and_size(&AttributeValues::U64(Vec::new())),
+            ("U", 8)
+        );
+        assert_eq!(
+            type_name_and_size(&AttributeValues::F32(Vec::new())),
+            ("F", 4)
+        );
+        assert_eq!(
+            type_name_and_size(&AttributeValues::F64(Vec::new())),
+            ("F", 8)
+        );
+    }
+
+    #[test]
+    fn test_float_values() {
+        assert_eq!(
+            float_values([0.5, 1e-10].into_iter(), true),
+            AttributeValues::F64(Vec::from([0.5, 1e-10]))
+        );
+        assert_eq!(
+            float_values([0.5, 1e-10].into_iter(), false),
+            AttributeValues::F32(Vec::from([0.5, 1e-10]))
+        );
+    }
+
+    #[test]
+    fn test_push_le_bytes() {
+        let mut values = AttributeValues::I16(Vec::new());
+        push_le_bytes(&mut values, &(-2i16).to_le_bytes());
+        push_le_bytes(&mut values, &[0x01, 0x02]);
+        assert_eq!(values, AttributeValues::I16(Vec::from([-2, 0x0201])));
+
+        let mut values = AttributeValues::F64(Vec::new());
+        push_le_bytes(&mut values, &2.5f64.to_le_bytes());
+        assert_eq!(values, AttributeValues::F64(Vec::from([2.5])));
+    }
+
+    #[test]
+    fn test_push_token() {
+        let mut values = AttributeValues::U8(Vec::new());
+        push_token(&mut values, "255").unwrap();
+        // Out of range and non-numeric tokens are rejected
+        assert!(matches!(
+            push_token(&mut values, "256"),
+            Err(PcdError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            push_token(&mut values, "-1"),
+            Err(PcdError::InvalidValue(_))
+        ));
+        assert_eq!(values, AttributeValues::U8(Vec::from([255])));
+
+        let mut values = AttributeValues::F32(Vec::new());
+        push_token(&mut values, "-1.5e3").unwrap();
+        push_token(&mut values, "nan").unwrap();
+        assert!(matches!(
+            push_token(&mut values, "abc"),
+            Err(PcdError::InvalidValue(_))
+        ));
+        let AttributeValues::F32(values) = values else {
+            panic!("The values keep their type");
+        };
+        assert_eq!(values[0], -1500.0);
+        assert!(values[1].is_nan());
+        assert_eq!(values.len(), 2);
+    }
+
+    #[test]
+    fn test_extend_le_bytes() {
+        let values = AttributeValues::U16(Vec::from([0x0102, 0x0304]));
+        let mut output = Vec::from([0xff]);
+        extend_le_bytes(&values, 1, &mut output);
+        assert_eq!(output, [0xff, 0x04, 0x03]);
+
+        let values = AttributeValues::F32(Vec::from([1.0]));
+        output.clear();
+        extend_le_bytes(&values, 0, &mut output);
+        assert_eq!(output, 1.0f32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_format_value() {
+        let values = AttributeValues::F32(Vec::from([f32::NAN, 1.5, 1e6, f32::INFINITY]));
+        assert_eq!(format_value(&values, 0), "nan");
+        assert_eq!(format_value(&values, 1), "1.5");
+        assert_eq!(format_value(&values, 2), "1000000");
+        assert_eq!(format_value(&values, 3), "inf");
+
+        let values = AttributeValues::I8(Vec::from([-3]));
+        assert_eq!(format_value(&values, 0), "-3");
+        let values = AttributeValues::U64(Vec::from([u64::MAX]));
+        assert_eq!(format_value(&values, 0), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn test_lzf_decompress() {
+        // A literal run of 3 bytes, followed by a back reference copying them
+        assert_eq!(
+            lzf_decompress(&[0x02, b'a', b'b', b'c', 0x20, 0x02], 6).unwrap(),
+            b"abcabc"
+        );
+        // A long back reference overlapping the bytes it writes
+        assert_eq!(
+            lzf_decompress(&[0x00, b'x', 0xe0, 0x03, 0x00], 13).unwrap(),
+            [b'x'; 13]
+        );
+        assert!(lzf_decompress(&[], 0).unwrap().is_empty());
+
+        for (input, output_len) in [
+            // A back reference before the start of the data
+            (&[0x20, 0x05][..], 3),
+            // A truncated literal run
+            (&[0x03, 1, 2], 4),
+            // A truncated long back reference
+            (&[0x00, 1, 0xe0], 10),
+            // A truncated back reference offset
+            (&[0x00, 1, 0x20], 4),
+            // The data decompresses to more or less than declared
+            (&[0x02, 1, 2, 3], 2),
+            (&[0x02, 1, 2, 3], 4),
+        ] {
+            assert!(matches!(
+                lzf_decompress(input, output_len),
+                Err(PcdError::InvalidCompressedData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_lzf_compress() {
+        assert_eq!(
+            lzf_compress(b"abcabc"),
+            [0x02, b'a', b'b', b'c', 0x20, 0x02]
+        );
+        // Literal runs are split into chunks of at most 32 bytes
+        let distinct = (0..40).collect::<Vec<u8>>();
+        let compressed = lzf_compress(&distinct);
+        assert_eq!(compressed[0], 31);
+        assert_eq!(compressed[33], 7);
+        assert_eq!(compressed.len(), 42);
+        assert!(lzf_compress(&[]).is_empty());
+
+        let mut data = Vec::new();
+        data.extend((0..10000).map(|idx| (idx % 251) as u8));
+        data.extend(std::iter::repeat_n(7, 1000));
+        data.extend((0..3000u32).flat_map(|idx| idx.wrapping_mul(2654435761).to_le_bytes()));
+        for input in [&data[..], &data[..2], &distinct] {
+            let compressed = lzf_compress(input);
+            assert_eq!(lzf_decompress(&compressed, input.len()).unwrap(), input);
+        }
+        assert!(lzf_compress(&data).len() < data.len());
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(
+            parse_values::<usize>("SIZE", &["4", "8", "1"]).unwrap(),
+            [4, 8, 1]
+        );
+        assert!(parse_values::<f64>("VIEWPOINT", &[]).unwrap().is_empty());
+        assert!(matches!(
+            parse_values::<usize>("SIZE", &["4", "-8"]),
+            Err(PcdError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_single() {
+        assert_eq!(parse_single::<usize>("WIDTH", &["640"]).unwrap(), 640);
+        for tokens in [&["abc"][..], &["1.5"], &[], &["1", "2"]] {
+            assert!(matches!(
+                parse_single::<usize>("WIDTH", tokens),
+                Err(PcdError::InvalidHeader(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_take_field() {
+        let field = |name: &str, components: usize| AttributeChannel {
+            name: name.to_owned(),
+            components,
+            values: AttributeValues::F32(Vec::new()),
+        };
+        let mut fields = Vec::from([field("x", 1), field("fpfh", 33), field("y", 1)]);
+
+        // The amount of components must match as well
+        assert!(take_field(&mut fields, "fpfh", 1).is_none());
+        assert!(take_field(&mut fields, "z", 1).is_none());
+        assert_eq!(take_field(&mut fields, "x", 1), Some(field("x", 1)));
+        assert_eq!(fields, [field("fpfh", 33), field("y", 1)]);
+        assert!(take_field(&mut fields, "x", 1).is_none());
+
+        assert!(take_field(&mut Vec::new(), "x", 1).is_none());
+    }
+
+    #[test]
+    fn test_is_organised() {
+        let mut cloud = PcdPointCloud::from(PointCloud::new(Vec::from([Point2::new(0.0, 0.0); 4])));
+        assert!(!cloud.is_organised());
+        cloud.width = 2;
+        cloud.height = 2;
+        assert!(cloud.is_organised());
+    }
+
+    #[test]
+    fn test_point_index() {
+        let mut cloud = PcdPointCloud::from(PointCloud::new(Vec::from([Point2::new(0.0, 0.0); 6])));
+        assert_eq!(cloud.point_index(0, 5), Some(5));
+        assert_eq!(cloud.point_index(1, 0), None);
+
+        cloud.width = 3;
+        cloud.height = 2;
+        assert_eq!(cloud.point_index(1, 2), Some(5));
+        assert_eq!(cloud.point_index(0, 1), Some(1));
+        assert_eq!(cloud.point_index(0, 3), None);
+        assert_eq!(cloud.point_index(2, 0), None);
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let cloud = PcdPointCloud {
+            cloud: generate_cloud(),
+            width: 2,
+            height: 2,
+            viewpoint: Isometry3::new(Vector3::new(1.0, 2.0, 3.0), Vector3::z() * 0.5),
+        };
+        for encoding in [
+            PcdEncoding::Ascii,
+            PcdEncoding::Binary,
+            PcdEncoding::BinaryCompressed,
+        ] {
+            let mut bytes = Vec::new();
+            write_pcd(&mut bytes, &cloud, encoding).unwrap();
+            let read_cloud: PcdPointCloud<f32, 3> = read_pcd(bytes.as_slice()).unwrap();
+            assert_clouds_eq(&read_cloud.cloud, &cloud.cloud);
+            assert_eq!((read_cloud.width, read_cloud.height), (2, 2));
+            assert!(read_cloud.is_organised());
+            assert_eq!(read_cloud.point_index(1, 0), Some(2));
+            assert_eq!(read_cloud.point_index(2, 0), None);
+            assert!(
+                (read_cloud.viewpoint.translation.vector - Vector3::new(1.0, 2.0, 3.0)).norm()
+                    < 1e-6
+            );
+            assert!((read_cloud.viewpoint.rotation.angle() - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_write_header() {
+        let cloud = PcdPointCloud::from(PointCloud::new(Vec::from([Point2::new(1.0, 2.0)])));
+        let mut bytes = Vec::new();
+        write_pcd(&mut bytes, &cloud, PcdEncoding::Ascii).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "# .PCD v0.7 - Point Cloud Data file format\nVERSION 0.7\nFIELDS x y\nSIZE 8 8\nTYPE F F\nCOUNT 1 1\nWIDTH 1\nHEIGHT 1\nVIEWPOINT 0 0 0 1 0 0 0\nPOINTS 1\nDATA ascii\n1 2\n"
+        );
+
+        let mismatched = PcdPointCloud { width: 2, ..cloud };
+        assert!(matches!(
+            write_pcd(Vec::new(), &mismatched, PcdEncoding::Binary),
+            Err(PcdError::OrganizationMismatch {
+                width: 2,
+                height: 1,
+                points: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_read_ascii() {
+        // The colour is stored as a float, written by PCL as its integer bit pattern
+        let file = "# .PCD v0.7 - Point Cloud Data file format\n\
+            VERSION 0.7\n\
+            FIELDS x y z rgb _ curvature\n\
+            SIZE 4 4 4 4 1 4\n\
+            TYPE F F F F U F\n\
+            COUNT 1 1 1 1 3 1\n\
+            WIDTH 3\n\
+            HEIGHT 1\n\
+            POINTS 3\n\
+            DATA ascii\n\
+            0 0 0 16711680 0 0 0 0.5\n\
+            \n\
+            1 0 nan 65280 0 0 0 0.25\n\
+            0 1 0 255 0 0 0 0\n";
+
+        let cloud: PcdPointCloud<f64, 3> = read_pcd(file.as_bytes()).unwrap();
+        assert!(!cloud.is_organised());
+        assert_eq!(cloud.cloud[0], Point3::origin());
+        assert!(cloud.cloud[1].z.is_nan());
+        assert_eq!(
+            cloud.cloud.colours(),
+            Some([[255, 0, 0], [0, 255, 0], [0, 0, 255]].as_slice())
+        );
+        assert_eq!(cloud.cloud.channels().len(), 1);
+        assert_eq!(
+            cloud.cloud.channel("curvature").unwrap().values,
+            AttributeValues::F32(Vec::from([0.5, 0.25, 0.0]))
+        );
+
+        // Fields of coordinates beyond the requested dimensions are kept as custom channels
+        let planar: PcdPointCloud<f64, 2> = read_pcd(file.as_bytes()).unwrap();
+        assert_eq!(planar.cloud[2], Point2::new(0.0, 1.0));
+        assert!(planar.cloud.channel("z").is_some());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert!(matches!(
+            read_pcd::<f32, 3, _>("VERSION 0.7\nFIELDS x y z\n".as_bytes()),
+            Err(PcdError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 3, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 1\nDATA ascii\n1 2\n".as_bytes()
+            ),
+            Err(PcdError::MissingCoordinate("z"))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 2\nHEIGHT 2\nPOINTS 3\nDATA ascii\n"
+                    .as_bytes()
+            ),
+            Err(PcdError::OrganizationMismatch { .. })
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 2\nTYPE F F\nWIDTH 1\nDATA ascii\n1 2\n".as_bytes()
+            ),
+            Err(PcdError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 1\nDATA ascii\n1\n".as_bytes()
+            ),
+            Err(PcdError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 1\nDATA binary\n\0\0".as_bytes()
+            ),
+            Err(PcdError::Io(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 4, _>("".as_bytes()),
+            Err(PcdError::UnsupportedDimensions(4))
+        ));
+    }
+
+    #[test]
+    fn test_read_malformed_header() {
+        // The organisation overflows
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 4294967296\nHEIGHT 4294967296\nDATA ascii\n"
+                    .as_bytes()
+            ),
+            Err(PcdError::InvalidHeader(_))
+        ));
+        // A single point's size overflows
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 8\nTYPE F F\nCOUNT 1 4611686018427387904\nWIDTH 1\nDATA binary\n"
+                    .as_bytes()
+            ),
+            Err(PcdError::InvalidHeader(_))
+        ));
+        // The size of all points overflows
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 8 8\nTYPE F F\nWIDTH 4611686018427387904\nDATA binary\n"
+                    .as_bytes()
+            ),
+            Err(PcdError::InvalidHeader(_))
+        ));
+
+        // Huge declared sizes fail on the missing data, rather than allocating for it
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 1000000000000\nDATA binary\n\0\0\0\0"
+                    .as_bytes()
+            ),
+            Err(PcdError::Io(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(
+                "FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH 1000000000000\nDATA ascii\n1 2\n".as_bytes()
+            ),
+            Err(PcdError::InvalidValue(_))
+        ));
+
+        let compressed_file = |points: usize, compressed_size: u32, uncompressed_size: u32| {
+            let mut bytes =
+                format!("FIELDS x y\nSIZE 4 4\nTYPE F F\nWIDTH {points}\nDATA binary_compressed\n")
+                    .into_bytes();
+            bytes.extend_from_slice(&compressed_size.to_le_bytes());
+            bytes.extend_from_slice(&uncompressed_size.to_le_bytes());
+            bytes.extend_from_slice(&[0, 1]);
+            bytes
+        };
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(compressed_file(1, u32::MAX, 8).as_slice()),
+            Err(PcdError::Io(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(compressed_file(1 << 28, 2, 1 << 31).as_slice()),
+            Err(PcdError::InvalidCompressedData(_))
+        ));
+        assert!(matches!(
+            read_pcd::<f32, 2, _>(compressed_file(2, 2, 8).as_slice()),
+            Err(PcdError::InvalidCompressedData(_))
+        ));
+    }
+}
@@ -0,0 +1,888 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::io::{self, BufRead, BufWriter, Write};
+
+use nalgebra::{Point, RealField, SVector};
+use num_traits::AsPrimitive;
+
+use crate::point_clouds::PointCloud;
+
+use super::scalar::ScalarType;
+
+const COORDINATE_NAMES: [&str; 3] = ["x", "y", "z"];
+const NORMAL_NAMES: [&str; 3] = ["nx", "ny", "nz"];
+const COLOUR_NAMES: [&str; 3] = ["red", "green", "blue"];
+const INTENSITY_NAME: &str = "intensity";
+
+/// The encoding of a PLY file's body.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlyFormat {
+    /// Human readable text, one element per line.
+    Ascii,
+    /// Packed binary values, least significant byte first.
+    BinaryLittleEndian,
+    /// Packed binary values, most significant byte first.
+    BinaryBigEndian,
+}
+
+/// An error type containing the various errors that might arise when reading or writing a PLY file.
+#[derive(Debug, thiserror::Error)]
+pub enum PlyError {
+    /// The underlying reader or writer failed.
+    #[error("Failed reading or writing PLY data: {0}")]
+    Io(#[from] io::Error),
+    /// The header is malformed or uses an unsupported feature.
+    #[error("Invalid PLY header: {0}")]
+    InvalidHeader(String),
+    /// A value in the body could not be parsed.
+    #[error("Invalid PLY value: {0}")]
+    InvalidValue(String),
+    /// The file does not contain a `vertex` element.
+    #[error("The PLY file does not contain a vertex element")]
+    MissingVertexElement,
+    /// The `vertex` element does not contain one of the point's coordinates.
+    #[error("The vertex element does not contain the {0} property")]
+    MissingCoordinate(&'static str),
+    /// The requested point dimensions cannot be stored as PLY vertices, which have at most 3 coordinates.
+    #[error(
+        "Points with {0} dimensions are not supported, PLY vertices have at most 3 coordinates"
+    )]
+    UnsupportedDimensions(usize),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum PlyPropertyKind {
+    Scalar(ScalarType),
+    List {
+        count_type: ScalarType,
+        item_type: ScalarType,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct PlyProperty {
+    name: String,
+    kind: PlyPropertyKind,
+}
+
+#[derive(Clone, Debug)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+impl PlyElement {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find PLY Property", skip_all, level = "trace")
+    )]
+    fn property_index(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|property| {
+            property.name == name && matches!(property.kind, PlyPropertyKind::Scalar(_))
+        })
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Parse PLY Scalar Type", skip_all, level = "trace")
+)]
+fn parse_scalar_type(name: &str) -> Result<ScalarType, PlyError> {
+    Ok(match name {
+        "char" | "int8" => ScalarType::I8,
+        "uchar" | "uint8" => ScalarType::U8,
+        "short" | "int16" => ScalarType::I16,
+        "ushort" | "uint16" => ScalarType::U16,
+        "int" | "int32" => ScalarType::I32,
+        "uint" | "uint32" => ScalarType::U32,
+        "float" | "float32" => ScalarType::F32,
+        "double" | "float64" => ScalarType::F64,
+        _ => {
+            return Err(PlyError::InvalidHeader(format!(
+                "Unknown property type {name}"
+            )))
+        }
+    })
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Get PLY Scalar Type Name", skip_all, level = "trace")
+)]
+fn scalar_type_name(scalar_type: ScalarType) -> &'static str {
+    match scalar_type {
+        ScalarType::I8 => "char",
+        ScalarType::U8 => "uchar",
+        ScalarType::I16 => "short",
+        ScalarType::U16 => "ushort",
+        ScalarType::I32 => "int",
+        ScalarType::U32 => "uint",
+        ScalarType::F32 => "float",
+        ScalarType::F64 => "double",
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PLY Line", skip_all, level = "trace")
+)]
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<bool, PlyError> {
+    line.clear();
+    Ok(reader.read_line(line)? > 0)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PLY Header", skip_all, level = "debug")
+)]
+fn read_header<R: BufRead>(reader: &mut R) -> Result<(PlyFormat, Vec<PlyElement>), PlyError> {
+    let mut line = String::new();
+    if !read_line(reader, &mut line)? || line.trim_end() != "ply" {
+        return Err(PlyError::InvalidHeader(
+            "The file does not begin with the PLY magic number".to_owned(),
+        ));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    loop {
+        if !read_line(reader, &mut line)? {
+            return Err(PlyError::InvalidHeader(
+                "The header ended before end_header".to_owned(),
+            ));
+        }
+
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", format_name, _version] => {
+                format = Some(match *format_name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => {
+                        return Err(PlyError::InvalidHeader(format!(
+                            "Unknown format {format_name}"
+                        )))
+                    }
+                });
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| {
+                    PlyError::InvalidHeader(format!("Invalid element count {count}"))
+                })?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| {
+                    PlyError::InvalidHeader("A property was declared before any element".to_owned())
+                })?
+                .properties
+                .push(PlyProperty {
+                    name: name.to_string(),
+                    kind: PlyPropertyKind::List {
+                        count_type: parse_scalar_type(count_type)?,
+                        item_type: parse_scalar_type(item_type)?,
+                    },
+                }),
+            ["property", scalar_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| {
+                    PlyError::InvalidHeader("A property was declared before any element".to_owned())
+                })?
+                .properties
+                .push(PlyProperty {
+                    name: name.to_string(),
+                    kind: PlyPropertyKind::Scalar(parse_scalar_type(scalar_type)?),
+                }),
+            _ => {
+                return Err(PlyError::InvalidHeader(format!(
+                    "Unexpected header line {}",
+                    line.trim_end()
+                )))
+            }
+        }
+    }
+
+    let format = format.ok_or_else(|| PlyError::InvalidHeader("Missing format line".to_owned()))?;
+    Ok((format, elements))
+}
+
+// Reads a single element instance, list properties are skipped and stored as NaN
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PLY Element Instance", skip_all, level = "trace")
+)]
+fn read_instance<R: BufRead>(
+    reader: &mut R,
+    format: PlyFormat,
+    element: &PlyElement,
+    line: &mut String,
+    values: &mut Vec<f64>,
+) -> Result<(), PlyError> {
+    values.clear();
+    if format == PlyFormat::Ascii {
+        // Blank lines carry no values, and are not counted as instances
+        while line.trim().is_empty() {
+            if !read_line(reader, line)? {
+                return Err(PlyError::InvalidValue(format!(
+                    "The file ended before all {} elements were read",
+                    element.name
+                )));
+            }
+        }
+
+        let mut tokens = line.split_whitespace().map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| PlyError::InvalidValue(format!("Could not parse {token}")))
+        });
+        let mut next_token = || {
+            tokens.next().unwrap_or_else(|| {
+                Err(PlyError::InvalidValue(format!(
+                    "A {} element has too few values",
+                    element.name
+                )))
+            })
+        };
+        for property in element.properties.iter() {
+            match property.kind {
+                PlyPropertyKind::Scalar(_) => values.push(next_token()?),
+                PlyPropertyKind::List { .. } => {
+                    let item_count = next_token()? as usize;
+                    for _ in 0..item_count {
+                        next_token()?;
+                    }
+                    values.push(f64::NAN);
+                }
+            }
+        }
+        line.clear();
+        return Ok(());
+    }
+
+    let big_endian = format == PlyFormat::BinaryBigEndian;
+    for property in element.properties.iter() {
+        match property.kind {
+            PlyPropertyKind::Scalar(scalar_type) => {
+                values.push(scalar_type.read(reader, big_endian)?)
+            }
+            PlyPropertyKind::List {
+                count_type,
+                item_type,
+            } => {
+                let item_count = count_type.read(reader, big_endian)? as u64;
+                let list_size = item_count * item_type.size() as u64;
+                if io::copy(
+                    &mut io::Read::take(&mut *reader, list_size),
+                    &mut io::sink(),
+                )? != list_size
+                {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                values.push(f64::NAN);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a point cloud from a PLY file, in any of the [`PlyFormat`] encodings.
+///
+/// The points are read from the `x`, `y` and `z` properties of the `vertex` element,
+/// the `nx`, `ny` and `nz` properties are read as normals, `red`, `green` and `blue` as colours and `intensity` as intensities,
+/// other elements, such as faces, and all other properties are skipped.
+///
+/// # Arguments
+/// * `reader`: a [`BufRead`], positioned at the beginning of the PLY file.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points, at most 3.
+///
+/// # Returns
+/// A [`PointCloud`] with the channels found in the file, or a [`PlyError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Read PLY Point Cloud", skip_all, level = "info")
+)]
+pub fn read_ply<T, const N: usize, R>(mut reader: R) -> Result<PointCloud<T, N>, PlyError>
+where
+    T: Copy + RealField,
+    f64: AsPrimitive<T>,
+    R: BufRead,
+{
+    if N > COORDINATE_NAMES.len() {
+        return Err(PlyError::UnsupportedDimensions(N));
+    }
+
+    let (format, elements) = read_header(&mut reader)?;
+    if !elements.iter().any(|element| element.name == "vertex") {
+        return Err(PlyError::MissingVertexElement);
+    }
+
+    let mut line = String::new();
+    let mut values = Vec::new();
+    for element in elements.iter() {
+        if element.name != "vertex" {
+            for _ in 0..element.count {
+                read_instance(&mut reader, format, element, &mut line, &mut values)?;
+            }
+            continue;
+        }
+
+        let mut coordinate_indices = [0; N];
+        for (coordinate_idx, name) in COORDINATE_NAMES[..N].iter().enumerate() {
+            coordinate_indices[coordinate_idx] = element
+                .property_index(name)
+                .ok_or(PlyError::MissingCoordinate(name))?;
+        }
+        let normal_indices = NORMAL_NAMES[..N]
+            .iter()
+            .map(|name| element.property_index(name))
+            .collect::<Option<Vec<_>>>();
+        let colour_indices = COLOUR_NAMES
+            .iter()
+            .map(|name| element.property_index(name))
+            .collect::<Option<Vec<_>>>();
+        let intensity_index = element.property_index(INTENSITY_NAME);
+
+        // The element count is not trusted for preallocation, as the file may be truncated
+        let mut points = Vec::new();
+        let mut normals = Vec::new();
+        let mut colours = Vec::new();
+        let mut intensities = Vec::new();
+        for _ in 0..element.count {
+            read_instance(&mut reader, format, element, &mut line, &mut values)?;
+            points.push(Point::from(SVector::<T, N>::from_fn(|idx, _| {
+                values[coordinate_indices[idx]].as_()
+            })));
+            if let Some(normal_indices) = normal_indices.as_ref() {
+                normals.push(SVector::<T, N>::from_fn(|idx, _| {
+                    values[normal_indices[idx]].as_()
+                }));
+            }
+            if let Some(colour_indices) = colour_indices.as_ref() {
+                colours
+                    .push([0, 1, 2].map(|idx| values[colour_indices[idx]].clamp(0.0, 255.0) as u8));
+            }
+            if let Some(intensity_index) = intensity_index {
+                intensities.push(values[intensity_index].as_());
+            }
+        }
+
+        // All channels were read alongside the points, so their lengths always match
+        let mut cloud = PointCloud::new(points);
+        if normal_indices.is_some() {
+            cloud = cloud.with_normals(normals).expect("Channel length matches");
+        }
+        if colour_indices.is_some() {
+            cloud = cloud.with_colours(colours).expect("Channel length matches");
+        }
+        if intensity_index.is_some() {
+            cloud = cloud
+                .with_intensities(intensities)
+                .expect("Channel length matches");
+        }
+        return Ok(cloud);
+    }
+
+    Err(PlyError::MissingVertexElement)
+}
+
+/// Writes a point cloud as a PLY file, including its normal, colour and intensity channels, if present.
+///
+/// Coordinates, normals and intensities are written as `float` or `double` properties, according to the size of `T`,
+/// colours are written as `uchar` properties.
+///
+/// # Arguments
+/// * `writer`: a [`Write`], to which the PLY file is written.
+/// * `cloud`: a [`PointCloud`], the point cloud to write.
+/// * `format`: a [`PlyFormat`], the encoding of the file's body.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points, at most 3.
+///
+/// # Returns
+/// Nothing on success, or a [`PlyError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Write PLY Point Cloud", skip_all, level = "info")
+)]
+pub fn write_ply<T, const N: usize, W>(
+    writer: W,
+    cloud: &PointCloud<T, N>,
+    format: PlyFormat,
+) -> Result<(), PlyError>
+where
+    T: AsPrimitive<f64> + Copy + RealField,
+    W: Write,
+{
+    if N > COORDINATE_NAMES.len() {
+        return Err(PlyError::UnsupportedDimensions(N));
+    }
+
+    let float_type = if core::mem::size_of::<T>() == 8 {
+        ScalarType::F64
+    } else {
+        ScalarType::F32
+    };
+    let mut properties = COORDINATE_NAMES[..N]
+        .iter()
+        .map(|name| (*name, float_type))
+        .collect::<Vec<_>>();
+    if cloud.normals().is_some() {
+        properties.extend(NORMAL_NAMES[..N].iter().map(|name| (*name, float_type)));
+    }
+    if cloud.colours().is_some() {
+        properties.extend(COLOUR_NAMES.iter().map(|name| (*name, ScalarType::U8)));
+    }
+    if cloud.intensities().is_some() {
+        properties.push((INTENSITY_NAME, float_type));
+    }
+
+    let mut writer = BufWriter::new(writer);
+    let format_name = match format {
+        PlyFormat::Ascii => "ascii",
+        PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        PlyFormat::BinaryBigEndian => "binary_big_endian",
+    };
+    writeln!(writer, "ply\nformat {format_name} 1.0")?;
+    writeln!(writer, "element vertex {}", cloud.len())?;
+    for (name, scalar_type) in properties.iter() {
+        writeln!(writer, "property {} {name}", scalar_type_name(*scalar_type))?;
+    }
+    writeln!(writer, "end_header")?;
+
+    let mut values = Vec::with_capacity(properties.len());
+    let mut encoded = Vec::new();
+    for (point_idx, point) in cloud.iter().enumerate() {
+        values.clear();
+        values.extend(point.iter().map(|coordinate| coordinate.as_()));
+        if let Some(normals) = cloud.normals() {
+            values.extend(normals[point_idx].iter().map(|component| component.as_()));
+        }
+        if let Some(colours) = cloud.colours() {
+            values.extend(colours[point_idx].map(f64::from));
+        }
+        if let Some(intensities) = cloud.intensities() {
+            values.push(intensities[point_idx].as_());
+        }
+
+        encoded.clear();
+        match format {
+            PlyFormat::Ascii => {
+                let line = values
+                    .iter()
+                    .zip(properties.iter())
+                    .map(|(value, (_, scalar_type))| scalar_type.format(*value))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(writer, "{line}")?;
+                continue;
+            }
+            PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => {
+                let big_endian = format == PlyFormat::BinaryBigEndian;
+                for (value, (_, scalar_type)) in values.iter().zip(properties.iter()) {
+                    scalar_type.encode(*value, big_endian, &mut encoded);
+                }
+            }
+        }
+        writer.write_all(&encoded)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector3};
+
+    use super::*;
+
+    fn generate_cloud() -> PointCloud<f32, 3> {
+        PointCloud::new(Vec::from([
+            Point3::new(0.1, -2.5, 3.0),
+            Point3::new(1e-3, 4.25, -1e6),
+        ]))
+        .with_normals(Vec::from([Vector3::x(), Vector3::new(0.0, 0.6, 0.8)]))
+        .unwrap()
+        .with_colours(Vec::from([[1, 2, 3], [250, 128, 0]]))
+        .unwrap()
+        .with_intensities(Vec::from([0.5, 100.0]))
+        .unwrap()
+    }
+
+    fn scalar_element(name: &str, properties: &[&str]) -> PlyElement {
+        PlyElement {
+            name: name.to_owned(),
+            count: 1,
+            properties: properties
+                .iter()
+                .map(|property| PlyProperty {
+                    name: property.to_string(),
+                    kind: PlyPropertyKind::Scalar(ScalarType::F32),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_property_index() {
+        let mut element = scalar_element("vertex", &["x", "y"]);
+        element.properties.push(PlyProperty {
+            name: "z".to_owned(),
+            kind: PlyPropertyKind::List {
+                count_type: ScalarType::U8,
+                item_type: ScalarType::F32,
+            },
+        });
+        assert_eq!(element.property_index("y"), Some(1));
+        // List properties cannot be used as scalar values
+        assert_eq!(element.property_index("z"), None);
+        assert_eq!(element.property_index("w"), None);
+    }
+
+    #[test]
+    fn test_parse_scalar_type() {
+        for (names, scalar_type) in [
+            (["char", "int8"], ScalarType::I8),
+            (["uchar", "uint8"], ScalarType::U8),
+            (["short", "int16"], ScalarType::I16),
+            (["ushort", "uint16"], ScalarType::U16),
+            (["int", "int32"], ScalarType::I32),
+            (["uint", "uint32"], ScalarType::U32),
+            (["float", "float32"], ScalarType::F32),
+            (["double", "float64"], ScalarType::F64),
+        ] {
+            for name in names {
+                assert_eq!(parse_scalar_type(name).unwrap(), scalar_type);
+            }
+            assert_eq!(
+                parse_scalar_type(scalar_type_name(scalar_type)).unwrap(),
+                scalar_type
+            );
+        }
+
+        assert!(matches!(
+            parse_scalar_type("int64"),
+            Err(PlyError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_header() {
+        let (format, elements) = read_header(
+            &mut "ply\nformat binary_big_endian 1.0\ncomment a\nobj_info b\n\nelement vertex 2\nproperty float x\nelement face 1\nproperty list uchar uint vertex_indices\nend_header\ntrailing"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(format, PlyFormat::BinaryBigEndian);
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].name, "vertex");
+        assert_eq!(elements[0].count, 2);
+        assert_eq!(
+            elements[0].properties[0].kind,
+            PlyPropertyKind::Scalar(ScalarType::F32)
+        );
+        assert_eq!(
+            elements[1].properties[0].kind,
+            PlyPropertyKind::List {
+                count_type: ScalarType::U8,
+                item_type: ScalarType::U32
+            }
+        );
+
+        for header in [
+            "",
+            "plx\n",
+            "ply\nformat ascii 1.0\n",
+            "ply\nformat text 1.0\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex -1\nend_header\n",
+            "ply\nformat ascii 1.0\nproperty float x\nend_header\n",
+            "ply\nformat ascii 1.0\nproperty list uchar int x\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty long x\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty list uchar long x\nend_header\n",
+            "ply\nformat ascii 1.0\nunexpected line\nend_header\n",
+            "ply\nelement vertex 1\nend_header\n",
+        ] {
+            assert!(matches!(
+                read_header(&mut header.as_bytes()),
+                Err(PlyError::InvalidHeader(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_read_instance_ascii() {
+        let mut element = scalar_element("vertex", &["x", "y"]);
+        element.properties.insert(
+            1,
+            PlyProperty {
+                name: "list".to_owned(),
+                kind: PlyPropertyKind::List {
+                    count_type: ScalarType::U8,
+                    item_type: ScalarType::F32,
+                },
+            },
+        );
+
+        let mut reader = "\n  \n1.5 2 7 8 -3\n4 0 5\n".as_bytes();
+        let (mut line, mut values) = (String::new(), Vec::new());
+        read_instance(
+            &mut reader,
+            PlyFormat::Ascii,
+            &element,
+            &mut line,
+            &mut values,
+        )
+        .unwrap();
+        assert_eq!(values[0], 1.5);
+        assert!(values[1].is_nan());
+        assert_eq!(values[2], -3.0);
+        read_instance(
+            &mut reader,
+            PlyFormat::Ascii,
+            &element,
+            &mut line,
+            &mut values,
+        )
+        .unwrap();
+        assert_eq!(values[2], 5.0);
+
+        // The file has ended
+        assert!(matches!(
+            read_instance(
+                &mut reader,
+                PlyFormat::Ascii,
+                &element,
+                &mut line,
+                &mut values
+            ),
+            Err(PlyError::InvalidValue(_))
+        ));
+        for body in ["1 3 1 2\n", "1 0 a\n"] {
+            assert!(matches!(
+                read_instance(
+                    &mut body.as_bytes(),
+                    PlyFormat::Ascii,
+                    &element,
+                    &mut String::new(),
+                    &mut values
+                ),
+                Err(PlyError::InvalidValue(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_read_instance_binary() {
+        let mut element = scalar_element("vertex", &["x"]);
+        element.properties.push(PlyProperty {
+            name: "list".to_owned(),
+            kind: PlyPropertyKind::List {
+                count_type: ScalarType::U16,
+                item_type: ScalarType::I16,
+            },
+        });
+
+        let mut values = Vec::new();
+        for (format, bytes) in [
+            (
+                PlyFormat::BinaryLittleEndian,
+                [0, 0, 0xC0, 0x3F, 2, 0, 1, 1, 2, 2],
+            ),
+            (
+                PlyFormat::BinaryBigEndian,
+                [0x3F, 0xC0, 0, 0, 0, 2, 1, 1, 2, 2],
+            ),
+        ] {
+            read_instance(
+                &mut bytes.as_slice(),
+                format,
+                &element,
+                &mut String::new(),
+                &mut values,
+            )
+            .unwrap();
+            assert_eq!(values[0], 1.5);
+            assert!(values[1].is_nan());
+        }
+
+        // The list is shorter than its declared length
+        assert!(matches!(
+            read_instance(
+                &mut [0u8, 0, 0xC0, 0x3F, 3, 0, 1, 1].as_slice(),
+                PlyFormat::BinaryLittleEndian,
+                &element,
+                &mut String::new(),
+                &mut values
+            ),
+            Err(PlyError::Io(_))
+        ));
+        assert!(matches!(
+            read_instance(
+                &mut [0u8, 0].as_slice(),
+                PlyFormat::BinaryLittleEndian,
+                &element,
+                &mut String::new(),
+                &mut values
+            ),
+            Err(PlyError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let cloud = generate_cloud();
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let mut bytes = Vec::new();
+            write_ply(&mut bytes, &cloud, format).unwrap();
+            let read_cloud: PointCloud<f32, 3> = read_ply(bytes.as_slice()).unwrap();
+            assert_eq!(read_cloud, cloud);
+        }
+    }
+
+    #[test]
+    fn test_write_header() {
+        let cloud = PointCloud::new(Vec::from([Point2::new(1.0, 2.0)]));
+        let mut bytes = Vec::new();
+        write_ply(&mut bytes, &cloud, PlyFormat::Ascii).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty double x\nproperty double y\nend_header\n1 2\n"
+        );
+    }
+
+    #[test]
+    fn test_read_ascii_with_faces() {
+        let file = "ply\r\n\
+            format ascii 1.0\r\n\
+            comment exported by hand\r\n\
+            element face 1\r\n\
+            property list uchar int vertex_indices\r\n\
+            element vertex 3\r\n\
+            property float x\r\n\
+            property float y\r\n\
+            property float z\r\n\
+            property uchar red\r\n\
+            property uchar green\r\n\
+            property uchar blue\r\n\
+            property list uchar float extra\r\n\
+            end_header\r\n\
+            3 0 1 2\r\n\
+            0 0 0 255 0 0 0\r\n\
+            \r\n\
+            1 0 0 0 255 0 2 0.5 0.5\r\n\
+            0 1 0 0 0 255 1 0.0\r\n";
+
+        let cloud: PointCloud<f64, 3> = read_ply(file.as_bytes()).unwrap();
+        assert_eq!(
+            cloud.points(),
+            [
+                Point3::origin(),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0)
+            ]
+        );
+        assert_eq!(
+            cloud.colours(),
+            Some([[255, 0, 0], [0, 255, 0], [0, 0, 255]].as_slice())
+        );
+        assert_eq!(cloud.normals(), None);
+        assert_eq!(cloud.intensities(), None);
+
+        // The points can also be read in fewer dimensions
+        let planar: PointCloud<f64, 2> = read_ply(file.as_bytes()).unwrap();
+        assert_eq!(planar[1], Point2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_read_binary_with_faces() {
+        let mut bytes = b"ply\nformat binary_big_endian 1.0\nelement vertex 2\nproperty double x\nproperty double y\nproperty double z\nproperty short intensity\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n".to_vec();
+        for (point, intensity) in [([1.0f64, 2.0, 3.0], -4i16), ([5.0, 6.0, 7.0], 8)] {
+            point
+                .iter()
+                .for_each(|coordinate| bytes.extend_from_slice(&coordinate.to_be_bytes()));
+            bytes.extend_from_slice(&intensity.to_be_bytes());
+        }
+        bytes.push(2);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let cloud: PointCloud<f64, 3> = read_ply(bytes.as_slice()).unwrap();
+        assert_eq!(
+            cloud.points(),
+            [Point3::new(1.0, 2.0, 3.0), Point3::new(5.0, 6.0, 7.0)]
+        );
+        assert_eq!(cloud.intensities(), Some([-4.0, 8.0].as_slice()));
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert!(matches!(
+            read_ply::<f32, 3, _>("plx\n".as_bytes()),
+            Err(PlyError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            read_ply::<f32, 3, _>("ply\nformat ascii 1.0\nelement face 0\nend_header\n".as_bytes()),
+            Err(PlyError::MissingVertexElement)
+        ));
+        assert!(matches!(
+            read_ply::<f32, 3, _>(
+                "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nend_header\n1 2\n"
+                    .as_bytes()
+            ),
+            Err(PlyError::MissingCoordinate("z"))
+        ));
+        assert!(matches!(
+            read_ply::<f32, 2, _>(
+                "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nend_header\n1 2\n"
+                    .as_bytes()
+            ),
+            Err(PlyError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            read_ply::<f32, 2, _>(
+                "ply\nformat binary_little_endian 1.0\nelement vertex 2\nproperty float x\nproperty float y\nend_header\n\0\0"
+                    .as_bytes()
+            ),
+            Err(PlyError::Io(_))
+        ));
+        assert!(matches!(
+            read_ply::<f32, 4, _>("ply\n".as_bytes()),
+            Err(PlyError::UnsupportedDimensions(4))
+        ));
+    }
+}
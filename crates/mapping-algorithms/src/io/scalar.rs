@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::io::{self, Read};
+
+/// The primitive numeric types used by binary point cloud formats.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Get Scalar Size", skip_all, level = "trace")
+    )]
+    pub(crate) fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Decodes a single value from the start of `bytes`, which must contain at least [`Self::size`] bytes.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Decode Scalar", skip_all, level = "trace")
+    )]
+    pub(crate) fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! decode_as {
+            ($ty:ty) => {{
+                let raw = bytes[..core::mem::size_of::<$ty>()]
+                    .try_into()
+                    .expect("Slice length matches the type's size");
+                (if big_endian {
+                    <$ty>::from_be_bytes(raw)
+                } else {
+                    <$ty>::from_le_bytes(raw)
+                }) as f64
+            }};
+        }
+
+        match self {
+            Self::I8 => decode_as!(i8),
+            Self::U8 => decode_as!(u8),
+            Self::I16 => decode_as!(i16),
+            Self::U16 => decode_as!(u16),
+            Self::I32 => decode_as!(i32),
+            Self::U32 => decode_as!(u32),
+            Self::F32 => decode_as!(f32),
+            Self::F64 => decode_as!(f64),
+        }
+    }
+
+    /// Reads and decodes a single value.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Read Scalar", skip_all, level = "trace")
+    )]
+    pub(crate) fn read<R: Read>(self, reader: &mut R, big_endian: bool) -> io::Result<f64> {
+        let mut buffer = [0u8; 8];
+        reader.read_exact(&mut buffer[..self.size()])?;
+        Ok(self.decode(&buffer, big_endian))
+    }
+
+    /// Encodes a single value, integer types are rounded and saturated to their range.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Encode Scalar", skip_all, level = "trace")
+    )]
+    pub(crate) fn encode(self, value: f64, big_endian: bool, output: &mut Vec<u8>) {
+        macro_rules! encode_as {
+            ($ty:ty, $value:expr) => {{
+                let value = $value as $ty;
+                output.extend_from_slice(&if big_endian {
+                    value.to_be_bytes()
+                } else {
+                    value.to_le_bytes()
+                });
+            }};
+        }
+
+        match self {
+            Self::I8 => encode_as!(i8, value.round()),
+            Self::U8 => encode_as!(u8, value.round()),
+            Self::I16 => encode_as!(i16, value.round()),
+            Self::U16 => encode_as!(u16, value.round()),
+            Self::I32 => encode_as!(i32, value.round()),
+            Self::U32 => encode_as!(u32, value.round()),
+            Self::F32 => encode_as!(f32, value),
+            Self::F64 => encode_as!(f64, value),
+        }
+    }
+
+    /// Formats a single value as text, using the shortest representation that reads back identically.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Format Scalar", skip_all, level = "trace")
+    )]
+    pub(crate) fn format(self, value: f64) -> String {
+        match self {
+            Self::F32 => (value as f32).to_string(),
+            Self::F64 => value.to_string(),
+            _ => (value.round() as i64).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_TYPES: [ScalarType; 8] = [
+        ScalarType::I8,
+        ScalarType::U8,
+        ScalarType::I16,
+        ScalarType::U16,
+        ScalarType::I32,
+        ScalarType::U32,
+        ScalarType::F32,
+        ScalarType::F64,
+    ];
+
+    #[test]
+    fn test_size() {
+        assert_eq!(ALL_TYPES.map(ScalarType::size), [1, 1, 2, 2, 4, 4, 4, 8]);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(ScalarType::I8.decode(&[0xFF], false), -1.0);
+        assert_eq!(ScalarType::U8.decode(&[0xFF], true), 255.0);
+        assert_eq!(ScalarType::I16.decode(&[0xFF, 0xFE], true), -2.0);
+        assert_eq!(ScalarType::U16.decode(&[0x01, 0x02], false), 513.0);
+        assert_eq!(
+            ScalarType::I32.decode(&[0xFE, 0xFF, 0xFF, 0xFF], false),
+            -2.0
+        );
+        assert_eq!(ScalarType::U32.decode(&[0, 0, 1, 0], true), 256.0);
+        assert_eq!(ScalarType::F32.decode(&1.5f32.to_be_bytes(), true), 1.5);
+        assert_eq!(
+            ScalarType::F64.decode(&(-0.25f64).to_le_bytes(), false),
+            -0.25
+        );
+
+        // Trailing bytes are ignored
+        assert_eq!(ScalarType::U8.decode(&[7, 8, 9], false), 7.0);
+    }
+
+    #[test]
+    fn test_read() {
+        let mut bytes = [1u8, 0, 2, 0, 0, 0].as_slice();
+        assert_eq!(ScalarType::U16.read(&mut bytes, false).unwrap(), 1.0);
+        assert_eq!(ScalarType::U16.read(&mut bytes, true).unwrap(), 512.0);
+
+        // Only two bytes are left
+        assert_eq!(
+            ScalarType::U32.read(&mut bytes, false).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut bytes = Vec::new();
+        ScalarType::U8.encode(300.0, false, &mut bytes);
+        ScalarType::I8.encode(-300.0, false, &mut bytes);
+        ScalarType::U16.encode(-5.0, false, &mut bytes);
+        assert_eq!(bytes, [255, 128, 0, 0]);
+
+        bytes.clear();
+        ScalarType::I16.encode(2.6, true, &mut bytes);
+        assert_eq!(bytes, [0, 3]);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        for (scalar_type, value) in [
+            (ScalarType::I8, -12.0),
+            (ScalarType::U8, 250.0),
+            (ScalarType::I16, -30000.0),
+            (ScalarType::U16, 65000.0),
+            (ScalarType::I32, -7.0),
+            (ScalarType::U32, 4_000_000_000.0),
+            (ScalarType::F32, 0.5),
+            (ScalarType::F64, 0.1),
+        ] {
+            for big_endian in [false, true] {
+                let mut bytes = Vec::new();
+                scalar_type.encode(value, big_endian, &mut bytes);
+                assert_eq!(bytes.len(), scalar_type.size());
+                assert_eq!(scalar_type.decode(&bytes, big_endian), value);
+                assert_eq!(
+                    scalar_type.read(&mut bytes.as_slice(), big_endian).unwrap(),
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(ScalarType::U16.format(12.4), "12");
+        assert_eq!(ScalarType::I32.format(-2.5), "-3");
+        assert_eq!(ScalarType::F32.format(0.1), "0.1");
+        assert_eq!(ScalarType::F64.format(0.1), "0.1");
+        assert_eq!(ScalarType::F64.format(1e300), 1e300.to_string());
+    }
+}
@@ -24,7 +24,23 @@
 use nalgebra::{Point, Scalar};
 use num_traits::{NumOps, Zero};
 
-use crate::{utils::distance_squared, Box, Ordering};
+use crate::{utils::distance_squared, Box, Ordering, Vec};
+
+/// A single result of a [`KDTree`] neighbour query.
+///
+/// # Generics
+/// `T`: Either an [`f32`] or [`f64`]
+/// `N`: a const usize specifying how many dimensions each point has.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KDNeighbour<T: Scalar, const N: usize> {
+    /// The index of the point, in order of insertion into the tree,
+    /// for a tree created from a slice, this is the point's index in that slice.
+    pub index: usize,
+    /// The neighbouring point.
+    pub point: Point<T, N>,
+    /// The squared distance between the neighbouring point and the query point.
+    pub distance_squared: T,
+}
 
 #[derive(Clone, Debug, Default)]
 struct KDNode<T, const N: usize>
@@ -32,6 +48,8 @@ struct KDNode<T, const N: usize>
     T: Copy + Default + NumOps + PartialOrd + Scalar + Zero,
 {
     internal_data: Point<T, N>,
+    index: usize,
+    duplicate_indices: Vec<usize>,
     right: Option<Box<KDNode<T, N>>>,
     left: Option<Box<KDNode<T, N>>>,
 }
@@ -40,9 +58,11 @@ impl<T, const N: usize> KDNode<T, N>
 where
     T: Copy + Default + NumOps + PartialOrd + Scalar + Zero,
 {
-    fn new(root: Point<T, N>) -> Self {
+    fn new(root: Point<T, N>, index: usize) -> Self {
         Self {
             internal_data: root,
+            index,
+            duplicate_indices: Vec::new(),
             left: None,
             right: None,
         }
@@ -52,7 +72,7 @@ fn new(root: Point<T, N>) -> Self {
         feature = "tracing",
         tracing::instrument("Insert New Point", skip_all, level = "trace")
     )]
-    fn insert(&mut self, data: Point<T, N>, depth: usize) -> bool {
+    fn insert(&mut self, data: Point<T, N>, index: usize, depth: usize) -> bool {
         let dimension_to_check = depth % N;
 
         let (branch_to_use, verify_equals) =
@@ -63,16 +83,87 @@ fn insert(&mut self, data: Point<T, N>, depth: usize) -> bool {
                 Ordering::Greater => (&mut self.right, false)
             };
 
-        if let Some(branch_exists) = branch_to_use.as_mut() {
-            return branch_exists.insert(data, depth + 1);
-        } else if verify_equals && self.internal_data == data {
+        if verify_equals && self.internal_data == data {
+            // Duplicates are not stored as separate nodes, but their indices are kept for neighbour queries
+            self.duplicate_indices.push(index);
             return false;
+        } else if let Some(branch_exists) = branch_to_use.as_mut() {
+            return branch_exists.insert(data, index, depth + 1);
         }
 
-        *branch_to_use = Some(Box::new(KDNode::new(data)));
+        *branch_to_use = Some(Box::new(KDNode::new(data, index)));
         true
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Offer Node As Neighbour", skip_all, level = "trace")
+    )]
+    fn push_neighbours<F: FnMut(KDNeighbour<T, N>)>(&self, distance_squared: T, func: &mut F) {
+        for index in Some(self.index)
+            .into_iter()
+            .chain(self.duplicate_indices.iter().copied())
+        {
+            func(KDNeighbour {
+                index,
+                point: self.internal_data,
+                distance_squared,
+            });
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Branch K Nearest Neighbours", skip_all, level = "trace")
+    )]
+    fn nearest_k(
+        &self,
+        target: &Point<T, N>,
+        k: usize,
+        depth: usize,
+        neighbours: &mut Vec<KDNeighbour<T, N>>,
+    ) {
+        let dimension_to_check = depth % N;
+        let (next_branch, opposite_branch) =
+            if target.coords[dimension_to_check] < self.internal_data.coords[dimension_to_check] {
+                (self.left.as_ref(), self.right.as_ref())
+            } else {
+                (self.right.as_ref(), self.left.as_ref())
+            };
+
+        if let Some(branch) = next_branch {
+            branch.nearest_k(target, k, depth + 1, neighbours);
+        }
+
+        // Keep the neighbours sorted by distance, and discard any beyond the k closest
+        self.push_neighbours(
+            distance_squared(&self.internal_data, target),
+            &mut |neighbour| {
+                if neighbours.len() == k
+                    && neighbours[k - 1].distance_squared <= neighbour.distance_squared
+                {
+                    return;
+                }
+
+                let position = neighbours.partition_point(|current| {
+                    current.distance_squared <= neighbour.distance_squared
+                });
+                neighbours.insert(position, neighbour);
+                neighbours.truncate(k);
+            },
+        );
+
+        let axis_distance =
+            target.coords[dimension_to_check] - self.internal_data.coords[dimension_to_check];
+        if neighbours.len() < k
+            || (axis_distance * axis_distance) < neighbours[k - 1].distance_squared
+        {
+            if let Some(branch) = opposite_branch {
+                branch.nearest_k(target, k, depth + 1, neighbours);
+            }
+        }
+    }
+
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument("Branch Nearest Neighbour", skip_all, level = "trace")
@@ -111,6 +202,88 @@ fn nearest(&self, target: &Point<T, N>, depth: usize) -> Option<Point<T, N>> {
         Some(best)
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Branch Filtered Nearest Neighbour", skip_all, level = "trace")
+    )]
+    fn nearest_filtered<F>(
+        &self,
+        target: &Point<T, N>,
+        depth: usize,
+        cost: &mut F,
+        best: &mut Option<(T, Option<KDNeighbour<T, N>>)>,
+    ) where
+        F: FnMut(&KDNeighbour<T, N>) -> Option<T>,
+    {
+        let dimension_to_check = depth % N;
+        let (next_branch, opposite_branch) =
+            if target.coords[dimension_to_check] < self.internal_data.coords[dimension_to_check] {
+                (self.left.as_ref(), self.right.as_ref())
+            } else {
+                (self.right.as_ref(), self.left.as_ref())
+            };
+
+        if let Some(branch) = next_branch {
+            branch.nearest_filtered(target, depth + 1, cost, best);
+        }
+
+        self.push_neighbours(
+            distance_squared(&self.internal_data, target),
+            &mut |neighbour| {
+                if let Some(neighbour_cost) = cost(&neighbour) {
+                    if best.is_none_or(|(best_cost, _)| neighbour_cost < best_cost) {
+                        *best = Some((neighbour_cost, Some(neighbour)));
+                    }
+                }
+            },
+        );
+
+        // A point's cost is never below its squared distance, so the opposite branch cannot beat a closer best
+        let axis_distance =
+            target.coords[dimension_to_check] - self.internal_data.coords[dimension_to_check];
+        if best.is_none_or(|(best_cost, _)| axis_distance * axis_distance < best_cost) {
+            if let Some(branch) = opposite_branch {
+                branch.nearest_filtered(target, depth + 1, cost, best);
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Branch Neighbours Within Radius", skip_all, level = "trace")
+    )]
+    fn within_radius(
+        &self,
+        target: &Point<T, N>,
+        radius_squared: T,
+        depth: usize,
+        neighbours: &mut Vec<KDNeighbour<T, N>>,
+    ) {
+        let dimension_to_check = depth % N;
+        let distance = distance_squared(&self.internal_data, target);
+        if distance <= radius_squared {
+            self.push_neighbours(distance, &mut |neighbour| neighbours.push(neighbour));
+        }
+
+        let axis_distance =
+            target.coords[dimension_to_check] - self.internal_data.coords[dimension_to_check];
+        let (next_branch, opposite_branch) =
+            if target.coords[dimension_to_check] < self.internal_data.coords[dimension_to_check] {
+                (self.left.as_ref(), self.right.as_ref())
+            } else {
+                (self.right.as_ref(), self.left.as_ref())
+            };
+
+        if let Some(branch) = next_branch {
+            branch.within_radius(target, radius_squared, depth + 1, neighbours);
+        }
+        if axis_distance * axis_distance <= radius_squared {
+            if let Some(branch) = opposite_branch {
+                branch.within_radius(target, radius_squared, depth + 1, neighbours);
+            }
+        }
+    }
+
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument("Traverse Branch With Function", skip_all, level = "debug")
@@ -152,6 +325,7 @@ pub struct KDTree<T, const N: usize>
 {
     root: Option<KDNode<T, N>>,
     element_count: usize,
+    insertion_count: usize,
 }
 
 impl<T, const N: usize> KDTree<T, N>
@@ -159,6 +333,7 @@ impl<T, const N: usize> KDTree<T, N>
     T: Copy + Default + NumOps + PartialOrd + Scalar + Zero,
 {
     /// Inserts a new data points into the tree, taking into consideration it's position.
+    /// Each inserted point is given an index according to the order of insertion, which is returned by neighbour queries.
     ///
     /// # Arguments
     /// * `data`: a [`Point`], to be inserted into the tree.
@@ -167,12 +342,15 @@ impl<T, const N: usize> KDTree<T, N>
         tracing::instrument("Insert To Tree", skip_all, level = "debug")
     )]
     pub fn insert(&mut self, data: Point<T, N>) {
+        let index = self.insertion_count;
+        self.insertion_count += 1;
+
         if let Some(root) = self.root.as_mut() {
-            if root.insert(data, 0) {
+            if root.insert(data, index, 0) {
                 self.element_count += 1;
             }
         } else {
-            self.root = Some(KDNode::new(data));
+            self.root = Some(KDNode::new(data, index));
             self.element_count = 1;
         }
     }
@@ -207,6 +385,81 @@ pub fn nearest(&self, target: &Point<T, N>) -> Option<Point<T, N>> {
         self.root.as_ref().and_then(|root| root.nearest(target, 0))
     }
 
+    /// Finds the `k` nearest points in the tree to the specified target point.
+    /// Duplicate points are returned once for each time they were inserted.
+    ///
+    /// # Arguments
+    /// * `target`: a [`Point`], to search the closest points for.
+    /// * `k`: the maximum number of neighbours to return.
+    ///
+    /// # Returns
+    /// A [`Vec`] of up to `k` [`KDNeighbour`]s, sorted by ascending distance from `target`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find K Nearest Neighbours", skip_all, level = "debug")
+    )]
+    pub fn nearest_k(&self, target: &Point<T, N>, k: usize) -> Vec<KDNeighbour<T, N>> {
+        let mut neighbours = Vec::with_capacity(k);
+        if let Some(root) = self.root.as_ref().filter(|_| k > 0) {
+            root.nearest_k(target, k, 0, &mut neighbours);
+        }
+        neighbours
+    }
+
+    /// Finds the point in the tree with the lowest cost for the specified target point, among the points accepted by `cost`.
+    /// Duplicate points are offered to `cost` once for each time they were inserted.
+    ///
+    /// # Arguments
+    /// * `target`: a [`Point`], to search the lowest cost point for.
+    /// * `bound`: an optional cost, only points with a lower cost are considered.
+    /// * `cost`: a closure of type [`FnMut`], receiving each candidate [`KDNeighbour`] and returning its cost,
+    ///   or [`None`] to skip that point, a cost must never be lower than the candidate's squared distance from `target`.
+    ///
+    /// # Returns
+    /// [`None`] if no point was accepted with a cost below `bound`,
+    /// otherwise the [`KDNeighbour`] with the lowest cost, along with that cost.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find Filtered Nearest Neighbour", skip_all, level = "debug")
+    )]
+    pub fn nearest_filtered<F>(
+        &self,
+        target: &Point<T, N>,
+        bound: Option<T>,
+        mut cost: F,
+    ) -> Option<(KDNeighbour<T, N>, T)>
+    where
+        F: FnMut(&KDNeighbour<T, N>) -> Option<T>,
+    {
+        let mut best = bound.map(|bound| (bound, None));
+        if let Some(root) = self.root.as_ref() {
+            root.nearest_filtered(target, 0, &mut cost, &mut best);
+        }
+
+        best.and_then(|(best_cost, neighbour)| neighbour.map(|neighbour| (neighbour, best_cost)))
+    }
+
+    /// Finds all points in the tree within the specified radius of the target point, inclusive.
+    /// Duplicate points are returned once for each time they were inserted.
+    ///
+    /// # Arguments
+    /// * `target`: a [`Point`], to search the surrounding points for.
+    /// * `radius`: the maximum distance from `target`, for a point to be considered a neighbour.
+    ///
+    /// # Returns
+    /// A [`Vec`] of [`KDNeighbour`]s, in no particular order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find Neighbours Within Radius", skip_all, level = "debug")
+    )]
+    pub fn within_radius(&self, target: &Point<T, N>, radius: T) -> Vec<KDNeighbour<T, N>> {
+        let mut neighbours = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            root.within_radius(target, radius * radius, 0, &mut neighbours);
+        }
+        neighbours
+    }
+
     /// Allows traversal of the entire tree structure, calling the `func` closure on each branch's data.
     ///
     /// # Arguments
@@ -259,7 +512,7 @@ fn from(point_cloud: &[Point<T, N>]) -> Self {
 mod tests {
     use nalgebra::{Point2, Point3};
 
-    use crate::{point_clouds::find_nearest_neighbour_naive, Vec};
+    use crate::point_clouds::{find_nearest_neighbour_naive, generate_point_cloud};
 
     use super::*;
 
@@ -394,6 +647,194 @@ fn compare_nearest_with_naive_version() {
         assert_eq!(closest_points_naive, closest_point_kd);
     }
 
+    #[test]
+    fn test_nearest_k() {
+        // Test an empty tree
+        {
+            let tree = KDTree::<f32, 2>::default();
+            assert!(tree.nearest_k(&Point2::new(0.0, 0.0), 3).is_empty())
+        }
+
+        let tree = generate_tree();
+        assert!(tree.nearest_k(&Point3::new(0.0, 0.0, 0.0), 0).is_empty());
+
+        let neighbours = tree.nearest_k(&Point3::new(1.32, 2.7, 0.2), 2);
+        assert_eq!(neighbours.len(), 2);
+        assert_eq!(neighbours[0].point, Point3::new(1.3, 2.5, 0.5));
+        assert_eq!(neighbours[0].index, 2);
+        assert_eq!(neighbours[1].point, Point3::new(0.0, 2.0, 1.0));
+        assert_eq!(neighbours[1].index, 0);
+
+        // Requesting more neighbours than there are points returns all points
+        assert_eq!(tree.nearest_k(&Point3::new(0.0, 0.0, 0.0), 10).len(), 4);
+    }
+
+    #[test]
+    fn test_nearest_k_duplicates() {
+        let tree = KDTree::from(
+            [
+                Point2::new(0.0f32, 0.0f32),
+                Point2::new(5.0, 5.0),
+                Point2::new(0.0, 0.0),
+            ]
+            .as_slice(),
+        );
+        assert_eq!(tree.len(), 2);
+
+        let neighbours = tree.nearest_k(&Point2::new(0.1, 0.1), 3);
+        let mut indices = neighbours
+            .iter()
+            .map(|neighbour| neighbour.index)
+            .collect::<Vec<_>>();
+        indices[..2].sort();
+        assert_eq!(indices, Vec::from([0, 2, 1]));
+    }
+
+    #[test]
+    fn compare_nearest_k_with_naive_version() {
+        let points = generate_point_cloud(500, [-15.0..=15.0, -15.0..=15.0, -15.0..=15.0]);
+        let tree = KDTree::from(points.as_slice());
+
+        for target in generate_point_cloud(20, [-20.0..=20.0, -20.0..=20.0, -20.0..=20.0]) {
+            let mut naive = points
+                .iter()
+                .enumerate()
+                .map(|(idx, point)| (distance_squared(point, &target), idx))
+                .collect::<Vec<_>>();
+            naive.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let neighbours = tree.nearest_k(&target, 7);
+            assert_eq!(
+                neighbours
+                    .iter()
+                    .map(|neighbour| neighbour.index)
+                    .collect::<Vec<_>>(),
+                naive
+                    .iter()
+                    .take(7)
+                    .map(|(_, idx)| *idx)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_nearest_filtered() {
+        // Test an empty tree
+        {
+            let tree = KDTree::<f32, 2>::default();
+            assert!(tree
+                .nearest_filtered(&Point2::new(0.0, 0.0), None, |neighbour| Some(
+                    neighbour.distance_squared
+                ))
+                .is_none())
+        }
+
+        let tree = generate_tree();
+        let target = Point3::new(1.32, 2.7, 0.2);
+        let (neighbour, cost) = tree
+            .nearest_filtered(&target, None, |neighbour| Some(neighbour.distance_squared))
+            .unwrap();
+        assert_eq!(neighbour.index, 2);
+        assert_eq!(cost, neighbour.distance_squared);
+
+        // Skipping the nearest point finds the next one
+        let (neighbour, _) = tree
+            .nearest_filtered(&target, None, |neighbour| {
+                (neighbour.index != 2).then_some(neighbour.distance_squared)
+            })
+            .unwrap();
+        assert_eq!(neighbour.index, 0);
+
+        // Costs may exceed the squared distance
+        let (neighbour, cost) = tree
+            .nearest_filtered(&target, None, |neighbour| {
+                Some(neighbour.distance_squared + [100.0, 50.0, 100.0, 0.0][neighbour.index])
+            })
+            .unwrap();
+        assert_eq!(neighbour.index, 3);
+        assert_eq!(cost, neighbour.distance_squared);
+
+        // Nothing is below the bound, or nothing is accepted
+        assert!(tree
+            .nearest_filtered(&target, Some(0.1), |neighbour| Some(
+                neighbour.distance_squared
+            ))
+            .is_none());
+        assert!(tree.nearest_filtered(&target, None, |_| None).is_none());
+    }
+
+    #[test]
+    fn compare_nearest_filtered_with_naive_version() {
+        let points = generate_point_cloud(500, [-15.0..=15.0, -15.0..=15.0]);
+        let tree = KDTree::from(points.as_slice());
+        let cost = |idx: usize, distance_squared: f64| {
+            (idx >= 100).then_some(distance_squared.max((idx % 5) as f64))
+        };
+
+        for target in generate_point_cloud(20, [-20.0..=20.0, -20.0..=20.0]) {
+            let naive = points
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, point)| cost(idx, distance_squared(point, &target)))
+                .fold(f64::MAX, f64::min);
+
+            let (neighbour, best_cost) = tree
+                .nearest_filtered(&target, None, |neighbour| {
+                    cost(neighbour.index, neighbour.distance_squared)
+                })
+                .unwrap();
+            assert!(neighbour.index >= 100);
+            assert_eq!(best_cost, naive);
+        }
+    }
+
+    #[test]
+    fn test_within_radius() {
+        // Test an empty tree
+        {
+            let tree = KDTree::<f32, 2>::default();
+            assert!(tree.within_radius(&Point2::new(0.0, 0.0), 3.0).is_empty())
+        }
+
+        let tree = generate_tree();
+        let mut indices = tree
+            .within_radius(&Point3::new(0.5, 2.0, 1.0), 1.1)
+            .into_iter()
+            .map(|neighbour| neighbour.index)
+            .collect::<Vec<_>>();
+        indices.sort();
+        assert_eq!(indices, Vec::from([0, 2]));
+
+        // The radius is inclusive
+        let neighbours = tree.within_radius(&Point3::new(0.0, 2.0, 2.0), 1.0);
+        assert_eq!(neighbours.len(), 1);
+        assert_eq!(neighbours[0].distance_squared, 1.0);
+    }
+
+    #[test]
+    fn compare_within_radius_with_naive_version() {
+        let points = generate_point_cloud(500, [-15.0..=15.0, -15.0..=15.0]);
+        let tree = KDTree::from(points.as_slice());
+
+        for target in generate_point_cloud(20, [-20.0..=20.0, -20.0..=20.0]) {
+            let naive = points
+                .iter()
+                .enumerate()
+                .filter(|(_, point)| distance_squared(*point, &target) <= 9.0)
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+
+            let mut indices = tree
+                .within_radius(&target, 3.0)
+                .into_iter()
+                .map(|neighbour| neighbour.index)
+                .collect::<Vec<_>>();
+            indices.sort();
+            assert_eq!(indices, naive);
+        }
+    }
+
     #[test]
     fn test_traverse_tree() {
         let tree = generate_tree();
@@ -39,10 +39,11 @@
     borrow::ToOwned,
     boxed::Box,
     cmp::Ordering,
-    collections::{HashMap, VecDeque},
+    collections::{BinaryHeap, HashMap, VecDeque},
     fmt::Debug,
     iter::Sum,
-    marker, ops,
+    marker, mem, ops,
+    string::String,
     vec::Vec,
 };
 
@@ -51,10 +52,11 @@
     alloc::{
         borrow::ToOwned,
         boxed::Box,
-        collections::{BTreeMap as HashMap, VecDeque},
+        collections::{BTreeMap as HashMap, BinaryHeap, VecDeque},
+        string::String,
         vec::Vec,
     },
-    core::{array, cmp::Ordering, fmt::Debug, iter::Sum, marker, ops},
+    core::{array, cmp::Ordering, fmt::Debug, iter::Sum, marker, mem, ops},
 };
 
 ///A module containing common and interfacing structs and types.
@@ -63,6 +65,9 @@
 /// A K-Dimensional Tree data structure, useful for various geo-spatial computations.
 pub mod kd_tree;
 
+/// An R-Tree data structure, useful for indexing and querying large collections of bounding boxes.
+pub mod r_tree;
+
 /// A module containing various algorithms for point clouds.
 pub mod point_clouds;
 
@@ -75,5 +80,12 @@
 /// A module containing various line algorithms.
 pub mod lines;
 
+/// A module containing camera models, and conversions from camera images to point clouds.
+pub mod camera;
+
+/// A module containing readers and writers for common point cloud file formats.
+#[cfg(feature = "std")]
+pub mod io;
+
 /// Various utility functions that are commonly used by these algorithms.
 pub mod utils;
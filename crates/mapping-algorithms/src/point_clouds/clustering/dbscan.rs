@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point, Scalar};
+use num_traits::{NumOps, Zero};
+
+use crate::{kd_tree::KDTree, Vec, VecDeque};
+
+use super::ClusterLabel;
+
+/// Clusters the point cloud using DBSCAN (Density-Based Spatial Clustering of Applications with Noise).
+///
+/// A point is a core point if at least `min_points` points, itself included, lie within `eps` of it.
+/// Clusters are grown from core points, points that are within `eps` of a core point but are not core points
+/// themselves join the first cluster that reaches them, and all other points are labelled as noise.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `eps`: the neighbourhood radius of each point, inclusive.
+/// * `min_points`: the minimal amount of points in a neighbourhood for its center to be a core point.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of [`ClusterLabel`], one for each point in `points`, clusters are numbered in order of their first point.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("DBSCAN Clustering", skip_all, level = "info")
+)]
+pub fn dbscan<T, const N: usize>(
+    points: &[Point<T, N>],
+    eps: T,
+    min_points: usize,
+) -> Vec<ClusterLabel>
+where
+    T: Copy + Default + NumOps + PartialOrd + Scalar + Zero,
+{
+    let points_tree = KDTree::from(points);
+    let mut labels: Vec<Option<ClusterLabel>> =
+        Vec::from_iter(core::iter::repeat_n(None, points.len()));
+    let mut cluster_count = 0;
+    let mut queue = VecDeque::new();
+
+    for seed_idx in 0..points.len() {
+        if labels[seed_idx].is_some() {
+            continue;
+        }
+
+        let seed_neighbours = points_tree.within_radius(&points[seed_idx], eps);
+        if seed_neighbours.len() < min_points {
+            // Might still be claimed as a border point by a later cluster
+            labels[seed_idx] = Some(ClusterLabel::Noise);
+            continue;
+        }
+
+        let cluster = ClusterLabel::Cluster(cluster_count);
+        cluster_count += 1;
+        labels[seed_idx] = Some(cluster);
+        queue.extend(seed_neighbours.into_iter().map(|neighbour| neighbour.index));
+
+        while let Some(current_idx) = queue.pop_front() {
+            match labels[current_idx] {
+                Some(ClusterLabel::Noise) => labels[current_idx] = Some(cluster),
+                None => {
+                    labels[current_idx] = Some(cluster);
+                    let current_neighbours = points_tree.within_radius(&points[current_idx], eps);
+                    if current_neighbours.len() >= min_points {
+                        queue.extend(
+                            current_neighbours
+                                .into_iter()
+                                .map(|neighbour| neighbour.index)
+                                .filter(|&index| {
+                                    !matches!(labels[index], Some(ClusterLabel::Cluster(_)))
+                                }),
+                        );
+                    }
+                }
+                Some(ClusterLabel::Cluster(_)) => {}
+            }
+        }
+    }
+
+    labels
+        .into_iter()
+        .map(|label| label.unwrap_or(ClusterLabel::Noise))
+        .collect()
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_dbscan {
+    ($precision:expr, doc $doc:tt, $nd:expr) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the DBSCAN clustering function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<dbscan_ $nd d>](points: &[Point<$precision, $nd>], eps: $precision, min_points: usize) -> Vec<ClusterLabel> {
+                super::dbscan(points, eps, min_points)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point;
+                use crate::Vec;
+                use super::ClusterLabel;
+
+                impl_dbscan!($precision, doc $doc, 2);
+                impl_dbscan!($precision, doc $doc, 3);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_dbscan!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_dbscan!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector3};
+
+    use crate::{array, point_clouds::generate_point_cloud};
+
+    use super::*;
+
+    #[test]
+    fn test_dbscan() {
+        let mut points = Vec::new();
+        for center in [Vector3::new(10.0, 0.0, 0.0), Vector3::new(-10.0, 5.0, 0.0)] {
+            points.extend(
+                generate_point_cloud(200, array::from_fn(|_| -1.0..=1.0))
+                    .into_iter()
+                    .map(|point: Point3<f64>| point + center),
+            );
+        }
+        points.push(Point3::new(0.0, 0.0, 20.0));
+        points.push(Point3::new(0.0, 0.0, -20.0));
+
+        let labels = dbscan(&points, 0.6, 5);
+        assert!(labels[..200]
+            .iter()
+            .all(|label| *label == ClusterLabel::Cluster(0)));
+        assert!(labels[200..400]
+            .iter()
+            .all(|label| *label == ClusterLabel::Cluster(1)));
+        assert_eq!(labels[400..], [ClusterLabel::Noise; 2]);
+    }
+
+    #[test]
+    fn test_dbscan_border_points() {
+        let points = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.5, 0.0),
+            Point2::new(-0.5, 0.0),
+            Point2::new(0.0, 0.5),
+            Point2::new(0.5, 0.5),
+            Point2::new(1.0, 0.0), // Border point, only reached by a core point
+            Point2::new(1.6, 0.0), // Noise, too far from any core point
+        ];
+
+        let labels = dbscan(&points, 0.5, 4);
+        assert_eq!(labels[..6], [ClusterLabel::Cluster(0); 6]);
+        assert_eq!(labels[6], ClusterLabel::Noise);
+        assert_eq!(labels[5].cluster_index(), Some(0));
+        assert_eq!(labels[6].cluster_index(), None);
+
+        // No point has enough neighbours
+        assert!(dbscan(&points, 0.5, 10)
+            .into_iter()
+            .all(|label| label == ClusterLabel::Noise));
+    }
+}
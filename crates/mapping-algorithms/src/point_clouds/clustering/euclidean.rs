@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point, Scalar};
+use num_traits::{NumOps, Zero};
+
+use crate::{kd_tree::KDTree, Vec, VecDeque};
+
+/// Groups the point cloud into clusters of connected points,
+/// where two points are connected if the distance between them is at most `distance_tolerance`.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `distance_tolerance`: the maximum distance between two points of the same cluster, inclusive.
+/// * `min_cluster_size`: clusters with less points than this are discarded.
+/// * `max_cluster_size`: clusters with more points than this are discarded.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of clusters, each being a [`Vec`] of indices into `points` in ascending order,
+/// the clusters are sorted by descending size, and then by their first index.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Extract Euclidean Clusters", skip_all, level = "info")
+)]
+pub fn extract_euclidean_clusters<T, const N: usize>(
+    points: &[Point<T, N>],
+    distance_tolerance: T,
+    min_cluster_size: usize,
+    max_cluster_size: usize,
+) -> Vec<Vec<usize>>
+where
+    T: Copy + Default + NumOps + PartialOrd + Scalar + Zero,
+{
+    let points_tree = KDTree::from(points);
+    let mut visited = Vec::from_iter(core::iter::repeat_n(false, points.len()));
+    let mut clusters = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for seed_idx in 0..points.len() {
+        if visited[seed_idx] {
+            continue;
+        }
+
+        visited[seed_idx] = true;
+        queue.push_back(seed_idx);
+        let mut cluster = Vec::new();
+        while let Some(current_idx) = queue.pop_front() {
+            cluster.push(current_idx);
+            for neighbour in points_tree.within_radius(&points[current_idx], distance_tolerance) {
+                if !visited[neighbour.index] {
+                    visited[neighbour.index] = true;
+                    queue.push_back(neighbour.index);
+                }
+            }
+        }
+
+        if (min_cluster_size..=max_cluster_size).contains(&cluster.len()) {
+            cluster.sort_unstable();
+            clusters.push(cluster);
+        }
+    }
+
+    // Clusters are discovered in order of their first index, so a stable sort keeps that as the secondary order
+    clusters.sort_by_key(|cluster| core::cmp::Reverse(cluster.len()));
+    clusters
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_euclidean_clustering {
+    ($precision:expr, doc $doc:tt, $nd:expr) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the euclidean cluster extraction function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<extract_euclidean_clusters_ $nd d>](points: &[Point<$precision, $nd>], distance_tolerance: $precision, min_cluster_size: usize, max_cluster_size: usize) -> Vec<Vec<usize>> {
+                super::extract_euclidean_clusters(points, distance_tolerance, min_cluster_size, max_cluster_size)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point;
+                use crate::Vec;
+
+                impl_euclidean_clustering!($precision, doc $doc, 2);
+                impl_euclidean_clustering!($precision, doc $doc, 3);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_euclidean_clustering!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_euclidean_clustering!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector3};
+
+    use crate::{array, point_clouds::generate_point_cloud};
+
+    use super::*;
+
+    #[test]
+    fn test_extract_euclidean_clusters() {
+        // Three obstacles of different sizes, far apart from each other
+        let mut points = Vec::new();
+        for (center, num_points) in [
+            (Vector3::new(10.0, 0.0, 0.0), 50),
+            (Vector3::new(-10.0, 5.0, 0.0), 150),
+            (Vector3::new(0.0, -10.0, 2.0), 100),
+        ] {
+            points.extend(
+                generate_point_cloud(num_points, array::from_fn(|_| -1.0..=1.0))
+                    .into_iter()
+                    .map(|point: Point3<f64>| point + center),
+            );
+        }
+
+        let clusters = extract_euclidean_clusters(&points, 0.8, 1, usize::MAX);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0], (50..200).collect::<Vec<_>>());
+        assert_eq!(clusters[1], (200..300).collect::<Vec<_>>());
+        assert_eq!(clusters[2], (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_extract_euclidean_clusters_size_limits() {
+        let points = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.5, 0.0),
+            Point2::new(1.0, 0.0), // Chain of three points
+            Point2::new(10.0, 10.0),
+            Point2::new(10.0, 10.5), // Pair
+            Point2::new(-20.0, 5.0), // Isolated point
+        ];
+
+        let clusters = extract_euclidean_clusters(&points, 0.5, 1, 10);
+        assert_eq!(
+            clusters,
+            Vec::from([Vec::from([0, 1, 2]), Vec::from([3, 4]), Vec::from([5])])
+        );
+
+        let clusters = extract_euclidean_clusters(&points, 0.5, 2, 2);
+        assert_eq!(clusters, Vec::from([Vec::from([3, 4])]));
+
+        // A tighter tolerance splits the chain
+        let clusters = extract_euclidean_clusters(&points, 0.4, 1, 10);
+        assert_eq!(clusters.len(), 6);
+    }
+
+    #[test]
+    fn test_extract_euclidean_clusters_empty() {
+        assert!(extract_euclidean_clusters::<f32, 3>(&[], 1.0, 0, 10).is_empty());
+    }
+}
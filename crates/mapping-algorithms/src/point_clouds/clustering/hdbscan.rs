@@ -0,0 +1,610 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{ComplexField, Point, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{kd_tree::KDTree, Ordering, Vec};
+
+use super::ClusterLabel;
+
+#[derive(Clone, Debug)]
+struct DisjointSet {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSet {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Create Disjoint Set", skip_all, level = "trace")
+    )]
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            sizes: Vec::from_iter(core::iter::repeat_n(1, len)),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find Set Root", skip_all, level = "trace")
+    )]
+    fn find(&mut self, mut element: usize) -> usize {
+        while self.parents[element] != element {
+            self.parents[element] = self.parents[self.parents[element]];
+            element = self.parents[element];
+        }
+        element
+    }
+
+    // Returns the root of the merged set, or None if both elements were already in the same set
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Union Sets", skip_all, level = "trace")
+    )]
+    fn union(&mut self, element_a: usize, element_b: usize) -> Option<usize> {
+        let (root_a, root_b) = (self.find(element_a), self.find(element_b));
+        if root_a == root_b {
+            return None;
+        }
+
+        let (root, child) = if self.sizes[root_a] < self.sizes[root_b] {
+            (root_b, root_a)
+        } else {
+            (root_a, root_b)
+        };
+        self.parents[child] = root;
+        self.sizes[root] += self.sizes[child];
+        Some(root)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct ReachabilityEdge<T> {
+    endpoints: [usize; 2],
+    // The squared mutual reachability distance between both endpoints
+    weight_squared: T,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct DendrogramMerge<T> {
+    children: [usize; 2],
+    distance: T,
+    size: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CondensedCluster<T> {
+    parent: usize,
+    birth_lambda: T,
+    stability: T,
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Sort Edges By Weight", skip_all, level = "trace")
+)]
+fn sort_edges<T: RealField>(edges: &mut [ReachabilityEdge<T>]) {
+    edges.sort_by(|a, b| {
+        a.weight_squared
+            .partial_cmp(&b.weight_squared)
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Calculate Mutual Reachability Graph", skip_all, level = "debug")
+)]
+fn calculate_mutual_reachability_graph<T, const N: usize>(
+    points_tree: &KDTree<T, N>,
+    points: &[Point<T, N>],
+    min_samples: usize,
+) -> (Vec<T>, Vec<ReachabilityEdge<T>>)
+where
+    T: Copy + Default + RealField,
+{
+    let neighbourhoods = points
+        .iter()
+        .map(|point| points_tree.nearest_k(point, min_samples))
+        .collect::<Vec<_>>();
+
+    // The core distance of a point is the distance to its furthest neighbour, counting the point itself
+    let core_distances_squared = neighbourhoods
+        .iter()
+        .map(|neighbours| {
+            neighbours
+                .last()
+                .map(|neighbour| neighbour.distance_squared)
+                .unwrap_or_else(T::zero)
+        })
+        .collect::<Vec<_>>();
+
+    let edges = neighbourhoods
+        .iter()
+        .enumerate()
+        .flat_map(|(point_idx, neighbours)| {
+            let core_distances_squared = &core_distances_squared;
+            neighbours
+                .iter()
+                .filter(move |neighbour| neighbour.index != point_idx)
+                .map(move |neighbour| ReachabilityEdge {
+                    endpoints: [point_idx, neighbour.index],
+                    weight_squared: core_distances_squared[point_idx]
+                        .max(core_distances_squared[neighbour.index])
+                        .max(neighbour.distance_squared),
+                })
+        })
+        .collect();
+
+    (core_distances_squared, edges)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Build Minimum Spanning Tree", skip_all, level = "debug")
+)]
+fn build_minimum_spanning_tree<T, const N: usize>(
+    points_tree: &KDTree<T, N>,
+    points: &[Point<T, N>],
+    core_distances_squared: &[T],
+    mut edges: Vec<ReachabilityEdge<T>>,
+) -> Vec<ReachabilityEdge<T>>
+where
+    T: Copy + Default + RealField,
+{
+    let mut components = DisjointSet::new(points.len());
+    sort_edges(&mut edges);
+    let mut spanning_tree = edges
+        .into_iter()
+        .filter(|edge| {
+            components
+                .union(edge.endpoints[0], edge.endpoints[1])
+                .is_some()
+        })
+        .collect::<Vec<_>>();
+
+    // The neighbour graph might be disconnected, in which case the components are bridged Borůvka-style,
+    // each round joins every component to the one reachable through its shortest bridge, at least halving their amount
+    loop {
+        let roots = (0..points.len())
+            .map(|point_idx| components.find(point_idx))
+            .collect::<Vec<_>>();
+        if roots.iter().all(|root| *root == roots[0]) {
+            break;
+        }
+
+        let mut component_bridges: Vec<Option<ReachabilityEdge<T>>> =
+            Vec::from_iter(core::iter::repeat_n(None, points.len()));
+        for (point_idx, &root) in roots.iter().enumerate() {
+            let bound = component_bridges[root].map(|edge| edge.weight_squared);
+            // No edge from this point can weigh less than its own core distance
+            if bound.is_some_and(|bound| core_distances_squared[point_idx] >= bound) {
+                continue;
+            }
+
+            // The mutual reachability distance is never below the squared distance, as the query requires
+            if let Some((neighbour, weight_squared)) =
+                points_tree.nearest_filtered(&points[point_idx], bound, |neighbour| {
+                    (roots[neighbour.index] != root).then(|| {
+                        core_distances_squared[point_idx]
+                            .max(core_distances_squared[neighbour.index])
+                            .max(neighbour.distance_squared)
+                    })
+                })
+            {
+                component_bridges[root] = Some(ReachabilityEdge {
+                    endpoints: [point_idx, neighbour.index],
+                    weight_squared,
+                });
+            }
+        }
+
+        let mut bridges = component_bridges.into_iter().flatten().collect::<Vec<_>>();
+        sort_edges(&mut bridges);
+        spanning_tree.extend(bridges.into_iter().filter(|edge| {
+            components
+                .union(edge.endpoints[0], edge.endpoints[1])
+                .is_some()
+        }));
+    }
+
+    spanning_tree
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Build Single Linkage Dendrogram", skip_all, level = "debug")
+)]
+fn build_single_linkage_dendrogram<T>(
+    points_len: usize,
+    mut spanning_tree: Vec<ReachabilityEdge<T>>,
+) -> Vec<DendrogramMerge<T>>
+where
+    T: Copy + RealField,
+{
+    sort_edges(&mut spanning_tree);
+    let mut components = DisjointSet::new(points_len);
+    // Dendrogram nodes below `points_len` are the points themselves, the rest are merges
+    let mut component_nodes = (0..points_len).collect::<Vec<_>>();
+
+    spanning_tree
+        .into_iter()
+        .enumerate()
+        .map(|(merge_idx, edge)| {
+            let children = edge
+                .endpoints
+                .map(|point_idx| component_nodes[components.find(point_idx)]);
+            let root = components
+                .union(edge.endpoints[0], edge.endpoints[1])
+                .expect("Spanning tree edges always join two separate components");
+            component_nodes[root] = points_len + merge_idx;
+
+            DendrogramMerge {
+                children,
+                distance: ComplexField::sqrt(edge.weight_squared),
+                size: components.sizes[root],
+            }
+        })
+        .collect()
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Condense Dendrogram", skip_all, level = "debug")
+)]
+fn condense_dendrogram<T>(
+    points_len: usize,
+    dendrogram: &[DendrogramMerge<T>],
+    min_cluster_size: usize,
+) -> (Vec<CondensedCluster<T>>, Vec<usize>)
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let node_size = |node: usize| {
+        node.checked_sub(points_len)
+            .map(|merge_idx| dendrogram[merge_idx].size)
+            .unwrap_or(1)
+    };
+
+    let mut clusters = Vec::from([CondensedCluster {
+        parent: 0,
+        birth_lambda: T::zero(),
+        stability: T::zero(),
+    }]);
+    let mut point_clusters = Vec::from_iter(core::iter::repeat_n(0, points_len));
+    let mut pending_nodes = Vec::from([(points_len + dendrogram.len() - 1, 0)]);
+    let mut fallen_nodes = Vec::new();
+
+    while let Some((node, cluster_idx)) = pending_nodes.pop() {
+        let Some(merge) = node
+            .checked_sub(points_len)
+            .map(|merge_idx| dendrogram[merge_idx])
+        else {
+            continue;
+        };
+
+        let lambda = if merge.distance > T::zero() {
+            T::one() / merge.distance
+        } else {
+            T::max_value().unwrap_or_else(T::one)
+        };
+        let lifetime = lambda - clusters[cluster_idx].birth_lambda;
+        let child_sizes = merge.children.map(node_size);
+
+        if child_sizes.iter().all(|&size| size >= min_cluster_size) {
+            // A true split, both children become new clusters
+            clusters[cluster_idx].stability += lifetime * merge.size.as_();
+            for child in merge.children {
+                pending_nodes.push((child, clusters.len()));
+                clusters.push(CondensedCluster {
+                    parent: cluster_idx,
+                    birth_lambda: lambda,
+                    stability: T::zero(),
+                });
+            }
+            continue;
+        }
+
+        for (child, child_size) in merge.children.into_iter().zip(child_sizes) {
+            if child_size >= min_cluster_size {
+                pending_nodes.push((child, cluster_idx));
+                continue;
+            }
+
+            // Too small to be a cluster, all points of this child fall out of the current cluster
+            clusters[cluster_idx].stability += lifetime * child_size.as_();
+            fallen_nodes.push(child);
+            while let Some(fallen_node) = fallen_nodes.pop() {
+                match fallen_node.checked_sub(points_len) {
+                    Some(merge_idx) => fallen_nodes.extend(dendrogram[merge_idx].children),
+                    None => point_clusters[fallen_node] = cluster_idx,
+                }
+            }
+        }
+    }
+
+    (clusters, point_clusters)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Select Stable Clusters", skip_all, level = "debug")
+)]
+fn select_stable_clusters<T>(clusters: &[CondensedCluster<T>]) -> Vec<Option<usize>>
+where
+    T: Copy + RealField,
+{
+    // Children are always created after their parents, so a reverse pass sees every child before its parent
+    let mut selected = Vec::from_iter(core::iter::repeat_n(false, clusters.len()));
+    let mut children_stability = Vec::from_iter(core::iter::repeat_n(T::zero(), clusters.len()));
+    for cluster_idx in (1..clusters.len()).rev() {
+        let cluster = &clusters[cluster_idx];
+        let best_stability = if cluster.stability >= children_stability[cluster_idx] {
+            selected[cluster_idx] = true;
+            cluster.stability
+        } else {
+            children_stability[cluster_idx]
+        };
+        children_stability[cluster.parent] += best_stability;
+    }
+
+    // The root cluster is never selected, each cluster is assigned to its outermost selected ancestor
+    let mut assigned_clusters = Vec::from_iter(core::iter::repeat_n(None, clusters.len()));
+    for cluster_idx in 1..clusters.len() {
+        assigned_clusters[cluster_idx] = assigned_clusters[clusters[cluster_idx].parent]
+            .or(selected[cluster_idx].then_some(cluster_idx));
+    }
+
+    assigned_clusters
+}
+
+/// Clusters the point cloud using HDBSCAN (Hierarchical Density-Based Spatial Clustering of Applications with Noise).
+///
+/// Builds a hierarchy of clusters over all density levels from the mutual reachability distances between points,
+/// and selects the most stable clusters from that hierarchy, so unlike [`dbscan`](super::dbscan) no single radius is required.
+/// The core distance of each point is the distance to its `min_cluster_size`-th nearest neighbour, counting itself.
+///
+/// The spanning tree of the hierarchy is built from the mutual reachability graph between those nearest neighbours,
+/// any separate parts of that graph are bridged through the shortest mutual reachability edges between them.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `min_cluster_size`: the smallest amount of points that is considered a cluster, values below 2 are treated as 2.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of [`ClusterLabel`], one for each point in `points`, clusters are numbered in order of their first point.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("HDBSCAN Clustering", skip_all, level = "info")
+)]
+pub fn hdbscan<T, const N: usize>(
+    points: &[Point<T, N>],
+    min_cluster_size: usize,
+) -> Vec<ClusterLabel>
+where
+    T: Copy + Default + RealField,
+    usize: AsPrimitive<T>,
+{
+    let min_cluster_size = min_cluster_size.max(2);
+    if points.len() < min_cluster_size {
+        return Vec::from_iter(core::iter::repeat_n(ClusterLabel::Noise, points.len()));
+    }
+
+    let points_tree = KDTree::from(points);
+    let (core_distances_squared, edges) =
+        calculate_mutual_reachability_graph(&points_tree, points, min_cluster_size);
+    let spanning_tree =
+        build_minimum_spanning_tree(&points_tree, points, &core_distances_squared, edges);
+    let dendrogram = build_single_linkage_dendrogram(points.len(), spanning_tree);
+    let (clusters, point_clusters) =
+        condense_dendrogram(points.len(), &dendrogram, min_cluster_size);
+    let assigned_clusters = select_stable_clusters(&clusters);
+
+    let mut cluster_labels = Vec::from_iter(core::iter::repeat_n(None, clusters.len()));
+    let mut cluster_count = 0;
+    point_clusters
+        .into_iter()
+        .map(|cluster_idx| {
+            assigned_clusters[cluster_idx].map_or(ClusterLabel::Noise, |assigned_idx| {
+                *cluster_labels[assigned_idx].get_or_insert_with(|| {
+                    cluster_count += 1;
+                    ClusterLabel::Cluster(cluster_count - 1)
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_hdbscan {
+    ($precision:expr, doc $doc:tt, $nd:expr) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the HDBSCAN clustering function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<hdbscan_ $nd d>](points: &[Point<$precision, $nd>], min_cluster_size: usize) -> Vec<ClusterLabel> {
+                super::hdbscan(points, min_cluster_size)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point;
+                use crate::Vec;
+                use super::ClusterLabel;
+
+                impl_hdbscan!($precision, doc $doc, 2);
+                impl_hdbscan!($precision, doc $doc, 3);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_hdbscan!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_hdbscan!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector2, Vector3};
+
+    use crate::{array, point_clouds::generate_point_cloud};
+
+    use super::*;
+
+    #[test]
+    fn test_hdbscan() {
+        // Three blobs of different densities, which no single DBSCAN radius would separate well
+        let mut points = Vec::new();
+        for (center, half_extent, num_points) in [
+            (Vector3::new(10.0, 0.0, 0.0), 0.5, 100),
+            (Vector3::new(-10.0, 5.0, 0.0), 2.0, 150),
+            (Vector3::new(0.0, -10.0, 2.0), 1.0, 120),
+        ] {
+            points.extend(
+                generate_point_cloud(num_points, array::from_fn(|_| -half_extent..=half_extent))
+                    .into_iter()
+                    .map(|point: Point3<f64>| point + center),
+            );
+        }
+        points.extend([
+            Point3::new(0.0, 0.0, 30.0),
+            Point3::new(30.0, 30.0, -30.0),
+            Point3::new(-30.0, 0.0, 0.0),
+        ]);
+
+        let labels = hdbscan(&points, 10);
+        for (range, expected) in [(0..100, 0), (100..250, 1), (250..370, 2)] {
+            let blob_labels = &labels[range];
+            let matching = blob_labels
+                .iter()
+                .filter(|label| **label == ClusterLabel::Cluster(expected))
+                .count();
+            // Sparse points at the fringe of a blob may be labelled as noise
+            assert!(matching as f64 >= 0.9 * blob_labels.len() as f64);
+            assert!(blob_labels
+                .iter()
+                .all(|label| matches!(label, ClusterLabel::Noise)
+                    || *label == ClusterLabel::Cluster(expected)));
+        }
+        assert_eq!(labels[370..], [ClusterLabel::Noise; 3]);
+    }
+
+    #[test]
+    fn test_hdbscan_disconnected_neighbourhoods() {
+        // Each group is smaller than the neighbourhood size, so the neighbour graph alone is disconnected
+        let mut points = Vec::new();
+        for offset in [0.0, 0.3, 50.0, 50.3] {
+            points.extend((0..4).map(|idx| Point2::new(offset + idx as f32 * 0.05, 0.0)));
+        }
+
+        let labels = hdbscan(&points, 8);
+        assert_eq!(labels[..8], [ClusterLabel::Cluster(0); 8]);
+        assert_eq!(labels[8..], [ClusterLabel::Cluster(1); 8]);
+    }
+
+    #[test]
+    fn test_disjoint_set() {
+        let mut components = DisjointSet::new(5);
+        assert!((0..5).all(|element| components.find(element) == element));
+
+        // The larger set keeps its root
+        let root = components.union(0, 1).unwrap();
+        assert_eq!(components.sizes[root], 2);
+        assert_eq!(components.union(2, 0), Some(root));
+        assert_eq!(components.sizes[root], 3);
+        assert_eq!(components.find(2), root);
+
+        // Elements of the same set are not merged again
+        assert!(components.union(1, 2).is_none());
+
+        components.union(3, 4).unwrap();
+        assert_ne!(components.find(3), root);
+        assert_eq!(components.union(4, 1), Some(root));
+        assert!((0..5).all(|element| components.find(element) == root));
+        assert_eq!(components.sizes[root], 5);
+    }
+
+    #[test]
+    fn test_sort_edges() {
+        let mut edges = [3.0, 1.0, 2.0].map(|weight_squared| ReachabilityEdge {
+            endpoints: [0, 1],
+            weight_squared,
+        });
+        sort_edges(&mut edges);
+        assert_eq!(edges.map(|edge| edge.weight_squared), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_build_minimum_spanning_tree_many_components() {
+        // A grid of tight triplets, each of them being a separate part of the neighbour graph
+        let points = (0..2500)
+            .flat_map(|group_idx| {
+                let origin = Point2::new((group_idx % 50) as f64, (group_idx / 50) as f64 * 2.0);
+                (0..3).map(move |idx| origin + Vector2::new(idx as f64 * 0.01, 0.0))
+            })
+            .collect::<Vec<_>>();
+        let points_tree = KDTree::from(points.as_slice());
+        let (core_distances_squared, edges) =
+            calculate_mutual_reachability_graph(&points_tree, &points, 3);
+
+        let spanning_tree =
+            build_minimum_spanning_tree(&points_tree, &points, &core_distances_squared, edges);
+        assert_eq!(spanning_tree.len(), points.len() - 1);
+
+        let mut components = DisjointSet::new(points.len());
+        for edge in spanning_tree.iter() {
+            assert!(components
+                .union(edge.endpoints[0], edge.endpoints[1])
+                .is_some());
+        }
+
+        // Triplets are joined along their rows, and the rows are joined to one another once each
+        let total_weight = spanning_tree
+            .iter()
+            .map(|edge| ComplexField::sqrt(edge.weight_squared))
+            .sum::<f64>();
+        let expected_weight = 2500.0 * 2.0 * 0.02 + 49.0 * 50.0 * 0.98 + 49.0 * 2.0;
+        assert!((total_weight - expected_weight).abs() < 1e-6);
+
+        let labels = hdbscan(&points, 3);
+        assert!(labels
+            .chunks(3)
+            .all(|group_labels| group_labels.iter().all(|label| *label == group_labels[0])));
+    }
+
+    #[test]
+    fn test_hdbscan_too_few_points() {
+        let points = [Point2::new(0.0, 0.0), Point2::new(1.0, 0.0)];
+        assert_eq!(hdbscan(&points, 5), [ClusterLabel::Noise; 2]);
+        assert!(hdbscan::<f64, 2>(&[], 5).is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+pub use dbscan::dbscan;
+pub use euclidean::extract_euclidean_clusters;
+pub use hdbscan::hdbscan;
+pub use types::ClusterLabel;
+
+mod dbscan;
+mod euclidean;
+mod hdbscan;
+mod types;
+
+#[cfg(feature = "pregenerated")]
+pub(super) mod single_precision {
+    pub use super::dbscan::single_precision::*;
+    pub use super::euclidean::single_precision::*;
+    pub use super::hdbscan::single_precision::*;
+}
+
+#[cfg(feature = "pregenerated")]
+pub(super) mod double_precision {
+    pub use super::dbscan::double_precision::*;
+    pub use super::euclidean::double_precision::*;
+    pub use super::hdbscan::double_precision::*;
+}
@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use core::ops::RangeInclusive;
+
+use nalgebra::{
+    AbstractRotation, ComplexField, Isometry, Isometry3, Point, Point2, Point3, RealField, Scalar,
+};
+use num_traits::{AsPrimitive, Bounded};
+
+use crate::{
+    polygons::{calculate_polygon_extents, is_single_point_in_polygon},
+    types::PolygonExtents,
+    Vec,
+};
+
+/// The viewing volume of a camera, a pyramid along the camera's Z axis, truncated by the near and far planes.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum<T> {
+    /// The full horizontal field of view, in radians, along the camera's X axis.
+    pub horizontal_fov: T,
+    /// The full vertical field of view, in radians, along the camera's Y axis.
+    pub vertical_fov: T,
+    /// The minimal depth of a point inside the frustum.
+    pub near_distance: T,
+    /// The maximal depth of a point inside the frustum.
+    pub far_distance: T,
+}
+
+#[inline]
+fn collect_indices<T, const N: usize, F>(
+    points: &[Point<T, N>],
+    invert: bool,
+    is_inside: F,
+) -> Vec<usize>
+where
+    T: Scalar,
+    F: Fn(&Point<T, N>) -> bool,
+{
+    points
+        .iter()
+        .enumerate()
+        .filter_map(|(point_idx, point)| (is_inside(point) != invert).then_some(point_idx))
+        .collect()
+}
+
+#[inline]
+fn is_within_extents<T, const N: usize>(point: &Point<T, N>, extents: &PolygonExtents<T, N>) -> bool
+where
+    T: PartialOrd + Scalar,
+{
+    point
+        .coords
+        .iter()
+        .zip(extents.iter())
+        .all(|(coordinate, range)| range.contains(coordinate))
+}
+
+/// Crops the point cloud using an axis aligned box.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `extents`: a [`PolygonExtents`], the inclusive range of the box along each axis.
+/// * `invert`: whether to return the points outside of the box instead.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of the indices of all points inside the box, or outside of it if `invert` is set, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Crop Point Cloud With Box", skip_all)
+)]
+pub fn crop_box<T, const N: usize>(
+    points: &[Point<T, N>],
+    extents: &PolygonExtents<T, N>,
+    invert: bool,
+) -> Vec<usize>
+where
+    T: PartialOrd + Scalar,
+{
+    collect_indices(points, invert, |point| is_within_extents(point, extents))
+}
+
+/// Crops the point cloud using an arbitrarily oriented box.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `box_pose`: an [`Isometry`], transforming points from the box's frame to the point cloud's frame.
+/// * `extents`: a [`PolygonExtents`], the inclusive range of the box along each axis of the box's frame.
+/// * `invert`: whether to return the points outside of the box instead.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+/// * `R`: An [`AbstractRotation`], representing the box's rotation.
+///
+/// # Returns
+/// A [`Vec`] of the indices of all points inside the box, or outside of it if `invert` is set, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Crop Point Cloud With Oriented Box", skip_all)
+)]
+pub fn crop_oriented_box<T, const N: usize, R>(
+    points: &[Point<T, N>],
+    box_pose: &Isometry<T, R, N>,
+    extents: &PolygonExtents<T, N>,
+    invert: bool,
+) -> Vec<usize>
+where
+    T: Copy + RealField,
+    R: AbstractRotation<T, N>,
+{
+    collect_indices(points, invert, |point| {
+        is_within_extents(&box_pose.inverse_transform_point(point), extents)
+    })
+}
+
+/// Filters the point cloud by the range of a single coordinate, leaving all other coordinates unbounded.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `axis`: the index of the coordinate to filter by, `0` for X, `1` for Y and so on.
+/// * `range`: the inclusive range of allowed values.
+/// * `invert`: whether to return the points outside of the range instead.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of the indices of all points within the range, or outside of it if `invert` is set, in ascending order.
+///
+/// # Panics
+/// If `axis` is not smaller than `N`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Pass Through Filter Point Cloud", skip_all)
+)]
+pub fn pass_through_filter<T, const N: usize>(
+    points: &[Point<T, N>],
+    axis: usize,
+    range: RangeInclusive<T>,
+    invert: bool,
+) -> Vec<usize>
+where
+    T: PartialOrd + Scalar,
+{
+    assert!(axis < N, "Axis {axis} does not exist in {N} dimensions");
+    collect_indices(points, invert, |point| range.contains(&point[axis]))
+}
+
+/// Crops the point cloud using a prism, made by extruding a polygon in the XY plane along the Z axis.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point3`], representing the point cloud.
+/// * `polygon`: a slice of [`Point2`], the vertices of the prism's base.
+/// * `height_range`: the inclusive range of Z coordinates the polygon is extruded over.
+/// * `invert`: whether to return the points outside of the prism instead.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`Vec`] of the indices of all points inside the prism, or outside of it if `invert` is set, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Crop Point Cloud With Polygon Prism", skip_all)
+)]
+pub fn crop_polygon_prism<T>(
+    points: &[Point3<T>],
+    polygon: &[Point2<T>],
+    height_range: RangeInclusive<T>,
+    invert: bool,
+) -> Vec<usize>
+where
+    T: Bounded + Copy + RealField,
+    f32: AsPrimitive<T>,
+{
+    // The polygon's extents are a cheap way to reject most points before the full test
+    let Some(polygon_extents) = calculate_polygon_extents(polygon) else {
+        return collect_indices(points, invert, |_| false);
+    };
+
+    collect_indices(points, invert, |point| {
+        let planar_point = point.xy();
+        height_range.contains(&point.z)
+            && is_within_extents(&planar_point, &polygon_extents)
+            && is_single_point_in_polygon(&planar_point, polygon)
+    })
+}
+
+/// Crops the point cloud to the points visible by a camera, ignoring occlusions.
+///
+/// The camera's frame follows the common computer vision convention,
+/// with the Z axis pointing forward along the optical axis, the X axis to the right and the Y axis downwards.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point3`], representing the point cloud.
+/// * `camera_pose`: an [`Isometry3`], transforming points from the camera's frame to the point cloud's frame.
+/// * `frustum`: a [`Frustum`], describing the camera's viewing volume.
+/// * `invert`: whether to return the points outside of the frustum instead.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`Vec`] of the indices of all points inside the frustum, or outside of it if `invert` is set, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Crop Point Cloud With Frustum", skip_all)
+)]
+pub fn crop_frustum<T>(
+    points: &[Point3<T>],
+    camera_pose: &Isometry3<T>,
+    frustum: &Frustum<T>,
+    invert: bool,
+) -> Vec<usize>
+where
+    T: Copy + RealField,
+{
+    let two = T::one() + T::one();
+    let horizontal_slope = ComplexField::tan(frustum.horizontal_fov / two);
+    let vertical_slope = ComplexField::tan(frustum.vertical_fov / two);
+
+    collect_indices(points, invert, |point| {
+        let camera_point = camera_pose.inverse_transform_point(point);
+        camera_point.z >= frustum.near_distance
+            && camera_point.z <= frustum.far_distance
+            && camera_point.x.abs() <= camera_point.z * horizontal_slope
+            && camera_point.y.abs() <= camera_point.z * vertical_slope
+    })
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_crop_filters {
+    ($precision:expr, doc $doc:tt, $nd:expr, $rot:ty) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the axis aligned box crop function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<crop_box_ $nd d>](points: &[Point<$precision, $nd>], extents: &PolygonExtents<$precision, $nd>, invert: bool) -> Vec<usize> {
+                super::crop_box(points, extents, invert)
+            }
+
+            #[doc = "A premade variant of the oriented box crop function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<crop_oriented_box_ $nd d>](points: &[Point<$precision, $nd>], box_pose: &Isometry<$precision, $rot, $nd>, extents: &PolygonExtents<$precision, $nd>, invert: bool) -> Vec<usize> {
+                super::crop_oriented_box(points, box_pose, extents, invert)
+            }
+
+            #[doc = "A premade variant of the pass through filter function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<pass_through_filter_ $nd d>](points: &[Point<$precision, $nd>], axis: usize, range: RangeInclusive<$precision>, invert: bool) -> Vec<usize> {
+                super::pass_through_filter(points, axis, range, invert)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use core::ops::RangeInclusive;
+                use nalgebra::{Isometry, Isometry3, Point, Point2, Point3, UnitComplex, UnitQuaternion};
+                use crate::{types::PolygonExtents, Vec};
+                use super::Frustum;
+
+                impl_crop_filters!($precision, doc $doc, 2, UnitComplex<$precision>);
+                impl_crop_filters!($precision, doc $doc, 3, UnitQuaternion<$precision>);
+
+                #[doc = "A premade variant of the polygon prism crop function, in " $doc "-precision floats."]
+                pub fn crop_polygon_prism(points: &[Point3<$precision>], polygon: &[Point2<$precision>], height_range: RangeInclusive<$precision>, invert: bool) -> Vec<usize> {
+                    super::crop_polygon_prism(points, polygon, height_range, invert)
+                }
+
+                #[doc = "A premade variant of the camera frustum crop function, in " $doc "-precision floats."]
+                pub fn crop_frustum(points: &[Point3<$precision>], camera_pose: &Isometry3<$precision>, frustum: &Frustum<$precision>, invert: bool) -> Vec<usize> {
+                    super::crop_frustum(points, camera_pose, frustum, invert)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_crop_filters!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_crop_filters!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Isometry2, Translation3, UnitQuaternion, Vector2, Vector3};
+
+    use super::*;
+
+    fn generate_grid() -> Vec<Point3<f64>> {
+        (-5..=5)
+            .flat_map(|x| {
+                (-5..=5).flat_map(move |y| {
+                    (-5..=5).map(move |z| Point3::new(x as f64, y as f64, z as f64))
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crop_box() {
+        let points = generate_grid();
+        let inside = crop_box(&points, &[-1.0..=1.0, 0.0..=2.0, 3.0..=10.0], false);
+        assert_eq!(inside.len(), 3 * 3 * 3);
+        assert!(inside.iter().all(|&idx| points[idx].x.abs() <= 1.0
+            && (0.0..=2.0).contains(&points[idx].y)
+            && points[idx].z >= 3.0));
+
+        let outside = crop_box(&points, &[-1.0..=1.0, 0.0..=2.0, 3.0..=10.0], true);
+        assert_eq!(inside.len() + outside.len(), points.len());
+        assert!(outside.iter().all(|idx| !inside.contains(idx)));
+    }
+
+    #[test]
+    fn test_crop_oriented_box() {
+        // A thin box along the diagonal of the XY plane
+        let points = (-10..=10)
+            .flat_map(|x| (-10..=10).map(move |y| Point2::new(x as f32 * 0.5, y as f32 * 0.5)))
+            .collect::<Vec<_>>();
+        let box_pose = Isometry2::new(Vector2::new(1.0, 1.0), core::f32::consts::FRAC_PI_4);
+
+        let inside = crop_oriented_box(&points, &box_pose, &[-2.0..=2.0, -0.1..=0.1], false);
+        // The diagonal points within a distance of 2 from (1, 1)
+        assert_eq!(inside.len(), 5);
+        assert!(inside.iter().all(|&idx| points[idx].x == points[idx].y));
+
+        let outside = crop_oriented_box(&points, &box_pose, &[-2.0..=2.0, -0.1..=0.1], true);
+        assert_eq!(outside.len(), points.len() - 5);
+    }
+
+    #[test]
+    fn test_pass_through_filter() {
+        let points = generate_grid();
+        let inside = pass_through_filter(&points, 2, -0.5..=1.5, false);
+        assert_eq!(inside.len(), 11 * 11 * 2);
+        assert!(inside
+            .iter()
+            .all(|&idx| [0.0, 1.0].contains(&points[idx].z)));
+
+        let outside = pass_through_filter(&points, 2, -0.5..=1.5, true);
+        assert_eq!(outside.len(), 11 * 11 * 9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_pass_through_filter_invalid_axis() {
+        pass_through_filter(&generate_grid(), 3, 0.0..=1.0, false);
+    }
+
+    #[test]
+    fn test_crop_polygon_prism() {
+        let points = generate_grid();
+        // A triangle with its right angle at the origin
+        let polygon = [
+            Point2::new(-0.5, -0.5),
+            Point2::new(3.5, -0.5),
+            Point2::new(-0.5, 3.5),
+        ];
+
+        let inside = crop_polygon_prism(&points, &polygon, 0.0..=1.0, false);
+        // The points (x, y) with x, y >= 0 and x + y <= 3, on two layers
+        assert_eq!(inside.len(), 10 * 2);
+        assert!(inside.iter().all(|&idx| {
+            let point = points[idx];
+            point.x >= 0.0 && point.y >= 0.0 && point.x + point.y <= 3.0
+        }));
+
+        let outside = crop_polygon_prism(&points, &polygon, 0.0..=1.0, true);
+        assert_eq!(outside.len(), points.len() - 20);
+        assert_eq!(
+            crop_polygon_prism(&points, &[], 0.0..=1.0, true).len(),
+            points.len()
+        );
+    }
+
+    #[test]
+    fn test_crop_frustum() {
+        let points = generate_grid();
+        // Looking along the world's X axis from (-6, 0, 0), with the image's Y axis pointing down the world's Z axis
+        let camera_pose = Isometry3::from_parts(
+            Translation3::new(-6.0, 0.0, 0.0),
+            UnitQuaternion::face_towards(&Vector3::x(), &-Vector3::z()),
+        );
+        let frustum = Frustum {
+            horizontal_fov: 2.0 * 0.7f64.atan(),
+            vertical_fov: 2.0 * 0.7f64.atan(),
+            near_distance: 2.0,
+            far_distance: 8.0,
+        };
+
+        let inside = crop_frustum(&points, &camera_pose, &frustum, false);
+        assert!(!inside.is_empty());
+        for (point_idx, point) in points.iter().enumerate() {
+            let depth = point.x + 6.0;
+            let expected = (2.0..=8.0).contains(&depth)
+                && point.y.abs() <= 0.7 * depth
+                && point.z.abs() <= 0.7 * depth;
+            assert_eq!(inside.contains(&point_idx), expected);
+        }
+
+        let outside = crop_frustum(&points, &camera_pose, &frustum, true);
+        assert_eq!(inside.len() + outside.len(), points.len());
+    }
+}
@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry3, Point3, RealField, UnitQuaternion};
+
+use crate::{point_clouds::PointCloud, Vec};
+
+/// A pose of the sensor at a specific time.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimedPose<T: RealField> {
+    /// The time of the pose, in the same units as the points' timestamps.
+    pub time: T,
+    /// The pose of the sensor in a fixed frame, such as the odometry frame.
+    pub pose: Isometry3<T>,
+}
+
+/// An error type containing the errors that might arise when deskewing a point cloud, when compiling with the `std` feature, it will also derive [`thiserror::Error`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum DeskewError {
+    /// The amount of timestamps differs from the amount of points.
+    #[cfg_attr(
+        feature = "std",
+        error("There are {actual} timestamps, but the point cloud has {expected} points")
+    )]
+    TimestampLengthMismatch {
+        /// The amount of points.
+        expected: usize,
+        /// The amount of timestamps.
+        actual: usize,
+    },
+    /// The point cloud does not have a timestamp channel.
+    #[cfg_attr(feature = "std", error("The point cloud does not have timestamps"))]
+    MissingTimestamps,
+}
+
+/// Interpolates between two poses, linearly for the translation and using SLERP for the rotation,
+/// which always follows the shortest arc between the two rotations.
+///
+/// # Arguments
+/// * `start`: an [`Isometry3`], the pose at a fraction of `0`.
+/// * `end`: an [`Isometry3`], the pose at a fraction of `1`.
+/// * `fraction`: the position between the poses, values outside of `[0, 1]` extrapolate the motion.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// An [`Isometry3`], the interpolated pose.
+#[inline]
+pub fn interpolate_pose<T: Copy + RealField>(
+    start: &Isometry3<T>,
+    end: &Isometry3<T>,
+    fraction: T,
+) -> Isometry3<T> {
+    let mut delta = start.rotation.inverse() * end.rotation;
+    // Both signs of a quaternion represent the same rotation, the positive one takes the shorter arc
+    if delta.w < T::zero() {
+        delta = UnitQuaternion::new_unchecked(-delta.into_inner());
+    }
+
+    Isometry3::from_parts(
+        start
+            .translation
+            .vector
+            .lerp(&end.translation.vector, fraction)
+            .into(),
+        start.rotation * delta.powf(fraction),
+    )
+}
+
+/// Deskews a scan taken while the sensor was moving, using a pose callback.
+/// Each point is transformed by the pose of the sensor at its own timestamp, and then into the sensor's frame at the reference time.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point3`], the scan in the sensor's frame.
+/// * `timestamps`: a slice containing the time each point was measured at.
+/// * `pose_at`: a callback returning the pose of the sensor in a fixed frame at a given time,
+///   it is called once for each point and once for `reference_time`.
+/// * `reference_time`: the time whose sensor frame the deskewed points are expressed in, usually the scan's start, middle or end.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `F`: A callback from a time to an [`Isometry3`].
+///
+/// # Returns
+/// A [`Vec`] of [`Point3`], the deskewed points in the order of `points`, or a [`DeskewError`] if the amount of timestamps does not match.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Deskew Point Cloud With Callback", skip_all, level = "info")
+)]
+pub fn deskew_point_cloud_with<T, F>(
+    points: &[Point3<T>],
+    timestamps: &[T],
+    mut pose_at: F,
+    reference_time: T,
+) -> Result<Vec<Point3<T>>, DeskewError>
+where
+    T: Copy + RealField,
+    F: FnMut(T) -> Isometry3<T>,
+{
+    if timestamps.len() != points.len() {
+        return Err(DeskewError::TimestampLengthMismatch {
+            expected: points.len(),
+            actual: timestamps.len(),
+        });
+    }
+
+    let reference_inverse = pose_at(reference_time).inverse();
+    Ok(points
+        .iter()
+        .zip(timestamps.iter())
+        .map(|(point, &timestamp)| reference_inverse * (pose_at(timestamp) * point))
+        .collect())
+}
+
+/// Deskews a scan taken while the sensor was moving, interpolating its pose between two timed poses, see [`interpolate_pose`].
+/// Each point is transformed by the pose of the sensor at its own timestamp, and then into the sensor's frame at the reference time.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point3`], the scan in the sensor's frame.
+/// * `timestamps`: a slice containing the time each point was measured at.
+/// * `start`: a [`TimedPose`], usually the sensor's pose at the scan's start.
+/// * `end`: a [`TimedPose`], usually the sensor's pose at the scan's end, points outside of the two poses' times extrapolate the motion.
+/// * `reference_time`: the time whose sensor frame the deskewed points are expressed in, usually the scan's start, middle or end.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`Vec`] of [`Point3`], the deskewed points in the order of `points`, or a [`DeskewError`] if the amount of timestamps does not match.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Deskew Point Cloud", skip_all, level = "info")
+)]
+pub fn deskew_point_cloud<T>(
+    points: &[Point3<T>],
+    timestamps: &[T],
+    start: &TimedPose<T>,
+    end: &TimedPose<T>,
+    reference_time: T,
+) -> Result<Vec<Point3<T>>, DeskewError>
+where
+    T: Copy + RealField,
+{
+    deskew_point_cloud_with(
+        points,
+        timestamps,
+        |time| interpolate_pose(&start.pose, &end.pose, pose_fraction(start, end, time)),
+        reference_time,
+    )
+}
+
+#[inline]
+fn pose_fraction<T: Copy + RealField>(start: &TimedPose<T>, end: &TimedPose<T>, time: T) -> T {
+    let duration = end.time - start.time;
+    // Without a duration there is no motion to interpolate
+    if duration.abs() <= T::default_epsilon() {
+        T::zero()
+    } else {
+        (time - start.time) / duration
+    }
+}
+
+impl<T: Copy + RealField> PointCloud<T, 3> {
+    /// Deskews the point cloud using its timestamps, interpolating the sensor's pose between two timed poses.
+    /// Normals are rotated along with their points, all other channels are kept as they are.
+    ///
+    /// # Arguments
+    /// * `start`: a [`TimedPose`], usually the sensor's pose at the scan's start.
+    /// * `end`: a [`TimedPose`], usually the sensor's pose at the scan's end.
+    /// * `reference_time`: the time whose sensor frame the deskewed points are expressed in.
+    ///
+    /// # Returns
+    /// The deskewed point cloud, or [`DeskewError::MissingTimestamps`] if the point cloud has no timestamps.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Deskew Attributed Point Cloud", skip_all, level = "info")
+    )]
+    pub fn deskew(
+        &self,
+        start: &TimedPose<T>,
+        end: &TimedPose<T>,
+        reference_time: T,
+    ) -> Result<Self, DeskewError> {
+        let timestamps = self.timestamps().ok_or(DeskewError::MissingTimestamps)?;
+        let reference_inverse = interpolate_pose(
+            &start.pose,
+            &end.pose,
+            pose_fraction(start, end, reference_time),
+        )
+        .inverse();
+        let corrections = timestamps
+            .iter()
+            .map(|&timestamp| {
+                reference_inverse
+                    * interpolate_pose(&start.pose, &end.pose, pose_fraction(start, end, timestamp))
+            })
+            .collect::<Vec<_>>();
+
+        let mut deskewed = self.clone();
+        let mut point_corrections = corrections.iter();
+        deskewed.map_points(|point| {
+            point_corrections
+                .next()
+                .expect("There is a correction for every point")
+                * *point
+        });
+        if let Some(normals) = self.normals() {
+            let rotated_normals = normals
+                .iter()
+                .zip(corrections.iter())
+                .map(|(normal, correction)| correction.rotation * normal)
+                .collect();
+            deskewed = deskewed
+                .with_normals(rotated_normals)
+                .expect("Channel length matches");
+        }
+
+        Ok(deskewed)
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_deskew {
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::{Isometry3, Point3};
+                use super::{DeskewError, TimedPose};
+                use crate::Vec;
+
+                #[doc = "A premade variant of the pose interpolation function, in " $doc "-precision floats."]
+                pub fn interpolate_pose(start: &Isometry3<$precision>, end: &Isometry3<$precision>, fraction: $precision) -> Isometry3<$precision> {
+                    super::interpolate_pose(start, end, fraction)
+                }
+
+                #[doc = "A premade variant of the callback based deskewing function, in " $doc "-precision floats."]
+                pub fn deskew_point_cloud_with<F: FnMut($precision) -> Isometry3<$precision>>(points: &[Point3<$precision>], timestamps: &[$precision], pose_at: F, reference_time: $precision) -> Result<Vec<Point3<$precision>>, DeskewError> {
+                    super::deskew_point_cloud_with(points, timestamps, pose_at, reference_time)
+                }
+
+                #[doc = "A premade variant of the interpolating deskewing function, in " $doc "-precision floats."]
+                pub fn deskew_point_cloud(points: &[Point3<$precision>], timestamps: &[$precision], start: &TimedPose<$precision>, end: &TimedPose<$precision>, reference_time: $precision) -> Result<Vec<Point3<$precision>>, DeskewError> {
+                    super::deskew_point_cloud(points, timestamps, start, end, reference_time)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_deskew!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_deskew!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::{PI, TAU};
+
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    use super::*;
+
+    const SCAN_DURATION: f64 = 0.1;
+
+    // The sensor drives forward at 10 m/s while turning at 1 rad/s
+    fn sensor_pose(time: f64) -> Isometry3<f64> {
+        Isometry3::new(Vector3::new(10.0 * time, 0.0, 0.0), Vector3::z() * time)
+    }
+
+    // A rotating LiDAR sweeping a static cylinder of radius 10 around its starting position,
+    // returning the measured points in the sensor's frame, their timestamps and their true positions in the world
+    fn generate_scan() -> (Vec<Point3<f64>>, Vec<f64>, Vec<Point3<f64>>) {
+        let num_points = 720;
+        let mut measured = Vec::with_capacity(num_points);
+        let mut timestamps = Vec::with_capacity(num_points);
+        let mut world = Vec::with_capacity(num_points);
+        for point_idx in 0..num_points {
+            let time = SCAN_DURATION * point_idx as f64 / num_points as f64;
+            let angle = TAU * point_idx as f64 / num_points as f64 - PI;
+            let world_point = Point3::new(
+                10.0 * angle.cos(),
+                10.0 * angle.sin(),
+                (point_idx % 8) as f64 * 0.1,
+            );
+            measured.push(sensor_pose(time).inverse_transform_point(&world_point));
+            timestamps.push(time);
+            world.push(world_point);
+        }
+        (measured, timestamps, world)
+    }
+
+    #[test]
+    fn test_interpolate_pose() {
+        let start = Isometry3::new(Vector3::new(1.0f64, 2.0, 3.0), Vector3::z() * 0.2);
+        let end = Isometry3::new(Vector3::new(3.0, 2.0, 1.0), Vector3::z() * 0.6);
+        assert!(
+            (interpolate_pose(&start, &end, 0.0).to_homogeneous() - start.to_homogeneous()).norm()
+                < 1e-12
+        );
+        assert!(
+            (interpolate_pose(&start, &end, 1.0).to_homogeneous() - end.to_homogeneous()).norm()
+                < 1e-12
+        );
+        let middle = interpolate_pose(&start, &end, 0.5);
+        assert!((middle.translation.vector - Vector3::new(2.0, 2.0, 2.0)).norm() < 1e-12);
+        assert!((middle.rotation.angle() - 0.4).abs() < 1e-12);
+        assert!((interpolate_pose(&start, &end, 1.5).rotation.angle() - 0.8).abs() < 1e-12);
+
+        // The rotation takes the shorter arc, across the half-turn
+        let before_half_turn = Isometry3::from_parts(
+            Vector3::zeros().into(),
+            UnitQuaternion::from_euler_angles(0.0, 0.0, PI - 0.1),
+        );
+        let after_half_turn = Isometry3::from_parts(
+            Vector3::zeros().into(),
+            UnitQuaternion::from_euler_angles(0.0, 0.0, -PI + 0.1),
+        );
+        let across = interpolate_pose(&before_half_turn, &after_half_turn, 0.5);
+        assert!((across.rotation.angle() - PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_deskew_point_cloud() {
+        let (measured, timestamps, world) = generate_scan();
+        let start = TimedPose {
+            time: 0.0,
+            pose: sensor_pose(0.0),
+        };
+        let end = TimedPose {
+            time: SCAN_DURATION,
+            pose: sensor_pose(SCAN_DURATION),
+        };
+
+        // The skewed scan is several centimetres off, the deskewed scan is only off by the interpolation's error
+        let end_inverse = end.pose.inverse();
+        let max_error = |points: &[Point3<f64>]| {
+            points
+                .iter()
+                .zip(world.iter())
+                .map(|(point, world_point)| (point - end_inverse * world_point).norm())
+                .fold(0.0, f64::max)
+        };
+        assert!(max_error(&measured) > 0.5);
+        let deskewed =
+            deskew_point_cloud(&measured, &timestamps, &start, &end, SCAN_DURATION).unwrap();
+        assert!(max_error(&deskewed) < 1e-3);
+
+        let with_callback =
+            deskew_point_cloud_with(&measured, &timestamps, sensor_pose, SCAN_DURATION).unwrap();
+        assert!(max_error(&with_callback) < 1e-9);
+
+        assert_eq!(
+            deskew_point_cloud(&measured, &timestamps[1..], &start, &end, 0.0),
+            Err(DeskewError::TimestampLengthMismatch {
+                expected: 720,
+                actual: 719
+            })
+        );
+
+        // Without motion, deskewing changes nothing
+        let still = TimedPose {
+            time: 0.0,
+            pose: sensor_pose(0.05),
+        };
+        let unchanged = deskew_point_cloud(&measured, &timestamps, &still, &still, 0.05).unwrap();
+        for (point, measured_point) in unchanged.iter().zip(measured.iter()) {
+            assert!((point - measured_point).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_deskew_attributed_point_cloud() {
+        let (measured, timestamps, world) = generate_scan();
+        let start = TimedPose {
+            time: 0.0,
+            pose: sensor_pose(0.0),
+        };
+        let end = TimedPose {
+            time: SCAN_DURATION,
+            pose: sensor_pose(SCAN_DURATION),
+        };
+        let cloud = PointCloud::new(measured.clone());
+        assert_eq!(
+            cloud.deskew(&start, &end, 0.0).err(),
+            Some(DeskewError::MissingTimestamps)
+        );
+
+        let normals = timestamps
+            .iter()
+            .map(|&time| sensor_pose(time).inverse_transform_vector(&Vector3::x()))
+            .collect();
+        let cloud = cloud
+            .with_timestamps(timestamps.clone())
+            .unwrap()
+            .with_normals(normals)
+            .unwrap();
+        let deskewed = cloud.deskew(&start, &end, 0.0).unwrap();
+        assert_eq!(deskewed.timestamps(), Some(timestamps.as_slice()));
+        for (point, world_point) in deskewed.iter().zip(world.iter()) {
+            assert!((point - world_point).norm() < 1e-3);
+        }
+        for normal in deskewed.normals().unwrap() {
+            assert!((normal - Vector3::x()).norm() < 1e-3);
+        }
+    }
+}
@@ -21,10 +21,61 @@
  * SOFTWARE.
  */
 
-use nalgebra::{ComplexField, Point, Scalar};
-use num_traits::{AsPrimitive, NumAssign};
+use nalgebra::{ComplexField, Point, RealField, Scalar};
+use num_traits::{AsPrimitive, NumAssign, NumOps};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
 
-use crate::{array, HashMap, Vec};
+use crate::{array, point_clouds::PointNormal, utils::distance_squared, HashMap, Vec};
+
+// Moves a uniformly drawn subset of `amount` elements to the start of the slice, in random order
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Partially Shuffle Elements", skip_all, level = "debug")
+)]
+fn partial_shuffle<E>(elements: &mut [E], amount: usize, rng: &mut SmallRng) {
+    for current_idx in 0..amount.min(elements.len()) {
+        let swap_idx = rng.gen_range(current_idx..elements.len());
+        elements.swap(current_idx, swap_idx);
+    }
+}
+
+/// Groups the indices of all points by the voxel containing them.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `voxel_size`: a floating point number, specifying the size for each voxel.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `O`: Either an [`f32`] or [`f64`], used for the voxel computations.
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`HashMap`] from each occupied voxel's integer coordinates to the indices of the points inside it, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Group Points By Voxel", skip_all, level = "debug")
+)]
+pub(crate) fn group_points_by_voxel<T, O, const N: usize>(
+    points: &[Point<T, N>],
+    voxel_size: O,
+) -> HashMap<[isize; N], Vec<usize>>
+where
+    O: AsPrimitive<isize> + ComplexField + Copy,
+    T: AsPrimitive<O> + Scalar,
+{
+    let mut voxel_map: HashMap<[isize; N], Vec<usize>> = HashMap::new();
+    for (point_idx, point) in points.iter().enumerate() {
+        let voxel_coords: [isize; N] = array::from_fn(|idx| {
+            (AsPrimitive::<O>::as_(point[idx]) / voxel_size)
+                .floor()
+                .as_()
+        });
+        voxel_map.entry(voxel_coords).or_default().push(point_idx);
+    }
+
+    voxel_map
+}
 
 /// Downsample a points cloud, returning a new point cloud, with all points within each voxel combined into their mean.
 ///
@@ -55,35 +106,382 @@ pub fn downsample_point_cloud_voxel<T, O, const N: usize>(
     T: AsPrimitive<O> + Scalar + NumAssign,
     usize: AsPrimitive<T>,
 {
-    let mut voxel_map: HashMap<[isize; N], Vec<Point<T, N>>> = HashMap::new();
-
-    // Assign points to voxels
-    for point in points {
-        let voxel_coords: [isize; N] = array::from_fn(|idx| {
-            (AsPrimitive::<O>::as_(point[idx]) / voxel_size)
-                .floor()
-                .as_()
-        });
-        voxel_map.entry(voxel_coords).or_default().push(*point);
-    }
-
     // Compute centroid for each voxel and collect them as the downsampled points
-    voxel_map
+    group_points_by_voxel(points, voxel_size)
         .into_values()
-        .map(|points_in_voxel| {
-            let num_points = points_in_voxel.len().as_();
-            let sum = points_in_voxel
+        .map(|indices_in_voxel| {
+            let num_points = indices_in_voxel.len().as_();
+            let sum = indices_in_voxel
                 .into_iter()
-                .fold(Point::default(), |acc, p| acc + p.coords);
+                .fold(Point::default(), |acc, point_idx| {
+                    acc + points[point_idx].coords
+                });
             sum / num_points
         })
         .collect()
 }
 
+/// Downsample a point cloud by drawing a uniformly random subset of its points.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `num_samples`: the amount of points to keep, if this is not smaller than the point cloud, all points are kept.
+/// * `seed`: the seed for the random number generator, identical seeds and inputs produce identical results.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of the kept points' indices, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Downsample Point Cloud Randomly", skip_all)
+)]
+pub fn downsample_point_cloud_random<T, const N: usize>(
+    points: &[Point<T, N>],
+    num_samples: usize,
+    seed: u64,
+) -> Vec<usize>
+where
+    T: Scalar,
+{
+    let mut indices = (0..points.len()).collect::<Vec<_>>();
+    partial_shuffle(
+        &mut indices,
+        num_samples,
+        &mut SmallRng::seed_from_u64(seed),
+    );
+    indices.truncate(num_samples);
+    indices.sort_unstable();
+    indices
+}
+
+/// Downsample a point cloud using a uniform grid, keeping the original point closest to the center of each voxel,
+/// unlike [`downsample_point_cloud_voxel`], no new points are synthesised.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `voxel_size`: a floating point number, specifying the size for each voxel.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `O`: Either an [`f32`] or [`f64`], used for the voxel computations.
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of the kept points' indices, in ascending order, exactly one for each occupied voxel.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Downsample Point Cloud To Voxel Centers", skip_all)
+)]
+pub fn downsample_point_cloud_voxel_nearest<T, O, const N: usize>(
+    points: &[Point<T, N>],
+    voxel_size: O,
+) -> Vec<usize>
+where
+    O: AsPrimitive<isize> + Copy + RealField,
+    T: AsPrimitive<O> + Scalar,
+{
+    let half = O::one() / (O::one() + O::one());
+    let mut voxel_map: HashMap<[isize; N], (usize, O)> = HashMap::new();
+
+    for (point_idx, point) in points.iter().enumerate() {
+        let scaled: [O; N] = array::from_fn(|idx| AsPrimitive::<O>::as_(point[idx]) / voxel_size);
+        let voxel_coords: [isize; N] = array::from_fn(|idx| scaled[idx].floor().as_());
+        // The squared distance from the voxel's center, measured in voxel units
+        let center_distance = scaled.iter().fold(O::zero(), |acc, &it| {
+            let offset = it - it.floor() - half;
+            acc + offset * offset
+        });
+
+        voxel_map
+            .entry(voxel_coords)
+            .and_modify(|(best_idx, best_distance)| {
+                if center_distance < *best_distance {
+                    *best_idx = point_idx;
+                    *best_distance = center_distance;
+                }
+            })
+            .or_insert((point_idx, center_distance));
+    }
+
+    let mut indices = voxel_map
+        .into_values()
+        .map(|(point_idx, _)| point_idx)
+        .collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices
+}
+
+/// Downsample a point cloud using farthest point sampling,
+/// where each kept point is the one farthest away from all previously kept points.
+/// Every point is kept at most once, coincident points are only kept after all distinct points were.
+/// Sampling always starts from the first point, and takes `O(points * num_samples)` time.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `num_samples`: the amount of points to keep, if this is not smaller than the point cloud, all points are kept.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of the kept points' indices, in the order they were selected,
+/// so every prefix of it is a farthest point sampling as well.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Downsample Point Cloud Using Farthest Points", skip_all)
+)]
+pub fn downsample_point_cloud_farthest_point<T, const N: usize>(
+    points: &[Point<T, N>],
+    num_samples: usize,
+) -> Vec<usize>
+where
+    T: Copy + Default + NumOps + PartialOrd + Scalar,
+{
+    let num_samples = num_samples.min(points.len());
+    let mut selected = Vec::with_capacity(num_samples);
+    if num_samples == 0 {
+        return selected;
+    }
+
+    selected.push(0);
+    let mut is_selected = Vec::from_iter(core::iter::repeat_n(false, points.len()));
+    is_selected[0] = true;
+    let mut nearest_distances = points
+        .iter()
+        .map(|point| distance_squared(point, &points[0]))
+        .collect::<Vec<_>>();
+
+    while selected.len() < num_samples {
+        // Points coinciding with kept ones have a zero distance, so they are only picked once all distinct points were kept
+        let farthest = nearest_distances
+            .iter()
+            .enumerate()
+            .filter(|(point_idx, _)| !is_selected[*point_idx])
+            .fold(
+                None,
+                |farthest: Option<(usize, T)>, (point_idx, &distance)| {
+                    if farthest.is_none_or(|(_, farthest_distance)| distance > farthest_distance) {
+                        Some((point_idx, distance))
+                    } else {
+                        farthest
+                    }
+                },
+            );
+        let Some((farthest_idx, _)) = farthest else {
+            break;
+        };
+        selected.push(farthest_idx);
+        is_selected[farthest_idx] = true;
+
+        for (nearest_distance, point) in nearest_distances.iter_mut().zip(points.iter()) {
+            let distance = distance_squared(point, &points[farthest_idx]);
+            if distance < *nearest_distance {
+                *nearest_distance = distance;
+            }
+        }
+    }
+
+    selected
+}
+
+/// Downsample a point cloud using normal space sampling,
+/// points are grouped into buckets by their normal's direction, and samples are drawn evenly from all buckets.
+/// Normals are only defined up to their sign, so opposite normals are considered the same orientation, and share a bucket.
+/// This keeps points on small but distinctly oriented surfaces, which constrain registration algorithms such as [`icp`](crate::point_clouds::icp).
+///
+/// # Arguments
+/// * `normals`: a slice of [`Option<PointNormal>`], as returned by [`estimate_normals`](crate::point_clouds::estimate_normals), points without a normal are never kept.
+/// * `num_samples`: the amount of points to keep, if there are not enough points with normals, all of them are kept.
+/// * `bins_per_axis`: the amount of buckets along each axis of the normal space.
+/// * `seed`: the seed for the random number generator, identical seeds and inputs produce identical results.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] of the kept points' indices, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Downsample Point Cloud In Normal Space", skip_all)
+)]
+pub fn downsample_point_cloud_normal_space<T, const N: usize>(
+    normals: &[Option<PointNormal<T, N>>],
+    num_samples: usize,
+    bins_per_axis: usize,
+    seed: u64,
+) -> Vec<usize>
+where
+    T: AsPrimitive<usize> + Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let bins_per_axis = bins_per_axis.max(1);
+    let mut bucket_map: HashMap<[usize; N], Vec<usize>> = HashMap::new();
+    for (point_idx, normal) in normals.iter().enumerate() {
+        let Some(normal) = normal else {
+            continue;
+        };
+
+        // Flip the normal so that its largest component is positive, the sign of a near-zero component is only noise
+        let largest = normal.normal.iter().fold(T::zero(), |largest, component| {
+            if component.abs() > largest.abs() {
+                *component
+            } else {
+                largest
+            }
+        });
+        let sign = if largest < T::zero() {
+            -T::one()
+        } else {
+            T::one()
+        };
+
+        // Components are within [-1, 1], and are mapped onto [0, bins_per_axis)
+        let bucket_coords: [usize; N] = array::from_fn(|idx| {
+            let scaled = (normal.normal[idx] * sign + T::one()) / (T::one() + T::one())
+                * bins_per_axis.as_();
+            AsPrimitive::<usize>::as_(scaled.max(T::zero()).floor()).min(bins_per_axis - 1)
+        });
+        bucket_map.entry(bucket_coords).or_default().push(point_idx);
+    }
+
+    // Sorted, so the result does not depend on the map's iteration order
+    let mut buckets = bucket_map.into_iter().collect::<Vec<_>>();
+    buckets.sort_unstable_by_key(|(bucket_coords, _)| *bucket_coords);
+
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut buckets = buckets
+        .into_iter()
+        .map(|(_, mut bucket)| {
+            let bucket_len = bucket.len();
+            partial_shuffle(&mut bucket, bucket_len, &mut rng);
+            bucket
+        })
+        .collect::<Vec<_>>();
+    let buckets_len = buckets.len();
+    partial_shuffle(&mut buckets, buckets_len, &mut rng);
+
+    // Draw from each bucket in turn, so sparsely populated orientations are kept as well
+    let mut selected = Vec::new();
+    let mut round = 0;
+    while selected.len() < num_samples {
+        let previous_len = selected.len();
+        selected.extend(
+            buckets
+                .iter()
+                .filter_map(|bucket| bucket.get(round).copied())
+                .take(num_samples - previous_len),
+        );
+        if selected.len() == previous_len {
+            break;
+        }
+        round += 1;
+    }
+
+    selected.sort_unstable();
+    selected
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_downsample {
+    ($precision:expr, doc $doc:tt, $nd:expr) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the random downsampling function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<downsample_point_cloud_random_ $nd d>](points: &[Point<$precision, $nd>], num_samples: usize, seed: u64) -> Vec<usize> {
+                super::downsample_point_cloud_random(points, num_samples, seed)
+            }
+
+            #[doc = "A premade variant of the voxel nearest downsampling function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<downsample_point_cloud_voxel_nearest_ $nd d>](points: &[Point<$precision, $nd>], voxel_size: $precision) -> Vec<usize> {
+                super::downsample_point_cloud_voxel_nearest(points, voxel_size)
+            }
+
+            #[doc = "A premade variant of the farthest point downsampling function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<downsample_point_cloud_farthest_point_ $nd d>](points: &[Point<$precision, $nd>], num_samples: usize) -> Vec<usize> {
+                super::downsample_point_cloud_farthest_point(points, num_samples)
+            }
+
+            #[doc = "A premade variant of the normal space downsampling function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<downsample_point_cloud_normal_space_ $nd d>](normals: &[Option<PointNormal<$precision, $nd>>], num_samples: usize, bins_per_axis: usize, seed: u64) -> Vec<usize> {
+                super::downsample_point_cloud_normal_space(normals, num_samples, bins_per_axis, seed)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point;
+                use crate::{point_clouds::PointNormal, Vec};
+
+                impl_downsample!($precision, doc $doc, 2);
+                impl_downsample!($precision, doc $doc, 3);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_downsample!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_downsample!(f64, doc double);
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nalgebra::Point3;
+    use nalgebra::{Point2, Point3, Vector3};
+
+    use crate::point_clouds::generate_point_cloud;
+
+    #[test]
+    fn test_partial_shuffle() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let mut elements = (0..20).collect::<Vec<_>>();
+        partial_shuffle(&mut elements, 5, &mut rng);
+
+        // All elements are kept, only their order changes
+        let mut sorted = elements.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert_ne!(elements[..5], [0, 1, 2, 3, 4]);
+
+        // Identical seeds produce identical shuffles
+        let mut repeated = (0..20).collect::<Vec<_>>();
+        partial_shuffle(&mut repeated, 5, &mut SmallRng::seed_from_u64(42));
+        assert_eq!(elements[..5], repeated[..5]);
+
+        // Amounts larger than the slice shuffle the whole slice
+        let mut short = [1, 2, 3];
+        partial_shuffle(&mut short, 10, &mut rng);
+        short.sort_unstable();
+        assert_eq!(short, [1, 2, 3]);
+
+        let mut empty: [usize; 0] = [];
+        partial_shuffle(&mut empty, 3, &mut rng);
+    }
+
+    #[test]
+    fn test_group_points_by_voxel() {
+        let point_cloud = [
+            Point2::new(0.1, 0.1),
+            Point2::new(-0.1, 0.1),
+            Point2::new(0.4, 0.2),
+            Point2::new(1.2, -0.7),
+            Point2::new(-0.4, 0.3),
+        ];
+
+        let voxels = group_points_by_voxel(point_cloud.as_slice(), 0.5);
+        assert_eq!(voxels.len(), 3);
+        assert_eq!(voxels[&[0, 0]], [0, 2]);
+        assert_eq!(voxels[&[-1, 0]], [1, 4]);
+        assert_eq!(voxels[&[2, -2]], [3]);
+
+        assert!(group_points_by_voxel::<f64, f64, 2>(&[], 0.5).is_empty());
+    }
 
     #[test]
     fn test_downsample_point_cloud() {
@@ -107,4 +505,161 @@ fn test_downsample_point_cloud() {
             .iter()
             .any(|element| *element == Point3::new(-5.95, -5.0, -3.95)));
     }
+
+    #[test]
+    fn test_downsample_point_cloud_random() {
+        let point_cloud: Vec<Point3<f32>> =
+            generate_point_cloud(100, array::from_fn(|_| -10.0..=10.0));
+
+        let res = downsample_point_cloud_random(&point_cloud, 30, 42);
+        assert_eq!(res.len(), 30);
+        assert!(res.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(res.iter().all(|&idx| idx < point_cloud.len()));
+
+        // Identical seeds produce identical samples
+        assert_eq!(res, downsample_point_cloud_random(&point_cloud, 30, 42));
+        assert_ne!(res, downsample_point_cloud_random(&point_cloud, 30, 43));
+
+        assert_eq!(
+            downsample_point_cloud_random(&point_cloud, 200, 42),
+            (0..100).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_downsample_point_cloud_voxel_nearest() {
+        let point_cloud = [
+            Point3::new(0.1, 0.1, 0.1),
+            Point3::new(0.26, 0.24, 0.25), // Closest to the center of the first voxel
+            Point3::new(0.4, 0.4, 0.4),
+            Point3::new(-0.3, 0.2, 0.2),
+            Point3::new(1.0, 2.0, 3.0),
+            Point3::new(1.2, 2.2, 3.2),
+        ];
+
+        let res = downsample_point_cloud_voxel_nearest(point_cloud.as_slice(), 0.5);
+        assert_eq!(res, [1, 3, 5]);
+    }
+
+    #[test]
+    fn test_downsample_point_cloud_farthest_point() {
+        let point_cloud = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.1, 0.0),
+            Point2::new(10.0, 0.0),
+            Point2::new(10.0, 0.1),
+            Point2::new(5.0, 8.0),
+            Point2::new(5.1, 8.0),
+        ];
+
+        assert_eq!(
+            downsample_point_cloud_farthest_point(&point_cloud, 3),
+            [0, 3, 4]
+        );
+        assert_eq!(
+            downsample_point_cloud_farthest_point(&point_cloud, 10).len(),
+            6
+        );
+        assert!(downsample_point_cloud_farthest_point(&point_cloud, 0).is_empty());
+    }
+
+    #[test]
+    fn test_downsample_point_cloud_farthest_point_coincident_points() {
+        let point_cloud = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+        ];
+
+        assert_eq!(
+            downsample_point_cloud_farthest_point(&point_cloud, 3),
+            [0, 2, 1]
+        );
+        assert_eq!(
+            downsample_point_cloud_farthest_point(&[Point2::new(1.0, 1.0); 4], 10),
+            [0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_downsample_point_cloud_normal_space() {
+        // A large floor and a small wall, uniform sampling would mostly keep floor points
+        let normals = (0..1000)
+            .map(|idx| {
+                Some(PointNormal {
+                    normal: if idx < 950 {
+                        Vector3::new(0.0, 0.0, 1.0)
+                    } else {
+                        Vector3::new(1.0, 0.0, 0.0)
+                    },
+                    curvature: 0.0,
+                })
+            })
+            .chain([None])
+            .collect::<Vec<_>>();
+
+        let res = downsample_point_cloud_normal_space(&normals, 100, 4, 42);
+        assert_eq!(res.len(), 100);
+        assert!(res.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(res.iter().filter(|&&idx| idx >= 950).count(), 50);
+        assert!(!res.contains(&1000));
+
+        assert_eq!(
+            res,
+            downsample_point_cloud_normal_space(&normals, 100, 4, 42)
+        );
+        assert_eq!(
+            downsample_point_cloud_normal_space(&normals, 5000, 4, 42).len(),
+            1000
+        );
+    }
+
+    #[test]
+    fn test_downsample_point_cloud_normal_space_opposite_normals() {
+        // Floor normals point both up and down, but are still a single orientation
+        let normals = (0..1000)
+            .map(|idx| {
+                Some(PointNormal {
+                    normal: if idx >= 950 {
+                        Vector3::new(-1.0, 0.0, 0.0)
+                    } else if idx % 2 == 0 {
+                        Vector3::new(0.0, 0.0, 1.0)
+                    } else {
+                        Vector3::new(0.0, 0.0, -1.0)
+                    },
+                    curvature: 0.0,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let res = downsample_point_cloud_normal_space(&normals, 100, 4, 42);
+        assert_eq!(res.len(), 100);
+        assert_eq!(res.iter().filter(|&&idx| idx >= 950).count(), 50);
+    }
+
+    #[test]
+    fn test_downsample_point_cloud_normal_space_noisy_normals() {
+        // Nearly axis-aligned normals, as estimated from real scans, have tiny components of either sign
+        let noise = |idx: usize| ((idx * 7) % 11) as f64 * 1e-7 - 5e-7;
+        let normals = (0..1000)
+            .map(|idx| {
+                let normal = if idx >= 950 {
+                    Vector3::new(1.0, noise(idx), noise(idx + 1))
+                } else if idx % 2 == 0 {
+                    Vector3::new(noise(idx), noise(idx + 1), 1.0)
+                } else {
+                    Vector3::new(noise(idx), noise(idx + 1), -1.0)
+                };
+                Some(PointNormal {
+                    normal: normal.normalize(),
+                    curvature: 0.0,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // The floor is still a single bucket, so the wall keeps half of the samples, an odd bin count keeps zero inside a bin
+        let res = downsample_point_cloud_normal_space(&normals, 100, 3, 42);
+        assert_eq!(res.len(), 100);
+        assert_eq!(res.iter().filter(|&&idx| idx >= 950).count(), 50);
+    }
 }
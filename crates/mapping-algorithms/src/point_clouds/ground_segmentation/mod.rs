@@ -0,0 +1,420 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{ComplexField, Point3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{
+    point_clouds::{calculate_point_cloud_center, normals::calculate_normal, PlaneModel},
+    Ordering, Vec,
+};
+
+pub use types::{
+    ConcentricZone, GroundPlane, GroundSegmentation, GroundSegmentationConfiguration,
+    GroundSegmentationConfigurationBuilder,
+};
+
+mod types;
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Locate Polar Grid Bin", skip_all, level = "trace")
+)]
+fn locate_bin<T>(
+    point: &Point3<T>,
+    config: &GroundSegmentationConfiguration<T>,
+) -> Option<(usize, usize, usize)>
+where
+    T: AsPrimitive<usize> + Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let range = ComplexField::sqrt(point.x * point.x + point.y * point.y);
+    if range < config.min_range {
+        return None;
+    }
+
+    let mut zone_start = config.min_range;
+    for (zone_idx, zone) in config.zones.iter().enumerate() {
+        if range >= zone.max_range {
+            zone_start = zone.max_range;
+            continue;
+        }
+
+        let (num_rings, num_sectors) = (zone.num_rings.max(1), zone.num_sectors.max(1));
+        let ring_width = (zone.max_range - zone_start) / num_rings.as_();
+        let ring_idx: usize = ((range - zone_start) / ring_width).floor().as_();
+
+        // Shifted from (-pi, pi] to (0, 2pi]
+        let azimuth = point.y.atan2(point.x) + T::pi();
+        let sector_idx: usize = (azimuth / (T::two_pi() / num_sectors.as_())).floor().as_();
+        return Some((
+            zone_idx,
+            ring_idx.min(num_rings - 1),
+            sector_idx.min(num_sectors - 1),
+        ));
+    }
+
+    None
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Fit Bin Ground Plane", skip_all, level = "debug")
+)]
+fn fit_bin_plane<T>(
+    points: &[Point3<T>],
+    bin: &mut [usize],
+    config: &GroundSegmentationConfiguration<T>,
+) -> Option<(PlaneModel<T>, Point3<T>, Vec<usize>)>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    bin.sort_by(|a, b| {
+        points[*a]
+            .z
+            .partial_cmp(&points[*b].z)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    // The mean height of the lowest points is a robust estimate of the ground, even with some noise below it
+    let num_seed_points = config.num_seed_points.clamp(1, bin.len());
+    let lowest_mean_height = bin[..num_seed_points]
+        .iter()
+        .fold(T::zero(), |acc, &point_idx| acc + points[point_idx].z)
+        / num_seed_points.as_();
+    let mut ground = bin
+        .iter()
+        .copied()
+        .take_while(|&point_idx| {
+            points[point_idx].z < lowest_mean_height + config.seed_height_threshold
+        })
+        .collect::<Vec<_>>();
+
+    let mut fitted_plane = None;
+    for _ in 0..config.num_iterations.max(1) {
+        let ground_points = ground
+            .iter()
+            .map(|&point_idx| points[point_idx])
+            .collect::<Vec<_>>();
+        let normal = calculate_normal(&ground_points)?.normal;
+        let normal = if normal.z < T::zero() {
+            -normal
+        } else {
+            normal
+        };
+        let centroid = calculate_point_cloud_center(&ground_points);
+        let plane = PlaneModel {
+            normal,
+            offset: -normal.dot(&centroid.coords),
+        };
+
+        // Points below the plane are kept as well, as they are more likely to be ground than anything else
+        ground = bin
+            .iter()
+            .copied()
+            .filter(|&point_idx| {
+                plane.normal.dot(&points[point_idx].coords) + plane.offset
+                    < config.distance_threshold
+            })
+            .collect();
+        fitted_plane = Some((plane, centroid));
+    }
+
+    fitted_plane.map(|(plane, centroid)| (plane, centroid, ground))
+}
+
+/// Segments a 3D LiDAR scan into ground and non-ground points,
+/// using a concentric zone model similar to Patchwork.
+///
+/// The scan is split into a polar grid of bins, with larger bins further away from the sensor.
+/// A plane is iteratively fitted to the lowest points of each bin, and the bin's plane is accepted as ground
+/// if it is upright enough and not elevated too far above the expected ground height.
+/// Points below, or slightly above, an accepted plane are classified as ground.
+///
+/// The point cloud may first be reduced using [`downsample_point_cloud_voxel`](crate::point_clouds::downsample_point_cloud_voxel),
+/// in which case the resulting indices refer to the downsampled point cloud.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point3`], representing the scan in the sensor's frame, with the Z axis pointing upwards.
+/// * `config`: a [`GroundSegmentationConfiguration`], specifying the grid and the plane acceptance thresholds.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`GroundSegmentation`], containing the ground and non-ground indices, and all accepted [`GroundPlane`]s.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Segment Ground", skip_all, level = "info")
+)]
+pub fn segment_ground<T>(
+    points: &[Point3<T>],
+    config: GroundSegmentationConfiguration<T>,
+) -> GroundSegmentation<T>
+where
+    T: AsPrimitive<usize> + Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let zone_offsets = config
+        .zones
+        .iter()
+        .scan(0, |offset, zone| {
+            let zone_offset = *offset;
+            *offset += zone.num_rings.max(1) * zone.num_sectors.max(1);
+            Some(zone_offset)
+        })
+        .collect::<Vec<_>>();
+    let num_bins = config
+        .zones
+        .iter()
+        .map(|zone| zone.num_rings.max(1) * zone.num_sectors.max(1))
+        .sum::<usize>();
+
+    let mut bins = Vec::from_iter(core::iter::repeat_n(Vec::new(), num_bins));
+    for (point_idx, point) in points.iter().enumerate() {
+        if let Some((zone_idx, ring_idx, sector_idx)) = locate_bin(point, &config) {
+            let num_sectors = config.zones[zone_idx].num_sectors.max(1);
+            bins[zone_offsets[zone_idx] + ring_idx * num_sectors + sector_idx].push(point_idx);
+        }
+    }
+
+    let mut is_ground = Vec::from_iter(core::iter::repeat_n(false, points.len()));
+    let mut ground_planes = Vec::new();
+    for (zone_idx, zone) in config.zones.iter().enumerate() {
+        let num_sectors = zone.num_sectors.max(1);
+        for bin_idx in 0..zone.num_rings.max(1) * num_sectors {
+            let bin = &mut bins[zone_offsets[zone_idx] + bin_idx];
+            if bin.len() < config.min_points_per_bin.max(3) {
+                continue;
+            }
+
+            let Some((plane, centroid, ground)) = fit_bin_plane(points, bin, &config) else {
+                continue;
+            };
+            if plane.normal.z < config.uprightness_threshold
+                || centroid.z > config.max_ground_elevation - config.sensor_height
+            {
+                continue;
+            }
+
+            ground
+                .into_iter()
+                .for_each(|point_idx| is_ground[point_idx] = true);
+            ground_planes.push(GroundPlane {
+                zone_idx,
+                ring_idx: bin_idx / num_sectors,
+                sector_idx: bin_idx % num_sectors,
+                plane,
+                centroid,
+            });
+        }
+    }
+
+    let (ground_indices, non_ground_indices) =
+        (0..points.len()).partition(|&point_idx| is_ground[point_idx]);
+    GroundSegmentation {
+        ground_indices,
+        non_ground_indices,
+        ground_planes,
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_ground_segmentation {
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point3;
+                use super::{GroundSegmentation, GroundSegmentationConfiguration};
+
+                #[doc = "A premade variant of the ground segmentation function, in " $doc "-precision floats."]
+                pub fn segment_ground(points: &[Point3<$precision>], config: GroundSegmentationConfiguration<$precision>) -> GroundSegmentation<$precision> {
+                    super::segment_ground(points, config)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_ground_segmentation!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_ground_segmentation!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point3, RealField};
+
+    use crate::point_clouds::downsample_point_cloud_voxel;
+
+    use super::*;
+
+    const SENSOR_HEIGHT: f64 = 1.73;
+
+    // A flat ground plane around the sensor, with slight deterministic noise
+    fn generate_ground() -> Vec<Point3<f64>> {
+        (0..120)
+            .flat_map(|range_idx| {
+                (0..180).map(move |azimuth_idx| {
+                    let range = 3.0 + range_idx as f64 * 0.5;
+                    let azimuth = (azimuth_idx as f64).to_radians() * 2.0;
+                    let noise = 0.02 * ((range_idx * 180 + azimuth_idx) as f64 * 12.9898).sin();
+                    Point3::new(
+                        range * azimuth.cos(),
+                        range * azimuth.sin(),
+                        noise - SENSOR_HEIGHT,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    // A box shaped obstacle standing on the ground, without its bottom part
+    fn generate_obstacle() -> Vec<Point3<f64>> {
+        (0..20)
+            .flat_map(|x_idx| {
+                (0..20).flat_map(move |y_idx| {
+                    (0..18).map(move |z_idx| {
+                        Point3::new(
+                            10.0 + x_idx as f64 * 0.1,
+                            -1.0 + y_idx as f64 * 0.1,
+                            -1.2 + z_idx as f64 * 0.1,
+                        )
+                    })
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_segment_ground() {
+        let ground = generate_ground();
+        let obstacle = generate_obstacle();
+        let points = [ground.as_slice(), obstacle.as_slice()].concat();
+
+        let segmentation = segment_ground(
+            &points,
+            GroundSegmentationConfiguration::builder()
+                .with_sensor_height(SENSOR_HEIGHT)
+                .build(),
+        );
+        assert_eq!(
+            segmentation.ground_indices,
+            (0..ground.len()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            segmentation.non_ground_indices,
+            (ground.len()..points.len()).collect::<Vec<_>>()
+        );
+
+        assert!(!segmentation.ground_planes.is_empty());
+        for ground_plane in segmentation.ground_planes {
+            assert!(ground_plane.plane.normal.z > 0.99);
+            assert!((ground_plane.centroid.z + SENSOR_HEIGHT).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_segment_ground_rejects_walls_and_roofs() {
+        // A wall and an elevated platform, with no ground in their bins
+        let wall = (0..50).flat_map(|y_idx| {
+            (0..30)
+                .map(move |z_idx| Point3::new(5.0, y_idx as f64 * 0.05, -1.7 + z_idx as f64 * 0.1))
+        });
+        let platform = (0..50).flat_map(|x_idx| {
+            (0..50)
+                .map(move |y_idx| Point3::new(-6.0 - x_idx as f64 * 0.05, y_idx as f64 * 0.05, 0.5))
+        });
+        let points = wall.chain(platform).collect::<Vec<_>>();
+
+        let segmentation = segment_ground(
+            &points,
+            GroundSegmentationConfiguration::builder()
+                .with_sensor_height(SENSOR_HEIGHT)
+                .build(),
+        );
+        assert!(segmentation.ground_indices.is_empty());
+        assert!(segmentation.ground_planes.is_empty());
+        assert_eq!(segmentation.non_ground_indices.len(), points.len());
+    }
+
+    #[test]
+    fn test_segment_ground_sloped() {
+        // A gentle uphill slope in front of the sensor
+        let points = generate_ground()
+            .into_iter()
+            .map(|point| Point3::new(point.x, point.y, point.z + point.x.max(0.0) * 0.05))
+            .collect::<Vec<_>>();
+
+        let segmentation = segment_ground(
+            &points,
+            GroundSegmentationConfiguration::builder()
+                .with_sensor_height(SENSOR_HEIGHT)
+                .with_max_ground_elevation(3.0)
+                .build(),
+        );
+        assert!(segmentation.ground_indices.len() as f64 >= 0.99 * points.len() as f64);
+    }
+
+    #[test]
+    fn test_segment_ground_after_downsampling() {
+        let points = [generate_ground(), generate_obstacle()].concat();
+        let downsampled = downsample_point_cloud_voxel(&points, 0.3);
+
+        let segmentation = segment_ground(
+            &downsampled,
+            GroundSegmentationConfiguration::builder()
+                .with_sensor_height(SENSOR_HEIGHT)
+                .build(),
+        );
+        assert!(!segmentation.ground_indices.is_empty());
+        assert!(segmentation
+            .ground_indices
+            .iter()
+            .all(|&point_idx| downsampled[point_idx].z < 0.1 - SENSOR_HEIGHT));
+        assert!(segmentation
+            .non_ground_indices
+            .iter()
+            .all(|&point_idx| downsampled[point_idx].z > -1.3));
+    }
+
+    #[test]
+    fn test_locate_bin() {
+        let config = GroundSegmentationConfiguration::<f32>::builder().build();
+        assert_eq!(locate_bin(&Point3::new(1.0, 0.0, 0.0), &config), None);
+        assert_eq!(locate_bin(&Point3::new(100.0, 0.0, 0.0), &config), None);
+        assert_eq!(
+            locate_bin(&Point3::new(-3.0, -0.01, 0.0), &config),
+            Some((0, 0, 0))
+        );
+        assert_eq!(
+            locate_bin(&Point3::new(15.0, 0.0, 0.0), &config),
+            Some((1, 1, 16))
+        );
+        assert_eq!(
+            locate_bin(&Point3::new(79.0, 0.0, -f32::pi()), &config),
+            Some((3, 3, 16))
+        );
+    }
+}
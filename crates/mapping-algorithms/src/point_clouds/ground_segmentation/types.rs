@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{point_clouds::PlaneModel, Vec};
+
+/// A single concentric zone of the ground segmentation's polar grid,
+/// each zone begins where the previous zone (or the minimum range) ends.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConcentricZone<T> {
+    /// The horizontal distance from the sensor at which this zone ends.
+    pub max_range: T,
+    /// The amount of equally sized rings this zone is split into.
+    pub num_rings: usize,
+    /// The amount of equally sized angular sectors each ring is split into.
+    pub num_sectors: usize,
+}
+
+/// A local plane that was fitted to a single bin of the polar grid, and accepted as ground.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroundPlane<T: RealField> {
+    /// The index of the [`ConcentricZone`] containing the bin.
+    pub zone_idx: usize,
+    /// The index of the bin's ring inside its zone.
+    pub ring_idx: usize,
+    /// The index of the bin's sector inside its ring.
+    pub sector_idx: usize,
+    /// The fitted plane, its normal always points upwards.
+    pub plane: PlaneModel<T>,
+    /// The mean of all points the plane was fitted to.
+    pub centroid: Point3<T>,
+}
+
+/// Contains the result of a ground segmentation.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct GroundSegmentation<T: RealField> {
+    /// The indices of all points classified as ground, in ascending order.
+    pub ground_indices: Vec<usize>,
+    /// The indices of all other points, in ascending order.
+    pub non_ground_indices: Vec<usize>,
+    /// The local planes of all bins that were accepted as ground.
+    pub ground_planes: Vec<GroundPlane<T>>,
+}
+
+/// A struct specifying configuration options for the ground segmentation.
+#[derive(Clone, Debug)]
+pub struct GroundSegmentationConfiguration<T> {
+    /// The height of the sensor above the ground, the ground is expected around `z = -sensor_height`.
+    pub(crate) sensor_height: T,
+    /// Points horizontally closer to the sensor than this are never considered ground.
+    pub(crate) min_range: T,
+    /// The concentric zones of the polar grid, ordered by range, points beyond the last zone are never considered ground.
+    pub(crate) zones: Vec<ConcentricZone<T>>,
+    /// Bins with less points than this are not fitted, and their points are not considered ground.
+    pub(crate) min_points_per_bin: usize,
+    /// The amount of lowest points in each bin, whose mean height is the base for selecting the initial seeds.
+    pub(crate) num_seed_points: usize,
+    /// The maximum height above the mean of the lowest points, for a point to be an initial seed.
+    pub(crate) seed_height_threshold: T,
+    /// The maximum signed distance above a fitted plane for a point to be considered ground.
+    pub(crate) distance_threshold: T,
+    /// The amount of times each plane is refitted to the points classified as ground.
+    pub(crate) num_iterations: usize,
+    /// The minimum vertical component of a plane's unit normal, for the plane to be accepted as ground.
+    pub(crate) uprightness_threshold: T,
+    /// The maximum height of a plane's centroid above the expected ground height, for the plane to be accepted as ground.
+    pub(crate) max_ground_elevation: T,
+}
+
+impl<T: 'static + Copy> GroundSegmentationConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values are tuned for an automotive LiDAR, mounted 1.73 meters above the ground.
+    ///
+    /// # Returns
+    /// A [`GroundSegmentationConfigurationBuilder`].
+    pub fn builder() -> GroundSegmentationConfigurationBuilder<T> {
+        GroundSegmentationConfigurationBuilder {
+            _internal: GroundSegmentationConfiguration {
+                sensor_height: 1.73.as_(),
+                min_range: 2.7.as_(),
+                zones: Vec::from([
+                    ConcentricZone {
+                        max_range: 12.3625.as_(),
+                        num_rings: 2,
+                        num_sectors: 16,
+                    },
+                    ConcentricZone {
+                        max_range: 22.025.as_(),
+                        num_rings: 4,
+                        num_sectors: 32,
+                    },
+                    ConcentricZone {
+                        max_range: 41.35.as_(),
+                        num_rings: 4,
+                        num_sectors: 54,
+                    },
+                    ConcentricZone {
+                        max_range: 80.0.as_(),
+                        num_rings: 4,
+                        num_sectors: 32,
+                    },
+                ]),
+                min_points_per_bin: 10,
+                num_seed_points: 20,
+                seed_height_threshold: 0.4.as_(),
+                distance_threshold: 0.125.as_(),
+                num_iterations: 3,
+                uprightness_threshold: 0.707.as_(),
+                max_ground_elevation: 0.5.as_(),
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`GroundSegmentationConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct GroundSegmentationConfigurationBuilder<T> {
+    _internal: GroundSegmentationConfiguration<T>,
+}
+
+impl<T: Copy> GroundSegmentationConfigurationBuilder<T> {
+    /// The height of the sensor above the ground.
+    ///
+    /// # Arguments
+    /// * `sensor_height`: The sensor's height, the ground is expected around `z = -sensor_height` in the sensor's frame.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_sensor_height(&self, sensor_height: T) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                sensor_height,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The minimum horizontal distance from the sensor, closer points usually hit the vehicle itself.
+    ///
+    /// # Arguments
+    /// * `min_range`: The minimum range, where the first zone begins.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_min_range(&self, min_range: T) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                min_range,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The concentric zones of the polar grid, using larger bins further away accounts for the sparser points there.
+    ///
+    /// # Arguments
+    /// * `zones`: A [`Vec`] of [`ConcentricZone`], ordered by their maximum range.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_zones(&self, zones: Vec<ConcentricZone<T>>) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                zones,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The minimum amount of points in a bin for it to be fitted with a plane.
+    ///
+    /// # Arguments
+    /// * `min_points_per_bin`: The minimum number of points, sparser bins are considered non-ground.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_min_points_per_bin(&self, min_points_per_bin: usize) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                min_points_per_bin,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The amount of lowest points in each bin that are averaged to find the initial ground height.
+    ///
+    /// # Arguments
+    /// * `num_seed_points`: The number of lowest points to average.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_num_seed_points(&self, num_seed_points: usize) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                num_seed_points,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The maximum height above the initial ground height for a point to be used when fitting the first plane.
+    ///
+    /// # Arguments
+    /// * `seed_height_threshold`: The seed height threshold.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_seed_height_threshold(&self, seed_height_threshold: T) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                seed_height_threshold,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The maximum distance above a bin's plane for a point to be classified as ground, points below the plane are always ground.
+    ///
+    /// # Arguments
+    /// * `distance_threshold`: The distance threshold.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_distance_threshold(&self, distance_threshold: T) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                distance_threshold,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The amount of times each bin's plane is refitted to the points classified as ground by the previous plane.
+    ///
+    /// # Arguments
+    /// * `num_iterations`: The number of fitting iterations.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_num_iterations(&self, num_iterations: usize) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                num_iterations,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The minimum vertical component of a plane's unit normal, rejecting walls and steep slopes.
+    ///
+    /// # Arguments
+    /// * `uprightness_threshold`: The cosine of the maximal allowed inclination of a ground plane.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_uprightness_threshold(&self, uprightness_threshold: T) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                uprightness_threshold,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The maximum height of a plane above the expected ground height, rejecting flat elevated surfaces such as roofs.
+    ///
+    /// # Arguments
+    /// * `max_ground_elevation`: The maximal elevation of a ground plane's centroid.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_ground_elevation(&self, max_ground_elevation: T) -> Self {
+        Self {
+            _internal: GroundSegmentationConfiguration {
+                max_ground_elevation,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// Generates a [`GroundSegmentationConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`GroundSegmentationConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> GroundSegmentationConfiguration<T> {
+        self._internal.clone()
+    }
+}
@@ -26,11 +26,13 @@
 
 use crate::{types::IsNan, Ordering, Vec};
 
-fn validate_input<T: Scalar + PartialOrd + IsNan, const N: usize>(input: &[Point<T, N>]) -> bool {
+pub(crate) fn validate_input<T: Scalar + PartialOrd + IsNan, const N: usize>(
+    input: &[Point<T, N>],
+) -> bool {
     !(N.is_zero() || input.iter().any(|a| a.coords.iter().any(|b| b.is_nan())))
 }
 
-fn lex_sort_func<T: Scalar + PartialOrd + IsNan, const N: usize>(
+pub(crate) fn lex_sort_func<T: Scalar + PartialOrd + IsNan, const N: usize>(
     a: &Point<T, N>,
     b: &Point<T, N>,
 ) -> Ordering {
@@ -21,12 +21,41 @@
  * SOFTWARE.
  */
 
-pub use downsample::downsample_point_cloud_voxel;
+pub use clustering::{dbscan, extract_euclidean_clusters, hdbscan, ClusterLabel};
+pub use crop::{
+    crop_box, crop_frustum, crop_oriented_box, crop_polygon_prism, pass_through_filter, Frustum,
+};
+pub use deskew::{
+    deskew_point_cloud, deskew_point_cloud_with, interpolate_pose, DeskewError, TimedPose,
+};
+pub use downsample::{
+    downsample_point_cloud_farthest_point, downsample_point_cloud_normal_space,
+    downsample_point_cloud_random, downsample_point_cloud_voxel,
+    downsample_point_cloud_voxel_nearest,
+};
+pub use ground_segmentation::{
+    segment_ground, ConcentricZone, GroundPlane, GroundSegmentation,
+    GroundSegmentationConfiguration, GroundSegmentationConfigurationBuilder,
+};
 pub use icp::{
     icp, icp_iteration, ICPConfiguration, ICPConfigurationBuilder, ICPError, ICPResult, ICPSuccess,
 };
 pub use lex_sort::{lex_sort, lex_sort_in_place, lex_sort_ref};
 pub use nearest_neighbour::find_nearest_neighbour_naive;
+pub use normals::{
+    estimate_normals, orient_normals_towards_viewpoint, orient_normals_with_minimum_spanning_tree,
+    NeighbourhoodSearch, PointNormal,
+};
+pub use outlier_removal::{remove_radius_outliers, remove_statistical_outliers};
+pub use point_cloud::{AttributeChannel, AttributeValues, PointCloud, PointCloudError};
+pub use range_image::{
+    project_to_range_image, RangeImage, RangeImageConfiguration, RangeImageConfigurationBuilder,
+    RangePixel,
+};
+pub use ransac::{
+    ransac, CylinderModel, Line2Model, LineModel, PlaneModel, RansacConfiguration,
+    RansacConfigurationBuilder, RansacError, RansacModel, RansacResult, RansacSuccess, SphereModel,
+};
 
 use nalgebra::{
     AbstractRotation, ClosedAddAssign, ClosedDivAssign, Isometry, Point, RealField, Scalar,
@@ -35,21 +64,48 @@
 
 use crate::{array, Vec};
 
+mod clustering;
+mod crop;
+mod deskew;
 mod downsample;
+mod ground_segmentation;
 mod icp;
 mod lex_sort;
 mod nearest_neighbour;
+mod normals;
+mod outlier_removal;
+mod point_cloud;
+mod range_image;
+mod ransac;
 
 #[cfg(feature = "pregenerated")]
 #[doc = "Contains pregenerated functions for single precision point cloud algorithms."]
 pub mod single_precision {
+    pub use super::clustering::single_precision::*;
+    pub use super::crop::single_precision::*;
+    pub use super::deskew::single_precision::*;
+    pub use super::downsample::single_precision::*;
+    pub use super::ground_segmentation::single_precision::*;
     pub use super::icp::single_precision::*;
+    pub use super::normals::single_precision::*;
+    pub use super::outlier_removal::single_precision::*;
+    pub use super::range_image::single_precision::*;
+    pub use super::ransac::single_precision::*;
 }
 
 #[cfg(feature = "pregenerated")]
 #[doc = "Contains pregenerated functions for double precision point cloud algorithms."]
 pub mod double_precision {
+    pub use super::clustering::double_precision::*;
+    pub use super::crop::double_precision::*;
+    pub use super::deskew::double_precision::*;
+    pub use super::downsample::double_precision::*;
+    pub use super::ground_segmentation::double_precision::*;
     pub use super::icp::double_precision::*;
+    pub use super::normals::double_precision::*;
+    pub use super::outlier_removal::double_precision::*;
+    pub use super::range_image::double_precision::*;
+    pub use super::ransac::double_precision::*;
 }
 
 /// Calculates the mean(centroid) of the point cloud.
@@ -0,0 +1,552 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point, RealField, SMatrix, SVector};
+use num_traits::AsPrimitive;
+
+use crate::{
+    kd_tree::{KDNeighbour, KDTree},
+    point_clouds::calculate_point_cloud_center,
+    utils::symmetric_eigen_decomposition,
+    BinaryHeap, Ordering, Vec,
+};
+
+/// Specifies how the local neighbourhood of each point is selected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NeighbourhoodSearch<T> {
+    /// Use the `k` nearest neighbours of each point, including the point itself.
+    KNearest(usize),
+    /// Use all points within the given radius of each point, including the point itself.
+    Radius(T),
+}
+
+/// The estimated surface normal of a single point.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointNormal<T, const N: usize> {
+    /// The unit normal vector, its orientation is arbitrary unless it was explicitly oriented,
+    /// see [`orient_normals_towards_viewpoint`] and [`orient_normals_with_minimum_spanning_tree`].
+    pub normal: SVector<T, N>,
+    /// The surface variation of the neighbourhood, being the smallest eigenvalue divided by the sum of all eigenvalues,
+    /// This is `0` for a perfectly planar neighbourhood, and at most `1 / N` for an isotropic one.
+    pub curvature: T,
+}
+
+/// Finds the neighbourhood of a point, according to the search type.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Find Point Neighbourhood", skip_all, level = "trace")
+)]
+fn find_neighbourhood<T, const N: usize>(
+    points_tree: &KDTree<T, N>,
+    point: &Point<T, N>,
+    neighbourhood: NeighbourhoodSearch<T>,
+) -> Vec<KDNeighbour<T, N>>
+where
+    T: Copy + Default + RealField,
+{
+    match neighbourhood {
+        NeighbourhoodSearch::KNearest(k) => points_tree.nearest_k(point, k),
+        NeighbourhoodSearch::Radius(radius) => points_tree.within_radius(point, radius),
+    }
+}
+
+/// Calculates the covariance matrix of a set of points.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], must not be empty.
+///
+/// # Returns
+/// An `N` by `N` [`SMatrix`], containing the covariance of the points.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Calculate Covariance Matrix", skip_all, level = "trace")
+)]
+pub(crate) fn calculate_covariance_matrix<T, const N: usize>(
+    points: &[Point<T, N>],
+) -> SMatrix<T, N, N>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let center = calculate_point_cloud_center(points);
+    points
+        .iter()
+        .fold(SMatrix::<T, N, N>::zeros(), |acc, point| {
+            let diff = point - center;
+            acc + diff * diff.transpose()
+        })
+        / points.len().as_()
+}
+
+/// Calculates the normal of a set of points, using Principal Component Analysis.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the local neighbourhood.
+///
+/// # Returns
+/// [`None`] if there are less than `N` points, otherwise a [`PointNormal`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Calculate Neighbourhood Normal", skip_all, level = "trace")
+)]
+pub(crate) fn calculate_normal<T, const N: usize>(
+    points: &[Point<T, N>],
+) -> Option<PointNormal<T, N>>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    if points.len() < N {
+        return None;
+    }
+
+    let (eigenvalues, eigenvectors) =
+        symmetric_eigen_decomposition(&calculate_covariance_matrix(points));
+    let eigenvalue_sum = eigenvalues.iter().fold(T::zero(), |acc, it| acc + *it);
+    let curvature = if eigenvalue_sum > T::zero() {
+        eigenvalues[0].max(T::zero()) / eigenvalue_sum
+    } else {
+        T::zero()
+    };
+
+    Some(PointNormal {
+        normal: eigenvectors.column(0).into_owned(),
+        curvature,
+    })
+}
+
+/// Estimates the surface normal of each point in the point cloud,
+/// using Principal Component Analysis of each point's local neighbourhood.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `neighbourhood`: a [`NeighbourhoodSearch`], specifying how to select each point's neighbourhood.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A [`Vec`] with the same size as `points`, containing a [`PointNormal`] for each point,
+/// or [`None`] for points whose neighbourhood contained less than `N` points.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Estimate Point Cloud Normals", skip_all, level = "info")
+)]
+pub fn estimate_normals<T, const N: usize>(
+    points: &[Point<T, N>],
+    neighbourhood: NeighbourhoodSearch<T>,
+) -> Vec<Option<PointNormal<T, N>>>
+where
+    T: Copy + Default + RealField,
+    usize: AsPrimitive<T>,
+{
+    let points_tree = KDTree::from(points);
+    points
+        .iter()
+        .map(|point| {
+            let neighbourhood_points = find_neighbourhood(&points_tree, point, neighbourhood)
+                .into_iter()
+                .map(|neighbour| neighbour.point)
+                .collect::<Vec<_>>();
+            calculate_normal(&neighbourhood_points)
+        })
+        .collect()
+}
+
+/// Flips each normal so that it points towards the viewpoint, i.e., the sensor origin.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `normals`: a mutable slice of optional [`PointNormal`]s, matching `points`, as returned by [`estimate_normals`].
+/// * `viewpoint`: a reference to a [`Point`], towards which all normals will point.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Orient Normals Towards Viewpoint", skip_all, level = "info")
+)]
+pub fn orient_normals_towards_viewpoint<T, const N: usize>(
+    points: &[Point<T, N>],
+    normals: &mut [Option<PointNormal<T, N>>],
+    viewpoint: &Point<T, N>,
+) where
+    T: Copy + RealField,
+{
+    for (point, point_normal) in points
+        .iter()
+        .zip(normals.iter_mut())
+        .filter_map(|(point, normal)| normal.as_mut().map(|normal| (point, normal)))
+    {
+        if point_normal.normal.dot(&(viewpoint - point)) < T::zero() {
+            point_normal.normal.neg_mut();
+        }
+    }
+}
+
+/// An edge in the neighbourhood graph, ordered such that the [`BinaryHeap`] pops the lowest cost first.
+#[derive(Debug)]
+struct PropagationEdge<T> {
+    cost: T,
+    from: usize,
+    to: usize,
+}
+
+impl<T: PartialOrd> PartialEq for PropagationEdge<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd> Eq for PropagationEdge<T> {}
+
+impl<T: PartialOrd> PartialOrd for PropagationEdge<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd> Ord for PropagationEdge<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Orients all normals consistently, by propagating the orientation along a minimum spanning tree of the
+/// `k_neighbours` nearest neighbour graph, where edges between nearly parallel normals are preferred.
+/// The propagation of each connected component begins at the point with the largest last coordinate (i.e. the highest `z` in 3D),
+/// whose normal is oriented to have a positive last coordinate.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `normals`: a mutable slice of optional [`PointNormal`]s, matching `points`, as returned by [`estimate_normals`].
+/// * `k_neighbours`: the number of nearest neighbours to connect each point to in the graph.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Orient Normals Using Minimum Spanning Tree", skip_all, level = "info")
+)]
+pub fn orient_normals_with_minimum_spanning_tree<T, const N: usize>(
+    points: &[Point<T, N>],
+    normals: &mut [Option<PointNormal<T, N>>],
+    k_neighbours: usize,
+) where
+    T: Copy + Default + RealField,
+{
+    let points_tree = KDTree::from(points);
+    let mut visited = normals
+        .iter()
+        .map(|normal| normal.is_none())
+        .collect::<Vec<_>>();
+
+    // Seeds are sorted by descending last coordinate
+    let mut seeds = (0..points.len().min(normals.len())).collect::<Vec<_>>();
+    seeds.sort_by(|a, b| {
+        points[*b][N - 1]
+            .partial_cmp(&points[*a][N - 1])
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut edge_queue = BinaryHeap::new();
+    for seed in seeds {
+        if visited[seed] {
+            continue;
+        }
+
+        visited[seed] = true;
+        if let Some(seed_normal) = normals[seed].as_mut() {
+            if seed_normal.normal[N - 1] < T::zero() {
+                seed_normal.normal.neg_mut();
+            }
+        }
+
+        edge_queue.push(PropagationEdge {
+            cost: T::zero(),
+            from: seed,
+            to: seed,
+        });
+        while let Some(PropagationEdge { from, to, .. }) = edge_queue.pop() {
+            if from != to {
+                if visited[to] {
+                    continue;
+                }
+                visited[to] = true;
+
+                let parent_normal = normals[from].map(|normal| normal.normal);
+                if let (Some(parent_normal), Some(current_normal)) =
+                    (parent_normal, normals[to].as_mut())
+                {
+                    if parent_normal.dot(&current_normal.normal) < T::zero() {
+                        current_normal.normal.neg_mut();
+                    }
+                }
+            }
+
+            let Some(current_normal) = normals[to].map(|normal| normal.normal) else {
+                continue;
+            };
+            for neighbour in points_tree.nearest_k(&points[to], k_neighbours + 1) {
+                let Some(neighbour_normal) = normals
+                    .get(neighbour.index)
+                    .and_then(|normal| normal.as_ref())
+                    .filter(|_| !visited[neighbour.index])
+                else {
+                    continue;
+                };
+
+                edge_queue.push(PropagationEdge {
+                    cost: T::one() - current_normal.dot(&neighbour_normal.normal).abs(),
+                    from: to,
+                    to: neighbour.index,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_normal_estimation {
+    ($precision:expr, doc $doc:tt, $nd:expr) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the normal estimation function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<estimate_normals_ $nd d>](points: &[Point<$precision, $nd>], neighbourhood: NeighbourhoodSearch<$precision>) -> Vec<Option<PointNormal<$precision, $nd>>> {
+                super::estimate_normals(points, neighbourhood)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point;
+                use crate::Vec;
+                use super::{NeighbourhoodSearch, PointNormal};
+
+                impl_normal_estimation!($precision, doc $doc, 2);
+                impl_normal_estimation!($precision, doc $doc, 3);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_normal_estimation!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_normal_estimation!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector2, Vector3};
+
+    use crate::point_clouds::generate_point_cloud;
+
+    use super::*;
+
+    fn generate_sphere(num_points: usize, radius: f64) -> Vec<Point3<f64>> {
+        generate_point_cloud(num_points, [-1.0..=1.0, -1.0..=1.0, -1.0..=1.0])
+            .into_iter()
+            .filter(|point: &Point3<f64>| point.coords.norm() > 0.1)
+            .map(|point| Point3::from(point.coords.normalize() * radius))
+            .collect()
+    }
+
+    #[test]
+    fn test_calculate_covariance_matrix() {
+        let points = [
+            Point2::new(-1.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(0.0, 2.0),
+            Point2::new(0.0, -2.0),
+        ];
+        assert_eq!(
+            calculate_covariance_matrix(&points),
+            SMatrix::<f64, 2, 2>::new(0.5, 0.0, 0.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn test_calculate_normal() {
+        // Not enough points
+        assert!(
+            calculate_normal(&[Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0)]).is_none()
+        );
+
+        // Perfect plane
+        let normal = calculate_normal(&[
+            Point3::new(0.0f64, 0.0, 1.0),
+            Point3::new(1.0, 0.0, 1.0),
+            Point3::new(0.0, 1.0, 1.0),
+            Point3::new(1.0, 1.0, 1.0),
+        ])
+        .unwrap();
+        assert!((normal.normal.z.abs() - 1.0f64).abs() < 1e-12);
+        assert!(normal.curvature.abs() < 1e-12);
+
+        // Degenerate identical points have no curvature
+        let normal = calculate_normal(&[Point2::new(1.0, 1.0); 4]).unwrap();
+        assert_eq!(normal.curvature, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_normals_plane() {
+        let points = generate_point_cloud(400, [-5.0..=5.0, -5.0..=5.0, 0.0..=0.0])
+            .into_iter()
+            .map(|point: Point3<f64>| Point3::new(point.x, point.y, 0.5 * point.x + 2.0))
+            .collect::<Vec<_>>();
+        let expected_normal = Vector3::new(-0.5, 0.0, 1.0).normalize();
+
+        for neighbourhood in [
+            NeighbourhoodSearch::KNearest(10),
+            NeighbourhoodSearch::Radius(1.5),
+        ] {
+            let normals = estimate_normals(&points, neighbourhood);
+            assert_eq!(normals.len(), points.len());
+            for point_normal in normals.into_iter().map(Option::unwrap) {
+                assert!((point_normal.normal.dot(&expected_normal).abs() - 1.0).abs() < 1e-6);
+                assert!(point_normal.curvature < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_estimate_normals_isolated_point() {
+        let points = [
+            Point2::new(0.0f64, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(2.0, 0.0),
+            Point2::new(50.0, 50.0),
+        ];
+
+        let normals = estimate_normals(&points, NeighbourhoodSearch::Radius(1.0));
+        assert!(normals[3].is_none());
+        assert!((normals[1].unwrap().normal.y.abs() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_orient_normals_towards_viewpoint() {
+        let points = generate_sphere(500, 3.0);
+        let mut normals = estimate_normals(&points, NeighbourhoodSearch::KNearest(12));
+        orient_normals_towards_viewpoint(&points, &mut normals, &Point3::origin());
+
+        // Every normal should point inwards, towards the center of the sphere
+        for (point, point_normal) in points.iter().zip(normals.iter()) {
+            let point_normal = point_normal.unwrap();
+            assert!(point_normal.normal.dot(&point.coords) < 0.0);
+            assert!(point_normal.curvature > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_propagation_edge_ordering() {
+        let mut queue = BinaryHeap::from([
+            PropagationEdge {
+                cost: 0.5,
+                from: 0,
+                to: 1,
+            },
+            PropagationEdge {
+                cost: 0.1,
+                from: 0,
+                to: 2,
+            },
+            PropagationEdge {
+                cost: 0.9,
+                from: 0,
+                to: 3,
+            },
+        ]);
+
+        assert_eq!(queue.pop().unwrap().to, 2);
+        assert_eq!(queue.pop().unwrap().to, 1);
+        assert_eq!(queue.pop().unwrap().to, 3);
+    }
+
+    #[test]
+    fn test_orient_normals_with_minimum_spanning_tree() {
+        let points = generate_sphere(800, 3.0);
+        let mut normals = estimate_normals(&points, NeighbourhoodSearch::KNearest(12));
+
+        // Scramble the orientations
+        for (idx, point_normal) in normals.iter_mut().enumerate() {
+            if idx % 3 == 0 {
+                point_normal.as_mut().unwrap().normal.neg_mut();
+            }
+        }
+
+        orient_normals_with_minimum_spanning_tree(&points, &mut normals, 8);
+
+        // The seed is the topmost point, whose normal points up, so all normals should point outwards
+        for (point, point_normal) in points.iter().zip(normals.iter()) {
+            assert!(point_normal.unwrap().normal.dot(&point.coords) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_orient_normals_with_minimum_spanning_tree_components() {
+        // Two separate lines, with a missing normal in between
+        let points = [
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(2.0, 0.0),
+            Point2::new(0.0, 10.0),
+            Point2::new(1.0, 10.0),
+        ];
+        let mut normals = [
+            Some(PointNormal {
+                normal: Vector2::new(0.0, -1.0),
+                curvature: 0.0,
+            }),
+            None,
+            Some(PointNormal {
+                normal: Vector2::new(0.0, 1.0),
+                curvature: 0.0,
+            }),
+            Some(PointNormal {
+                normal: Vector2::new(0.0, 1.0),
+                curvature: 0.0,
+            }),
+            Some(PointNormal {
+                normal: Vector2::new(0.0, -1.0),
+                curvature: 0.0,
+            }),
+        ];
+
+        orient_normals_with_minimum_spanning_tree(&points, &mut normals, 2);
+        assert!(normals[1].is_none());
+        for point_normal in normals.iter().flatten() {
+            assert_eq!(point_normal.normal, Vector2::new(0.0, 1.0));
+        }
+    }
+}
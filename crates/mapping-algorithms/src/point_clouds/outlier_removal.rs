@@ -0,0 +1,330 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{ComplexField, Point, RealField, Scalar};
+use num_traits::{AsPrimitive, NumOps, Zero};
+
+use crate::{kd_tree::KDTree, Vec};
+
+/// Calculates the mean distance of each point to its `k_neighbours` nearest neighbours, excluding itself.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `points_tree`: a [`KDTree`] constructed from `points`.
+/// * `k_neighbours`: the number of neighbours to average over.
+///
+/// # Returns
+/// A [`Vec`] with the same size as `points`, containing the mean neighbour distance of each point.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Calculate Mean Neighbour Distances", skip_all, level = "debug")
+)]
+fn calculate_mean_neighbour_distances<T, const N: usize>(
+    points: &[Point<T, N>],
+    points_tree: &KDTree<T, N>,
+    k_neighbours: usize,
+) -> Vec<T>
+where
+    T: Copy + Default + RealField,
+    usize: AsPrimitive<T>,
+{
+    points
+        .iter()
+        .enumerate()
+        .map(|(point_idx, point)| {
+            // Query one extra neighbour, as the point itself is contained in the tree
+            let (distance_sum, neighbour_count) = points_tree
+                .nearest_k(point, k_neighbours + 1)
+                .into_iter()
+                .filter(|neighbour| neighbour.index != point_idx)
+                .take(k_neighbours)
+                .fold((T::zero(), 0usize), |(sum, count), neighbour| {
+                    (
+                        sum + ComplexField::sqrt(neighbour.distance_squared),
+                        count + 1,
+                    )
+                });
+
+            if neighbour_count == 0 {
+                T::zero()
+            } else {
+                distance_sum / neighbour_count.as_()
+            }
+        })
+        .collect()
+}
+
+/// Removes points whose mean distance to their `k_neighbours` nearest neighbours is more than `std_dev_multiplier`
+/// standard deviations above the mean of that value across the entire point cloud.
+/// This is useful for removing sparse noise, such as LiDAR multipath returns.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `k_neighbours`: the number of nearest neighbours to use when calculating each point's mean distance.
+/// * `std_dev_multiplier`: the number of standard deviations above the global mean, beyond which a point is considered an outlier.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A tuple of
+/// * A [`Vec`] of [`Point`], containing the inlier points, in their original order.
+/// * A [`Vec`] of [`usize`], containing the indices of the removed outlier points in `points`, in ascending order.
+///
+/// If `k_neighbours` is zero, or there are less than two points, all points are considered inliers.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Remove Statistical Outliers", skip_all, level = "info")
+)]
+pub fn remove_statistical_outliers<T, const N: usize>(
+    points: &[Point<T, N>],
+    k_neighbours: usize,
+    std_dev_multiplier: T,
+) -> (Vec<Point<T, N>>, Vec<usize>)
+where
+    T: Copy + Default + RealField,
+    usize: AsPrimitive<T>,
+{
+    if k_neighbours == 0 || points.len() < 2 {
+        return (points.to_vec(), Vec::new());
+    }
+
+    let points_tree = KDTree::from(points);
+    let mean_distances = calculate_mean_neighbour_distances(points, &points_tree, k_neighbours);
+
+    let num_points: T = points.len().as_();
+    let global_mean = mean_distances
+        .iter()
+        .fold(T::zero(), |acc, distance| acc + *distance)
+        / num_points;
+    let variance = mean_distances.iter().fold(T::zero(), |acc, distance| {
+        let diff = *distance - global_mean;
+        acc + diff * diff
+    }) / num_points;
+    let distance_threshold = global_mean + std_dev_multiplier * ComplexField::sqrt(variance);
+
+    let mut inliers = Vec::with_capacity(points.len());
+    let mut outlier_indices = Vec::new();
+    for (point_idx, (point, mean_distance)) in points.iter().zip(mean_distances).enumerate() {
+        if mean_distance > distance_threshold {
+            outlier_indices.push(point_idx);
+        } else {
+            inliers.push(*point);
+        }
+    }
+
+    (inliers, outlier_indices)
+}
+
+/// Removes points that have less than `min_neighbours` other points within `radius` of them.
+/// This is cheaper than [`remove_statistical_outliers`], and is useful for removing isolated returns from sparse scans.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point`], representing the point cloud.
+/// * `radius`: the radius around each point in which to count neighbours, inclusive.
+/// * `min_neighbours`: the minimum number of neighbours, not including the point itself, required for a point to be kept.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+///
+/// # Returns
+/// A tuple of
+/// * A [`Vec`] of [`Point`], containing the inlier points, in their original order.
+/// * A [`Vec`] of [`usize`], containing the indices of the removed outlier points in `points`, in ascending order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Remove Radius Outliers", skip_all, level = "info")
+)]
+pub fn remove_radius_outliers<T, const N: usize>(
+    points: &[Point<T, N>],
+    radius: T,
+    min_neighbours: usize,
+) -> (Vec<Point<T, N>>, Vec<usize>)
+where
+    T: Copy + Default + NumOps + PartialOrd + Scalar + Zero,
+{
+    if min_neighbours == 0 {
+        return (points.to_vec(), Vec::new());
+    }
+
+    let points_tree = KDTree::from(points);
+
+    let mut inliers = Vec::with_capacity(points.len());
+    let mut outlier_indices = Vec::new();
+    for (point_idx, point) in points.iter().enumerate() {
+        // The point itself is always within the radius, so it is not counted
+        if points_tree.within_radius(point, radius).len() > min_neighbours {
+            inliers.push(*point);
+        } else {
+            outlier_indices.push(point_idx);
+        }
+    }
+
+    (inliers, outlier_indices)
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_outlier_removal {
+    ($precision:expr, doc $doc:tt, $nd:expr) => {
+        ::paste::paste! {
+            #[doc = "A premade variant of the statistical outlier removal function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<remove_statistical_outliers_ $nd d>](points: &[Point<$precision, $nd>], k_neighbours: usize, std_dev_multiplier: $precision) -> (Vec<Point<$precision, $nd>>, Vec<usize>) {
+                super::remove_statistical_outliers(points, k_neighbours, std_dev_multiplier)
+            }
+
+            #[doc = "A premade variant of the radius outlier removal function, in " $nd "D space and " $doc "-precision floats."]
+            pub fn [<remove_radius_outliers_ $nd d>](points: &[Point<$precision, $nd>], radius: $precision, min_neighbours: usize) -> (Vec<Point<$precision, $nd>>, Vec<usize>) {
+                super::remove_radius_outliers(points, radius, min_neighbours)
+            }
+        }
+    };
+
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point;
+                use crate::Vec;
+
+                impl_outlier_removal!($precision, doc $doc, 2);
+                impl_outlier_removal!($precision, doc $doc, 3);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_outlier_removal!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_outlier_removal!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3};
+
+    use crate::point_clouds::generate_point_cloud;
+
+    use super::*;
+
+    #[test]
+    fn test_calculate_mean_neighbour_distances() {
+        let points = [
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(3.0, 0.0),
+        ];
+        let tree = KDTree::from(points.as_slice());
+
+        let distances = calculate_mean_neighbour_distances(&points, &tree, 1);
+        assert_eq!(distances, Vec::from([1.0, 1.0, 2.0]));
+
+        let distances = calculate_mean_neighbour_distances(&points, &tree, 2);
+        assert_eq!(distances, Vec::from([2.0, 1.5, 2.5]));
+    }
+
+    #[test]
+    fn test_remove_statistical_outliers() {
+        let mut points = generate_point_cloud(300, [-1.0..=1.0, -1.0..=1.0, -1.0..=1.0]);
+        points.insert(17, Point3::new(25.0, 0.0, 0.0));
+        points.push(Point3::new(-10.0, 30.0, 5.0));
+
+        let (inliers, outliers) = remove_statistical_outliers(&points, 8, 3.0);
+        assert_eq!(outliers, Vec::from([17, 301]));
+        assert_eq!(inliers.len(), 300);
+        assert_eq!(inliers[17], points[18]);
+    }
+
+    #[test]
+    fn test_remove_statistical_outliers_duplicates() {
+        // Duplicate points are each other's neighbours, and must not be mistaken for the point itself
+        let points = [
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 0.0),
+            Point2::new(0.0, 0.0),
+            Point2::new(50.0, 50.0),
+        ];
+
+        let (inliers, outliers) = remove_statistical_outliers(&points, 2, 1.0);
+        assert_eq!(outliers, Vec::from([4]));
+        assert_eq!(inliers, Vec::from([Point2::new(0.0, 0.0); 4]));
+    }
+
+    #[test]
+    fn test_remove_statistical_outliers_trivial_input() {
+        let points = [Point2::new(0.0, 0.0), Point2::new(50.0, 50.0)];
+
+        let (inliers, outliers) = remove_statistical_outliers(&points, 0, 1.0);
+        assert_eq!(inliers.len(), 2);
+        assert!(outliers.is_empty());
+
+        let (inliers, outliers) = remove_statistical_outliers(&points[..1], 3, 1.0);
+        assert_eq!(inliers.len(), 1);
+        assert!(outliers.is_empty());
+
+        let (inliers, outliers) = remove_statistical_outliers::<f64, 2>(&[], 3, 1.0);
+        assert!(inliers.is_empty());
+        assert!(outliers.is_empty());
+    }
+
+    #[test]
+    fn test_remove_radius_outliers_2d() {
+        // A sparse 2D laser scan of a wall, with two isolated returns
+        let mut points = (0..50)
+            .map(|idx| Point2::new(idx as f32 * 0.1, 2.0))
+            .collect::<Vec<_>>();
+        points.insert(10, Point2::new(1.0, -3.0));
+        points.push(Point2::new(8.0, 8.0));
+
+        let (inliers, outliers) = remove_radius_outliers(&points, 0.25, 2);
+        assert_eq!(outliers, Vec::from([10, 51]));
+        assert_eq!(inliers.len(), 50);
+        assert!(inliers.iter().all(|point| point.y == 2.0));
+    }
+
+    #[test]
+    fn test_remove_radius_outliers_3d() {
+        let mut points = generate_point_cloud(1000, [-1.0..=1.0, -1.0..=1.0, -1.0..=1.0]);
+        points.insert(0, Point3::new(5.0, 5.0, 5.0));
+        points.insert(0, Point3::new(5.1, 5.0, 5.0));
+
+        // The two far points are each other's only neighbour
+        let (inliers, outliers) = remove_radius_outliers(&points, 0.5, 1);
+        assert!(outliers.is_empty());
+        assert_eq!(inliers.len(), 1002);
+
+        let (inliers, outliers) = remove_radius_outliers(&points, 0.5, 2);
+        assert_eq!(outliers, Vec::from([0, 1]));
+        assert_eq!(inliers.as_slice(), &points[2..]);
+    }
+
+    #[test]
+    fn test_remove_radius_outliers_zero_neighbours() {
+        let points = [Point2::new(0.0, 0.0), Point2::new(50.0, 50.0)];
+
+        let (inliers, outliers) = remove_radius_outliers(&points, 1.0, 0);
+        assert_eq!(inliers.len(), 2);
+        assert!(outliers.is_empty());
+    }
+}
@@ -0,0 +1,1148 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{AbstractRotation, ComplexField, Isometry, Point, RealField, SVector, Scalar};
+use num_traits::AsPrimitive;
+
+use crate::{
+    ops::Deref,
+    point_clouds::{
+        downsample::group_points_by_voxel,
+        lex_sort::{lex_sort_func, validate_input},
+        remove_radius_outliers, remove_statistical_outliers,
+    },
+    types::IsNan,
+    Ordering, String, Vec,
+};
+
+/// An error type containing the errors that might arise when assigning attribute channels to a [`PointCloud`], when compiling with the `std` feature, it will also derive [`thiserror::Error`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum PointCloudError {
+    /// The amount of values in an attribute channel differs from the amount of points.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "The attribute channel has {actual} values, but the point cloud has {expected} points"
+        )
+    )]
+    AttributeLengthMismatch {
+        /// The amount of points in the point cloud.
+        expected: usize,
+        /// The amount of values in the attribute channel.
+        actual: usize,
+    },
+}
+
+/// The values of a custom attribute channel, stored in their original numeric type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeValues {
+    /// Signed 8 bit integers.
+    I8(Vec<i8>),
+    /// Unsigned 8 bit integers.
+    U8(Vec<u8>),
+    /// Signed 16 bit integers.
+    I16(Vec<i16>),
+    /// Unsigned 16 bit integers.
+    U16(Vec<u16>),
+    /// Signed 32 bit integers.
+    I32(Vec<i32>),
+    /// Unsigned 32 bit integers.
+    U32(Vec<u32>),
+    /// Signed 64 bit integers.
+    I64(Vec<i64>),
+    /// Unsigned 64 bit integers.
+    U64(Vec<u64>),
+    /// Single precision floats.
+    F32(Vec<f32>),
+    /// Double precision floats.
+    F64(Vec<f64>),
+}
+
+// Applies the same expression to the inner vector of any variant, producing the same variant
+macro_rules! map_attribute_values {
+    ($values:expr, $inner:ident => $mapped:expr) => {
+        match $values {
+            AttributeValues::I8($inner) => AttributeValues::I8($mapped),
+            AttributeValues::U8($inner) => AttributeValues::U8($mapped),
+            AttributeValues::I16($inner) => AttributeValues::I16($mapped),
+            AttributeValues::U16($inner) => AttributeValues::U16($mapped),
+            AttributeValues::I32($inner) => AttributeValues::I32($mapped),
+            AttributeValues::U32($inner) => AttributeValues::U32($mapped),
+            AttributeValues::I64($inner) => AttributeValues::I64($mapped),
+            AttributeValues::U64($inner) => AttributeValues::U64($mapped),
+            AttributeValues::F32($inner) => AttributeValues::F32($mapped),
+            AttributeValues::F64($inner) => AttributeValues::F64($mapped),
+        }
+    };
+}
+
+// Applies the same statement to the inner vector of any variant
+macro_rules! for_attribute_values {
+    ($values:expr, $inner:ident => $body:expr) => {
+        match $values {
+            AttributeValues::I8($inner) => $body,
+            AttributeValues::U8($inner) => $body,
+            AttributeValues::I16($inner) => $body,
+            AttributeValues::U16($inner) => $body,
+            AttributeValues::I32($inner) => $body,
+            AttributeValues::U32($inner) => $body,
+            AttributeValues::I64($inner) => $body,
+            AttributeValues::U64($inner) => $body,
+            AttributeValues::F32($inner) => $body,
+            AttributeValues::F64($inner) => $body,
+        }
+    };
+}
+
+impl AttributeValues {
+    /// Returns the total amount of values, for all points and components.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::I8(values) => values.len(),
+            Self::U8(values) => values.len(),
+            Self::I16(values) => values.len(),
+            Self::U16(values) => values.len(),
+            Self::I32(values) => values.len(),
+            Self::U32(values) => values.len(),
+            Self::I64(values) => values.len(),
+            Self::U64(values) => values.len(),
+            Self::F32(values) => values.len(),
+            Self::F64(values) => values.len(),
+        }
+    }
+
+    /// Returns whether there are no values at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a single value, converted to an [`f64`], 64 bit integers beyond 2^53 lose precision.
+    ///
+    /// # Arguments
+    /// * `index`: the index of the value, for a channel with multiple components, this is `point_idx * components + component_idx`.
+    ///
+    /// # Returns
+    /// [`Some`] containing the converted value, or [`None`] if the index is out of bounds.
+    pub fn get_f64(&self, index: usize) -> Option<f64> {
+        match self {
+            Self::I8(values) => values.get(index).map(|&value| value as f64),
+            Self::U8(values) => values.get(index).map(|&value| value as f64),
+            Self::I16(values) => values.get(index).map(|&value| value as f64),
+            Self::U16(values) => values.get(index).map(|&value| value as f64),
+            Self::I32(values) => values.get(index).map(|&value| value as f64),
+            Self::U32(values) => values.get(index).map(|&value| value as f64),
+            Self::I64(values) => values.get(index).map(|&value| value as f64),
+            Self::U64(values) => values.get(index).map(|&value| value as f64),
+            Self::F32(values) => values.get(index).map(|&value| value as f64),
+            Self::F64(values) => values.get(index).copied(),
+        }
+    }
+
+    fn swap(&mut self, point_a: usize, point_b: usize, components: usize) {
+        for_attribute_values!(self, values => {
+            for component_idx in 0..components {
+                values.swap(
+                    point_a * components + component_idx,
+                    point_b * components + component_idx,
+                );
+            }
+        })
+    }
+
+    fn reverse(&mut self, components: usize) {
+        // Reversing all values reverses the components of each point as well, which are then restored
+        for_attribute_values!(self, values => {
+            values.reverse();
+            values
+                .chunks_mut(components.max(1))
+                .for_each(|point_values| point_values.reverse());
+        })
+    }
+
+    fn select(&self, indices: &[usize], components: usize) -> Self {
+        map_attribute_values!(
+            self,
+            values => indices
+                .iter()
+                .flat_map(|&point_idx| {
+                    values[point_idx * components..(point_idx + 1) * components]
+                        .iter()
+                        .copied()
+                })
+                .collect()
+        )
+    }
+}
+
+/// A named attribute channel of arbitrary numeric type, for attributes without a dedicated channel in [`PointCloud`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeChannel {
+    /// The name of the attribute.
+    pub name: String,
+    /// The amount of values each point has in this channel.
+    pub components: usize,
+    /// The values of all points, with the components of each point stored consecutively.
+    pub values: AttributeValues,
+}
+
+/// A point cloud, storing its points alongside optional per-point attributes, each in a separate channel.
+///
+/// Every attribute channel that is present contains exactly one value per point, in the same order as the points.
+/// The point cloud dereferences to a slice of its points, so it can be passed to any function accepting a slice of [`Point`],
+/// and index returning functions such as [`crop_box`](crate::point_clouds::crop_box) can be followed by [`PointCloud::select`]
+/// to keep the attributes of the selected points.
+/// To keep the points and attributes matching, points are only modified one at a time through [`PointCloud::map_points`],
+/// and reordered through methods such as [`PointCloud::sort_by`], which reorder every channel along with the points.
+///
+/// Attributes are carried through [`PointCloud::select`], [`PointCloud::transform`], [`PointCloud::downsample_voxel`],
+/// [`PointCloud::remove_statistical_outliers`] and [`PointCloud::remove_radius_outliers`].
+/// Other algorithms, such as [`estimate_normals`](crate::point_clouds::estimate_normals), the clustering functions and [`icp`](crate::point_clouds::icp),
+/// are used through the slice of points, their per-point results are in the same order as the points, and are not stored in the point cloud.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions in the points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloud<T: Scalar, const N: usize> {
+    points: Vec<Point<T, N>>,
+    intensities: Option<Vec<T>>,
+    rings: Option<Vec<u16>>,
+    timestamps: Option<Vec<T>>,
+    colours: Option<Vec<[u8; 3]>>,
+    normals: Option<Vec<SVector<T, N>>>,
+    channels: Vec<AttributeChannel>,
+}
+
+#[inline]
+fn swap_channel<V>(channel: &mut Option<Vec<V>>, point_a: usize, point_b: usize) {
+    if let Some(values) = channel.as_mut() {
+        values.swap(point_a, point_b);
+    }
+}
+
+#[inline]
+fn reverse_channel<V>(channel: &mut Option<Vec<V>>) {
+    if let Some(values) = channel.as_mut() {
+        values.reverse();
+    }
+}
+
+#[inline]
+fn select_channel<V: Clone>(channel: &Option<Vec<V>>, indices: &[usize]) -> Option<Vec<V>> {
+    channel.as_ref().map(|values| {
+        indices
+            .iter()
+            .map(|&point_idx| values[point_idx].clone())
+            .collect()
+    })
+}
+
+impl<T: Scalar, const N: usize> PointCloud<T, N> {
+    /// Creates a new point cloud without any attribute channels.
+    ///
+    /// # Arguments
+    /// * `points`: a [`Vec`] of [`Point`], the points of the point cloud.
+    ///
+    /// # Returns
+    /// A new [`PointCloud`].
+    pub fn new(points: Vec<Point<T, N>>) -> Self {
+        Self {
+            points,
+            intensities: None,
+            rings: None,
+            timestamps: None,
+            colours: None,
+            normals: None,
+            channels: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn verify_channel_length(&self, channel_len: usize) -> Result<(), PointCloudError> {
+        if channel_len != self.points.len() {
+            return Err(PointCloudError::AttributeLengthMismatch {
+                expected: self.points.len(),
+                actual: channel_len,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Adds an intensity channel to the point cloud, replacing any existing one.
+    ///
+    /// # Arguments
+    /// * `intensities`: a [`Vec`] containing the intensity of each point.
+    ///
+    /// # Returns
+    /// The point cloud with the new channel, or a [`PointCloudError`] if the channel's length does not match the amount of points.
+    pub fn with_intensities(mut self, intensities: Vec<T>) -> Result<Self, PointCloudError> {
+        self.verify_channel_length(intensities.len())?;
+        self.intensities = Some(intensities);
+        Ok(self)
+    }
+
+    /// Adds a ring channel to the point cloud, replacing any existing one.
+    ///
+    /// # Arguments
+    /// * `rings`: a [`Vec`] containing the index of the laser that measured each point.
+    ///
+    /// # Returns
+    /// The point cloud with the new channel, or a [`PointCloudError`] if the channel's length does not match the amount of points.
+    pub fn with_rings(mut self, rings: Vec<u16>) -> Result<Self, PointCloudError> {
+        self.verify_channel_length(rings.len())?;
+        self.rings = Some(rings);
+        Ok(self)
+    }
+
+    /// Adds a timestamp channel to the point cloud, replacing any existing one.
+    ///
+    /// # Arguments
+    /// * `timestamps`: a [`Vec`] containing the time at which each point was measured.
+    ///
+    /// # Returns
+    /// The point cloud with the new channel, or a [`PointCloudError`] if the channel's length does not match the amount of points.
+    pub fn with_timestamps(mut self, timestamps: Vec<T>) -> Result<Self, PointCloudError> {
+        self.verify_channel_length(timestamps.len())?;
+        self.timestamps = Some(timestamps);
+        Ok(self)
+    }
+
+    /// Adds a colour channel to the point cloud, replacing any existing one.
+    ///
+    /// # Arguments
+    /// * `colours`: a [`Vec`] containing the red, green and blue components of each point.
+    ///
+    /// # Returns
+    /// The point cloud with the new channel, or a [`PointCloudError`] if the channel's length does not match the amount of points.
+    pub fn with_colours(mut self, colours: Vec<[u8; 3]>) -> Result<Self, PointCloudError> {
+        self.verify_channel_length(colours.len())?;
+        self.colours = Some(colours);
+        Ok(self)
+    }
+
+    /// Adds a normal channel to the point cloud, replacing any existing one.
+    ///
+    /// # Arguments
+    /// * `normals`: a [`Vec`] containing the unit surface normal at each point.
+    ///
+    /// # Returns
+    /// The point cloud with the new channel, or a [`PointCloudError`] if the channel's length does not match the amount of points.
+    pub fn with_normals(mut self, normals: Vec<SVector<T, N>>) -> Result<Self, PointCloudError> {
+        self.verify_channel_length(normals.len())?;
+        self.normals = Some(normals);
+        Ok(self)
+    }
+
+    /// Adds a custom attribute channel to the point cloud, replacing any existing channel with the same name.
+    ///
+    /// # Arguments
+    /// * `channel`: an [`AttributeChannel`], containing `components` values for every point.
+    ///
+    /// # Returns
+    /// The point cloud with the new channel, or a [`PointCloudError`] if the channel's length does not match the amount of points.
+    pub fn with_channel(mut self, channel: AttributeChannel) -> Result<Self, PointCloudError> {
+        if channel.values.len() != self.points.len() * channel.components {
+            return Err(PointCloudError::AttributeLengthMismatch {
+                expected: self.points.len() * channel.components,
+                actual: channel.values.len(),
+            });
+        }
+
+        self.channels
+            .retain(|existing_channel| existing_channel.name != channel.name);
+        self.channels.push(channel);
+        Ok(self)
+    }
+
+    /// Returns the points of the point cloud.
+    ///
+    /// # Returns
+    /// A slice of [`Point`], in the same order as all attribute channels.
+    pub fn points(&self) -> &[Point<T, N>] {
+        &self.points
+    }
+
+    /// Returns the intensity channel, if present.
+    ///
+    /// # Returns
+    /// A slice containing the intensity of each point, or [`None`] if the point cloud has no intensity channel.
+    pub fn intensities(&self) -> Option<&[T]> {
+        self.intensities.as_deref()
+    }
+
+    /// Returns the ring channel, if present.
+    ///
+    /// # Returns
+    /// A slice containing the ring of each point, or [`None`] if the point cloud has no ring channel.
+    pub fn rings(&self) -> Option<&[u16]> {
+        self.rings.as_deref()
+    }
+
+    /// Returns the timestamp channel, if present.
+    ///
+    /// # Returns
+    /// A slice containing the timestamp of each point, or [`None`] if the point cloud has no timestamp channel.
+    pub fn timestamps(&self) -> Option<&[T]> {
+        self.timestamps.as_deref()
+    }
+
+    /// Returns the colour channel, if present.
+    ///
+    /// # Returns
+    /// A slice containing the colour of each point, or [`None`] if the point cloud has no colour channel.
+    pub fn colours(&self) -> Option<&[[u8; 3]]> {
+        self.colours.as_deref()
+    }
+
+    /// Returns the normal channel, if present.
+    ///
+    /// # Returns
+    /// A slice containing the normal of each point, or [`None`] if the point cloud has no normal channel.
+    pub fn normals(&self) -> Option<&[SVector<T, N>]> {
+        self.normals.as_deref()
+    }
+
+    /// Returns all custom attribute channels, in the order they were added.
+    ///
+    /// # Returns
+    /// A slice of [`AttributeChannel`], which is empty if no custom channels were added.
+    pub fn channels(&self) -> &[AttributeChannel] {
+        &self.channels
+    }
+
+    /// Returns the custom attribute channel with the given name, if present.
+    ///
+    /// # Arguments
+    /// * `name`: the name of the channel.
+    ///
+    /// # Returns
+    /// A reference to the [`AttributeChannel`], or [`None`] if no channel has that name.
+    pub fn channel(&self, name: &str) -> Option<&AttributeChannel> {
+        self.channels.iter().find(|channel| channel.name == name)
+    }
+
+    /// Consumes the point cloud, discarding all attribute channels.
+    ///
+    /// # Returns
+    /// A [`Vec`] of [`Point`], the points of the point cloud.
+    pub fn into_points(self) -> Vec<Point<T, N>> {
+        self.points
+    }
+
+    /// Creates a new point cloud from a subset of the points, keeping all attribute channels.
+    ///
+    /// # Arguments
+    /// * `indices`: a slice of indices of the points to keep, such as the ones returned by filtering or downsampling functions.
+    ///
+    /// # Returns
+    /// A new [`PointCloud`], containing the selected points in the order of `indices`.
+    ///
+    /// # Panics
+    /// If any index is out of bounds.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Select Point Cloud Subset", skip_all)
+    )]
+    pub fn select(&self, indices: &[usize]) -> Self {
+        self.with_selected_attributes(
+            indices
+                .iter()
+                .map(|&point_idx| self.points[point_idx].clone())
+                .collect(),
+            indices,
+        )
+    }
+
+    // Creates a point cloud from points that were already selected by `indices`, selecting the same values from every channel
+    fn with_selected_attributes(&self, points: Vec<Point<T, N>>, indices: &[usize]) -> Self {
+        Self {
+            points,
+            intensities: select_channel(&self.intensities, indices),
+            rings: select_channel(&self.rings, indices),
+            timestamps: select_channel(&self.timestamps, indices),
+            colours: select_channel(&self.colours, indices),
+            normals: select_channel(&self.normals, indices),
+            channels: self
+                .channels
+                .iter()
+                .map(|channel| AttributeChannel {
+                    values: channel.values.select(indices, channel.components),
+                    ..channel.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Modifies every point in place, leaving all attribute channels unchanged.
+    ///
+    /// # Arguments
+    /// * `func`: a closure of type [`FnMut`], receiving each [`Point`] and returning its replacement.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Map Point Cloud Points", skip_all)
+    )]
+    pub fn map_points<F>(&mut self, mut func: F)
+    where
+        F: FnMut(&Point<T, N>) -> Point<T, N>,
+    {
+        self.points
+            .iter_mut()
+            .for_each(|point| *point = func(point));
+    }
+
+    /// Swaps two points, along with all of their attributes.
+    ///
+    /// # Arguments
+    /// * `point_a`: the index of the first point.
+    /// * `point_b`: the index of the second point.
+    ///
+    /// # Panics
+    /// If either index is out of bounds.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Swap Point Cloud Points", skip_all)
+    )]
+    pub fn swap(&mut self, point_a: usize, point_b: usize) {
+        self.points.swap(point_a, point_b);
+        swap_channel(&mut self.intensities, point_a, point_b);
+        swap_channel(&mut self.rings, point_a, point_b);
+        swap_channel(&mut self.timestamps, point_a, point_b);
+        swap_channel(&mut self.colours, point_a, point_b);
+        swap_channel(&mut self.normals, point_a, point_b);
+        for channel in self.channels.iter_mut() {
+            channel.values.swap(point_a, point_b, channel.components);
+        }
+    }
+
+    /// Reverses the order of the points, along with all of their attributes.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Reverse Point Cloud", skip_all)
+    )]
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+        reverse_channel(&mut self.intensities);
+        reverse_channel(&mut self.rings);
+        reverse_channel(&mut self.timestamps);
+        reverse_channel(&mut self.colours);
+        reverse_channel(&mut self.normals);
+        for channel in self.channels.iter_mut() {
+            channel.values.reverse(channel.components);
+        }
+    }
+
+    /// Sorts the points with a comparator function, reordering all of their attributes to match.
+    /// The sort is stable, points that compare as equal keep their relative order.
+    ///
+    /// # Arguments
+    /// * `compare`: a closure of type [`FnMut`], returning the [`Ordering`] of two points.
+    #[cfg_attr(feature = "tracing", tracing::instrument("Sort Point Cloud", skip_all))]
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&Point<T, N>, &Point<T, N>) -> Ordering,
+    {
+        let mut order = (0..self.points.len()).collect::<Vec<_>>();
+        order.sort_by(|&point_a, &point_b| compare(&self.points[point_a], &self.points[point_b]));
+        *self = self.select(&order);
+    }
+
+    /// Sorts the points in lexicographical order, reordering all of their attributes to match,
+    /// see [`lex_sort_in_place`](crate::point_clouds::lex_sort_in_place).
+    ///
+    /// # Returns
+    /// a [`bool`], indicating if the operation was successful, the point cloud is left unchanged if any coordinate is NaN.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Lexicographical Sort Point Cloud", skip_all)
+    )]
+    pub fn lex_sort_in_place(&mut self) -> bool
+    where
+        T: PartialOrd + IsNan,
+    {
+        if !validate_input(&self.points) {
+            return false;
+        }
+
+        self.sort_by(lex_sort_func);
+        true
+    }
+}
+
+impl<T: Copy + RealField, const N: usize> PointCloud<T, N> {
+    /// Transforms the point cloud, rotating the normals as well.
+    ///
+    /// # Arguments
+    /// * `isometry`: an [`Isometry`], the transformation to apply.
+    ///
+    /// # Generics
+    /// * `R`: An [`AbstractRotation`], representing the isometry's rotation.
+    ///
+    /// # Returns
+    /// A new, transformed [`PointCloud`], with all other attribute channels unchanged.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Transform Point Cloud With Attributes", skip_all)
+    )]
+    pub fn transform<R>(&self, isometry: &Isometry<T, R, N>) -> Self
+    where
+        R: AbstractRotation<T, N>,
+    {
+        Self {
+            points: self
+                .points
+                .iter()
+                .map(|point| isometry.transform_point(point))
+                .collect(),
+            normals: self.normals.as_ref().map(|normals| {
+                normals
+                    .iter()
+                    .map(|normal| isometry.rotation.transform_vector(normal))
+                    .collect()
+            }),
+            ..self.clone()
+        }
+    }
+
+    /// Downsample the point cloud, combining all points within each voxel, along with their attributes.
+    ///
+    /// Positions, intensities, timestamps and colours are averaged, normals are averaged and then normalized,
+    /// and since rings and custom channels may be discrete, the values of the voxel's first point are kept.
+    ///
+    /// # Arguments
+    /// * `voxel_size`: a floating point number, specifying the size for each voxel.
+    ///
+    /// # Generics
+    /// * `O`: Either an [`f32`] or [`f64`], used for the voxel computations.
+    ///
+    /// # Returns
+    /// A new [`PointCloud`], with one point per occupied voxel, ordered by the first point of each voxel.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Downsample Point Cloud With Attributes", skip_all)
+    )]
+    pub fn downsample_voxel<O>(&self, voxel_size: O) -> Self
+    where
+        O: AsPrimitive<isize> + ComplexField + Copy,
+        T: AsPrimitive<O>,
+        usize: AsPrimitive<T>,
+    {
+        let mut voxels = group_points_by_voxel(&self.points, voxel_size)
+            .into_values()
+            .collect::<Vec<_>>();
+        voxels.sort_unstable_by_key(|indices_in_voxel| indices_in_voxel[0]);
+        let first_indices = voxels
+            .iter()
+            .map(|indices_in_voxel| indices_in_voxel[0])
+            .collect::<Vec<_>>();
+
+        let mean = |values: &[T], indices_in_voxel: &[usize]| {
+            indices_in_voxel
+                .iter()
+                .fold(T::zero(), |acc, &point_idx| acc + values[point_idx])
+                / indices_in_voxel.len().as_()
+        };
+
+        Self {
+            points: voxels
+                .iter()
+                .map(|indices_in_voxel| {
+                    let sum = indices_in_voxel
+                        .iter()
+                        .fold(SVector::zeros(), |acc, &point_idx| {
+                            acc + self.points[point_idx].coords
+                        });
+                    Point::from(sum / indices_in_voxel.len().as_())
+                })
+                .collect(),
+            intensities: self.intensities.as_ref().map(|intensities| {
+                voxels
+                    .iter()
+                    .map(|indices_in_voxel| mean(intensities, indices_in_voxel))
+                    .collect()
+            }),
+            rings: select_channel(&self.rings, &first_indices),
+            timestamps: self.timestamps.as_ref().map(|timestamps| {
+                voxels
+                    .iter()
+                    .map(|indices_in_voxel| mean(timestamps, indices_in_voxel))
+                    .collect()
+            }),
+            colours: self.colours.as_ref().map(|colours| {
+                voxels
+                    .iter()
+                    .map(|indices_in_voxel| {
+                        let sums = indices_in_voxel
+                            .iter()
+                            .fold([0usize; 3], |acc, &point_idx| {
+                                let colour = colours[point_idx];
+                                [
+                                    acc[0] + colour[0] as usize,
+                                    acc[1] + colour[1] as usize,
+                                    acc[2] + colour[2] as usize,
+                                ]
+                            });
+                        // Rounded to the nearest value
+                        let num_points = indices_in_voxel.len();
+                        sums.map(|sum| ((2 * sum + num_points) / (2 * num_points)) as u8)
+                    })
+                    .collect()
+            }),
+            normals: self.normals.as_ref().map(|normals| {
+                voxels
+                    .iter()
+                    .map(|indices_in_voxel| {
+                        let sum = indices_in_voxel
+                            .iter()
+                            .fold(SVector::zeros(), |acc, &point_idx| acc + normals[point_idx]);
+                        sum.try_normalize(T::default_epsilon())
+                            .unwrap_or_else(SVector::zeros)
+                    })
+                    .collect()
+            }),
+            channels: self
+                .channels
+                .iter()
+                .map(|channel| AttributeChannel {
+                    values: channel.values.select(&first_indices, channel.components),
+                    ..channel.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<T: Copy + Default + RealField, const N: usize> PointCloud<T, N> {
+    // Combines the inlier points with the attributes of every point that is not in the ascending `outlier_indices`
+    fn with_inliers(&self, inlier_points: Vec<Point<T, N>>, outlier_indices: &[usize]) -> Self {
+        let inlier_indices = (0..self.points.len())
+            .filter(|point_idx| outlier_indices.binary_search(point_idx).is_err())
+            .collect::<Vec<_>>();
+        self.with_selected_attributes(inlier_points, &inlier_indices)
+    }
+
+    /// Removes statistical outliers from the point cloud, keeping the attributes of the remaining points,
+    /// see [`remove_statistical_outliers`] for how outliers are determined.
+    ///
+    /// # Arguments
+    /// * `k_neighbours`: the number of nearest neighbours to use when calculating each point's mean distance.
+    /// * `std_dev_multiplier`: the number of standard deviations above the global mean, beyond which a point is considered an outlier.
+    ///
+    /// # Returns
+    /// A tuple of
+    /// * A new [`PointCloud`], containing the inlier points and their attributes, in their original order.
+    /// * A [`Vec`] of [`usize`], containing the indices of the removed outlier points, in ascending order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Remove Statistical Outliers With Attributes", skip_all)
+    )]
+    pub fn remove_statistical_outliers(
+        &self,
+        k_neighbours: usize,
+        std_dev_multiplier: T,
+    ) -> (Self, Vec<usize>)
+    where
+        usize: AsPrimitive<T>,
+    {
+        let (inlier_points, outlier_indices) =
+            remove_statistical_outliers(&self.points, k_neighbours, std_dev_multiplier);
+        (
+            self.with_inliers(inlier_points, &outlier_indices),
+            outlier_indices,
+        )
+    }
+
+    /// Removes points with too few neighbours from the point cloud, keeping the attributes of the remaining points,
+    /// see [`remove_radius_outliers`] for how outliers are determined.
+    ///
+    /// # Arguments
+    /// * `radius`: the radius around each point in which to count neighbours, inclusive.
+    /// * `min_neighbours`: the minimum number of neighbours, not including the point itself, required for a point to be kept.
+    ///
+    /// # Returns
+    /// A tuple of
+    /// * A new [`PointCloud`], containing the inlier points and their attributes, in their original order.
+    /// * A [`Vec`] of [`usize`], containing the indices of the removed outlier points, in ascending order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Remove Radius Outliers With Attributes", skip_all)
+    )]
+    pub fn remove_radius_outliers(&self, radius: T, min_neighbours: usize) -> (Self, Vec<usize>) {
+        let (inlier_points, outlier_indices) =
+            remove_radius_outliers(&self.points, radius, min_neighbours);
+        (
+            self.with_inliers(inlier_points, &outlier_indices),
+            outlier_indices,
+        )
+    }
+}
+
+impl<T: Scalar, const N: usize> Default for PointCloud<T, N> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T: Scalar, const N: usize> From<Vec<Point<T, N>>> for PointCloud<T, N> {
+    fn from(points: Vec<Point<T, N>>) -> Self {
+        Self::new(points)
+    }
+}
+
+impl<T: Scalar, const N: usize> From<&[Point<T, N>]> for PointCloud<T, N> {
+    fn from(points: &[Point<T, N>]) -> Self {
+        Self::new(points.to_vec())
+    }
+}
+
+impl<T: Scalar, const N: usize> FromIterator<Point<T, N>> for PointCloud<T, N> {
+    fn from_iter<I: IntoIterator<Item = Point<T, N>>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<T: Scalar, const N: usize> Deref for PointCloud<T, N> {
+    type Target = [Point<T, N>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Isometry3, Point2, Point3, Vector2, Vector3};
+
+    use crate::{
+        array,
+        point_clouds::{
+            crop_box, generate_point_cloud, icp, remove_radius_outliers, ICPConfiguration,
+        },
+        ToOwned,
+    };
+
+    use super::*;
+
+    fn generate_attributed_cloud() -> PointCloud<f64, 3> {
+        PointCloud::new(Vec::from([
+            Point3::new(0.1, 0.1, 0.1),
+            Point3::new(0.3, 0.3, 0.3),
+            Point3::new(5.0, 5.0, 5.0),
+        ]))
+        .with_intensities(Vec::from([10.0, 20.0, 30.0]))
+        .unwrap()
+        .with_rings(Vec::from([3, 4, 5]))
+        .unwrap()
+        .with_timestamps(Vec::from([0.0, 0.1, 0.2]))
+        .unwrap()
+        .with_colours(Vec::from([[255, 0, 0], [0, 0, 255], [0, 255, 0]]))
+        .unwrap()
+        .with_normals(Vec::from([Vector3::x(), Vector3::y(), Vector3::z()]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_attribute_length_mismatch() {
+        let cloud = PointCloud::new(Vec::from([Point2::new(0.0, 0.0), Point2::new(1.0, 1.0)]));
+        assert_eq!(
+            cloud.clone().with_intensities(Vec::from([1.0])),
+            Err(PointCloudError::AttributeLengthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert!(cloud.with_rings(Vec::from([0, 1])).is_ok());
+    }
+
+    #[test]
+    fn test_select() {
+        let cloud = generate_attributed_cloud();
+        let selected = cloud.select(&[2, 0]);
+        assert_eq!(
+            selected.points(),
+            [Point3::new(5.0, 5.0, 5.0), Point3::new(0.1, 0.1, 0.1)]
+        );
+        assert_eq!(selected.intensities(), Some([30.0, 10.0].as_slice()));
+        assert_eq!(selected.rings(), Some([5, 3].as_slice()));
+        assert_eq!(selected.timestamps(), Some([0.2, 0.0].as_slice()));
+        assert_eq!(
+            selected.colours(),
+            Some([[0, 255, 0], [255, 0, 0]].as_slice())
+        );
+        assert_eq!(
+            selected.normals(),
+            Some([Vector3::z(), Vector3::x()].as_slice())
+        );
+    }
+
+    #[test]
+    fn test_select_filtered_indices() {
+        let cloud = generate_attributed_cloud();
+        let (_, outlier_indices) = remove_radius_outliers(&cloud, 1.0, 1);
+        let inlier_indices = (0..cloud.len())
+            .filter(|point_idx| !outlier_indices.contains(point_idx))
+            .collect::<Vec<_>>();
+        let filtered = cloud.select(&inlier_indices);
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered.intensities(), Some([10.0, 20.0].as_slice()));
+
+        let cropped = cloud.select(&crop_box(&cloud, &[4.0..=6.0, 4.0..=6.0, 4.0..=6.0], false));
+        assert_eq!(cropped.rings(), Some([5].as_slice()));
+    }
+
+    #[test]
+    fn test_remove_statistical_outliers() {
+        let mut points = generate_point_cloud(100, array::from_fn(|_| -1.0..=1.0));
+        points.insert(40, Point3::new(30.0, 0.0, 0.0));
+        let cloud = PointCloud::new(points)
+            .with_intensities((0..101).map(|idx| idx as f64).collect())
+            .unwrap();
+
+        let (inliers, outlier_indices) = cloud.remove_statistical_outliers(8, 3.0);
+        assert_eq!(outlier_indices, [40]);
+        assert_eq!(inliers.len(), 100);
+        assert_eq!(inliers[40], cloud[41]);
+        assert_eq!(inliers.intensities().unwrap()[40], 41.0);
+    }
+
+    #[test]
+    fn test_remove_radius_outliers() {
+        let cloud = generate_attributed_cloud();
+        let (inliers, outlier_indices) = cloud.remove_radius_outliers(1.0, 1);
+        assert_eq!(outlier_indices, [2]);
+        assert_eq!(inliers, cloud.select(&[0, 1]));
+
+        let (unchanged, outlier_indices) = cloud.remove_radius_outliers(1.0, 0);
+        assert!(outlier_indices.is_empty());
+        assert_eq!(unchanged, cloud);
+    }
+
+    #[test]
+    fn test_custom_channels() {
+        let cloud = generate_attributed_cloud()
+            .with_channel(AttributeChannel {
+                name: "label".to_owned(),
+                components: 1,
+                values: AttributeValues::U32(Vec::from([7, 8, 9])),
+            })
+            .unwrap()
+            .with_channel(AttributeChannel {
+                name: "velocity".to_owned(),
+                components: 2,
+                values: AttributeValues::F32(Vec::from([1.0, 2.0, 3.0, 4.0, 5.0, 6.0])),
+            })
+            .unwrap();
+        assert_eq!(
+            cloud.clone().with_channel(AttributeChannel {
+                name: "label".to_owned(),
+                components: 2,
+                values: AttributeValues::I8(Vec::from([1, 2, 3])),
+            }),
+            Err(PointCloudError::AttributeLengthMismatch {
+                expected: 6,
+                actual: 3
+            })
+        );
+
+        let selected = cloud.select(&[2, 1]);
+        assert_eq!(
+            selected.channel("label").unwrap().values,
+            AttributeValues::U32(Vec::from([9, 8]))
+        );
+        assert_eq!(
+            selected.channel("velocity").unwrap().values,
+            AttributeValues::F32(Vec::from([5.0, 6.0, 3.0, 4.0]))
+        );
+        assert_eq!(
+            selected.channel("velocity").unwrap().values.get_f64(3),
+            Some(4.0)
+        );
+        assert_eq!(
+            selected.channel("velocity").unwrap().values.get_f64(4),
+            None
+        );
+
+        let downsampled = cloud.downsample_voxel(1.0);
+        assert_eq!(
+            downsampled.channel("label").unwrap().values,
+            AttributeValues::U32(Vec::from([7, 9]))
+        );
+        assert_eq!(downsampled.channels().len(), 2);
+
+        // Replacing a channel keeps a single channel with that name
+        let replaced = cloud
+            .with_channel(AttributeChannel {
+                name: "label".to_owned(),
+                components: 1,
+                values: AttributeValues::I8(Vec::from([-1, -2, -3])),
+            })
+            .unwrap();
+        assert_eq!(replaced.channels().len(), 2);
+        assert_eq!(replaced.channel("label").unwrap().values.len(), 3);
+    }
+
+    #[test]
+    fn test_transform() {
+        let cloud = generate_attributed_cloud();
+        let isometry = Isometry3::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::z() * core::f64::consts::FRAC_PI_2,
+        );
+        let transformed = cloud.transform(&isometry);
+
+        assert!((transformed[2] - Point3::new(-4.0, 7.0, 8.0)).norm() < 1e-9);
+        let normals = transformed.normals().unwrap();
+        assert!((normals[0] - Vector3::y()).norm() < 1e-9);
+        assert!((normals[1] + Vector3::x()).norm() < 1e-9);
+        assert!((normals[2] - Vector3::z()).norm() < 1e-9);
+        assert_eq!(transformed.intensities(), cloud.intensities());
+    }
+
+    #[test]
+    fn test_downsample_voxel() {
+        let cloud = generate_attributed_cloud();
+        let downsampled = cloud.downsample_voxel(1.0);
+
+        assert_eq!(downsampled.len(), 2);
+        assert!((downsampled[0] - Point3::new(0.2, 0.2, 0.2)).norm() < 1e-9);
+        assert_eq!(downsampled[1], Point3::new(5.0, 5.0, 5.0));
+        assert_eq!(downsampled.intensities(), Some([15.0, 30.0].as_slice()));
+        assert_eq!(downsampled.rings(), Some([3, 5].as_slice()));
+        assert!((downsampled.timestamps().unwrap()[0] - 0.05).abs() < 1e-9);
+        assert_eq!(
+            downsampled.colours(),
+            Some([[128, 0, 128], [0, 255, 0]].as_slice())
+        );
+        assert!(
+            (downsampled.normals().unwrap()[0] - Vector3::new(1.0, 1.0, 0.0).normalize()).norm()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_downsample_voxel_without_attributes() {
+        let cloud = PointCloud::from_iter([Point2::new(0.1, 0.1), Point2::new(0.2, 0.2)]);
+        let downsampled = cloud.downsample_voxel(1.0f32);
+        assert_eq!(downsampled.len(), 1);
+        assert!((downsampled[0] - Point2::new(0.15, 0.15)).norm() < 1e-6);
+        assert_eq!(downsampled.intensities(), None);
+        assert_eq!(downsampled.normals(), None);
+    }
+
+    #[test]
+    fn test_icp_with_point_cloud() {
+        let source: PointCloud<f64, 3> =
+            PointCloud::from(generate_point_cloud(300, array::from_fn(|_| -15.0..=15.0)));
+        let target = source.transform(&Isometry3::new(
+            Vector3::new(-0.5, 0.8, 0.2),
+            Vector3::new(0.05, -0.1, 0.1),
+        ));
+
+        let res = icp(
+            &source,
+            &target,
+            ICPConfiguration::builder()
+                .with_max_iterations(50)
+                .with_mse_interval_threshold(0.01)
+                .build(),
+        );
+        assert!(res.unwrap().mse < 0.05);
+    }
+
+    #[test]
+    fn test_map_points() {
+        let mut moved = PointCloud::from([Point2::new(0.0, 0.0)].as_slice())
+            .with_rings(Vec::from([1]))
+            .unwrap();
+        moved.map_points(|point| point + Vector2::new(1.0, 2.0));
+        assert_eq!(moved.rings(), Some([1].as_slice()));
+        assert_eq!(moved.into_points(), [Point2::new(1.0, 2.0)]);
+    }
+
+    fn generate_channel_cloud() -> PointCloud<f64, 3> {
+        generate_attributed_cloud()
+            .with_channel(AttributeChannel {
+                name: "velocity".to_owned(),
+                components: 2,
+                values: AttributeValues::F32(Vec::from([1.0, 2.0, 3.0, 4.0, 5.0, 6.0])),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut cloud = generate_channel_cloud();
+        cloud.swap(0, 2);
+        assert_eq!(cloud, generate_channel_cloud().select(&[2, 1, 0]));
+        assert_eq!(
+            cloud.channel("velocity").unwrap().values,
+            AttributeValues::F32(Vec::from([5.0, 6.0, 3.0, 4.0, 1.0, 2.0]))
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut cloud = generate_channel_cloud();
+        cloud.reverse();
+        assert_eq!(cloud, generate_channel_cloud().select(&[2, 1, 0]));
+        assert_eq!(cloud.intensities(), Some([30.0, 20.0, 10.0].as_slice()));
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let mut cloud = generate_channel_cloud();
+        cloud.sort_by(|point_a, point_b| point_b.x.partial_cmp(&point_a.x).unwrap());
+        assert_eq!(cloud, generate_channel_cloud().select(&[2, 1, 0]));
+        assert_eq!(cloud.rings(), Some([5, 4, 3].as_slice()));
+    }
+
+    #[test]
+    fn test_lex_sort_in_place() {
+        let mut cloud = PointCloud::new(Vec::from([
+            Point2::new(1.0, 0.0),
+            Point2::new(0.0, 1.0),
+            Point2::new(0.0, 0.0),
+        ]))
+        .with_intensities(Vec::from([1.0, 2.0, 3.0]))
+        .unwrap();
+        assert!(cloud.lex_sort_in_place());
+        assert_eq!(
+            cloud.points(),
+            [
+                Point2::new(0.0, 0.0),
+                Point2::new(0.0, 1.0),
+                Point2::new(1.0, 0.0)
+            ]
+        );
+        assert_eq!(cloud.intensities(), Some([3.0, 2.0, 1.0].as_slice()));
+
+        // NaN coordinates cannot be ordered, the point cloud is left unchanged
+        let mut invalid = PointCloud::new(Vec::from([
+            Point2::new(1.0, 0.0),
+            Point2::new(f64::NAN, 1.0),
+        ]));
+        assert!(!invalid.lex_sort_in_place());
+        assert_eq!(invalid[0], Point2::new(1.0, 0.0));
+    }
+}
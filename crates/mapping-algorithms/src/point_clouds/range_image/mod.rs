@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point3, RealField, Vector3};
+use num_traits::AsPrimitive;
+
+use crate::Vec;
+
+pub use types::{RangeImage, RangeImageConfiguration, RangeImageConfigurationBuilder, RangePixel};
+
+mod types;
+
+impl<T> RangeImage<T>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    /// Returns the unit direction through the centre of a pixel, in the sensor's frame.
+    ///
+    /// # Arguments
+    /// * `row`: the index of the row.
+    /// * `column`: the index of the column.
+    ///
+    /// # Returns
+    /// A [`Vector3`], the direction of the pixel's centre.
+    pub fn pixel_direction(&self, row: usize, column: usize) -> Vector3<T> {
+        let half: T = nalgebra::convert(0.5);
+        let azimuth = self.horizontal_fov * half
+            - (column.as_() + half) * self.horizontal_fov / self.width.as_();
+        let elevation = self.max_elevation
+            - (row.as_() + half) * (self.max_elevation - self.min_elevation) / self.height.as_();
+        Vector3::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+        )
+    }
+
+    /// Back-projects a single pixel, placing a point at the pixel's range along the direction of its centre.
+    ///
+    /// # Arguments
+    /// * `row`: the index of the row.
+    /// * `column`: the index of the column.
+    ///
+    /// # Returns
+    /// [`Some`] containing the back-projected [`Point3`], or [`None`] if the pixel is empty or out of bounds.
+    pub fn back_project_pixel(&self, row: usize, column: usize) -> Option<Point3<T>> {
+        let range = self.range(row, column)?;
+        Some(Point3::from(self.pixel_direction(row, column) * range))
+    }
+
+    /// Back-projects all occupied pixels into a point cloud, the exact points are available through [`Self::pixel`].
+    ///
+    /// # Returns
+    /// A [`Vec`] of [`Point3`], one for each occupied pixel, in row-major order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Back-Project Range Image", skip_all, level = "info")
+    )]
+    pub fn back_project(&self) -> Vec<Point3<T>> {
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |column| (row, column)))
+            .filter_map(|(row, column)| self.back_project_pixel(row, column))
+            .collect()
+    }
+
+    /// Returns the occupied pixels in a square window around a pixel, excluding the pixel itself.
+    /// Columns wrap around the image's edges if it covers a full revolution, rows never wrap.
+    ///
+    /// # Arguments
+    /// * `row`: the index of the window's central row.
+    /// * `column`: the index of the window's central column.
+    /// * `radius`: the amount of pixels the window extends in each direction, `1` gives the 8-neighbourhood.
+    ///
+    /// # Returns
+    /// An [`Iterator`] over the row and column of each occupied neighbouring pixel.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Get Range Image Neighbours", skip_all, level = "trace")
+    )]
+    pub fn neighbours(
+        &self,
+        row: usize,
+        column: usize,
+        radius: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rows = row.saturating_sub(radius)..(row + radius + 1).min(self.height);
+        let columns = if self.wraps_horizontally && 2 * radius + 1 >= self.width {
+            (0..self.width).collect::<Vec<_>>()
+        } else if self.wraps_horizontally {
+            (column + self.width - radius..=column + self.width + radius)
+                .map(|wrapped_column| wrapped_column % self.width)
+                .collect()
+        } else {
+            (column.saturating_sub(radius)..(column + radius + 1).min(self.width)).collect()
+        };
+
+        rows.flat_map(move |neighbour_row| {
+            columns
+                .clone()
+                .into_iter()
+                .map(move |neighbour_column| (neighbour_row, neighbour_column))
+        })
+        .filter(move |&(neighbour_row, neighbour_column)| {
+            (neighbour_row, neighbour_column) != (row, column)
+                && self.pixel(neighbour_row, neighbour_column).is_some()
+        })
+    }
+
+    /// Estimates the normal of each occupied pixel from its horizontal and vertical neighbours on the image grid,
+    /// which is far cheaper than searching for neighbours in 3D.
+    ///
+    /// The right and lower neighbours are preferred, falling back to the left and upper neighbours,
+    /// neighbours across a depth discontinuity are ignored, and normals are oriented towards the sensor.
+    ///
+    /// # Arguments
+    /// * `max_range_difference`: the maximum difference in range between a pixel and a neighbour used for its normal.
+    ///
+    /// # Returns
+    /// A [`Vec`] containing the unit normal of each pixel in row-major order,
+    /// or [`None`] for empty pixels and pixels without a usable horizontal and vertical neighbour.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Estimate Range Image Normals", skip_all, level = "info")
+    )]
+    pub fn estimate_normals(&self, max_range_difference: T) -> Vec<Option<Vector3<T>>> {
+        let is_usable = |pixel: &RangePixel<T>, neighbour: &RangePixel<T>| {
+            (neighbour.range - pixel.range).abs() <= max_range_difference
+        };
+        let left_column = |column: usize| {
+            if column > 0 {
+                Some(column - 1)
+            } else {
+                self.wraps_horizontally.then_some(self.width - 1)
+            }
+        };
+        let right_column = |column: usize| {
+            if column + 1 < self.width {
+                Some(column + 1)
+            } else {
+                self.wraps_horizontally.then_some(0)
+            }
+        };
+
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let pixel = self.pixel(row, column)?;
+                // Neighbours before the pixel are negated, so both candidates point in the same direction
+                let offset_to = |neighbour: Option<&RangePixel<T>>, sign: T| {
+                    neighbour
+                        .filter(|neighbour| is_usable(pixel, neighbour))
+                        .map(|neighbour| (neighbour.point - pixel.point) * sign)
+                };
+                let horizontal = offset_to(
+                    right_column(column).and_then(|right| self.pixel(row, right)),
+                    T::one(),
+                )
+                .or_else(|| {
+                    offset_to(
+                        left_column(column).and_then(|left| self.pixel(row, left)),
+                        -T::one(),
+                    )
+                })?;
+                let vertical = offset_to(self.pixel(row + 1, column), T::one()).or_else(|| {
+                    offset_to(
+                        row.checked_sub(1).and_then(|up| self.pixel(up, column)),
+                        -T::one(),
+                    )
+                })?;
+
+                let normal = horizontal
+                    .cross(&vertical)
+                    .try_normalize(T::default_epsilon())?;
+                Some(if normal.dot(&pixel.point.coords) > T::zero() {
+                    -normal
+                } else {
+                    normal
+                })
+            })
+            .collect()
+    }
+}
+
+/// Projects a point cloud into a spherical [`RangeImage`], in which each pixel keeps the closest point that falls within it.
+///
+/// # Arguments
+/// * `points`: a slice of [`Point3`], representing the scan in the sensor's frame, with the Z axis pointing upwards.
+/// * `config`: a [`RangeImageConfiguration`], specifying the image's resolution, field of view and range limits.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`RangeImage`], points outside the field of view or the range limits are not projected.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Project To Range Image", skip_all, level = "info")
+)]
+pub fn project_to_range_image<T>(
+    points: &[Point3<T>],
+    config: RangeImageConfiguration<T>,
+) -> RangeImage<T>
+where
+    T: AsPrimitive<isize> + Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let width = config.width.max(1);
+    let height = config.height.max(1);
+    let horizontal_resolution = config.horizontal_fov / width.as_();
+    let vertical_resolution = (config.max_elevation - config.min_elevation) / height.as_();
+    // Tolerates a full revolution given in single precision
+    let wraps_horizontally = config.horizontal_fov >= T::two_pi() - nalgebra::convert(1e-6);
+
+    let mut pixels = Vec::from_iter(core::iter::repeat_n(None, width * height));
+    for (point_idx, point) in points.iter().enumerate() {
+        let range = point.coords.norm();
+        if !range.is_finite() || range < config.min_range || range > config.max_range {
+            continue;
+        }
+
+        let azimuth = point.y.atan2(point.x);
+        let elevation = point.z.atan2(point.xy().coords.norm());
+        let mut column: isize = ((config.horizontal_fov * nalgebra::convert(0.5) - azimuth)
+            / horizontal_resolution)
+            .floor()
+            .as_();
+        let row: isize = ((config.max_elevation - elevation) / vertical_resolution)
+            .floor()
+            .as_();
+        if wraps_horizontally {
+            column = column.rem_euclid(width as isize);
+        }
+        if !(0..width as isize).contains(&column) || !(0..height as isize).contains(&row) {
+            continue;
+        }
+
+        let pixel = &mut pixels[row as usize * width + column as usize];
+        if pixel.is_none_or(|pixel: RangePixel<T>| range < pixel.range) {
+            *pixel = Some(RangePixel {
+                range,
+                point: *point,
+                point_idx,
+            });
+        }
+    }
+
+    RangeImage {
+        width,
+        height,
+        horizontal_fov: config.horizontal_fov,
+        max_elevation: config.max_elevation,
+        min_elevation: config.min_elevation,
+        wraps_horizontally,
+        pixels,
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_range_image {
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::Point3;
+                use super::{RangeImage, RangeImageConfiguration};
+
+                #[doc = "A premade variant of the range image projection function, in " $doc "-precision floats."]
+                pub fn project_to_range_image(points: &[Point3<$precision>], config: RangeImageConfiguration<$precision>) -> RangeImage<$precision> {
+                    super::project_to_range_image(points, config)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pregenerated")]
+impl_range_image!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_range_image!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    fn small_config() -> RangeImageConfiguration<f64> {
+        RangeImageConfiguration::builder()
+            .with_resolution(360, 40)
+            .with_vertical_fov((-20.0f64).to_radians(), 20.0f64.to_radians())
+            .build()
+    }
+
+    // A wall perpendicular to the X axis, 10 metres in front of the sensor
+    fn generate_wall() -> Vec<Point3<f64>> {
+        (-30..=30)
+            .flat_map(|y| {
+                (-20..=20).map(move |z| Point3::new(10.0, y as f64 * 0.1, z as f64 * 0.1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_projection_and_back_projection() {
+        let points = generate_wall();
+        let image = project_to_range_image(&points, small_config());
+        assert_eq!((image.width(), image.height()), (360, 40));
+        assert!(image.wraps_horizontally());
+
+        // The centre of the wall faces the sensor's X axis, at the centre of the image
+        let centre = points
+            .iter()
+            .position(|point| *point == Point3::new(10.0, 0.0, 0.0))
+            .unwrap();
+        // Its elevation of zero lies exactly on the upper edge of row 20, which covers elevations in (-1°, 0°]
+        let centre_pixel = image.pixel(20, 180).unwrap();
+        assert_eq!(centre_pixel.point_idx, centre);
+        assert_eq!(centre_pixel.range, 10.0);
+        assert_eq!(
+            image
+                .pixels()
+                .iter()
+                .flatten()
+                .filter(|pixel| pixel.point_idx == centre)
+                .count(),
+            1
+        );
+
+        // Each pixel keeps its closest point, back-projected points lie within the pixel's angular extent
+        for pixel in image.pixels().iter().flatten() {
+            assert_eq!(pixel.range, points[pixel.point_idx].coords.norm());
+        }
+        let back_projected = image.back_project();
+        assert_eq!(
+            back_projected.len(),
+            image.pixels().iter().flatten().count()
+        );
+        let resolution = 1.0f64.to_radians();
+        for (back_projected_point, pixel) in
+            back_projected.iter().zip(image.pixels().iter().flatten())
+        {
+            assert!((back_projected_point - pixel.point).norm() < pixel.range * resolution);
+        }
+        assert_eq!(image.ranges().len(), 360 * 40);
+        assert_eq!(image.back_project_pixel(0, 0), None);
+    }
+
+    #[test]
+    fn test_projection_limits() {
+        let points = [
+            Point3::new(-5.0, 0.0, 0.0),
+            Point3::new(-5.0, -1e-9, 0.0),
+            Point3::new(0.0, 5.0, 0.0),
+            Point3::new(5.0, 0.0, 5.0),
+            Point3::new(500.0, 0.0, 0.0),
+            Point3::new(f64::NAN, 0.0, 0.0),
+        ];
+        let image = project_to_range_image(&points, small_config());
+        // Behind the sensor is the image's edge, the first and last columns meet there
+        assert_eq!(image.pixel(20, 0).map(|pixel| pixel.point_idx), Some(0));
+        assert_eq!(image.pixel(20, 359).map(|pixel| pixel.point_idx), Some(1));
+        assert_eq!(image.pixel(20, 90).map(|pixel| pixel.point_idx), Some(2));
+        assert_eq!(image.pixels().iter().flatten().count(), 3);
+        assert!(image
+            .neighbours(20, 0, 1)
+            .any(|neighbour| neighbour == (20, 359)));
+
+        let front_image = project_to_range_image(
+            &points,
+            RangeImageConfiguration::builder()
+                .with_resolution(90, 40)
+                .with_horizontal_fov(FRAC_PI_2)
+                .with_vertical_fov(-FRAC_PI_2, FRAC_PI_2)
+                .with_range_limits(1.0, 1000.0)
+                .build(),
+        );
+        assert!(!front_image.wraps_horizontally());
+        assert_eq!(
+            front_image
+                .pixels()
+                .iter()
+                .flatten()
+                .map(|pixel| pixel.point_idx)
+                .collect::<Vec<_>>(),
+            [3, 4]
+        );
+        assert!(
+            (front_image.pixel_direction(0, 45).z - (PI / 2.0 - PI / 80.0).sin()).abs() < 1e-12
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let points = (0..8)
+            .map(|column| {
+                let azimuth = PI - (column as f64 + 0.5) * PI / 4.0;
+                Point3::new(azimuth.cos(), azimuth.sin(), 0.0) * 5.0
+            })
+            .chain([Point3::new(5.0, 0.0, 1.0)])
+            .collect::<Vec<_>>();
+        let image = project_to_range_image(
+            &points,
+            RangeImageConfiguration::builder()
+                .with_resolution(8, 3)
+                .with_vertical_fov(-0.3, 0.3)
+                .build(),
+        );
+        let mut neighbours = image.neighbours(1, 0, 1).collect::<Vec<_>>();
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, [(1, 1), (1, 7)]);
+        let mut neighbours = image.neighbours(1, 4, 1).collect::<Vec<_>>();
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, [(0, 4), (1, 3), (1, 5)]);
+        assert_eq!(image.neighbours(1, 0, 10).count(), 8);
+    }
+
+    #[test]
+    fn test_estimate_normals() {
+        let points = generate_wall();
+        let image = project_to_range_image(&points, small_config());
+        let normals = image.estimate_normals(0.5);
+        let estimated = normals.iter().flatten().collect::<Vec<_>>();
+        assert!(estimated.len() > image.pixels().iter().flatten().count() / 2);
+        for normal in estimated {
+            assert!((normal - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1e-9);
+        }
+        for (normal, pixel) in normals.iter().zip(image.pixels()) {
+            assert!(pixel.is_some() || normal.is_none());
+        }
+
+        // A depth discontinuity larger than the threshold leaves isolated pixels without normals
+        assert!(image.estimate_normals(0.0).iter().all(Option::is_none));
+    }
+}
@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::Vec;
+
+/// A single occupied pixel of a [`RangeImage`].
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RangePixel<T: RealField> {
+    /// The distance of the point from the sensor.
+    pub range: T,
+    /// The exact point projected into the pixel.
+    pub point: Point3<T>,
+    /// The index of the point in the projected point cloud.
+    pub point_idx: usize,
+}
+
+/// A spherical range image, each pixel holds the closest point whose direction falls within it.
+///
+/// Rows are ordered from the highest elevation to the lowest, and columns from the left edge of the horizontal field of view,
+/// at an azimuth of `horizontal_fov / 2`, clockwise when viewed from above, so that the column at the image's centre faces the sensor's X axis.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct RangeImage<T: RealField> {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) horizontal_fov: T,
+    pub(crate) max_elevation: T,
+    pub(crate) min_elevation: T,
+    pub(crate) wraps_horizontally: bool,
+    pub(crate) pixels: Vec<Option<RangePixel<T>>>,
+}
+
+impl<T: Copy + RealField> RangeImage<T> {
+    /// Returns the amount of columns in the image.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the amount of rows in the image.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether the image covers a full revolution, in which case the first and last columns are neighbours.
+    pub fn wraps_horizontally(&self) -> bool {
+        self.wraps_horizontally
+    }
+
+    /// Returns all pixels of the image, in row-major order.
+    pub fn pixels(&self) -> &[Option<RangePixel<T>>] {
+        &self.pixels
+    }
+
+    /// Returns the pixel in a specific row and column.
+    ///
+    /// # Arguments
+    /// * `row`: the index of the row, less than [`Self::height`].
+    /// * `column`: the index of the column, less than [`Self::width`].
+    ///
+    /// # Returns
+    /// [`Some`] containing the [`RangePixel`], or [`None`] if the pixel is empty or out of bounds.
+    pub fn pixel(&self, row: usize, column: usize) -> Option<&RangePixel<T>> {
+        if row >= self.height || column >= self.width {
+            return None;
+        }
+        self.pixels[row * self.width + column].as_ref()
+    }
+
+    /// Returns the range of the pixel in a specific row and column, if it is occupied.
+    pub fn range(&self, row: usize, column: usize) -> Option<T> {
+        self.pixel(row, column).map(|pixel| pixel.range)
+    }
+
+    /// Returns the ranges of all pixels as a row-major image, empty pixels have a range of zero.
+    pub fn ranges(&self) -> Vec<T> {
+        self.pixels
+            .iter()
+            .map(|pixel| pixel.map_or(T::zero(), |pixel| pixel.range))
+            .collect()
+    }
+}
+
+/// A struct specifying configuration options for projecting a point cloud into a [`RangeImage`].
+#[derive(Clone, Debug)]
+pub struct RangeImageConfiguration<T> {
+    /// The amount of columns, the horizontal resolution is `horizontal_fov / width`.
+    pub(crate) width: usize,
+    /// The amount of rows, the vertical resolution is `(max_elevation - min_elevation) / height`.
+    pub(crate) height: usize,
+    /// The horizontal field of view in radians, centred around the sensor's X axis.
+    pub(crate) horizontal_fov: T,
+    /// The elevation of the image's upper edge, in radians.
+    pub(crate) max_elevation: T,
+    /// The elevation of the image's lower edge, in radians.
+    pub(crate) min_elevation: T,
+    /// Points closer to the sensor than this are not projected.
+    pub(crate) min_range: T,
+    /// Points further from the sensor than this are not projected.
+    pub(crate) max_range: T,
+}
+
+impl<T: 'static + Copy> RangeImageConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values match a 64 beam spinning LiDAR, with a vertical field of view of +2 to -24.8 degrees.
+    ///
+    /// # Returns
+    /// A [`RangeImageConfigurationBuilder`].
+    pub fn builder() -> RangeImageConfigurationBuilder<T> {
+        RangeImageConfigurationBuilder {
+            _internal: RangeImageConfiguration {
+                width: 1024,
+                height: 64,
+                horizontal_fov: core::f32::consts::TAU.as_(),
+                max_elevation: 2.0f32.to_radians().as_(),
+                min_elevation: (-24.8f32).to_radians().as_(),
+                min_range: 0.0.as_(),
+                max_range: 120.0.as_(),
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`RangeImageConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct RangeImageConfigurationBuilder<T> {
+    _internal: RangeImageConfiguration<T>,
+}
+
+impl<T: Copy> RangeImageConfigurationBuilder<T> {
+    /// The resolution of the image, usually the amount of firings per revolution and the amount of beams.
+    ///
+    /// # Arguments
+    /// * `width`: The amount of columns.
+    /// * `height`: The amount of rows.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_resolution(&self, width: usize, height: usize) -> Self {
+        Self {
+            _internal: RangeImageConfiguration {
+                width,
+                height,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The horizontal field of view, a full revolution makes the image wrap around horizontally.
+    ///
+    /// # Arguments
+    /// * `horizontal_fov`: The field of view in radians, centred around the sensor's X axis.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_horizontal_fov(&self, horizontal_fov: T) -> Self {
+        Self {
+            _internal: RangeImageConfiguration {
+                horizontal_fov,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The vertical field of view, given by the elevations of the image's edges.
+    ///
+    /// # Arguments
+    /// * `min_elevation`: The elevation of the lower edge in radians, negative below the horizon.
+    /// * `max_elevation`: The elevation of the upper edge in radians.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_vertical_fov(&self, min_elevation: T, max_elevation: T) -> Self {
+        Self {
+            _internal: RangeImageConfiguration {
+                min_elevation,
+                max_elevation,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The range of distances from the sensor for a point to be projected.
+    ///
+    /// # Arguments
+    /// * `min_range`: The minimum range, closer points usually hit the vehicle itself.
+    /// * `max_range`: The maximum range.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_range_limits(&self, min_range: T, max_range: T) -> Self {
+        Self {
+            _internal: RangeImageConfiguration {
+                min_range,
+                max_range,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`RangeImageConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`RangeImageConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> RangeImageConfiguration<T> {
+        self._internal.clone()
+    }
+}
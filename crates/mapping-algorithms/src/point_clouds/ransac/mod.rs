@@ -0,0 +1,428 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+pub use models::{CylinderModel, Line2Model, LineModel, PlaneModel, RansacModel, SphereModel};
+pub use types::{
+    RansacConfiguration, RansacConfigurationBuilder, RansacError, RansacResult, RansacSuccess,
+};
+
+use nalgebra::RealField;
+use num_traits::AsPrimitive;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{types::IsNan, Vec};
+
+mod models;
+mod types;
+
+/// Draws `sample_size` distinct indices in the range `0..data_len`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Draw Random Sample", skip_all, level = "trace")
+)]
+fn draw_sample_indices(
+    rng: &mut SmallRng,
+    data_len: usize,
+    sample_size: usize,
+    sample_indices: &mut Vec<usize>,
+) {
+    sample_indices.clear();
+    while sample_indices.len() < sample_size {
+        let index = rng.gen_range(0..data_len);
+        if !sample_indices.contains(&index) {
+            sample_indices.push(index);
+        }
+    }
+}
+
+/// Finds the indices of all data elements within `distance_threshold` of the model.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Find Model Inliers", skip_all, level = "trace")
+)]
+fn find_inliers<T, D, M>(data: &[D], model: &M, distance_threshold: T) -> Vec<usize>
+where
+    T: Copy + RealField,
+    M: RansacModel<T, D>,
+{
+    data.iter()
+        .enumerate()
+        .filter(|(_, element)| model.distance(element) <= distance_threshold)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Estimates the amount of iterations required to draw at least one outlier-free sample with the given confidence.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Estimate Required Iterations", skip_all, level = "trace")
+)]
+fn estimate_required_iterations<T>(
+    inlier_ratio: T,
+    sample_size: usize,
+    confidence: T,
+    max_iterations: usize,
+) -> usize
+where
+    T: AsPrimitive<usize> + Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    let outlier_free_probability = inlier_ratio.powi(sample_size as i32);
+    if outlier_free_probability >= T::one() {
+        return 1;
+    }
+
+    let required_iterations =
+        (T::one() - confidence).ln() / (T::one() - outlier_free_probability).ln();
+    if required_iterations.is_finite() && required_iterations < max_iterations.as_() {
+        required_iterations.ceil().as_().max(1)
+    } else {
+        max_iterations
+    }
+}
+
+/// Estimates the parameters of a model from data containing outliers, using the RANSAC (RANdom SAmple Consensus) algorithm.
+/// Random minimal samples are repeatedly drawn and fitted, and the model with the largest set of inliers is kept,
+/// and finally refined using all of its inliers, if the model supports it.
+///
+/// # Arguments
+/// * `data`: a slice of data elements, i.e., [`Point`](nalgebra::Point)s.
+/// * `config`: a [`RansacConfiguration`], specifying the behaviour of the algorithm.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `D`: The type of data elements, this depends on the model, see [`RansacModel`].
+/// * `M`: The model to estimate, i.e., [`PlaneModel`], [`LineModel`], [`SphereModel`] or [`CylinderModel`].
+///
+/// # Returns
+/// A [`RansacSuccess`] containing the model and the indices of its inliers, or a [`RansacError`] explaining what went wrong.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Full RANSAC Algorithm", skip_all, level = "info")
+)]
+pub fn ransac<T, D, M>(data: &[D], config: RansacConfiguration<T>) -> RansacResult<M>
+where
+    T: AsPrimitive<usize> + Copy + IsNan + RealField,
+    D: Copy,
+    M: RansacModel<T, D>,
+    usize: AsPrimitive<T>,
+{
+    if data.len() < M::SAMPLE_SIZE || M::SAMPLE_SIZE == 0 {
+        return Err(RansacError::NotEnoughData);
+    }
+
+    if config.max_iterations == 0 {
+        return Err(RansacError::IterationNumIsZero);
+    }
+
+    if config.distance_threshold.is_nan() || config.distance_threshold <= T::zero() {
+        return Err(RansacError::DistanceThreshold);
+    }
+
+    if config.confidence.is_nan() || config.confidence <= T::zero() || config.confidence >= T::one()
+    {
+        return Err(RansacError::Confidence);
+    }
+
+    let mut rng = SmallRng::seed_from_u64(config.seed);
+    let mut sample_indices = Vec::with_capacity(M::SAMPLE_SIZE);
+    let mut sample = Vec::with_capacity(M::SAMPLE_SIZE);
+    let mut best: Option<(M, Vec<usize>, usize)> = None;
+    let mut required_iterations = config.max_iterations;
+
+    let mut iteration_num = 0;
+    while iteration_num < required_iterations {
+        draw_sample_indices(&mut rng, data.len(), M::SAMPLE_SIZE, &mut sample_indices);
+        sample.clear();
+        sample.extend(sample_indices.iter().map(|idx| data[*idx]));
+
+        if let Some(model) = M::fit(&sample) {
+            let inliers = find_inliers(data, &model, config.distance_threshold);
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_inliers, _)| inliers.len() > best_inliers.len())
+            {
+                log::trace!(
+                    "Iteration {iteration_num} found a model with {} inliers",
+                    inliers.len()
+                );
+                required_iterations = estimate_required_iterations(
+                    inliers.len().as_() / data.len().as_(),
+                    M::SAMPLE_SIZE,
+                    config.confidence,
+                    config.max_iterations,
+                );
+                best = Some((model, inliers, iteration_num));
+            }
+        }
+
+        iteration_num += 1;
+    }
+
+    let (model, inlier_indices, best_iteration_num) = best
+        .filter(|(_, inliers, _)| inliers.len() >= config.min_inliers.max(M::SAMPLE_SIZE))
+        .ok_or(RansacError::NoModelFound)?;
+
+    // Refining is only accepted if it does not lose inliers
+    let inlier_data = inlier_indices
+        .iter()
+        .map(|idx| data[*idx])
+        .collect::<Vec<_>>();
+    let (model, inlier_indices) = model
+        .refine(&inlier_data)
+        .map(|refined_model| {
+            let refined_inliers = find_inliers(data, &refined_model, config.distance_threshold);
+            (refined_model, refined_inliers)
+        })
+        .filter(|(_, refined_inliers)| refined_inliers.len() >= inlier_indices.len())
+        .unwrap_or((model, inlier_indices));
+
+    Ok(RansacSuccess {
+        model,
+        inlier_indices,
+        iteration_num: best_iteration_num,
+    })
+}
+
+#[cfg(feature = "pregenerated")]
+macro_rules! impl_ransac_algorithm {
+    ($precision:expr, doc $doc:tt) => {
+        ::paste::paste! {
+            pub(super) mod [<$doc _precision>] {
+                use nalgebra::{Point2, Point3, Vector3};
+                use super::{CylinderModel, Line2Model, PlaneModel, RansacConfiguration, RansacResult, SphereModel};
+
+                #[doc = "A premade variant of the RANSAC algorithm function for 3D planes, made for " $doc "-precision floats."]
+                pub fn ransac_plane(points: &[Point3<$precision>], config: RansacConfiguration<$precision>) -> RansacResult<PlaneModel<$precision>> {
+                    super::ransac(points, config)
+                }
+
+                #[doc = "A premade variant of the RANSAC algorithm function for 2D lines, made for " $doc "-precision floats."]
+                pub fn ransac_line_2d(points: &[Point2<$precision>], config: RansacConfiguration<$precision>) -> RansacResult<Line2Model<$precision>> {
+                    super::ransac(points, config)
+                }
+
+                #[doc = "A premade variant of the RANSAC algorithm function for 3D spheres, made for " $doc "-precision floats."]
+                pub fn ransac_sphere(points: &[Point3<$precision>], config: RansacConfiguration<$precision>) -> RansacResult<SphereModel<$precision>> {
+                    super::ransac(points, config)
+                }
+
+                #[doc = "A premade variant of the RANSAC algorithm function for 3D cylinders, made for " $doc "-precision floats."]
+                pub fn ransac_cylinder(points_with_normals: &[(Point3<$precision>, Vector3<$precision>)], config: RansacConfiguration<$precision>) -> RansacResult<CylinderModel<$precision>> {
+                    super::ransac(points_with_normals, config)
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "pregenerated")]
+impl_ransac_algorithm!(f32, doc single);
+#[cfg(feature = "pregenerated")]
+impl_ransac_algorithm!(f64, doc double);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3, Vector3};
+
+    use crate::{array, point_clouds::generate_point_cloud};
+
+    use super::*;
+
+    fn generate_plane_with_outliers() -> Vec<Point3<f64>> {
+        // 300 points on the plane z = 0.1x - 0.2y + 1, and 100 random outliers
+        let mut points = generate_point_cloud(300, [-10.0..=10.0, -10.0..=10.0, 0.0..=0.0])
+            .into_iter()
+            .map(|point: Point3<f64>| {
+                Point3::new(point.x, point.y, 0.1 * point.x - 0.2 * point.y + 1.0)
+            })
+            .collect::<Vec<_>>();
+        points.extend(generate_point_cloud(100, array::from_fn(|_| -10.0..=10.0)));
+        points
+    }
+
+    #[test]
+    fn test_draw_sample_indices() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut sample_indices = Vec::new();
+        for _ in 0..100 {
+            draw_sample_indices(&mut rng, 5, 4, &mut sample_indices);
+            assert_eq!(sample_indices.len(), 4);
+            assert!(sample_indices.iter().all(|idx| *idx < 5));
+
+            let mut sorted = sample_indices.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert_eq!(sorted.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_find_inliers() {
+        let model = Line2Model::fit(&[Point2::new(0.0, 0.0), Point2::new(1.0, 0.0)]).unwrap();
+        let points = [
+            Point2::new(5.0, 0.1),
+            Point2::new(5.0, 1.0),
+            Point2::new(-3.0, -0.2),
+        ];
+        assert_eq!(find_inliers(&points, &model, 0.2), Vec::from([0, 2]));
+    }
+
+    #[test]
+    fn test_estimate_required_iterations() {
+        assert_eq!(estimate_required_iterations(1.0, 3, 0.99, 1000), 1);
+        assert_eq!(estimate_required_iterations(0.0, 3, 0.99, 1000), 1000);
+        // log(0.01) / log(1 - 0.125) = 34.48
+        assert_eq!(estimate_required_iterations(0.5, 3, 0.99, 1000), 35);
+        assert_eq!(estimate_required_iterations(0.5f32, 3, 0.99, 10), 10);
+    }
+
+    #[test]
+    fn test_ransac_errors() {
+        let points = generate_plane_with_outliers();
+        let config_builder = RansacConfiguration::builder();
+
+        let mut res: RansacResult<PlaneModel<f64>> = ransac(&points[..2], config_builder.build());
+        assert_eq!(res.unwrap_err(), RansacError::NotEnoughData);
+
+        res = ransac(&points, config_builder.with_max_iterations(0).build());
+        assert_eq!(res.unwrap_err(), RansacError::IterationNumIsZero);
+
+        res = ransac(&points, config_builder.with_distance_threshold(0.0).build());
+        assert_eq!(res.unwrap_err(), RansacError::DistanceThreshold);
+
+        res = ransac(&points, config_builder.with_confidence(1.0).build());
+        assert_eq!(res.unwrap_err(), RansacError::Confidence);
+
+        res = ransac(&points, config_builder.with_min_inliers(350).build());
+        assert_eq!(res.unwrap_err(), RansacError::NoModelFound);
+    }
+
+    #[test]
+    fn test_ransac_plane() {
+        let points = generate_plane_with_outliers();
+        let res: RansacResult<PlaneModel<f64>> = ransac(
+            &points,
+            RansacConfiguration::builder()
+                .with_distance_threshold(0.01)
+                .build(),
+        );
+        let success = res.unwrap();
+
+        let expected_normal = Vector3::new(0.1, -0.2, -1.0).normalize();
+        assert!((success.model.normal.dot(&expected_normal).abs() - 1.0).abs() < 1e-9);
+        assert!(success.inlier_indices.len() >= 300);
+        assert!((0..300).all(|idx| success.inlier_indices.contains(&idx)));
+    }
+
+    #[test]
+    fn test_ransac_is_deterministic() {
+        let points = generate_plane_with_outliers();
+        let config = RansacConfiguration::builder()
+            .with_distance_threshold(0.01)
+            .with_confidence(0.5)
+            .with_seed(42);
+
+        let res_a: RansacSuccess<PlaneModel<f64>> = ransac(&points, config.build()).unwrap();
+        let res_b: RansacSuccess<PlaneModel<f64>> = ransac(&points, config.build()).unwrap();
+        assert_eq!(res_a.model, res_b.model);
+        assert_eq!(res_a.inlier_indices, res_b.inlier_indices);
+        assert_eq!(res_a.iteration_num, res_b.iteration_num);
+    }
+
+    #[test]
+    fn test_ransac_line_2d() {
+        // A wall in a 2D laser scan, and some clutter
+        let mut points = (0..100)
+            .map(|idx| Point2::new(idx as f32 * 0.1, 3.0 - idx as f32 * 0.05))
+            .collect::<Vec<_>>();
+        points.extend(generate_point_cloud(40, [-5.0..=15.0, -5.0..=5.0]));
+
+        let res: RansacResult<Line2Model<f32>> = ransac(
+            &points,
+            RansacConfiguration::builder()
+                .with_distance_threshold(0.01)
+                .with_min_inliers(50)
+                .build(),
+        );
+        let success = res.unwrap();
+        assert!((0..100).all(|idx| success.inlier_indices.contains(&idx)));
+        assert!(success.model.distance(&Point2::new(20.0, -7.0)) < 1e-3);
+    }
+
+    #[test]
+    fn test_ransac_sphere() {
+        let center = Point3::new(1.0, -2.0, 0.5);
+        let mut points = generate_point_cloud(200, array::from_fn(|_| -1.0..=1.0))
+            .into_iter()
+            .filter(|point: &Point3<f64>| point.coords.norm() > 0.1)
+            .map(|point| center + point.coords.normalize() * 4.0)
+            .collect::<Vec<_>>();
+        let num_sphere_points = points.len();
+        points.extend(generate_point_cloud(50, array::from_fn(|_| -8.0..=8.0)));
+
+        let res: RansacResult<SphereModel<f64>> = ransac(
+            &points,
+            RansacConfiguration::builder()
+                .with_distance_threshold(0.01)
+                .build(),
+        );
+        let success = res.unwrap();
+        assert!((success.model.center - center).norm() < 1e-9);
+        assert!((success.model.radius - 4.0).abs() < 1e-9);
+        assert!((0..num_sphere_points).all(|idx| success.inlier_indices.contains(&idx)));
+    }
+
+    #[test]
+    fn test_ransac_cylinder() {
+        let axis_point = Point3::new(2.0, 0.0, 0.0);
+        let axis_direction = Vector3::new(0.0, 1.0, 1.0).normalize();
+        let perpendicular_a = Vector3::new(1.0, 0.0, 0.0);
+        let perpendicular_b = axis_direction.cross(&perpendicular_a);
+
+        let mut points_with_normals = (0..200)
+            .map(|idx| {
+                let angle = idx as f64 * 0.1;
+                let height = (idx % 17) as f64 * 0.3;
+                let normal = perpendicular_a * angle.cos() + perpendicular_b * angle.sin();
+                (axis_point + axis_direction * height + normal * 1.5, normal)
+            })
+            .collect::<Vec<_>>();
+        points_with_normals.extend(
+            generate_point_cloud(60, array::from_fn(|_| -5.0..=5.0))
+                .into_iter()
+                .map(|point: Point3<f64>| (point, point.coords.normalize())),
+        );
+
+        let res: RansacResult<CylinderModel<f64>> = ransac(
+            &points_with_normals,
+            RansacConfiguration::builder()
+                .with_distance_threshold(0.01)
+                .build(),
+        );
+        let success = res.unwrap();
+        assert!((success.model.radius - 1.5).abs() < 1e-9);
+        assert!((success.model.axis_direction.dot(&axis_direction).abs() - 1.0).abs() < 1e-9);
+        assert!((0..200).all(|idx| success.inlier_indices.contains(&idx)));
+    }
+}
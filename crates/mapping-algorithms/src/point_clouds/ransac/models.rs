@@ -0,0 +1,437 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Matrix4, Point, Point3, RealField, SVector, Vector3, Vector4};
+use num_traits::AsPrimitive;
+
+use crate::{
+    point_clouds::{
+        calculate_point_cloud_center,
+        normals::{calculate_covariance_matrix, calculate_normal},
+    },
+    utils::symmetric_eigen_decomposition,
+};
+
+/// A geometric model that can be estimated from noisy data using [`ransac`](super::ransac).
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `D`: The type of data elements the model is fitted to, usually a [`Point`].
+pub trait RansacModel<T, D>: Sized {
+    /// The minimal amount of data elements required to fit a single model.
+    const SAMPLE_SIZE: usize;
+
+    /// Fits a model to exactly [`Self::SAMPLE_SIZE`] data elements.
+    ///
+    /// # Arguments
+    /// * `sample`: a slice of data elements, of length [`Self::SAMPLE_SIZE`].
+    ///
+    /// # Returns
+    /// [`None`] if the sample is degenerate, otherwise the fitted model.
+    fn fit(sample: &[D]) -> Option<Self>;
+
+    /// Refits the model to all of its inliers, i.e., using least squares.
+    /// The default implementation does not refine the model.
+    ///
+    /// # Arguments
+    /// * `inliers`: a slice of all data elements that are inliers of this model.
+    ///
+    /// # Returns
+    /// [`None`] if the model cannot be refined, otherwise the refined model.
+    fn refine(&self, _inliers: &[D]) -> Option<Self> {
+        None
+    }
+
+    /// Calculates the distance between a data element and the model.
+    ///
+    /// # Arguments
+    /// * `element`: a reference to a data element.
+    ///
+    /// # Returns
+    /// A non-negative `T`, representing the distance.
+    fn distance(&self, element: &D) -> T;
+}
+
+/// A plane in 3D space, defined by all points `p` for which `normal.dot(p) + offset == 0`.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaneModel<T: RealField> {
+    /// The unit normal of the plane.
+    pub normal: Vector3<T>,
+    /// The signed distance of the plane from the origin, along the negated normal.
+    pub offset: T,
+}
+
+impl<T> RansacModel<T, Point3<T>> for PlaneModel<T>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    const SAMPLE_SIZE: usize = 3;
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Fit Plane Model", skip_all, level = "trace")
+    )]
+    fn fit(sample: &[Point3<T>]) -> Option<Self> {
+        let normal = (sample[1] - sample[0]).cross(&(sample[2] - sample[0]));
+        let normal_norm = normal.norm();
+        if normal_norm <= T::default_epsilon() {
+            return None;
+        }
+
+        let normal = normal / normal_norm;
+        Some(Self {
+            normal,
+            offset: -normal.dot(&sample[0].coords),
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Refine Plane Model", skip_all, level = "trace")
+    )]
+    fn refine(&self, inliers: &[Point3<T>]) -> Option<Self> {
+        let normal = calculate_normal(inliers)?.normal;
+        let center = calculate_point_cloud_center(inliers);
+        Some(Self {
+            normal,
+            offset: -normal.dot(&center.coords),
+        })
+    }
+
+    #[inline]
+    fn distance(&self, element: &Point3<T>) -> T {
+        (self.normal.dot(&element.coords) + self.offset).abs()
+    }
+}
+
+/// An infinite line in `N` dimensional space, passing through a point along a direction.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `N`: A const usize, representing the number of dimensions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineModel<T: RealField, const N: usize> {
+    /// A point on the line.
+    pub point: Point<T, N>,
+    /// The unit direction of the line.
+    pub direction: SVector<T, N>,
+}
+
+impl<T, const N: usize> RansacModel<T, Point<T, N>> for LineModel<T, N>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    const SAMPLE_SIZE: usize = 2;
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Fit Line Model", skip_all, level = "trace")
+    )]
+    fn fit(sample: &[Point<T, N>]) -> Option<Self> {
+        let direction = sample[1] - sample[0];
+        let direction_norm = direction.norm();
+        if direction_norm <= T::default_epsilon() {
+            return None;
+        }
+
+        Some(Self {
+            point: sample[0],
+            direction: direction / direction_norm,
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Refine Line Model", skip_all, level = "trace")
+    )]
+    fn refine(&self, inliers: &[Point<T, N>]) -> Option<Self> {
+        if inliers.len() < 2 {
+            return None;
+        }
+
+        // The direction of a line is the principal component with the largest variance
+        let (_, eigenvectors) =
+            symmetric_eigen_decomposition(&calculate_covariance_matrix(inliers));
+        Some(Self {
+            point: calculate_point_cloud_center(inliers),
+            direction: eigenvectors.column(N - 1).into_owned(),
+        })
+    }
+
+    #[inline]
+    fn distance(&self, element: &Point<T, N>) -> T {
+        let diff = element - self.point;
+        (diff - self.direction * diff.dot(&self.direction)).norm()
+    }
+}
+
+/// A sphere in 3D space.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphereModel<T: RealField> {
+    /// The center of the sphere.
+    pub center: Point3<T>,
+    /// The radius of the sphere.
+    pub radius: T,
+}
+
+impl<T> RansacModel<T, Point3<T>> for SphereModel<T>
+where
+    T: Copy + RealField,
+{
+    const SAMPLE_SIZE: usize = 4;
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Fit Sphere Model", skip_all, level = "trace")
+    )]
+    fn fit(sample: &[Point3<T>]) -> Option<Self> {
+        // Every point on the sphere satisfies x^2 + y^2 + z^2 + Dx + Ey + Fz + G = 0
+        let coefficients = Matrix4::from_fn(|row, column| {
+            if column < 3 {
+                sample[row][column]
+            } else {
+                T::one()
+            }
+        });
+        let squared_norms = Vector4::from_fn(|row, _| -sample[row].coords.norm_squared());
+
+        if coefficients.determinant().abs() <= T::default_epsilon() {
+            return None;
+        }
+        let solution = coefficients.lu().solve(&squared_norms)?;
+
+        let two = T::one() + T::one();
+        let center = Point3::new(-solution[0] / two, -solution[1] / two, -solution[2] / two);
+        let radius_squared = center.coords.norm_squared() - solution[3];
+        (radius_squared > T::zero()).then(|| Self {
+            center,
+            radius: radius_squared.sqrt(),
+        })
+    }
+
+    #[inline]
+    fn distance(&self, element: &Point3<T>) -> T {
+        ((element - self.center).norm() - self.radius).abs()
+    }
+}
+
+/// An infinite cylinder in 3D space.
+/// Since a cylinder cannot be fitted from a minimal set of points alone, this model is fitted to points paired with their normals,
+/// see [`estimate_normals`](crate::point_clouds::estimate_normals).
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CylinderModel<T: RealField> {
+    /// A point on the axis of the cylinder.
+    pub axis_point: Point3<T>,
+    /// The unit direction of the axis of the cylinder.
+    pub axis_direction: Vector3<T>,
+    /// The radius of the cylinder.
+    pub radius: T,
+}
+
+impl<T> CylinderModel<T>
+where
+    T: Copy + RealField,
+{
+    #[inline]
+    fn distance_from_axis(&self, point: &Point3<T>) -> T {
+        let diff = point - self.axis_point;
+        (diff - self.axis_direction * diff.dot(&self.axis_direction)).norm()
+    }
+}
+
+impl<T> RansacModel<T, (Point3<T>, Vector3<T>)> for CylinderModel<T>
+where
+    T: Copy + RealField,
+{
+    const SAMPLE_SIZE: usize = 2;
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Fit Cylinder Model", skip_all, level = "trace")
+    )]
+    fn fit(sample: &[(Point3<T>, Vector3<T>)]) -> Option<Self> {
+        let (point_a, normal_a) = sample[0];
+        let (point_b, normal_b) = sample[1];
+
+        // The axis is perpendicular to all surface normals
+        let axis_direction = normal_a.cross(&normal_b);
+        let axis_direction_norm = axis_direction.norm();
+        if axis_direction_norm <= T::default_epsilon() {
+            return None;
+        }
+
+        // The lines along both normals intersect the axis, their closest points lie on it
+        let offset = point_a - point_b;
+        let (a, b, c) = (
+            normal_a.dot(&normal_a),
+            normal_a.dot(&normal_b),
+            normal_b.dot(&normal_b),
+        );
+        let (d, e) = (normal_a.dot(&offset), normal_b.dot(&offset));
+        let scale = (b * e - c * d) / (a * c - b * b);
+
+        let model = Self {
+            axis_point: point_a + normal_a * scale,
+            axis_direction: axis_direction / axis_direction_norm,
+            radius: T::zero(),
+        };
+        let radius = (model.distance_from_axis(&point_a) + model.distance_from_axis(&point_b))
+            / (T::one() + T::one());
+
+        (radius > T::default_epsilon()).then_some(Self { radius, ..model })
+    }
+
+    #[inline]
+    fn distance(&self, element: &(Point3<T>, Vector3<T>)) -> T {
+        (self.distance_from_axis(&element.0) - self.radius).abs()
+    }
+}
+
+/// A [`LineModel`] in 2D space.
+pub type Line2Model<T> = LineModel<T, 2>;
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Vector2};
+
+    use super::*;
+
+    #[test]
+    fn test_plane_model() {
+        let sample = [
+            Point3::new(0.0f64, 0.0, 1.0),
+            Point3::new(1.0, 0.0, 1.0),
+            Point3::new(0.0, 1.0, 1.0),
+        ];
+        let model = PlaneModel::fit(&sample).unwrap();
+        assert_eq!(model.normal, Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(model.offset, -1.0);
+        assert_eq!(model.distance(&Point3::new(5.0, -3.0, -2.0)), 3.0);
+
+        // Collinear points
+        assert!(PlaneModel::fit(&[
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+            Point3::new(2.0, 2.0, 2.0),
+        ])
+        .is_none());
+
+        let refined = model
+            .refine(&[
+                Point3::new(0.0, 0.0, 1.1),
+                Point3::new(1.0, 0.0, 0.9),
+                Point3::new(0.0, 1.0, 0.9),
+                Point3::new(1.0, 1.0, 1.1),
+            ])
+            .unwrap();
+        assert!((refined.normal.z.abs() - 1.0).abs() < 1e-12);
+        assert!((refined.distance(&Point3::new(0.5, 0.5, 1.0))).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_line_model() {
+        let model = Line2Model::fit(&[Point2::new(0.0, 1.0), Point2::new(2.0, 1.0)]).unwrap();
+        assert_eq!(model.direction, Vector2::new(1.0, 0.0));
+        assert_eq!(model.distance(&Point2::new(-7.0, 4.0)), 3.0);
+
+        assert!(Line2Model::fit(&[Point2::new(1.0, 1.0), Point2::new(1.0, 1.0)]).is_none());
+        assert!(model.refine(&[Point2::new(1.0, 1.0)]).is_none());
+
+        let refined = model
+            .refine(&[
+                Point2::new(0.0, 0.0),
+                Point2::new(1.0, 1.0),
+                Point2::new(2.0, 2.0),
+            ])
+            .unwrap();
+        assert_eq!(refined.point, Point2::new(1.0, 1.0));
+        assert!(refined.distance(&Point2::new(5.0, 5.0)) < 1e-12);
+
+        let model_3d =
+            LineModel::fit(&[Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 2.0)]).unwrap();
+        assert_eq!(model_3d.distance(&Point3::new(3.0, 4.0, 10.0)), 5.0);
+    }
+
+    #[test]
+    fn test_sphere_model() {
+        let sample = [
+            Point3::new(3.0, 2.0, 3.0),
+            Point3::new(1.0, 4.0, 3.0),
+            Point3::new(1.0, 2.0, 5.0),
+            Point3::new(-1.0, 2.0, 3.0),
+        ];
+        let model = SphereModel::fit(&sample).unwrap();
+        assert!((model.center - Point3::new(1.0, 2.0, 3.0)).norm() < 1e-12);
+        assert!((model.radius - 2.0f64).abs() < 1e-12);
+        assert!((model.distance(&Point3::new(1.0, 2.0, 8.0)) - 3.0).abs() < 1e-12);
+
+        // Coplanar points
+        assert!(SphereModel::fit(&[
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+        ])
+        .is_none());
+    }
+
+    #[test]
+    fn test_cylinder_model() {
+        // A cylinder of radius 2 around the Z axis, offset by (1, 1)
+        let sample = [
+            (Point3::new(3.0f64, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(1.0, 3.0, 5.0), Vector3::new(0.0, 1.0, 0.0)),
+        ];
+        let model = CylinderModel::fit(&sample).unwrap();
+        assert!((model.axis_direction.z.abs() - 1.0f64).abs() < 1e-12);
+        assert!((model.radius - 2.0).abs() < 1e-12);
+        assert!(
+            model
+                .distance(&(Point3::new(1.0, -1.0, 100.0), Vector3::zeros()))
+                .abs()
+                < 1e-12
+        );
+        assert!(
+            (model.distance(&(Point3::new(1.0, 1.0, 0.0), Vector3::zeros())) - 2.0).abs() < 1e-12
+        );
+
+        // Parallel normals
+        assert!(CylinderModel::fit(&[
+            (Point3::new(3.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(3.0, 1.0, 5.0), Vector3::new(1.0, 0.0, 0.0)),
+        ])
+        .is_none());
+        assert!(model.refine(&sample).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use num_traits::AsPrimitive;
+
+use crate::{Debug, Vec};
+
+/// Contains the resulting model, the indices of its inliers, and the number of iterations performed by a successful RANSAC run.
+#[derive(Clone, Debug)]
+pub struct RansacSuccess<M> {
+    /// The model with the largest inlier set found.
+    pub model: M,
+    /// The indices of all inliers of `model` in the input data, in ascending order.
+    pub inlier_indices: Vec<usize>,
+    /// The amount of iterations performed before the best model was accepted.
+    pub iteration_num: usize,
+}
+
+/// An error type containing the various errors that might arise during a RANSAC algorithm, when compiling with the `std` feature, it will also derive [`thiserror::Error`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum RansacError {
+    /// The input contains less elements than are required to fit a single model.
+    #[cfg_attr(
+        feature = "std",
+        error("The input does not contain enough elements to fit a model")
+    )]
+    NotEnoughData,
+    /// The amount of iterations was set to zero.
+    #[cfg_attr(feature = "std", error("The amount of iterations was set to zero"))]
+    IterationNumIsZero,
+    /// The inlier distance threshold is not a positive number.
+    #[cfg_attr(
+        feature = "std",
+        error("The inlier distance threshold must be positive")
+    )]
+    DistanceThreshold,
+    /// The confidence is not within the range (0, 1).
+    #[cfg_attr(
+        feature = "std",
+        error("The confidence must be within the range (0, 1)")
+    )]
+    Confidence,
+    /// No model had at least the minimum required amount of inliers.
+    #[cfg_attr(feature = "std", error("No model with enough inliers was found"))]
+    NoModelFound,
+}
+
+/// A type alias for the result of a RANSAC algorithm, containing either the successful result or an error.
+pub type RansacResult<M> = Result<RansacSuccess<M>, RansacError>;
+
+/// A struct specifying configuration options for a RANSAC algorithm.
+#[derive(Clone, Debug)]
+pub struct RansacConfiguration<T> {
+    /// The maximum distance between a point and a model for the point to be considered an inlier.
+    pub(crate) distance_threshold: T,
+    /// The maximum amount of random samples to evaluate.
+    pub(crate) max_iterations: usize,
+    /// The probability of having sampled at least one outlier-free sample, after which the algorithm may exit early.
+    pub(crate) confidence: T,
+    /// The minimum amount of inliers required for a model to be accepted.
+    pub(crate) min_inliers: usize,
+    /// The seed for the random number generator, identical seeds and inputs produce identical results.
+    pub(crate) seed: u64,
+}
+
+impl<T: 'static + Copy> RansacConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    ///
+    /// # Returns
+    /// A [`RansacConfigurationBuilder`].
+    pub fn builder() -> RansacConfigurationBuilder<T> {
+        RansacConfigurationBuilder {
+            _internal: RansacConfiguration {
+                distance_threshold: 0.05.as_(),
+                max_iterations: 1000,
+                confidence: 0.99.as_(),
+                min_inliers: 0,
+                seed: 3765665954583626552,
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`RansacConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct RansacConfigurationBuilder<T> {
+    _internal: RansacConfiguration<T>,
+}
+
+impl<T: Copy> RansacConfigurationBuilder<T> {
+    /// The maximum distance between a point and a model for the point to be considered an inlier.
+    ///
+    /// # Arguments
+    /// * `distance_threshold`: The inlier distance threshold, must be positive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_distance_threshold(&self, distance_threshold: T) -> Self {
+        Self {
+            _internal: RansacConfiguration {
+                distance_threshold,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The maximum amount of random samples to evaluate before returning the best model.
+    ///
+    /// # Arguments
+    /// * `max_iterations`: The maximum number of iterations to allow.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_iterations(&self, max_iterations: usize) -> Self {
+        Self {
+            _internal: RansacConfiguration {
+                max_iterations,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The desired probability of having drawn at least one sample containing only inliers,
+    /// the required amount of iterations is re-estimated from the best inlier ratio found so far, allowing an early exit.
+    ///
+    /// # Arguments
+    /// * `confidence`: A value in the range (0, 1), higher values result in more iterations.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_confidence(&self, confidence: T) -> Self {
+        Self {
+            _internal: RansacConfiguration {
+                confidence,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The minimum amount of inliers required for a model to be accepted.
+    ///
+    /// # Arguments
+    /// * `min_inliers`: The minimum number of inliers, the samples used to fit the model are counted as well.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_min_inliers(&self, min_inliers: usize) -> Self {
+        Self {
+            _internal: RansacConfiguration {
+                min_inliers,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The seed for the random number generator used to draw samples.
+    ///
+    /// # Arguments
+    /// * `seed`: The seed, identical seeds and inputs always produce identical results.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_seed(&self, seed: u64) -> Self {
+        Self {
+            _internal: RansacConfiguration {
+                seed,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`RansacConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`RansacConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> RansacConfiguration<T> {
+        self._internal.clone()
+    }
+}
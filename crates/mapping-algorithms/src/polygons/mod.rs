@@ -23,7 +23,10 @@
 
 pub use graham_scan::graham_scan;
 pub use jarvis_march::jarvis_march;
-pub use point_in_polygon::{are_multiple_points_in_polygon, is_single_point_in_polygon};
+pub use point_in_polygon::{
+    are_multiple_points_in_polygon, build_polygons_r_tree, is_single_point_in_polygon,
+    locate_points_in_indexed_polygons, locate_points_in_polygons,
+};
 
 use nalgebra::{ComplexField, Point, Point2, RealField, Scalar};
 use num_traits::{AsPrimitive, Bounded, NumOps};
@@ -24,7 +24,10 @@
 use nalgebra::{Point2, RealField, Vector2};
 use num_traits::{AsPrimitive, Bounded};
 
-use crate::Vec;
+use crate::{
+    r_tree::{RTree, DEFAULT_MAX_NODE_ENTRIES},
+    Vec,
+};
 
 use super::calculate_polygon_extents;
 
@@ -46,12 +49,11 @@ fn does_ray_intersect_polygon_segment<T>(
         && point.y <= vertex1.y.max(vertex2.y)
         && point.x <= vertex1.x.max(vertex2.x)
     {
-        let origin_x = (vertex1.y != vertex2.y)
-            .then(|| {
-                (point.y - vertex1.y) * (vertex2.x - vertex1.x) / (vertex2.y - vertex1.y)
-                    + vertex1.x
-            })
-            .unwrap_or(point.x);
+        let origin_x = if vertex1.y != vertex2.y {
+            (point.y - vertex1.y) * (vertex2.x - vertex1.x) / (vertex2.y - vertex1.y) + vertex1.x
+        } else {
+            point.x
+        };
 
         if vertex1.x == vertex2.x || point.x <= origin_x {
             return true;
@@ -144,6 +146,114 @@ pub fn are_multiple_points_in_polygon<T>(
     )
 }
 
+/// Constructs an [`RTree`] over the extents of each of the provided polygons, keyed by the polygon's index.
+/// Polygons with less than 3 vertices are not inserted into the tree.
+///
+/// # Arguments
+/// * `polygons`: A slice of polygons, each being a collection of [`Point2`]s, representing the vertices.
+///
+/// # Generics:
+/// * `T`: Either an [`prim@f32`] or [`prim@f64`]
+/// * `P`: Any type that can be referenced as a slice of [`Point2`], i.e., a [`Vec`] or an array.
+///
+/// # Returns
+/// An [`RTree`] containing the index of each polygon in `polygons`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Build Polygons R-Tree", skip_all, level = "info")
+)]
+pub fn build_polygons_r_tree<T, P>(polygons: &[P]) -> RTree<T, usize, 2>
+where
+    T: Bounded + Copy + RealField,
+    P: AsRef<[Point2<T>]>,
+{
+    let entries = polygons
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, polygon)| {
+            calculate_polygon_extents(polygon.as_ref()).map(|extents| (extents, idx))
+        })
+        .collect();
+
+    RTree::bulk_load(entries, DEFAULT_MAX_NODE_ENTRIES)
+}
+
+/// Finds which of the provided polygons contain each of the provided points, using a pre-built [`RTree`] of the polygons,
+/// so that only polygons whose extents contain the point are checked using [`is_single_point_in_polygon`].
+///
+/// # Arguments
+/// * `points`: A slice of [`Point2`].
+/// * `polygons`: A slice of polygons, each being a collection of [`Point2`]s, representing the vertices.
+/// * `polygons_tree`: A reference to an [`RTree`] of the polygons' extents, see [`build_polygons_r_tree`].
+///
+/// # Generics:
+/// * `T`: Either an [`prim@f32`] or [`prim@f64`]
+/// * `P`: Any type that can be referenced as a slice of [`Point2`], i.e., a [`Vec`] or an array.
+///
+/// # Returns
+/// A [`Vec`] with the same size as `points`, containing the sorted indices of all polygons containing each point.
+///
+/// # Panics
+/// If `polygons_tree` contains indices that are out of bounds for `polygons`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Locate Points In Indexed Polygons", skip_all, level = "info")
+)]
+pub fn locate_points_in_indexed_polygons<T, P>(
+    points: &[Point2<T>],
+    polygons: &[P],
+    polygons_tree: &RTree<T, usize, 2>,
+) -> Vec<Vec<usize>>
+where
+    T: Copy + RealField,
+    P: AsRef<[Point2<T>]>,
+    f32: AsPrimitive<T>,
+{
+    points
+        .iter()
+        .map(|current_point| {
+            let mut containing_polygons = polygons_tree
+                .locate_at_point(current_point)
+                .into_iter()
+                .copied()
+                .filter(|polygon_idx| {
+                    is_single_point_in_polygon(current_point, polygons[*polygon_idx].as_ref())
+                })
+                .collect::<Vec<_>>();
+            containing_polygons.sort_unstable();
+            containing_polygons
+        })
+        .collect()
+}
+
+/// This function finds which of the provided polygons contain each of the provided points,
+/// By first constructing an [`RTree`] of the polygons' extents, this is much faster than running [`are_multiple_points_in_polygon`] for each polygon.
+/// If the same polygons are queried multiple times, consider using [`build_polygons_r_tree`] and [`locate_points_in_indexed_polygons`] instead.
+///
+/// # Arguments
+/// * `points`: A slice of [`Point2`].
+/// * `polygons`: A slice of polygons, each being a collection of [`Point2`]s, representing the vertices.
+///
+/// # Generics:
+/// * `T`: Either an [`prim@f32`] or [`prim@f64`]
+/// * `P`: Any type that can be referenced as a slice of [`Point2`], i.e., a [`Vec`] or an array.
+///
+/// # Returns
+/// A [`Vec`] with the same size as `points`, containing the sorted indices of all polygons containing each point.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Locate Points In Polygons", skip_all, level = "info")
+)]
+pub fn locate_points_in_polygons<T, P>(points: &[Point2<T>], polygons: &[P]) -> Vec<Vec<usize>>
+where
+    T: Bounded + Copy + RealField,
+    P: AsRef<[Point2<T>]>,
+    f32: AsPrimitive<T>,
+{
+    let polygons_tree = build_polygons_r_tree(polygons);
+    locate_points_in_indexed_polygons(points, polygons, &polygons_tree)
+}
+
 #[cfg(feature = "pregenerated")]
 macro_rules! impl_p_i_p_algorithm {
     ($prec:expr, doc $doc:tt) => {
@@ -164,6 +274,14 @@ pub fn are_multiple_points_in_polygon(
                 ) -> Option<Vec<bool>> {
                     super::are_multiple_points_in_polygon(points, polygon)
                 }
+
+                #[doc = "A premade variant of the multiple polygons point-in-polygon algorithm function, made for " $doc " precision floating-point arithmetic."]
+                pub fn locate_points_in_polygons(
+                    points: &[Point2<$prec>],
+                    polygons: &[Vec<Point2<$prec>>],
+                ) -> Vec<Vec<usize>> {
+                    super::locate_points_in_polygons(points, polygons)
+                }
             }
         }
     };
@@ -252,4 +370,63 @@ fn test_multiple_points_in_polygon_counter_clockwise() {
         // Expecting [true, false] since the first point is inside and the second is outside.
         assert_eq!(result, Some(Vec::from([true, false])));
     }
+
+    #[test]
+    fn test_build_polygons_r_tree() {
+        let polygons = Vec::from([
+            get_polygon_for_tests(),
+            Vec::from([Point2::new(0.0, 0.0), Point2::new(1.0, 1.0)]), // Degenerate, should be skipped
+        ]);
+
+        let tree = build_polygons_r_tree(&polygons);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(
+            tree.locate_at_point(&Point2::new(0.5, 1.5)),
+            Vec::from([&0])
+        );
+    }
+
+    #[test]
+    fn test_locate_points_in_polygons() {
+        let square = |offset: f32, size: f32| {
+            Vec::from([
+                Point2::new(offset, offset),
+                Point2::new(offset + size, offset),
+                Point2::new(offset + size, offset + size),
+                Point2::new(offset, offset + size),
+            ])
+        };
+        let polygons = Vec::from([
+            get_polygon_for_tests(),
+            square(0.0, 1.0),
+            square(10.0, 2.0),
+            square(-5.0, 20.0),
+        ]);
+
+        let points = [
+            Point2::new(0.5, 1.5),   // Inside the first polygon and the big square
+            Point2::new(0.8, 0.2), // Inside the unit square and the big square, but outside the first polygon's shape
+            Point2::new(11.0, 11.0), // Inside the small square and the big square
+            Point2::new(100.0, 0.0), // Outside all polygons
+        ];
+
+        let result = locate_points_in_polygons(&points, &polygons);
+        assert_eq!(
+            result,
+            Vec::from([
+                Vec::from([0, 3]),
+                Vec::from([1, 3]),
+                Vec::from([2, 3]),
+                Vec::new()
+            ])
+        );
+
+        // Verify against the single polygon variant
+        for (polygon_idx, polygon) in polygons.iter().enumerate() {
+            let single_result = are_multiple_points_in_polygon(&points, polygon).unwrap();
+            for (point_idx, is_inside) in single_result.into_iter().enumerate() {
+                assert_eq!(result[point_idx].contains(&polygon_idx), is_inside);
+            }
+        }
+    }
 }
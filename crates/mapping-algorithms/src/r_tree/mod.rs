@@ -0,0 +1,817 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point, RealField};
+
+use crate::{array, mem, types::PolygonExtents, Ordering, Vec};
+
+pub(crate) const DEFAULT_MAX_NODE_ENTRIES: usize = 16;
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Union Of Extents", skip_all, level = "trace")
+)]
+fn extents_union<T, const N: usize>(
+    extents_a: &PolygonExtents<T, N>,
+    extents_b: &PolygonExtents<T, N>,
+) -> PolygonExtents<T, N>
+where
+    T: Copy + RealField,
+{
+    array::from_fn(|idx| {
+        extents_a[idx].start().min(*extents_b[idx].start())
+            ..=extents_a[idx].end().max(*extents_b[idx].end())
+    })
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Calculate Extents Volume", skip_all, level = "trace")
+)]
+fn extents_volume<T, const N: usize>(extents: &PolygonExtents<T, N>) -> T
+where
+    T: Copy + RealField,
+{
+    extents.iter().fold(T::one(), |acc, extent| {
+        acc * (*extent.end() - *extent.start())
+    })
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Do Extents Intersect", skip_all, level = "trace")
+)]
+fn extents_intersect<T, const N: usize>(
+    extents_a: &PolygonExtents<T, N>,
+    extents_b: &PolygonExtents<T, N>,
+) -> bool
+where
+    T: Copy + RealField,
+{
+    extents_a
+        .iter()
+        .zip(extents_b.iter())
+        .all(|(a, b)| a.start() <= b.end() && b.start() <= a.end())
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Do Extents Contain", skip_all, level = "trace")
+)]
+fn extents_contain<T, const N: usize>(
+    outer: &PolygonExtents<T, N>,
+    inner: &PolygonExtents<T, N>,
+) -> bool
+where
+    T: Copy + RealField,
+{
+    outer
+        .iter()
+        .zip(inner.iter())
+        .all(|(outer, inner)| outer.start() <= inner.start() && inner.end() <= outer.end())
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Extents Distance Squared", skip_all, level = "trace")
+)]
+fn extents_distance_squared<T, const N: usize>(
+    extents: &PolygonExtents<T, N>,
+    point: &Point<T, N>,
+) -> T
+where
+    T: Copy + RealField,
+{
+    extents
+        .iter()
+        .zip(point.coords.iter())
+        .fold(T::zero(), |acc, (extent, coord)| {
+            let diff = (*extent.start() - *coord)
+                .max(*coord - *extent.end())
+                .max(T::zero());
+            acc + diff * diff
+        })
+}
+
+#[inline]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Compare Extent Centers", skip_all, level = "trace")
+)]
+fn compare_extent_centers<T, const N: usize>(
+    extents_a: &PolygonExtents<T, N>,
+    extents_b: &PolygonExtents<T, N>,
+    dimension: usize,
+) -> Ordering
+where
+    T: Copy + RealField,
+{
+    // The center is (start + end) / 2, the division is redundant for ordering purposes
+    (*extents_a[dimension].start() + *extents_a[dimension].end())
+        .partial_cmp(&(*extents_b[dimension].start() + *extents_b[dimension].end()))
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Split Entries Into Chunks", skip_all, level = "trace")
+)]
+fn split_into_chunks<E>(entries: Vec<E>, chunk_size: usize) -> Vec<Vec<E>> {
+    let mut chunks = Vec::with_capacity(entries.len().div_ceil(chunk_size));
+    let mut entries_iter = entries.into_iter();
+    loop {
+        let chunk = entries_iter.by_ref().take(chunk_size).collect::<Vec<_>>();
+        if chunk.is_empty() {
+            return chunks;
+        }
+        chunks.push(chunk);
+    }
+}
+
+/// Performs the Sort-Tile-Recursive partitioning of the entries, into groups of at most `node_capacity` elements,
+/// Each dimension is sorted by extent center and sliced into slabs, which are then recursively tiled by the next dimension.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Sort Tile Recursive", skip_all, level = "debug")
+)]
+fn sort_tile_recursive<T, E, F, const N: usize>(
+    mut entries: Vec<E>,
+    get_extents: &F,
+    node_capacity: usize,
+    dimension: usize,
+) -> Vec<Vec<E>>
+where
+    T: Copy + RealField,
+    F: Fn(&E) -> &PolygonExtents<T, N>,
+{
+    entries.sort_by(|a, b| compare_extent_centers(get_extents(a), get_extents(b), dimension));
+    if dimension + 1 >= N || entries.len() <= node_capacity {
+        return split_into_chunks(entries, node_capacity);
+    }
+
+    // Find the smallest slab count, for which slab_count^(remaining dimensions) covers all required nodes
+    let node_count = entries.len().div_ceil(node_capacity);
+    let remaining_dimensions = (N - dimension) as u32;
+    let mut slab_count = 1usize;
+    while slab_count.saturating_pow(remaining_dimensions) < node_count {
+        slab_count += 1;
+    }
+    let slab_size = node_capacity * node_count.div_ceil(slab_count);
+
+    split_into_chunks(entries, slab_size)
+        .into_iter()
+        .flat_map(|slab| sort_tile_recursive(slab, get_extents, node_capacity, dimension + 1))
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+enum RTreeChildren<T, D, const N: usize> {
+    Leaves(Vec<(PolygonExtents<T, N>, D)>),
+    Branches(Vec<RTreeNode<T, D, N>>),
+}
+
+#[derive(Clone, Debug)]
+struct RTreeNode<T, D, const N: usize> {
+    extents: PolygonExtents<T, N>,
+    children: RTreeChildren<T, D, N>,
+}
+
+impl<T, D, const N: usize> RTreeNode<T, D, N>
+where
+    T: Copy + RealField,
+{
+    fn from_leaves(leaves: Vec<(PolygonExtents<T, N>, D)>) -> Self {
+        let extents = leaves
+            .iter()
+            .skip(1)
+            .fold(leaves[0].0.clone(), |acc, (extents, _)| {
+                extents_union(&acc, extents)
+            });
+
+        Self {
+            extents,
+            children: RTreeChildren::Leaves(leaves),
+        }
+    }
+
+    fn from_branches(branches: Vec<RTreeNode<T, D, N>>) -> Self {
+        let extents = branches
+            .iter()
+            .skip(1)
+            .fold(branches[0].extents.clone(), |acc, branch| {
+                extents_union(&acc, &branch.extents)
+            });
+
+        Self {
+            extents,
+            children: RTreeChildren::Branches(branches),
+        }
+    }
+
+    /// Splits a list of overflowing entries in half, along the dimension with the largest spread of centers.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Split Overflowing Node", skip_all, level = "trace")
+    )]
+    fn split_entries<E, F>(mut entries: Vec<E>, get_extents: F) -> (Vec<E>, Vec<E>)
+    where
+        F: Fn(&E) -> &PolygonExtents<T, N>,
+    {
+        let split_dimension = (0..N)
+            .map(|dimension| {
+                let (min, max) = entries.iter().fold(
+                    (T::max_value().unwrap(), T::min_value().unwrap()),
+                    |(min, max), entry| {
+                        let extent = &get_extents(entry)[dimension];
+                        let center = *extent.start() + *extent.end();
+                        (min.min(center), max.max(center))
+                    },
+                );
+                (dimension, max - min)
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            .map(|(dimension, _)| dimension)
+            .unwrap_or_default();
+
+        entries.sort_by(|a, b| {
+            compare_extent_centers(get_extents(a), get_extents(b), split_dimension)
+        });
+        let second_half = entries.split_off(entries.len() / 2);
+        (entries, second_half)
+    }
+
+    /// Inserts the entry into this node's subtree, returns a new sibling node if this node had to be split.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Insert Into Node", skip_all, level = "trace")
+    )]
+    fn insert(
+        &mut self,
+        extents: PolygonExtents<T, N>,
+        data: D,
+        max_node_entries: usize,
+    ) -> Option<RTreeNode<T, D, N>> {
+        self.extents = extents_union(&self.extents, &extents);
+
+        match &mut self.children {
+            RTreeChildren::Leaves(leaves) => {
+                leaves.push((extents, data));
+                if leaves.len() <= max_node_entries {
+                    return None;
+                }
+
+                let (first, second) =
+                    Self::split_entries(mem::take(leaves), |(extents, _)| extents);
+                *self = Self::from_leaves(first);
+                Some(Self::from_leaves(second))
+            }
+            RTreeChildren::Branches(branches) => {
+                // Choose the branch that requires the least enlargement, preferring smaller branches on ties
+                let best_branch = branches
+                    .iter_mut()
+                    .map(|branch| {
+                        let volume = extents_volume(&branch.extents);
+                        let enlargement =
+                            extents_volume(&extents_union(&branch.extents, &extents)) - volume;
+                        (branch, enlargement, volume)
+                    })
+                    .min_by(|a, b| {
+                        a.1.partial_cmp(&b.1)
+                            .unwrap_or(Ordering::Equal)
+                            .then_with(|| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
+                    })
+                    .map(|(branch, _, _)| branch)?;
+
+                let new_sibling = best_branch.insert(extents, data, max_node_entries)?;
+                branches.push(new_sibling);
+                if branches.len() <= max_node_entries {
+                    return None;
+                }
+
+                let (first, second) =
+                    Self::split_entries(mem::take(branches), |branch| &branch.extents);
+                *self = Self::from_branches(first);
+                Some(Self::from_branches(second))
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Traverse Node With Predicates", skip_all, level = "trace")
+    )]
+    fn locate<'a, P, L>(&'a self, node_predicate: &P, leaf_predicate: &L, results: &mut Vec<&'a D>)
+    where
+        P: Fn(&PolygonExtents<T, N>) -> bool,
+        L: Fn(&PolygonExtents<T, N>) -> bool,
+    {
+        if !node_predicate(&self.extents) {
+            return;
+        }
+
+        match &self.children {
+            RTreeChildren::Leaves(leaves) => results.extend(
+                leaves
+                    .iter()
+                    .filter(|(extents, _)| leaf_predicate(extents))
+                    .map(|(_, data)| data),
+            ),
+            RTreeChildren::Branches(branches) => branches
+                .iter()
+                .for_each(|branch| branch.locate(node_predicate, leaf_predicate, results)),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Branch Nearest Entry", skip_all, level = "trace")
+    )]
+    fn nearest<'a>(&'a self, target: &Point<T, N>, best: &mut Option<(T, &'a D)>) {
+        match &self.children {
+            RTreeChildren::Leaves(leaves) => {
+                for (extents, data) in leaves.iter() {
+                    let distance = extents_distance_squared(extents, target);
+                    if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                        *best = Some((distance, data));
+                    }
+                }
+            }
+            RTreeChildren::Branches(branches) => {
+                // Visit the closest branches first, so that farther branches are more likely to be pruned
+                let mut branch_distances = branches
+                    .iter()
+                    .map(|branch| (extents_distance_squared(&branch.extents, target), branch))
+                    .collect::<Vec<_>>();
+                branch_distances.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+                for (distance, branch) in branch_distances {
+                    if best.is_some_and(|(best_distance, _)| distance >= best_distance) {
+                        break;
+                    }
+                    branch.nearest(target, best);
+                }
+            }
+        }
+    }
+}
+
+/// An R-Tree spatial index, storing arbitrary data keyed by its [`PolygonExtents`].
+/// Allows for quick intersection, containment and nearest neighbour queries over large collections of bounding boxes.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `D`: The type of data stored alongside each bounding box, i.e., an index into a collection of polygons.
+/// * `N`: A const usize, representing the number of dimensions of the bounding boxes.
+#[derive(Clone, Debug)]
+pub struct RTree<T, D, const N: usize> {
+    root: Option<RTreeNode<T, D, N>>,
+    element_count: usize,
+    max_node_entries: usize,
+}
+
+impl<T, D, const N: usize> Default for RTree<T, D, N>
+where
+    T: Copy + RealField,
+{
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_NODE_ENTRIES)
+    }
+}
+
+impl<T, D, const N: usize> RTree<T, D, N>
+where
+    T: Copy + RealField,
+{
+    /// Creates a new, empty tree.
+    ///
+    /// # Arguments
+    /// * `max_node_entries`: the maximum number of entries in each node before it is split, values lower than 2 are clamped to 2.
+    ///
+    /// # Returns
+    /// An empty [`RTree`].
+    pub fn new(max_node_entries: usize) -> Self {
+        Self {
+            root: None,
+            element_count: 0,
+            max_node_entries: max_node_entries.max(2),
+        }
+    }
+
+    /// Constructs a tree from the given entries using Sort-Tile-Recursive bulk loading,
+    /// which produces a much better packed tree than inserting each entry individually.
+    ///
+    /// # Arguments
+    /// * `entries`: a [`Vec`] of tuples, each containing the [`PolygonExtents`] of an entry, and its data.
+    /// * `max_node_entries`: the maximum number of entries in each node, values lower than 2 are clamped to 2.
+    ///
+    /// # Returns
+    /// An [`RTree`] containing all the entries.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Bulk Load Tree", skip_all, level = "info")
+    )]
+    pub fn bulk_load(entries: Vec<(PolygonExtents<T, N>, D)>, max_node_entries: usize) -> Self {
+        let mut tree = Self::new(max_node_entries);
+        if entries.is_empty() {
+            return tree;
+        }
+
+        tree.element_count = entries.len();
+        let mut nodes = sort_tile_recursive(
+            entries,
+            &|(extents, _): &(PolygonExtents<T, N>, D)| extents,
+            tree.max_node_entries,
+            0,
+        )
+        .into_iter()
+        .map(RTreeNode::from_leaves)
+        .collect::<Vec<_>>();
+
+        while nodes.len() > 1 {
+            nodes = sort_tile_recursive(
+                nodes,
+                &|node: &RTreeNode<T, D, N>| &node.extents,
+                tree.max_node_entries,
+                0,
+            )
+            .into_iter()
+            .map(RTreeNode::from_branches)
+            .collect();
+        }
+
+        tree.root = nodes.pop();
+        tree
+    }
+
+    /// Inserts a new entry into the tree, splitting nodes as required.
+    ///
+    /// # Arguments
+    /// * `extents`: the [`PolygonExtents`] of the new entry.
+    /// * `data`: the data to associate with these extents.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Insert To Tree", skip_all, level = "debug")
+    )]
+    pub fn insert(&mut self, extents: PolygonExtents<T, N>, data: D) {
+        self.element_count += 1;
+
+        let Some(root) = self.root.as_mut() else {
+            self.root = Some(RTreeNode::from_leaves(Vec::from([(extents, data)])));
+            return;
+        };
+
+        if let Some(new_sibling) = root.insert(extents, data, self.max_node_entries) {
+            let old_root = self.root.take().unwrap();
+            self.root = Some(RTreeNode::from_branches(Vec::from([old_root, new_sibling])));
+        }
+    }
+
+    /// Returns the number of elements in the tree.
+    ///
+    /// # Returns
+    /// A [`usize`] representing the number of elements in the tree.
+    pub fn len(&self) -> usize {
+        self.element_count
+    }
+
+    /// Returns whether the tree is empty or not.
+    ///
+    /// # Returns
+    /// A [`bool`] representing whether the tree is empty or not.
+    pub fn is_empty(&self) -> bool {
+        self.element_count == 0
+    }
+
+    /// Finds all entries whose extents intersect the given extents, touching boundaries are considered intersecting.
+    ///
+    /// # Arguments
+    /// * `extents`: the [`PolygonExtents`] to query.
+    ///
+    /// # Returns
+    /// A [`Vec`] of references to the data of each intersecting entry, in no particular order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Locate Intersecting Entries", skip_all, level = "debug")
+    )]
+    pub fn locate_intersecting(&self, extents: &PolygonExtents<T, N>) -> Vec<&D> {
+        let mut results = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            let predicate =
+                |node_extents: &PolygonExtents<T, N>| extents_intersect(node_extents, extents);
+            root.locate(&predicate, &predicate, &mut results);
+        }
+        results
+    }
+
+    /// Finds all entries whose extents are entirely contained within the given extents.
+    ///
+    /// # Arguments
+    /// * `extents`: the [`PolygonExtents`] to query.
+    ///
+    /// # Returns
+    /// A [`Vec`] of references to the data of each contained entry, in no particular order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Locate Contained Entries", skip_all, level = "debug")
+    )]
+    pub fn locate_contained_in(&self, extents: &PolygonExtents<T, N>) -> Vec<&D> {
+        let mut results = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            root.locate(
+                &|node_extents: &PolygonExtents<T, N>| extents_intersect(node_extents, extents),
+                &|entry_extents: &PolygonExtents<T, N>| extents_contain(extents, entry_extents),
+                &mut results,
+            );
+        }
+        results
+    }
+
+    /// Finds all entries whose extents contain the given point.
+    ///
+    /// # Arguments
+    /// * `point`: a reference to a [`Point`] to query.
+    ///
+    /// # Returns
+    /// A [`Vec`] of references to the data of each containing entry, in no particular order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Locate Entries At Point", skip_all, level = "debug")
+    )]
+    pub fn locate_at_point(&self, point: &Point<T, N>) -> Vec<&D> {
+        let mut results = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            let predicate = |node_extents: &PolygonExtents<T, N>| {
+                node_extents
+                    .iter()
+                    .zip(point.coords.iter())
+                    .all(|(extent, coord)| extent.contains(coord))
+            };
+            root.locate(&predicate, &predicate, &mut results);
+        }
+        results
+    }
+
+    /// Finds the entry whose extents are closest to the given point, points inside an entry's extents have a distance of zero.
+    ///
+    /// # Arguments
+    /// * `target`: a reference to a [`Point`], to search the closest entry for.
+    ///
+    /// # Returns
+    /// [`None`] if the tree is empty, otherwise a reference to the data of the closest entry.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find Nearest Entry", skip_all, level = "debug")
+    )]
+    pub fn nearest(&self, target: &Point<T, N>) -> Option<&D> {
+        let mut best = None;
+        if let Some(root) = self.root.as_ref() {
+            root.nearest(target, &mut best);
+        }
+        best.map(|(_, data)| data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Point2, Point3};
+
+    use crate::{ops::RangeInclusive, point_clouds::generate_point_cloud};
+
+    use super::*;
+
+    fn generate_entries(num_entries: usize) -> Vec<(PolygonExtents<f64, 2>, usize)> {
+        generate_point_cloud(num_entries, [-100.0..=100.0, -100.0..=100.0])
+            .into_iter()
+            .enumerate()
+            .map(|(idx, corner)| {
+                (
+                    [
+                        corner.x..=corner.x + (idx % 5) as f64,
+                        corner.y..=corner.y + (idx % 3) as f64,
+                    ],
+                    idx,
+                )
+            })
+            .collect()
+    }
+
+    fn sorted(mut results: Vec<&usize>) -> Vec<usize> {
+        results.sort();
+        results.into_iter().copied().collect()
+    }
+
+    fn node_depths<T: Copy + RealField, D, const N: usize>(
+        node: &RTreeNode<T, D, N>,
+        depth: usize,
+        max_node_entries: usize,
+        depths: &mut Vec<usize>,
+    ) {
+        match &node.children {
+            RTreeChildren::Leaves(leaves) => {
+                assert!(leaves.len() <= max_node_entries);
+                depths.push(depth);
+            }
+            RTreeChildren::Branches(branches) => {
+                assert!(branches.len() <= max_node_entries);
+                for branch in branches {
+                    assert!(extents_contain(&node.extents, &branch.extents));
+                    node_depths(branch, depth + 1, max_node_entries, depths);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extents_helpers() {
+        let extents_a = [0.0..=2.0, 0.0..=2.0];
+        let extents_b = [1.0..=3.0, -1.0..=1.0];
+        let extents_c = [5.0..=6.0, 5.0..=6.0];
+
+        assert_eq!(
+            extents_union(&extents_a, &extents_b),
+            [0.0..=3.0, -1.0..=2.0]
+        );
+        assert_eq!(extents_volume(&extents_a), 4.0);
+        assert!(extents_intersect(&extents_a, &extents_b));
+        assert!(!extents_intersect(&extents_a, &extents_c));
+        assert!(extents_contain(&[-1.0..=7.0, -1.0..=7.0], &extents_c));
+        assert!(!extents_contain(&extents_a, &extents_b));
+        assert_eq!(
+            extents_distance_squared(&extents_a, &Point2::new(1.0, 1.0)),
+            0.0
+        );
+        assert_eq!(
+            extents_distance_squared(&extents_a, &Point2::new(5.0, -4.0)),
+            25.0
+        );
+    }
+
+    #[test]
+    fn test_split_into_chunks() {
+        let chunks = split_into_chunks(Vec::from([1, 2, 3, 4, 5, 6, 7]), 3);
+        assert_eq!(
+            chunks,
+            Vec::from([Vec::from([1, 2, 3]), Vec::from([4, 5, 6]), Vec::from([7])])
+        );
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = RTree::<f32, usize, 2>::default();
+        assert!(tree.is_empty());
+        assert!(tree.nearest(&Point2::new(0.0, 0.0)).is_none());
+        assert!(tree
+            .locate_intersecting(&[-1.0..=1.0, -1.0..=1.0])
+            .is_empty());
+
+        let tree = RTree::<f32, usize, 2>::bulk_load(Vec::new(), 4);
+        assert!(tree.is_empty());
+        assert!(tree.root.is_none());
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree = RTree::new(4);
+        for (extents, idx) in generate_entries(100) {
+            tree.insert(extents, idx);
+        }
+        assert_eq!(tree.len(), 100);
+
+        // All leaves must be at the same depth, and no node may overflow
+        let mut depths = Vec::new();
+        node_depths(tree.root.as_ref().unwrap(), 0, 4, &mut depths);
+        assert!(depths.iter().all(|depth| *depth == depths[0]));
+        assert!(depths[0] > 1);
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        let tree = RTree::bulk_load(generate_entries(1000), 8);
+        assert_eq!(tree.len(), 1000);
+
+        let mut depths = Vec::new();
+        node_depths(tree.root.as_ref().unwrap(), 0, 8, &mut depths);
+        assert!(depths.iter().all(|depth| *depth == depths[0]));
+        // 1000 entries tightly packed into nodes of 8 require 125 leaves, so a height of 4
+        assert_eq!(depths[0], 3);
+    }
+
+    #[test]
+    fn test_locate_intersecting() {
+        let entries = generate_entries(500);
+        let query = [-20.0..=30.0, 10.0..=45.0];
+
+        let expected = entries
+            .iter()
+            .filter(|(extents, _)| extents_intersect(extents, &query))
+            .map(|(_, idx)| *idx)
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+
+        let bulk_tree = RTree::bulk_load(entries.clone(), 6);
+        assert_eq!(sorted(bulk_tree.locate_intersecting(&query)), expected);
+
+        let mut inserted_tree = RTree::new(6);
+        for (extents, idx) in entries {
+            inserted_tree.insert(extents, idx);
+        }
+        assert_eq!(sorted(inserted_tree.locate_intersecting(&query)), expected);
+    }
+
+    #[test]
+    fn test_locate_contained_in() {
+        let mut tree = RTree::default();
+        tree.insert([0.0..=1.0, 0.0..=1.0], 0);
+        tree.insert([0.5..=3.0, 0.5..=3.0], 1);
+        tree.insert([1.5..=2.0, 1.5..=2.5], 2);
+        tree.insert([10.0..=11.0, 10.0..=11.0], 3);
+
+        assert_eq!(
+            sorted(tree.locate_contained_in(&[0.0..=2.5, 0.0..=2.5])),
+            Vec::from([0, 2])
+        );
+        assert_eq!(
+            sorted(tree.locate_intersecting(&[0.0..=2.5, 0.0..=2.5])),
+            Vec::from([0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_locate_at_point() {
+        let entries = generate_entries(500);
+        let tree = RTree::bulk_load(entries.clone(), 5);
+
+        for point in generate_point_cloud(50, [-100.0..=100.0, -100.0..=100.0]) {
+            let expected = entries
+                .iter()
+                .filter(|(extents, _)| {
+                    extents[0].contains(&point.x) && extents[1].contains(&point.y)
+                })
+                .map(|(_, idx)| *idx)
+                .collect::<Vec<_>>();
+            assert_eq!(sorted(tree.locate_at_point(&point)), expected);
+        }
+    }
+
+    #[test]
+    fn test_nearest() {
+        let entries = generate_entries(300);
+        let tree = RTree::bulk_load(entries.clone(), 7);
+
+        for point in generate_point_cloud(50, [-120.0..=120.0, -120.0..=120.0]) {
+            let nearest = tree.nearest(&point).unwrap();
+            let nearest_distance = extents_distance_squared(&entries[*nearest].0, &point);
+            let naive_distance = entries
+                .iter()
+                .map(|(extents, _)| extents_distance_squared(extents, &point))
+                .fold(f64::MAX, f64::min);
+            assert_eq!(nearest_distance, naive_distance);
+        }
+    }
+
+    #[test]
+    fn test_3d() {
+        let mut tree = RTree::new(2);
+        let boxes: [PolygonExtents<f32, 3>; 4] = [
+            [0.0..=1.0, 0.0..=1.0, 0.0..=1.0],
+            [2.0..=3.0, 2.0..=3.0, 2.0..=3.0],
+            [-3.0..=-2.0, 0.0..=1.0, 4.0..=5.0],
+            [RangeInclusive::new(0.5, 2.5), 0.5..=2.5, 0.5..=2.5],
+        ];
+        for (idx, extents) in boxes.into_iter().enumerate() {
+            tree.insert(extents, idx);
+        }
+
+        assert_eq!(tree.len(), 4);
+        assert_eq!(
+            sorted(tree.locate_at_point(&Point3::new(0.75, 0.75, 0.75))),
+            Vec::from([0, 3])
+        );
+        assert_eq!(tree.nearest(&Point3::new(-3.0, 0.0, 7.0)), Some(&2));
+    }
+}
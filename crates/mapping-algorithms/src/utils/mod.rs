@@ -24,6 +24,8 @@
 use nalgebra::{Const, DimMin, Point, RealField, SMatrix, Scalar};
 use num_traits::NumOps;
 
+use crate::{array, Ordering};
+
 #[cfg_attr(
     feature = "tracing",
     tracing::instrument("Calculate Distance Squared", skip_all, level = "trace")
@@ -62,10 +64,101 @@ pub(crate) fn verify_rotation_matrix_determinant<T, const N: usize>(
     u * v_t
 }
 
+/// Calculates the eigenvalues and eigenvectors of a symmetric matrix, using the cyclic Jacobi eigenvalue algorithm.
+/// This works for any number of dimensions, without requiring the dimension bounds of [`nalgebra::SymmetricEigen`].
+///
+/// # Arguments
+/// * `matrix`: a symmetric `N` by `N` [`SMatrix`], only the upper triangle is read.
+///
+/// # Returns
+/// A tuple of
+/// * An array of the eigenvalues, sorted in ascending order.
+/// * An [`SMatrix`], whose columns are the unit eigenvectors matching each eigenvalue.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Symmetric Eigen Decomposition", skip_all, level = "trace")
+)]
+pub(crate) fn symmetric_eigen_decomposition<T, const N: usize>(
+    matrix: &SMatrix<T, N, N>,
+) -> ([T; N], SMatrix<T, N, N>)
+where
+    T: Copy + RealField,
+{
+    let mut diagonalized = SMatrix::<T, N, N>::from_fn(|row, column| {
+        if row <= column {
+            matrix[(row, column)]
+        } else {
+            matrix[(column, row)]
+        }
+    });
+    let mut eigenvectors = SMatrix::<T, N, N>::identity();
+    let two = T::one() + T::one();
+
+    for _ in 0..64 {
+        let off_diagonal_norm = (0..N)
+            .flat_map(|row| ((row + 1)..N).map(move |column| (row, column)))
+            .fold(T::zero(), |acc, (row, column)| {
+                acc + diagonalized[(row, column)] * diagonalized[(row, column)]
+            });
+        if off_diagonal_norm <= T::default_epsilon() * T::default_epsilon() {
+            break;
+        }
+
+        for p in 0..N {
+            for q in (p + 1)..N {
+                let a_pq = diagonalized[(p, q)];
+                if a_pq == T::zero() {
+                    continue;
+                }
+
+                // Calculate the rotation that zeroes the (p, q) element
+                let theta = (diagonalized[(q, q)] - diagonalized[(p, p)]) / (two * a_pq);
+                let tangent = if theta == T::zero() {
+                    T::one()
+                } else {
+                    theta.signum() / (theta.abs() + (theta * theta + T::one()).sqrt())
+                };
+                let cosine = T::one() / (tangent * tangent + T::one()).sqrt();
+                let sine = tangent * cosine;
+
+                for k in 0..N {
+                    let (a_kp, a_kq) = (diagonalized[(k, p)], diagonalized[(k, q)]);
+                    diagonalized[(k, p)] = cosine * a_kp - sine * a_kq;
+                    diagonalized[(k, q)] = sine * a_kp + cosine * a_kq;
+                }
+                for k in 0..N {
+                    let (a_pk, a_qk) = (diagonalized[(p, k)], diagonalized[(q, k)]);
+                    diagonalized[(p, k)] = cosine * a_pk - sine * a_qk;
+                    diagonalized[(q, k)] = sine * a_pk + cosine * a_qk;
+                }
+                for k in 0..N {
+                    let (v_kp, v_kq) = (eigenvectors[(k, p)], eigenvectors[(k, q)]);
+                    eigenvectors[(k, p)] = cosine * v_kp - sine * v_kq;
+                    eigenvectors[(k, q)] = sine * v_kp + cosine * v_kq;
+                }
+            }
+        }
+    }
+
+    let mut order: [usize; N] = array::from_fn(|idx| idx);
+    order.sort_by(|a, b| {
+        diagonalized[(*a, *a)]
+            .partial_cmp(&diagonalized[(*b, *b)])
+            .unwrap_or(Ordering::Equal)
+    });
+
+    (
+        array::from_fn(|idx| diagonalized[(order[idx], order[idx])]),
+        SMatrix::from_fn(|row, column| eigenvectors[(row, order[column])]),
+    )
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use nalgebra::{Matrix2, Point3};
+    use nalgebra::{Matrix2, Matrix3, Point3, Vector3};
+
+    use crate::Vec;
 
     #[test]
     fn test_distance_squared() {
@@ -89,4 +182,44 @@ fn test_verify_rotation_matrix_determinant() {
         assert_eq!(func_dot.m12, -regular_dot.m12);
         assert_eq!(func_dot.m22, -regular_dot.m22);
     }
+
+    #[test]
+    fn test_symmetric_eigen_decomposition() {
+        let matrix = Matrix3::new(4.0, 1.0, -2.0, 1.0, 2.0, 0.0, -2.0, 0.0, 3.0);
+        let (eigenvalues, eigenvectors) = symmetric_eigen_decomposition(&matrix);
+
+        // Compare against nalgebra's implementation
+        let mut expected: Vec<f64> = matrix.symmetric_eigen().eigenvalues.as_slice().to_vec();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (eigenvalue, expected_eigenvalue) in eigenvalues.iter().zip(expected.iter()) {
+            assert!((eigenvalue - expected_eigenvalue).abs() < 1e-10);
+        }
+
+        for (idx, eigenvalue) in eigenvalues.iter().enumerate() {
+            let eigenvector = eigenvectors.column(idx);
+            assert!((eigenvector.norm() - 1.0).abs() < 1e-10);
+            assert!((matrix * eigenvector - eigenvector * *eigenvalue).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_symmetric_eigen_decomposition_diagonal() {
+        let matrix = Matrix3::from_diagonal(&Vector3::new(3.0f32, 1.0, 2.0));
+        let (eigenvalues, eigenvectors) = symmetric_eigen_decomposition(&matrix);
+
+        assert_eq!(eigenvalues, [1.0, 2.0, 3.0]);
+        assert_eq!(eigenvectors.column(0), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(eigenvectors.column(2), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_symmetric_eigen_decomposition_equal_diagonal() {
+        // When both diagonal elements are equal, the rotation angle is exactly 45 degrees
+        let matrix = Matrix2::new(2.0, 1.0, 1.0, 2.0);
+        let (eigenvalues, eigenvectors) = symmetric_eigen_decomposition(&matrix);
+
+        assert!((eigenvalues[0] - 1.0f64).abs() < 1e-12);
+        assert!((eigenvalues[1] - 3.0f64).abs() < 1e-12);
+        assert!((eigenvectors[(0, 1)].abs() - 0.5f64.sqrt()).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use core::cmp::Reverse;
+
+use nalgebra::{Point2, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{
+    occupancy_grid::{CellState, OccupancyGrid},
+    BinaryHeap, Vec,
+};
+
+/// A precomputed map of the distance from each cell of an occupancy grid to its nearest occupied cell,
+/// used by the likelihood field sensor model.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct LikelihoodField<T: RealField> {
+    origin: Point2<T>,
+    resolution: T,
+    min_cell: [isize; 2],
+    width: usize,
+    height: usize,
+    max_distance: T,
+    distances: Vec<T>,
+}
+
+impl<T> LikelihoodField<T>
+where
+    T: AsPrimitive<isize> + AsPrimitive<usize> + Copy + RealField,
+    isize: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    /// Computes the distance of each cell to the nearest occupied cell,
+    /// by propagating the nearest occupied cell outwards from all occupied cells at once.
+    ///
+    /// # Arguments
+    /// * `grid`: an [`OccupancyGrid`], its occupied cells are the obstacles.
+    /// * `max_distance`: distances are computed up to this, further cells are assigned this distance.
+    ///
+    /// # Returns
+    /// A new [`LikelihoodField`], covering the same cells as the grid.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute Likelihood Field", skip_all, level = "info")
+    )]
+    pub fn new(grid: &OccupancyGrid<T>, max_distance: T) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let max_cells: usize = (max_distance / grid.resolution()).ceil().as_();
+        let max_distance_squared = max_cells * max_cells;
+
+        let mut nearest_distances =
+            Vec::from_iter(core::iter::repeat_n(usize::MAX, width * height));
+        let mut heap = BinaryHeap::new();
+        for (cell_idx, state) in grid.to_ternary_grid().into_iter().enumerate() {
+            if state == CellState::Occupied {
+                nearest_distances[cell_idx] = 0;
+                heap.push(Reverse((0, cell_idx, cell_idx)));
+            }
+        }
+
+        let coordinates =
+            |cell_idx: usize| [(cell_idx % width) as isize, (cell_idx / width) as isize];
+        while let Some(Reverse((distance_squared, cell_idx, source_idx))) = heap.pop() {
+            if distance_squared > nearest_distances[cell_idx] {
+                continue;
+            }
+
+            let [column, row] = coordinates(cell_idx);
+            let source = coordinates(source_idx);
+            for (column_offset, row_offset) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (neighbour_column, neighbour_row) = (column + column_offset, row + row_offset);
+                if neighbour_column < 0
+                    || neighbour_row < 0
+                    || neighbour_column >= width as isize
+                    || neighbour_row >= height as isize
+                {
+                    continue;
+                }
+
+                let neighbour_idx = neighbour_row as usize * width + neighbour_column as usize;
+                let neighbour_distance = (neighbour_column - source[0]).pow(2) as usize
+                    + (neighbour_row - source[1]).pow(2) as usize;
+                if neighbour_distance < nearest_distances[neighbour_idx]
+                    && neighbour_distance <= max_distance_squared
+                {
+                    nearest_distances[neighbour_idx] = neighbour_distance;
+                    heap.push(Reverse((neighbour_distance, neighbour_idx, source_idx)));
+                }
+            }
+        }
+
+        Self {
+            origin: grid.cell_to_world(grid.min_cell())
+                - nalgebra::Vector2::repeat(grid.resolution() / (T::one() + T::one())),
+            resolution: grid.resolution(),
+            min_cell: grid.min_cell(),
+            width,
+            height,
+            max_distance,
+            distances: nearest_distances
+                .into_iter()
+                .map(|distance_squared| {
+                    if distance_squared == usize::MAX {
+                        max_distance
+                    } else {
+                        (AsPrimitive::<T>::as_(distance_squared).sqrt() * grid.resolution())
+                            .min(max_distance)
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the maximum distance the field was computed up to.
+    pub fn max_distance(&self) -> T {
+        self.max_distance
+    }
+
+    /// Returns the distance from a world position to the nearest occupied cell.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point2`], the world position.
+    ///
+    /// # Returns
+    /// The distance, capped at the maximum distance, positions outside of the grid are always at the maximum distance.
+    pub fn distance(&self, point: &Point2<T>) -> T {
+        let [column, row] = [0, 1].map(|axis| {
+            AsPrimitive::<isize>::as_(((point[axis] - self.origin[axis]) / self.resolution).floor())
+        });
+        if column < 0 || row < 0 || column as usize >= self.width || row as usize >= self.height {
+            return self.max_distance;
+        }
+
+        self.distances[row as usize * self.width + column as usize]
+    }
+
+    /// Returns the index of the lowest cell the field covers, identical to that of its grid.
+    pub fn min_cell(&self) -> [isize; 2] {
+        self.min_cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::occupancy_grid::OccupancyGridConfiguration;
+
+    #[test]
+    fn test_likelihood_field() {
+        let mut grid = OccupancyGrid::new(
+            OccupancyGridConfiguration::builder()
+                .with_resolution(0.5)
+                .build(),
+        );
+        grid.expand_to([0, 0], [9, 9]);
+        grid.update_cell([2, 2], true);
+        grid.update_cell([7, 2], true);
+
+        let field = LikelihoodField::new(&grid, 2.0);
+        assert_eq!(field.min_cell(), [0, 0]);
+        assert_eq!(field.distance(&Point2::new(1.2, 1.4)), 0.0);
+        assert_eq!(field.distance(&Point2::new(1.2, 2.4)), 1.0);
+        assert_eq!(field.distance(&Point2::new(2.3, 2.4)), 8.0f64.sqrt() * 0.5);
+        assert_eq!(field.distance(&Point2::new(3.7, 1.2)), 0.0);
+        // Capped by the maximum distance, both far away and outside of the grid
+        assert_eq!(field.distance(&Point2::new(1.2, 4.9)), 2.0);
+        assert_eq!(field.distance(&Point2::new(-10.0, 1.2)), 2.0);
+        assert_eq!(field.max_distance(), 2.0);
+    }
+}
@@ -0,0 +1,611 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry2, Matrix3, Point2, RealField, Vector2, Vector3};
+use num_traits::AsPrimitive;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    occupancy_grid::{CellState, OccupancyGrid},
+    sampling::{low_variance_resample, normalize_angle, sample_normal},
+    HashMap, Vec,
+};
+
+pub use likelihood_field::LikelihoodField;
+pub use types::{AmclConfiguration, AmclConfigurationBuilder, Particle, PoseEstimate};
+
+mod likelihood_field;
+mod types;
+
+/// An AMCL-style Monte Carlo localiser, tracking a robot's pose in a known occupancy grid using a particle filter.
+///
+/// Particles are propagated by an odometry motion model, weighted by a likelihood field sensor model,
+/// and resampled with low-variance resampling, with the amount of particles adapted using KLD-sampling.
+/// All randomness is drawn from a seeded generator, so identical inputs produce identical results.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct Amcl<T: RealField> {
+    config: AmclConfiguration<T>,
+    likelihood_field: LikelihoodField<T>,
+    free_cells: Vec<Point2<T>>,
+    resolution: T,
+    particles: Vec<Particle<T>>,
+    rng: SmallRng,
+    last_odometry: Option<Isometry2<T>>,
+    updates_since_resample: usize,
+}
+
+impl<T> Amcl<T>
+where
+    T: AsPrimitive<isize> + AsPrimitive<usize> + Copy + RealField,
+    f32: AsPrimitive<T>,
+    isize: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs a localiser for a map, without any particles, see [`Amcl::initialize_gaussian`] and [`Amcl::initialize_global`].
+    ///
+    /// # Arguments
+    /// * `config`: an [`AmclConfiguration`], specifying the filter's parameters.
+    /// * `grid`: an [`OccupancyGrid`], the map to localise in.
+    ///
+    /// # Returns
+    /// A new [`Amcl`].
+    pub fn new(config: AmclConfiguration<T>, grid: &OccupancyGrid<T>) -> Self {
+        let min_cell = grid.min_cell();
+        let free_cells = grid
+            .to_ternary_grid()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, state)| *state == CellState::Free)
+            .map(|(cell_idx, _)| {
+                grid.cell_to_world([
+                    min_cell[0] + (cell_idx % grid.width()) as isize,
+                    min_cell[1] + (cell_idx / grid.width()) as isize,
+                ])
+            })
+            .collect();
+
+        Self {
+            likelihood_field: LikelihoodField::new(grid, config.likelihood_max_distance),
+            free_cells,
+            resolution: grid.resolution(),
+            particles: Vec::new(),
+            rng: SmallRng::seed_from_u64(config.seed),
+            last_odometry: None,
+            updates_since_resample: 0,
+            config,
+        }
+    }
+
+    /// Returns the configuration the localiser was constructed with.
+    pub fn config(&self) -> &AmclConfiguration<T> {
+        &self.config
+    }
+
+    /// Returns the current particles, their weights sum to one.
+    pub fn particles(&self) -> &[Particle<T>] {
+        &self.particles
+    }
+
+    /// Returns the likelihood field computed from the map.
+    pub fn likelihood_field(&self) -> &LikelihoodField<T> {
+        &self.likelihood_field
+    }
+
+    #[inline]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Draw Uniform AMCL Sample", skip_all, level = "trace")
+    )]
+    fn uniform(&mut self) -> T {
+        self.rng.gen::<f32>().as_()
+    }
+
+    /// Replaces all particles with the maximal amount of particles, drawn from a normal distribution around a pose.
+    /// The next update only weighs the new particles, without applying motion.
+    ///
+    /// # Arguments
+    /// * `mean`: an [`Isometry2`], the initial pose estimate.
+    /// * `covariance`: a [`Matrix3`], the covariance of `x`, `y` and the heading.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Initialize AMCL Around Pose", skip_all, level = "info")
+    )]
+    pub fn initialize_gaussian(&mut self, mean: &Isometry2<T>, covariance: &Matrix3<T>) {
+        let factor = covariance
+            .cholesky()
+            .map(|cholesky| cholesky.l())
+            .unwrap_or_else(|| {
+                Matrix3::from_diagonal(
+                    &covariance
+                        .diagonal()
+                        .map(|variance| variance.max(T::zero()).sqrt()),
+                )
+            });
+        let yaw = mean.rotation.angle();
+        let weight = T::one() / self.config.max_particles.max(1).as_();
+
+        self.particles = (0..self.config.max_particles)
+            .map(|_| {
+                let offset = factor
+                    * Vector3::new(
+                        sample_normal(&mut self.rng, T::one()),
+                        sample_normal(&mut self.rng, T::one()),
+                        sample_normal(&mut self.rng, T::one()),
+                    );
+                Particle {
+                    pose: Isometry2::new(mean.translation.vector + offset.xy(), yaw + offset.z),
+                    weight,
+                }
+            })
+            .collect();
+        self.last_odometry = None;
+        self.updates_since_resample = 0;
+    }
+
+    /// Replaces all particles with the maximal amount of particles, drawn uniformly from the map's free cells and all headings.
+    /// The next update only weighs the new particles, without applying motion.
+    ///
+    /// # Returns
+    /// Whether the particles were initialised, `false` if the map has no free cells.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Initialize AMCL Globally", skip_all, level = "info")
+    )]
+    pub fn initialize_global(&mut self) -> bool {
+        if self.free_cells.is_empty() {
+            return false;
+        }
+
+        let weight = T::one() / self.config.max_particles.max(1).as_();
+        let half: T = 0.5.as_();
+        self.particles = (0..self.config.max_particles)
+            .map(|_| {
+                let cell = self.free_cells[self.rng.gen_range(0..self.free_cells.len())];
+                let jitter =
+                    Vector2::new(self.uniform() - half, self.uniform() - half) * self.resolution;
+                Particle {
+                    pose: Isometry2::new(
+                        cell.coords + jitter,
+                        (self.uniform() - half) * T::two_pi(),
+                    ),
+                    weight,
+                }
+            })
+            .collect();
+        self.last_odometry = None;
+        self.updates_since_resample = 0;
+        true
+    }
+
+    /// Updates the filter with a new odometry reading and a laser scan.
+    /// The filter is only updated once the robot has moved enough since the last update,
+    /// the first reading only weighs the particles, as there is no previous reading to measure motion from.
+    ///
+    /// # Arguments
+    /// * `odometry`: an [`Isometry2`], the robot's pose in the odometry frame, which may drift from the map's frame.
+    /// * `scan`: a slice of [`Point2`], the laser scan in the robot's frame.
+    ///
+    /// # Returns
+    /// Whether the filter was updated.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Update AMCL", skip_all, level = "info")
+    )]
+    pub fn update(&mut self, odometry: &Isometry2<T>, scan: &[Point2<T>]) -> bool {
+        if self.particles.is_empty() {
+            self.last_odometry = Some(*odometry);
+            return false;
+        }
+
+        if let Some(previous_odometry) = self.last_odometry {
+            let motion = previous_odometry.inverse() * odometry;
+            if motion.translation.vector.norm() < self.config.update_min_distance
+                && motion.rotation.angle().abs() < self.config.update_min_angle
+            {
+                return false;
+            }
+
+            let motion_model = self.config.motion_model;
+            for particle in self.particles.iter_mut() {
+                particle.pose = motion_model.sample(
+                    &particle.pose,
+                    &previous_odometry,
+                    odometry,
+                    &mut self.rng,
+                );
+            }
+        }
+        self.last_odometry = Some(*odometry);
+
+        self.apply_measurement(scan);
+        self.updates_since_resample += 1;
+        if self.updates_since_resample >= self.config.resample_interval.max(1) {
+            self.resample();
+            self.updates_since_resample = 0;
+        }
+        true
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Apply Likelihood Field Model", skip_all, level = "debug")
+    )]
+    fn apply_measurement(&mut self, scan: &[Point2<T>]) {
+        let step = scan.len().div_ceil(self.config.max_beams.max(1)).max(1);
+        let beams = scan
+            .iter()
+            .step_by(step)
+            .filter(|point| {
+                let range = point.coords.norm();
+                range.is_finite() && range < self.config.max_range
+            })
+            .collect::<Vec<_>>();
+
+        let two_sigma_squared =
+            (T::one() + T::one()) * self.config.sigma_hit * self.config.sigma_hit;
+        let random_likelihood = self.config.z_rand / self.config.max_range;
+        // The beams are independent, so the likelihood is their product, accumulated as log-likelihoods to avoid underflow
+        let log_likelihoods = self
+            .particles
+            .iter()
+            .map(|particle| {
+                beams.iter().fold(T::zero(), |acc, beam| {
+                    let distance = self.likelihood_field.distance(&(particle.pose * **beam));
+                    acc + (self.config.z_hit * (-(distance * distance) / two_sigma_squared).exp()
+                        + random_likelihood)
+                        .ln()
+                })
+            })
+            .collect::<Vec<_>>();
+        let max_log_likelihood = log_likelihoods
+            .iter()
+            .copied()
+            .reduce(|first, second| first.max(second))
+            .unwrap_or_else(T::zero);
+        for (particle, log_likelihood) in self.particles.iter_mut().zip(log_likelihoods) {
+            particle.weight *= (log_likelihood - max_log_likelihood).exp();
+        }
+
+        let total = self
+            .particles
+            .iter()
+            .fold(T::zero(), |acc, particle| acc + particle.weight);
+        let uniform_weight = T::one() / self.particles.len().as_();
+        for particle in self.particles.iter_mut() {
+            particle.weight = if total > T::zero() && total.is_finite() {
+                particle.weight / total
+            } else {
+                uniform_weight
+            };
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute KLD Particle Count", skip_all, level = "trace")
+    )]
+    fn kld_particle_count(&self, num_bins: usize) -> usize {
+        if num_bins <= 1 {
+            return self.config.min_particles;
+        }
+
+        let two = T::one() + T::one();
+        let degrees_of_freedom: T = (num_bins - 1).as_();
+        let ratio = two / (AsPrimitive::<T>::as_(9usize) * degrees_of_freedom);
+        let cube_root = T::one() - ratio + ratio.sqrt() * self.config.kld_z;
+        AsPrimitive::<usize>::as_(
+            (degrees_of_freedom / (two * self.config.kld_error)
+                * cube_root
+                * cube_root
+                * cube_root)
+                .ceil(),
+        )
+    }
+
+    /// Resamples the particles, the amount of particles is chosen with KLD-sampling,
+    /// drawing particles until their amount reaches the bound for the amount of histogram bins they occupy.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Resample AMCL Particles", skip_all, level = "debug")
+    )]
+    fn resample(&mut self) {
+        let weights = self
+            .particles
+            .iter()
+            .map(|particle| particle.weight)
+            .collect::<Vec<_>>();
+
+        // The candidates are drawn in a random order, so that the drawn prefix is itself a fair resampling
+        let mut candidates =
+            low_variance_resample(&weights, self.config.max_particles.max(1), &mut self.rng);
+        let mut bins = HashMap::new();
+        let mut required_particles = self.config.min_particles.max(1);
+        let mut num_particles = 0;
+        while num_particles < candidates.len() {
+            let swap_idx = self.rng.gen_range(num_particles..candidates.len());
+            candidates.swap(num_particles, swap_idx);
+
+            let pose = &self.particles[candidates[num_particles]].pose;
+            let coordinates = [
+                pose.translation.x,
+                pose.translation.y,
+                pose.rotation.angle(),
+            ];
+            let bin = [0, 1, 2].map(|axis| {
+                AsPrimitive::<isize>::as_(
+                    (coordinates[axis] / self.config.kld_bin_size[axis]).floor(),
+                )
+            });
+            if bins.insert(bin, ()).is_none() {
+                required_particles = required_particles.max(self.kld_particle_count(bins.len()));
+            }
+
+            num_particles += 1;
+            if num_particles >= required_particles {
+                break;
+            }
+        }
+
+        let weight = T::one() / num_particles.as_();
+        self.particles = candidates[..num_particles]
+            .iter()
+            .map(|&particle_idx| Particle {
+                pose: self.particles[particle_idx].pose,
+                weight,
+            })
+            .collect();
+    }
+
+    /// Extracts the weighted mean pose of the particles, and its covariance.
+    ///
+    /// # Returns
+    /// A [`PoseEstimate`], or [`None`] if the filter was not initialised.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Estimate AMCL Pose", skip_all, level = "info")
+    )]
+    pub fn estimate(&self) -> Option<PoseEstimate<T>> {
+        if self.particles.is_empty() {
+            return None;
+        }
+
+        let (total, position, sin, cos) = self.particles.iter().fold(
+            (T::zero(), Vector2::zeros(), T::zero(), T::zero()),
+            |(total, position, sin, cos), particle| {
+                let yaw = particle.pose.rotation.angle();
+                (
+                    total + particle.weight,
+                    position + particle.pose.translation.vector * particle.weight,
+                    sin + yaw.sin() * particle.weight,
+                    cos + yaw.cos() * particle.weight,
+                )
+            },
+        );
+        let mean_position = position / total;
+        let mean_yaw = sin.atan2(cos);
+
+        let covariance = self
+            .particles
+            .iter()
+            .fold(Matrix3::zeros(), |covariance, particle| {
+                let offset = Vector3::new(
+                    particle.pose.translation.x - mean_position.x,
+                    particle.pose.translation.y - mean_position.y,
+                    normalize_angle(particle.pose.rotation.angle() - mean_yaw),
+                );
+                covariance + offset * offset.transpose() * particle.weight
+            })
+            / total;
+
+        Some(PoseEstimate {
+            pose: Isometry2::new(mean_position, mean_yaw),
+            covariance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::occupancy_grid::OccupancyGridConfiguration;
+
+    // A 10 by 6 metre room, with a box in one corner, so that its symmetry is broken
+    fn room() -> OccupancyGrid<f64> {
+        let mut grid = OccupancyGrid::new(
+            OccupancyGridConfiguration::builder()
+                .with_resolution(0.1)
+                .build(),
+        );
+        for column in 0..100 {
+            for row in 0..60 {
+                let is_wall = column == 0 || row == 0 || column == 99 || row == 59;
+                let is_box = (70..80).contains(&column) && (40..50).contains(&row);
+                for _ in 0..3 {
+                    grid.update_cell([column, row], is_wall || is_box);
+                }
+            }
+        }
+        grid
+    }
+
+    fn simulate_scan(grid: &OccupancyGrid<f64>, pose: &Isometry2<f64>) -> Vec<Point2<f64>> {
+        (0..90)
+            .filter_map(|beam_idx| {
+                let angle = beam_idx as f64 * core::f64::consts::TAU / 90.0;
+                let direction = Vector2::new(angle.cos(), angle.sin());
+                (1..600).map(|step| step as f64 * 0.02).find_map(|range| {
+                    let point = pose * Point2::from(direction * range);
+                    (grid.cell_state(grid.world_to_cell(&point)) == CellState::Occupied)
+                        .then(|| Point2::from(direction * range))
+                })
+            })
+            .collect()
+    }
+
+    fn config() -> AmclConfiguration<f64> {
+        AmclConfiguration::builder()
+            .with_motion_model(crate::motion_model::OdometryMotionModel::DifferentialDrive(
+                crate::motion_model::OdometryNoise::uniform(0.05),
+            ))
+            .with_particle_limits(100, 1000)
+            .with_seed(1234)
+            .build()
+    }
+
+    fn trajectory() -> Vec<Isometry2<f64>> {
+        (0..12)
+            .map(|step| {
+                Isometry2::new(
+                    Vector2::new(2.0 + step as f64 * 0.4, 2.0),
+                    0.1 * step as f64,
+                )
+            })
+            .collect()
+    }
+
+    fn run(amcl: &mut Amcl<f64>, grid: &OccupancyGrid<f64>) {
+        // The odometry frame is offset from the map, only relative odometry motion is used
+        let odometry_offset = Isometry2::new(Vector2::new(-5.0, 3.0), 1.0);
+        for pose in trajectory() {
+            let scan = simulate_scan(grid, &pose);
+            assert!(amcl.update(&(odometry_offset * pose), &scan));
+        }
+    }
+
+    #[test]
+    fn test_tracking() {
+        let grid = room();
+        let mut amcl = Amcl::new(config(), &grid);
+        assert_eq!(amcl.estimate(), None);
+        assert!(!amcl.update(&Isometry2::identity(), &[]));
+
+        let initial_guess = Isometry2::new(Vector2::new(2.3, 1.8), 0.15);
+        amcl.initialize_gaussian(
+            &initial_guess,
+            &Matrix3::from_diagonal(&Vector3::new(0.1, 0.1, 0.05)),
+        );
+        assert_eq!(amcl.particles().len(), 1000);
+        run(&mut amcl, &grid);
+
+        let truth = trajectory().last().copied().unwrap();
+        let estimate = amcl.estimate().unwrap();
+        assert!(
+            (estimate.pose.translation.vector - truth.translation.vector).norm() < 0.1,
+            "{estimate:?}"
+        );
+        assert!(
+            estimate.pose.rotation.angle_to(&truth.rotation).abs() < 0.05,
+            "{estimate:?}"
+        );
+        assert!(estimate.covariance.trace() < 0.05, "{estimate:?}");
+        // The converged distribution occupies few bins, so the particle count shrinks
+        assert!(amcl.particles().len() < 1000);
+        let total = amcl
+            .particles()
+            .iter()
+            .map(|particle| particle.weight)
+            .sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // Standing still does not update the filter
+        let particles = amcl.particles().to_vec();
+        let odometry = Isometry2::new(Vector2::new(-5.0, 3.0), 1.0) * truth;
+        assert!(!amcl.update(&odometry, &simulate_scan(&grid, &truth)));
+        assert_eq!(amcl.particles(), particles.as_slice());
+    }
+
+    #[test]
+    fn test_determinism() {
+        let grid = room();
+        let mut first = Amcl::new(config(), &grid);
+        let mut second = Amcl::new(config(), &grid);
+        assert!(first.initialize_global());
+        assert!(second.initialize_global());
+        assert_eq!(first.particles(), second.particles());
+        assert!(first.particles().iter().all(|particle| grid
+            .cell_state(grid.world_to_cell(&particle.pose.translation.vector.into()))
+            == CellState::Free));
+
+        run(&mut first, &grid);
+        run(&mut second, &grid);
+        assert_eq!(first.particles(), second.particles());
+        assert_eq!(first.estimate(), second.estimate());
+    }
+
+    #[test]
+    fn test_kld_particle_count() {
+        let amcl = Amcl::new(config(), &room());
+        assert_eq!(amcl.kld_particle_count(1), 100);
+        // The bound grows roughly linearly with the amount of occupied bins
+        let few = amcl.kld_particle_count(10);
+        let many = amcl.kld_particle_count(100);
+        assert!(few > 100 && many > 5 * few, "{few} {many}");
+    }
+
+    #[test]
+    fn test_adaptive_resample() {
+        let mut amcl = Amcl::new(config(), &room());
+        let bin = |particle: &Particle<f64>| {
+            [
+                (particle.pose.translation.x / 0.5).floor() as isize,
+                (particle.pose.translation.y / 0.5).floor() as isize,
+                (particle.pose.rotation.angle() / 10.0f64.to_radians()).floor() as isize,
+            ]
+        };
+
+        // A concentrated distribution only needs the minimal amount of particles
+        amcl.initialize_gaussian(
+            &Isometry2::new(Vector2::new(2.25, 2.25), 0.05),
+            &Matrix3::from_diagonal(&Vector3::new(1e-6, 1e-6, 1e-6)),
+        );
+        amcl.resample();
+        assert_eq!(amcl.particles().len(), 100);
+
+        // Drawing stops as soon as the drawn particles satisfy the bound for the bins they occupy
+        amcl.initialize_gaussian(
+            &Isometry2::new(Vector2::new(5.25, 3.25), 5.0f64.to_radians()),
+            &Matrix3::from_diagonal(&Vector3::new(0.01, 0.01, 0.002)),
+        );
+        amcl.resample();
+        let num_bins = amcl
+            .particles()
+            .iter()
+            .map(|particle| (bin(particle), ()))
+            .collect::<HashMap<_, _>>()
+            .len();
+        let num_particles = amcl.particles().len();
+        assert!(
+            num_particles > 100 && num_particles < 1000,
+            "{num_particles} {num_bins}"
+        );
+        assert!(num_particles >= amcl.kld_particle_count(num_bins));
+        assert!(num_particles - 1 < amcl.kld_particle_count(num_bins).max(100));
+        assert!(amcl
+            .particles()
+            .iter()
+            .all(|particle| particle.weight == 1.0 / num_particles as f64));
+    }
+}
@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry2, Matrix3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::motion_model::{OdometryMotionModel, OdometryNoise};
+
+/// A single hypothesis of the robot's pose.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle<T: RealField> {
+    /// The hypothesised pose of the robot in the map's frame.
+    pub pose: Isometry2<T>,
+    /// The particle's normalised weight.
+    pub weight: T,
+}
+
+/// The pose estimate extracted from a particle set.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoseEstimate<T: RealField> {
+    /// The weighted mean pose, using the circular mean for the heading.
+    pub pose: Isometry2<T>,
+    /// The weighted covariance of `x`, `y` and the heading.
+    pub covariance: Matrix3<T>,
+}
+
+/// A struct specifying configuration options for the [`Amcl`](crate::amcl::Amcl) suite.
+#[derive(Clone, Debug)]
+pub struct AmclConfiguration<T> {
+    /// The motion model used to propagate particles between odometry readings.
+    pub(crate) motion_model: OdometryMotionModel<T>,
+    /// The minimal amount of particles after resampling.
+    pub(crate) min_particles: usize,
+    /// The maximal amount of particles, also the amount drawn at initialisation.
+    pub(crate) max_particles: usize,
+    /// The maximal KL-divergence between the particles and the true posterior.
+    pub(crate) kld_error: T,
+    /// The standard normal quantile of the desired confidence in the KL-divergence bound.
+    pub(crate) kld_z: T,
+    /// The sizes of the histogram bins used for KLD-sampling, along `x`, `y` and the heading.
+    pub(crate) kld_bin_size: [T; 3],
+    /// The weight of the Gaussian hit component of the sensor model.
+    pub(crate) z_hit: T,
+    /// The weight of the uniform random component of the sensor model.
+    pub(crate) z_rand: T,
+    /// The standard deviation of the Gaussian hit component of the sensor model.
+    pub(crate) sigma_hit: T,
+    /// The maximal amount of beams used from each scan, the scan is subsampled evenly.
+    pub(crate) max_beams: usize,
+    /// The sensor's maximum range, points at or beyond it are ignored.
+    pub(crate) max_range: T,
+    /// The maximal distance computed by the likelihood field.
+    pub(crate) likelihood_max_distance: T,
+    /// The filter is only updated after the robot has moved at least this far.
+    pub(crate) update_min_distance: T,
+    /// The filter is only updated after the robot has rotated at least this much.
+    pub(crate) update_min_angle: T,
+    /// The amount of filter updates between resamplings.
+    pub(crate) resample_interval: usize,
+    /// The seed of the filter's random number generator.
+    pub(crate) seed: u64,
+}
+
+impl<T: 'static + Copy> AmclConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values match those of ROS' AMCL, for a differential drive robot with a laser scanner.
+    ///
+    /// # Returns
+    /// A [`AmclConfigurationBuilder`].
+    pub fn builder() -> AmclConfigurationBuilder<T> {
+        AmclConfigurationBuilder {
+            _internal: AmclConfiguration {
+                motion_model: OdometryMotionModel::DifferentialDrive(OdometryNoise::uniform(
+                    0.2.as_(),
+                )),
+                min_particles: 100,
+                max_particles: 5000,
+                kld_error: 0.01.as_(),
+                kld_z: 2.326.as_(),
+                kld_bin_size: [0.5.as_(), 0.5.as_(), (10.0f32.to_radians()).as_()],
+                z_hit: 0.95.as_(),
+                z_rand: 0.05.as_(),
+                sigma_hit: 0.2.as_(),
+                max_beams: 30,
+                max_range: 12.0.as_(),
+                likelihood_max_distance: 2.0.as_(),
+                update_min_distance: 0.2.as_(),
+                update_min_angle: (30.0f32.to_radians()).as_(),
+                resample_interval: 1,
+                seed: 0,
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`AmclConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct AmclConfigurationBuilder<T> {
+    _internal: AmclConfiguration<T>,
+}
+
+impl<T: Copy> AmclConfigurationBuilder<T> {
+    /// The motion model used to propagate particles between odometry readings.
+    ///
+    /// # Arguments
+    /// * `motion_model`: An [`OdometryMotionModel`] matching the robot's drive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_motion_model(&self, motion_model: OdometryMotionModel<T>) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                motion_model,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The limits of the adaptive particle count.
+    ///
+    /// # Arguments
+    /// * `min_particles`: The minimal amount of particles.
+    /// * `max_particles`: The maximal amount of particles, also drawn at initialisation.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_particle_limits(&self, min_particles: usize, max_particles: usize) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                min_particles,
+                max_particles,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The parameters of KLD-sampling, which adapts the amount of particles to the spread of the distribution.
+    ///
+    /// # Arguments
+    /// * `kld_error`: The maximal KL-divergence between the particles and the true posterior.
+    /// * `kld_z`: The standard normal quantile of the desired confidence, `2.326` for 99%.
+    /// * `kld_bin_size`: The histogram's bin sizes along `x`, `y` and the heading.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_kld(&self, kld_error: T, kld_z: T, kld_bin_size: [T; 3]) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                kld_error,
+                kld_z,
+                kld_bin_size,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The likelihood field sensor model's mixture.
+    ///
+    /// # Arguments
+    /// * `z_hit`: The weight of the Gaussian component, around the nearest obstacle.
+    /// * `z_rand`: The weight of the uniform component, accounting for unexplained measurements.
+    /// * `sigma_hit`: The standard deviation of the Gaussian component.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_sensor_model(&self, z_hit: T, z_rand: T, sigma_hit: T) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                z_hit,
+                z_rand,
+                sigma_hit,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The beams used from each scan.
+    ///
+    /// # Arguments
+    /// * `max_beams`: The maximal amount of beams, fewer beams are faster but less discriminative.
+    /// * `max_range`: The sensor's maximum range, points at or beyond it are ignored.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_beams(&self, max_beams: usize, max_range: T) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                max_beams,
+                max_range,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The maximal distance computed by the likelihood field, further points are all equally unlikely.
+    ///
+    /// # Arguments
+    /// * `likelihood_max_distance`: The maximal obstacle distance.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_likelihood_max_distance(&self, likelihood_max_distance: T) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                likelihood_max_distance,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The motion required before the filter is updated, avoiding overconfidence from repeated scans of a standing robot.
+    ///
+    /// # Arguments
+    /// * `update_min_distance`: The minimal translation.
+    /// * `update_min_angle`: The minimal rotation, either is enough for an update.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_update_thresholds(&self, update_min_distance: T, update_min_angle: T) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                update_min_distance,
+                update_min_angle,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The amount of filter updates between resamplings.
+    ///
+    /// # Arguments
+    /// * `resample_interval`: The resampling interval, `1` resamples after every update.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_resample_interval(&self, resample_interval: usize) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                resample_interval,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The seed of the filter's random number generator, identical seeds and inputs produce identical results.
+    ///
+    /// # Arguments
+    /// * `seed`: The seed.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_seed(&self, seed: u64) -> Self {
+        Self {
+            _internal: AmclConfiguration {
+                seed,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`AmclConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`AmclConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> AmclConfiguration<T> {
+        self._internal.clone()
+    }
+}
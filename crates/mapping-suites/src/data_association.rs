@@ -0,0 +1,378 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{DMatrix, RealField};
+use num_traits::AsPrimitive;
+
+use crate::Vec;
+
+/// The method used to match landmark observations with the landmarks already in the map.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataAssociation<T> {
+    /// Observations carry the identity of their landmark, observations without one are discarded.
+    Known,
+    /// Each observation is matched with its nearest landmark by Mahalanobis distance, see [`nearest_neighbour`].
+    NearestNeighbour {
+        /// The standard normal quantile of the gate's confidence, `2.326` for 99%.
+        gate_z: T,
+        /// The standard normal quantile of the new landmark gate's confidence, which should be wider than the association gate.
+        new_landmark_z: T,
+    },
+    /// All observations are matched together, finding the largest jointly compatible set of pairings,
+    /// see [`joint_compatibility_branch_and_bound`].
+    JointCompatibility {
+        /// The standard normal quantile of the gate's confidence, `2.326` for 99%.
+        gate_z: T,
+        /// The standard normal quantile of the new landmark gate's confidence, which should be wider than the association gate.
+        new_landmark_z: T,
+    },
+}
+
+/// The result of associating a single observation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Association {
+    /// The observation was matched with the landmark at this index.
+    Landmark(usize),
+    /// The observation is outside the new landmark gate of all landmarks, and should be added to the map as a new one.
+    New,
+    /// The observation was left unmatched, but is inside the new landmark gate of some landmark, so adding it would likely duplicate that landmark.
+    Discarded,
+}
+
+/// Approximates a quantile of the chi-square distribution, using the Wilson-Hilferty transformation.
+///
+/// # Arguments
+/// * `degrees_of_freedom`: the distribution's degrees of freedom, the dimension of the gated vector.
+/// * `z`: the standard normal quantile of the same confidence, `2.326` for 99%.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// The squared Mahalanobis distance below which a vector of this dimension falls with the given confidence.
+pub fn chi_square_quantile<T>(degrees_of_freedom: usize, z: T) -> T
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    let degrees_of_freedom: T = degrees_of_freedom.as_();
+    let spread = (2.0.as_() / (9.0.as_() * degrees_of_freedom)).sqrt();
+    let base = (T::one() - spread * spread + z * spread).max(T::zero());
+    degrees_of_freedom * base * base * base
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Classify Unmatched Observations", skip_all, level = "trace")
+)]
+fn classify_unmatched<T: Copy + RealField>(
+    distances: &DMatrix<T>,
+    matches: &[Option<usize>],
+    new_landmark_gate: T,
+) -> Vec<Association> {
+    matches
+        .iter()
+        .enumerate()
+        .map(|(observation_idx, landmark_idx)| match landmark_idx {
+            Some(landmark_idx) => Association::Landmark(*landmark_idx),
+            None if distances
+                .row(observation_idx)
+                .iter()
+                .any(|distance| *distance <= new_landmark_gate) =>
+            {
+                Association::Discarded
+            }
+            None => Association::New,
+        })
+        .collect()
+}
+
+/// Matches observations with landmarks greedily, pairing the closest observation and landmark first,
+/// so that each landmark is matched with at most one observation.
+///
+/// # Arguments
+/// * `distances`: a [`DMatrix`] of the squared Mahalanobis distances of two-dimensional observations,
+///   with a row for each observation, and a column for each landmark.
+/// * `gate_z`: the standard normal quantile of the gate's confidence, pairings outside the gate are never made.
+/// * `new_landmark_z`: the standard normal quantile of the new landmark gate's confidence,
+///   unmatched observations outside it for all landmarks are considered new landmarks.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A [`Vec`] of the [`Association`] of each observation.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Nearest Neighbour Association", skip_all, level = "debug")
+)]
+pub fn nearest_neighbour<T>(
+    distances: &DMatrix<T>,
+    gate_z: T,
+    new_landmark_z: T,
+) -> Vec<Association>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    let gate = chi_square_quantile(2, gate_z);
+    let mut candidates = distances
+        .row_iter()
+        .enumerate()
+        .flat_map(|(observation_idx, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, distance)| **distance <= gate)
+                .map(|(landmark_idx, distance)| (*distance, observation_idx, landmark_idx))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|first, second| first.0.partial_cmp(&second.0).unwrap());
+
+    let mut matches = Vec::from_iter(core::iter::repeat_n(None, distances.nrows()));
+    let mut used = Vec::from_iter(core::iter::repeat_n(false, distances.ncols()));
+    for (_, observation_idx, landmark_idx) in candidates {
+        if matches[observation_idx].is_none() && !used[landmark_idx] {
+            matches[observation_idx] = Some(landmark_idx);
+            used[landmark_idx] = true;
+        }
+    }
+
+    classify_unmatched(distances, &matches, chi_square_quantile(2, new_landmark_z))
+}
+
+struct JointCompatibilitySearch<'a, T, F> {
+    distances: &'a DMatrix<T>,
+    gate_z: T,
+    individual_gate: T,
+    joint_distance: F,
+    pairings: Vec<(usize, usize)>,
+    used: Vec<bool>,
+    best_pairings: Vec<(usize, usize)>,
+    best_distance: T,
+}
+
+impl<T, F> JointCompatibilitySearch<'_, T, F>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+    F: FnMut(&[(usize, usize)]) -> T,
+{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Search Joint Compatibility Hypotheses", skip_all, level = "trace")
+    )]
+    fn search(&mut self, observation_idx: usize, distance: T) {
+        let num_observations = self.distances.nrows();
+        if observation_idx == num_observations {
+            if self.pairings.len() > self.best_pairings.len()
+                || (self.pairings.len() == self.best_pairings.len()
+                    && distance < self.best_distance)
+            {
+                self.best_pairings.clone_from(&self.pairings);
+                self.best_distance = distance;
+            }
+            return;
+        }
+
+        // Try the individually compatible landmarks from nearest to farthest, so good hypotheses are found early
+        let mut candidates = self
+            .distances
+            .row(observation_idx)
+            .iter()
+            .enumerate()
+            .filter(|(landmark_idx, distance)| {
+                !self.used[*landmark_idx] && **distance <= self.individual_gate
+            })
+            .map(|(landmark_idx, distance)| (*distance, landmark_idx))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|first, second| first.0.partial_cmp(&second.0).unwrap());
+
+        for (_, landmark_idx) in candidates {
+            self.pairings.push((observation_idx, landmark_idx));
+            let joint_distance = (self.joint_distance)(&self.pairings);
+            if joint_distance <= chi_square_quantile(2 * self.pairings.len(), self.gate_z) {
+                self.used[landmark_idx] = true;
+                self.search(observation_idx + 1, joint_distance);
+                self.used[landmark_idx] = false;
+            }
+            self.pairings.pop();
+        }
+
+        // Leaving this observation unmatched is only worth exploring if it can still beat the best hypothesis
+        if self.pairings.len() + num_observations - observation_idx - 1 > self.best_pairings.len() {
+            self.search(observation_idx + 1, distance);
+        }
+    }
+}
+
+/// Matches observations with landmarks using Joint Compatibility Branch and Bound,
+/// searching for the largest set of pairings whose innovations are jointly within the gate,
+/// preferring the set with the lowest joint distance when several are equally large.
+/// This rejects pairings that are individually plausible but inconsistent with the rest of the observations,
+/// at a cost that grows exponentially with the amount of ambiguous observations.
+///
+/// # Arguments
+/// * `distances`: a [`DMatrix`] of the squared Mahalanobis distances of two-dimensional observations,
+///   with a row for each observation, and a column for each landmark.
+/// * `gate_z`: the standard normal quantile of the gate's confidence, used for both the individual and the joint gates.
+/// * `new_landmark_z`: the standard normal quantile of the new landmark gate's confidence,
+///   unmatched observations outside it for all landmarks are considered new landmarks.
+/// * `joint_distance`: a function returning the squared Mahalanobis distance of the stacked innovations
+///   of a set of `(observation, landmark)` pairings.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `F`: A function computing the joint distance of a set of pairings.
+///
+/// # Returns
+/// A [`Vec`] of the [`Association`] of each observation.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Joint Compatibility Association", skip_all, level = "debug")
+)]
+pub fn joint_compatibility_branch_and_bound<T, F>(
+    distances: &DMatrix<T>,
+    gate_z: T,
+    new_landmark_z: T,
+    joint_distance: F,
+) -> Vec<Association>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+    F: FnMut(&[(usize, usize)]) -> T,
+{
+    let individual_gate = chi_square_quantile(2, gate_z);
+    let mut search = JointCompatibilitySearch {
+        distances,
+        gate_z,
+        individual_gate,
+        joint_distance,
+        pairings: Vec::with_capacity(distances.nrows()),
+        used: Vec::from_iter(core::iter::repeat_n(false, distances.ncols())),
+        best_pairings: Vec::new(),
+        best_distance: T::zero(),
+    };
+    search.search(0, T::zero());
+
+    let mut matches = Vec::from_iter(core::iter::repeat_n(None, distances.nrows()));
+    for (observation_idx, landmark_idx) in search.best_pairings {
+        matches[observation_idx] = Some(landmark_idx);
+    }
+    classify_unmatched(distances, &matches, chi_square_quantile(2, new_landmark_z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chi_square_quantile() {
+        // Tabulated 99% quantiles
+        for (degrees_of_freedom, expected) in [(2, 9.210), (4, 13.277), (10, 23.209)] {
+            let quantile = chi_square_quantile(degrees_of_freedom, 2.326f64);
+            assert!(
+                (quantile - expected).abs() < 0.1,
+                "{quantile} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbour() {
+        let distances = DMatrix::from_row_slice(
+            5,
+            3,
+            &[
+                1.0, 20.0, 30.0, //
+                0.5, 20.0, 30.0, //
+                20.0, 20.0, 2.0, //
+                40.0, 40.0, 40.0, //
+                40.0, 12.0, 40.0,
+            ],
+        );
+
+        // Both of the first observations are closest to the first landmark, and the nearer one wins,
+        // the last observation is outside the association gate, but too close to a landmark to be a new one
+        assert_eq!(
+            nearest_neighbour(&distances, 2.326, 3.09),
+            [
+                Association::Discarded,
+                Association::Landmark(0),
+                Association::Landmark(2),
+                Association::New,
+                Association::Discarded,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_joint_compatibility() {
+        // Two observations are each closest to the wrong landmark, but only the correct pairings are consistent together,
+        // modeled here by a joint distance that penalises the mixed hypotheses
+        let distances = DMatrix::from_row_slice(2, 2, &[1.0, 0.5, 0.5, 1.0]);
+        let joint_distance = |pairings: &[(usize, usize)]| {
+            pairings
+                .iter()
+                .map(|(observation_idx, landmark_idx)| {
+                    if observation_idx == landmark_idx {
+                        distances[(*observation_idx, *landmark_idx)]
+                    } else {
+                        100.0
+                    }
+                })
+                .sum::<f64>()
+        };
+        assert_eq!(
+            nearest_neighbour(&distances, 2.326, 3.09),
+            [Association::Landmark(1), Association::Landmark(0)]
+        );
+        assert_eq!(
+            joint_compatibility_branch_and_bound(&distances, 2.326, 3.09, joint_distance),
+            [Association::Landmark(0), Association::Landmark(1)]
+        );
+
+        // With independent innovations, the largest hypothesis with the lowest joint distance is chosen
+        let distances = DMatrix::from_row_slice(3, 2, &[1.0, 30.0, 2.0, 3.0, 30.0, 30.0]);
+        assert_eq!(
+            joint_compatibility_branch_and_bound(&distances, 2.326, 3.09, |pairings| {
+                pairings
+                    .iter()
+                    .map(|pairing| distances[*pairing])
+                    .sum::<f64>()
+            }),
+            [
+                Association::Landmark(0),
+                Association::Landmark(1),
+                Association::New,
+            ]
+        );
+    }
+}
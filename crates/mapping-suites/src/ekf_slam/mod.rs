@@ -0,0 +1,686 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{
+    DMatrix, DVector, Isometry2, Matrix2, Matrix3, Point2, RealField, SMatrix, Vector2,
+};
+use num_traits::AsPrimitive;
+
+use crate::{
+    data_association::{
+        joint_compatibility_branch_and_bound, nearest_neighbour, Association, DataAssociation,
+    },
+    landmark::{initialize_landmark, predict_observation, Landmark, RangeBearingObservation},
+    sampling::normalize_angle,
+    Vec,
+};
+
+pub use types::{EkfSlamConfiguration, EkfSlamConfigurationBuilder};
+
+mod types;
+
+/// The amount of state variables of the robot's pose, `x`, `y` and the heading.
+const POSE_DIMENSION: usize = 3;
+
+/// A landmark's predicted observation, linearised around the current state.
+struct LinearisedObservation<T: RealField> {
+    observation: Vector2<T>,
+    /// The Jacobian of the observation, with respect to the state variables at `indices`,
+    /// it is zero with respect to all other state variables.
+    jacobian: SMatrix<T, 2, 5>,
+    indices: [usize; 5],
+}
+
+/// An extended Kalman filter SLAM suite, jointly estimating a planar robot's pose and the positions of point landmarks
+/// observed by a range-bearing sensor.
+///
+/// The state holds the robot's `x`, `y` and heading, followed by the `x` and `y` of each landmark,
+/// with a full covariance matrix, so the cost of an update grows quadratically with the amount of landmarks.
+/// New landmarks are added to the state as they are observed, see [`DataAssociation`] for how observations are matched with existing ones.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct EkfSlam<T: RealField> {
+    config: EkfSlamConfiguration<T>,
+    mean: DVector<T>,
+    covariance: DMatrix<T>,
+    landmark_ids: Vec<Option<usize>>,
+    observation_counts: Vec<usize>,
+    last_odometry: Option<Isometry2<T>>,
+}
+
+impl<T> EkfSlam<T>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs a filter without landmarks, whose initial pose is certain, as it defines the map's frame.
+    ///
+    /// # Arguments
+    /// * `config`: an [`EkfSlamConfiguration`], specifying the filter's parameters.
+    /// * `initial_pose`: an [`Isometry2`], the robot's pose in the map's frame.
+    ///
+    /// # Returns
+    /// A new [`EkfSlam`].
+    pub fn new(config: EkfSlamConfiguration<T>, initial_pose: &Isometry2<T>) -> Self {
+        Self {
+            config,
+            mean: DVector::from_column_slice(&[
+                initial_pose.translation.x,
+                initial_pose.translation.y,
+                initial_pose.rotation.angle(),
+            ]),
+            covariance: DMatrix::zeros(POSE_DIMENSION, POSE_DIMENSION),
+            landmark_ids: Vec::new(),
+            observation_counts: Vec::new(),
+            last_odometry: None,
+        }
+    }
+
+    /// Returns the configuration the filter was constructed with.
+    pub fn config(&self) -> &EkfSlamConfiguration<T> {
+        &self.config
+    }
+
+    /// Returns the mean of the robot's pose.
+    pub fn pose(&self) -> Isometry2<T> {
+        Isometry2::new(Vector2::new(self.mean[0], self.mean[1]), self.mean[2])
+    }
+
+    /// Returns the covariance of the robot's `x`, `y` and heading.
+    pub fn pose_covariance(&self) -> Matrix3<T> {
+        self.covariance
+            .fixed_view::<POSE_DIMENSION, POSE_DIMENSION>(0, 0)
+            .into_owned()
+    }
+
+    /// Returns the mean of the full state, the robot's pose followed by the landmarks' positions.
+    pub fn mean(&self) -> &DVector<T> {
+        &self.mean
+    }
+
+    /// Returns the covariance of the full state.
+    pub fn covariance(&self) -> &DMatrix<T> {
+        &self.covariance
+    }
+
+    /// Returns the amount of landmarks in the state.
+    pub fn num_landmarks(&self) -> usize {
+        self.landmark_ids.len()
+    }
+
+    /// Returns a landmark in the state.
+    ///
+    /// # Arguments
+    /// * `landmark_idx`: the landmark's index, in the order the landmarks were added, adjusted for removed landmarks.
+    ///
+    /// # Returns
+    /// A [`Landmark`], or [`None`] if the index is out of bounds.
+    pub fn landmark(&self, landmark_idx: usize) -> Option<Landmark<T>> {
+        (landmark_idx < self.num_landmarks()).then(|| {
+            let offset = Self::landmark_offset(landmark_idx);
+            Landmark {
+                id: self.landmark_ids[landmark_idx],
+                position: Point2::new(self.mean[offset], self.mean[offset + 1]),
+                covariance: self
+                    .covariance
+                    .fixed_view::<2, 2>(offset, offset)
+                    .into_owned(),
+                observations: self.observation_counts[landmark_idx],
+            }
+        })
+    }
+
+    /// Returns all landmarks in the state.
+    pub fn landmarks(&self) -> Vec<Landmark<T>> {
+        (0..self.num_landmarks())
+            .filter_map(|landmark_idx| self.landmark(landmark_idx))
+            .collect()
+    }
+
+    /// Removes a landmark from the state, marginalising it out, e.g. when it was only observed a few times and is likely spurious.
+    /// The indices of all later landmarks are decremented.
+    ///
+    /// # Arguments
+    /// * `landmark_idx`: the landmark's index.
+    ///
+    /// # Returns
+    /// The removed [`Landmark`], or [`None`] if the index is out of bounds.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Remove EKF-SLAM Landmark", skip_all, level = "debug")
+    )]
+    pub fn remove_landmark(&mut self, landmark_idx: usize) -> Option<Landmark<T>> {
+        let landmark = self.landmark(landmark_idx)?;
+        let offset = Self::landmark_offset(landmark_idx);
+        self.mean = self.mean.clone().remove_rows(offset, 2);
+        self.covariance = self
+            .covariance
+            .clone()
+            .remove_rows(offset, 2)
+            .remove_columns(offset, 2);
+        self.landmark_ids.remove(landmark_idx);
+        self.observation_counts.remove(landmark_idx);
+        Some(landmark)
+    }
+
+    /// Propagates the robot's pose by the motion between the previous odometry reading and this one,
+    /// the first reading only serves as a reference.
+    ///
+    /// # Arguments
+    /// * `odometry`: an [`Isometry2`], the robot's odometry reading.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Predict EKF-SLAM", skip_all, level = "info")
+    )]
+    pub fn predict(&mut self, odometry: &Isometry2<T>) {
+        let Some(previous_odometry) = self.last_odometry.replace(*odometry) else {
+            return;
+        };
+
+        let prediction =
+            self.config
+                .motion_model
+                .predict(&self.pose(), &previous_odometry, odometry);
+        self.mean[0] = prediction.pose.translation.x;
+        self.mean[1] = prediction.pose.translation.y;
+        self.mean[2] = prediction.pose.rotation.angle();
+
+        // Landmarks are static, so only the pose's rows and columns are transformed
+        let rows = prediction.jacobian * self.covariance.fixed_rows::<POSE_DIMENSION>(0);
+        self.covariance
+            .fixed_rows_mut::<POSE_DIMENSION>(0)
+            .copy_from(&rows);
+        let columns =
+            self.covariance.fixed_columns::<POSE_DIMENSION>(0) * prediction.jacobian.transpose();
+        self.covariance
+            .fixed_columns_mut::<POSE_DIMENSION>(0)
+            .copy_from(&columns);
+        let mut pose_covariance = self
+            .covariance
+            .fixed_view_mut::<POSE_DIMENSION, POSE_DIMENSION>(0, 0);
+        pose_covariance += prediction.covariance;
+    }
+
+    /// Corrects the state with a set of landmark observations, taken at the current pose.
+    /// Observations are first matched with existing landmarks, which are corrected, and unmatched observations
+    /// that are not compatible with any landmark are added to the state as new landmarks.
+    /// Observations at a zero or non-finite range are discarded, as are corrections of a landmark the robot is on top of.
+    ///
+    /// # Arguments
+    /// * `observations`: a slice of [`RangeBearingObservation`], the landmarks observed from the current pose.
+    ///
+    /// # Returns
+    /// A [`Vec`] of the index of the landmark each observation was used for, or [`None`] if it was discarded.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Update EKF-SLAM", skip_all, level = "info")
+    )]
+    pub fn update(&mut self, observations: &[RangeBearingObservation<T>]) -> Vec<Option<usize>> {
+        let mut associations = self.associate(observations);
+        for (association, observation) in associations.iter_mut().zip(observations) {
+            if !observation.is_valid() {
+                *association = Association::Discarded;
+            }
+        }
+
+        let mut landmark_indices = associations
+            .iter()
+            .zip(observations)
+            .map(|(association, observation)| match association {
+                Association::Landmark(landmark_idx) => self
+                    .correct(observation, *landmark_idx)
+                    .then_some(*landmark_idx),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // New landmarks are added after all corrections, so they are initialised from the corrected pose
+        for (observation_idx, (association, observation)) in
+            associations.iter().zip(observations).enumerate()
+        {
+            // With known identities, a landmark might be observed more than once in a single update
+            let duplicate = observation.id.is_some()
+                && self.landmark_ids.contains(&observation.id)
+                && matches!(self.config.data_association, DataAssociation::Known);
+            if *association == Association::New && !duplicate {
+                landmark_indices[observation_idx] = Some(self.add_landmark(observation));
+            }
+        }
+
+        landmark_indices
+    }
+
+    #[inline]
+    fn landmark_offset(landmark_idx: usize) -> usize {
+        POSE_DIMENSION + 2 * landmark_idx
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute EKF-SLAM Observation Covariance", skip_all, level = "trace")
+    )]
+    fn observation_covariance(&self) -> Matrix2<T> {
+        Matrix2::from_diagonal(&Vector2::new(
+            self.config.range_noise * self.config.range_noise,
+            self.config.bearing_noise * self.config.bearing_noise,
+        ))
+    }
+
+    /// Linearises the observation of a landmark around the current state,
+    /// or returns [`None`] if the robot is on top of the landmark, where the observation's Jacobians are undefined.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Linearise EKF-SLAM Observation", skip_all, level = "trace")
+    )]
+    fn linearise(&self, landmark_idx: usize) -> Option<LinearisedObservation<T>> {
+        let offset = Self::landmark_offset(landmark_idx);
+        let prediction = predict_observation(
+            &self.pose(),
+            &Point2::new(self.mean[offset], self.mean[offset + 1]),
+        );
+        if prediction.observation.x <= T::zero() || !prediction.observation.x.is_finite() {
+            return None;
+        }
+
+        let mut jacobian = SMatrix::<T, 2, 5>::zeros();
+        jacobian
+            .fixed_view_mut::<2, POSE_DIMENSION>(0, 0)
+            .copy_from(&prediction.pose_jacobian);
+        jacobian
+            .fixed_view_mut::<2, 2>(0, POSE_DIMENSION)
+            .copy_from(&prediction.landmark_jacobian);
+        Some(LinearisedObservation {
+            observation: prediction.observation,
+            jacobian,
+            indices: [0, 1, 2, offset, offset + 1],
+        })
+    }
+
+    /// Computes the covariance between two predicted observations, `H₁ P H₂ᵀ`, using only the non-zero columns of the Jacobians.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute EKF-SLAM Cross Covariance", skip_all, level = "trace")
+    )]
+    fn cross_covariance(
+        &self,
+        first: &LinearisedObservation<T>,
+        second: &LinearisedObservation<T>,
+    ) -> Matrix2<T> {
+        let covariance = SMatrix::<T, 5, 5>::from_fn(|row, column| {
+            self.covariance[(first.indices[row], second.indices[column])]
+        });
+        first.jacobian * covariance * second.jacobian.transpose()
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Associate EKF-SLAM Observations", skip_all, level = "debug")
+    )]
+    fn associate(&self, observations: &[RangeBearingObservation<T>]) -> Vec<Association> {
+        if let DataAssociation::Known = self.config.data_association {
+            return observations
+                .iter()
+                .map(|observation| match observation.id {
+                    Some(id) => self
+                        .landmark_ids
+                        .iter()
+                        .position(|landmark_id| *landmark_id == Some(id))
+                        .map_or(Association::New, Association::Landmark),
+                    None => Association::Discarded,
+                })
+                .collect();
+        }
+
+        let linearised = (0..self.num_landmarks())
+            .map(|landmark_idx| self.linearise(landmark_idx))
+            .collect::<Vec<_>>();
+        let inverse_covariances = linearised
+            .iter()
+            .map(|landmark| {
+                landmark.as_ref().and_then(|landmark| {
+                    (self.cross_covariance(landmark, landmark) + self.observation_covariance())
+                        .try_inverse()
+                })
+            })
+            .collect::<Vec<_>>();
+        let distances = DMatrix::from_fn(
+            observations.len(),
+            linearised.len(),
+            |observation_idx, landmark_idx| {
+                let observation = &observations[observation_idx];
+                match (&linearised[landmark_idx], inverse_covariances[landmark_idx]) {
+                    (Some(landmark), Some(inverse)) if observation.is_valid() => {
+                        let innovation = observation.innovation(&landmark.observation);
+                        innovation.dot(&(inverse * innovation))
+                    }
+                    _ => T::max_value().unwrap(),
+                }
+            },
+        );
+
+        match self.config.data_association {
+            DataAssociation::NearestNeighbour {
+                gate_z,
+                new_landmark_z,
+            } => nearest_neighbour(&distances, gate_z, new_landmark_z),
+            DataAssociation::JointCompatibility {
+                gate_z,
+                new_landmark_z,
+            } => joint_compatibility_branch_and_bound(
+                &distances,
+                gate_z,
+                new_landmark_z,
+                |pairings| self.joint_distance(observations, &linearised, pairings),
+            ),
+            DataAssociation::Known => unreachable!(),
+        }
+    }
+
+    /// Computes the squared Mahalanobis distance of the stacked innovations of a set of pairings,
+    /// which are correlated through the robot's pose and the landmarks' shared history.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute EKF-SLAM Joint Distance", skip_all, level = "trace")
+    )]
+    fn joint_distance(
+        &self,
+        observations: &[RangeBearingObservation<T>],
+        linearised: &[Option<LinearisedObservation<T>>],
+        pairings: &[(usize, usize)],
+    ) -> T {
+        let Some(linearised) = pairings
+            .iter()
+            .map(|(_, landmark_idx)| linearised[*landmark_idx].as_ref())
+            .collect::<Option<Vec<_>>>()
+        else {
+            return T::max_value().unwrap();
+        };
+
+        let mut innovation = DVector::zeros(2 * pairings.len());
+        let mut covariance = DMatrix::zeros(2 * pairings.len(), 2 * pairings.len());
+        for (first_idx, (observation_idx, _)) in pairings.iter().enumerate() {
+            innovation.fixed_rows_mut::<2>(2 * first_idx).copy_from(
+                &observations[*observation_idx].innovation(&linearised[first_idx].observation),
+            );
+            for second_idx in 0..pairings.len() {
+                let mut block =
+                    self.cross_covariance(linearised[first_idx], linearised[second_idx]);
+                if first_idx == second_idx {
+                    block += self.observation_covariance();
+                }
+                covariance
+                    .fixed_view_mut::<2, 2>(2 * first_idx, 2 * second_idx)
+                    .copy_from(&block);
+            }
+        }
+
+        covariance
+            .cholesky()
+            .map_or(T::max_value().unwrap(), |cholesky| {
+                innovation.dot(&cholesky.solve(&innovation))
+            })
+    }
+
+    /// Applies the Kalman filter correction for a single observation of an existing landmark.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Correct EKF-SLAM Landmark", skip_all, level = "debug")
+    )]
+    fn correct(&mut self, observation: &RangeBearingObservation<T>, landmark_idx: usize) -> bool {
+        if !observation.is_valid() {
+            return false;
+        }
+        let Some(linearised) = self.linearise(landmark_idx) else {
+            return false;
+        };
+        let innovation = observation.innovation(&linearised.observation);
+        let Some(inverse_covariance) = (self.cross_covariance(&linearised, &linearised)
+            + self.observation_covariance())
+        .try_inverse() else {
+            return false;
+        };
+
+        // P Hᵀ, only the columns of the pose and the landmark contribute
+        let mut covariance_jacobian = DMatrix::zeros(self.mean.len(), 2);
+        for (column_idx, state_idx) in linearised.indices.iter().enumerate() {
+            for row_idx in 0..2 {
+                covariance_jacobian.column_mut(row_idx).axpy(
+                    linearised.jacobian[(row_idx, column_idx)],
+                    &self.covariance.column(*state_idx),
+                    T::one(),
+                );
+            }
+        }
+
+        let gain = &covariance_jacobian * inverse_covariance;
+        self.mean += &gain * innovation;
+        self.mean[2] = normalize_angle(self.mean[2]);
+        self.covariance -= &gain * covariance_jacobian.transpose();
+        // Keep the covariance symmetric despite rounding errors
+        self.covariance = (&self.covariance + self.covariance.transpose()) * 0.5.as_();
+
+        self.observation_counts[landmark_idx] += 1;
+        true
+    }
+
+    /// Augments the state with a newly observed landmark.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Add EKF-SLAM Landmark", skip_all, level = "debug")
+    )]
+    fn add_landmark(&mut self, observation: &RangeBearingObservation<T>) -> usize {
+        let (position, pose_jacobian, observation_jacobian) =
+            initialize_landmark(&self.pose(), observation);
+        let cross_covariance = pose_jacobian * self.covariance.fixed_rows::<POSE_DIMENSION>(0);
+        let landmark_covariance =
+            pose_jacobian * self.pose_covariance() * pose_jacobian.transpose()
+                + observation_jacobian
+                    * self.observation_covariance()
+                    * observation_jacobian.transpose();
+
+        let offset = self.mean.len();
+        self.mean.resize_vertically_mut(offset + 2, T::zero());
+        self.mean[offset] = position.x;
+        self.mean[offset + 1] = position.y;
+        self.covariance
+            .resize_mut(offset + 2, offset + 2, T::zero());
+        self.covariance
+            .view_mut((offset, 0), (2, offset))
+            .copy_from(&cross_covariance);
+        self.covariance
+            .view_mut((0, offset), (offset, 2))
+            .copy_from(&cross_covariance.transpose());
+        self.covariance
+            .fixed_view_mut::<2, 2>(offset, offset)
+            .copy_from(&landmark_covariance);
+
+        self.landmark_ids.push(observation.id);
+        self.observation_counts.push(1);
+        self.landmark_ids.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::PI;
+
+    use super::*;
+    use crate::{
+        landmark::simulation::{assert_map, initial_pose, run},
+        motion_model::{OdometryMotionModel, OdometryNoise},
+    };
+
+    fn config(data_association: DataAssociation<f64>) -> EkfSlamConfiguration<f64> {
+        EkfSlamConfiguration::builder()
+            .with_motion_model(OdometryMotionModel::DifferentialDrive(
+                OdometryNoise::uniform(0.01),
+            ))
+            .with_observation_noise(0.05, 0.01)
+            .with_data_association(data_association)
+            .build()
+    }
+
+    fn run_ekf_slam(ekf_slam: &mut EkfSlam<f64>, with_ids: bool) -> Isometry2<f64> {
+        run(with_ids, |step| {
+            ekf_slam.predict(&step.odometry);
+            ekf_slam.update(&step.observations);
+        })
+    }
+
+    fn assert_ekf_slam_map(ekf_slam: &EkfSlam<f64>, true_pose: &Isometry2<f64>) {
+        assert_map(
+            &ekf_slam.pose(),
+            &ekf_slam.landmarks(),
+            true_pose,
+            [0.1, 0.02, 0.15],
+        );
+        assert!(ekf_slam
+            .landmarks()
+            .iter()
+            .all(|landmark| landmark.covariance.trace() < 0.05));
+    }
+
+    #[test]
+    fn test_known_association() {
+        let mut ekf_slam = EkfSlam::new(config(DataAssociation::Known), &initial_pose());
+        let true_pose = run_ekf_slam(&mut ekf_slam, true);
+        assert_ekf_slam_map(&ekf_slam, &true_pose);
+
+        let mut ids = ekf_slam
+            .landmarks()
+            .iter()
+            .filter_map(|landmark| landmark.id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, (0..8).collect::<Vec<_>>());
+
+        // Observations without an identity cannot be associated
+        let observation = RangeBearingObservation {
+            range: 1.0,
+            bearing: 0.0,
+            id: None,
+        };
+        assert_eq!(ekf_slam.update(&[observation]), [None]);
+        assert_eq!(ekf_slam.num_landmarks(), 8);
+    }
+
+    #[test]
+    fn test_unknown_association() {
+        for data_association in [
+            DataAssociation::NearestNeighbour {
+                gate_z: 2.326,
+                new_landmark_z: 4.0,
+            },
+            DataAssociation::JointCompatibility {
+                gate_z: 2.326,
+                new_landmark_z: 4.0,
+            },
+        ] {
+            let mut ekf_slam = EkfSlam::new(config(data_association), &initial_pose());
+            let true_pose = run_ekf_slam(&mut ekf_slam, false);
+            assert_ekf_slam_map(&ekf_slam, &true_pose);
+        }
+    }
+
+    #[test]
+    fn test_predict_and_augment() {
+        let mut ekf_slam = EkfSlam::new(config(DataAssociation::Known), &Isometry2::identity());
+        ekf_slam.predict(&Isometry2::identity());
+        assert_eq!(ekf_slam.pose_covariance(), Matrix3::zeros());
+
+        // Motion increases the pose's uncertainty
+        ekf_slam.predict(&Isometry2::new(Vector2::new(1.0, 0.0), 0.0));
+        assert!((ekf_slam.pose().translation.x - 1.0).abs() < 1e-12);
+        let pose_covariance = ekf_slam.pose_covariance();
+        assert!(pose_covariance.trace() > 0.0);
+
+        let observations = [
+            RangeBearingObservation {
+                range: 2.0,
+                bearing: PI / 2.0,
+                id: Some(4),
+            },
+            RangeBearingObservation {
+                range: 1.0,
+                bearing: 0.0,
+                id: Some(9),
+            },
+        ];
+        assert_eq!(ekf_slam.update(&observations), [Some(0), Some(1)]);
+        assert_eq!(ekf_slam.mean().len(), 7);
+        assert_eq!(ekf_slam.covariance().shape(), (7, 7));
+        let landmark = ekf_slam.landmark(0).unwrap();
+        assert!((landmark.position - Point2::new(1.0, 2.0)).norm() < 1e-12);
+        // A new landmark inherits the pose's uncertainty, on top of the observation's
+        assert!(landmark.covariance.trace() > pose_covariance.trace());
+
+        // Re-observing the landmarks reduces their uncertainty, but not the pose's, as they were only ever observed from it
+        assert_eq!(ekf_slam.update(&observations), [Some(0), Some(1)]);
+        assert!(ekf_slam.landmark(0).unwrap().covariance.trace() < landmark.covariance.trace());
+        assert!((ekf_slam.pose_covariance() - pose_covariance).norm() < 1e-9);
+        assert_eq!(ekf_slam.landmark(1).unwrap().observations, 2);
+
+        let removed = ekf_slam.remove_landmark(0).unwrap();
+        assert_eq!(removed.id, Some(4));
+        assert_eq!(ekf_slam.num_landmarks(), 1);
+        assert_eq!(ekf_slam.covariance().shape(), (5, 5));
+        assert_eq!(ekf_slam.landmark(0).unwrap().id, Some(9));
+        assert!(ekf_slam.remove_landmark(1).is_none());
+    }
+
+    #[test]
+    fn test_zero_range() {
+        for data_association in [
+            DataAssociation::Known,
+            DataAssociation::NearestNeighbour {
+                gate_z: 2.326,
+                new_landmark_z: 4.0,
+            },
+        ] {
+            let mut ekf_slam = EkfSlam::new(config(data_association), &Isometry2::identity());
+            let observation = |range: f64, id: usize| RangeBearingObservation {
+                range,
+                bearing: 0.0,
+                id: Some(id),
+            };
+
+            // A landmark cannot be added at the robot's position
+            assert_eq!(ekf_slam.update(&[observation(0.0, 0)]), [None]);
+            assert_eq!(ekf_slam.num_landmarks(), 0);
+            assert_eq!(ekf_slam.update(&[observation(1.0, 1)]), [Some(0)]);
+
+            // Nor corrected once the robot drives on top of it
+            ekf_slam.predict(&Isometry2::identity());
+            ekf_slam.predict(&Isometry2::translation(1.0, 0.0));
+            let landmark = ekf_slam.landmark(0).unwrap();
+            assert_eq!(ekf_slam.pose().translation.vector, landmark.position.coords);
+            assert_ne!(ekf_slam.update(&[observation(1e-3, 1)])[0], Some(0));
+            assert_eq!(ekf_slam.landmark(0), Some(landmark));
+            assert!(ekf_slam.mean().iter().all(|value| value.is_finite()));
+            assert!(ekf_slam.covariance().iter().all(|value| value.is_finite()));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use num_traits::AsPrimitive;
+
+use crate::{
+    data_association::DataAssociation,
+    motion_model::{OdometryMotionModel, OdometryNoise},
+};
+
+/// A struct specifying configuration options for the [`EkfSlam`](crate::ekf_slam::EkfSlam) suite.
+#[derive(Clone, Debug)]
+pub struct EkfSlamConfiguration<T> {
+    /// The motion model used to predict the robot's pose between odometry readings.
+    pub(crate) motion_model: OdometryMotionModel<T>,
+    /// The standard deviation of the observations' range.
+    pub(crate) range_noise: T,
+    /// The standard deviation of the observations' bearing.
+    pub(crate) bearing_noise: T,
+    /// The method used to match observations with the landmarks in the state.
+    pub(crate) data_association: DataAssociation<T>,
+}
+
+impl<T: 'static + Copy> EkfSlamConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values are tuned for a small differential drive robot observing beacons with unknown identities.
+    ///
+    /// # Returns
+    /// A [`EkfSlamConfigurationBuilder`].
+    pub fn builder() -> EkfSlamConfigurationBuilder<T> {
+        EkfSlamConfigurationBuilder {
+            _internal: EkfSlamConfiguration {
+                motion_model: OdometryMotionModel::DifferentialDrive(OdometryNoise::uniform(
+                    0.05.as_(),
+                )),
+                range_noise: 0.1.as_(),
+                bearing_noise: (2.0f32.to_radians()).as_(),
+                data_association: DataAssociation::NearestNeighbour {
+                    gate_z: 2.326.as_(),
+                    new_landmark_z: 4.0.as_(),
+                },
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`EkfSlamConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct EkfSlamConfigurationBuilder<T> {
+    _internal: EkfSlamConfiguration<T>,
+}
+
+impl<T: Copy> EkfSlamConfigurationBuilder<T> {
+    /// The motion model used to predict the robot's pose between odometry readings, its noise parameters form the motion's covariance.
+    ///
+    /// # Arguments
+    /// * `motion_model`: An [`OdometryMotionModel`] matching the robot's drive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_motion_model(&self, motion_model: OdometryMotionModel<T>) -> Self {
+        Self {
+            _internal: EkfSlamConfiguration {
+                motion_model,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The noise of the range-bearing sensor, both must be positive.
+    ///
+    /// # Arguments
+    /// * `range_noise`: The standard deviation of the range.
+    /// * `bearing_noise`: The standard deviation of the bearing, in radians.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_observation_noise(&self, range_noise: T, bearing_noise: T) -> Self {
+        Self {
+            _internal: EkfSlamConfiguration {
+                range_noise,
+                bearing_noise,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The method used to match observations with the landmarks in the state.
+    ///
+    /// # Arguments
+    /// * `data_association`: A [`DataAssociation`], use [`DataAssociation::Known`] if observations carry their landmark's identity.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_data_association(&self, data_association: DataAssociation<T>) -> Self {
+        Self {
+            _internal: EkfSlamConfiguration {
+                data_association,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`EkfSlamConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`EkfSlamConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> EkfSlamConfiguration<T> {
+        self._internal.clone()
+    }
+}
@@ -0,0 +1,1092 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use nalgebra::{DMatrix, DVector, Isometry2, Matrix2, Matrix3, RealField, Vector2, Vector3};
+use num_traits::AsPrimitive;
+use rand::{rngs::SmallRng, SeedableRng};
+
+use crate::{
+    data_association::{
+        chi_square_quantile, joint_compatibility_branch_and_bound, nearest_neighbour, Association,
+        DataAssociation,
+    },
+    landmark::{
+        initialize_landmark, predict_observation, Landmark, ObservationPrediction,
+        RangeBearingObservation,
+    },
+    sampling::{effective_sample_size, low_variance_resample, normalize_angle, sample_normal},
+    Vec,
+};
+
+pub use types::{FastSlamConfiguration, FastSlamConfigurationBuilder, FastSlamVariant, Particle};
+
+mod types;
+
+/// A FastSLAM suite, a Rao-Blackwellised particle filter jointly estimating a planar robot's path and the positions of point landmarks
+/// observed by a range-bearing sensor.
+///
+/// Each particle samples a path, and estimates every landmark with its own small extended Kalman filter,
+/// as landmarks are independent given the path, so an update costs `O(particles · landmarks)`.
+/// Data association is done per particle, and particles are resampled with low-variance resampling
+/// once their effective sample size drops below the configured threshold.
+/// All randomness is drawn from a seeded generator, so identical inputs produce identical results.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct FastSlam<T: RealField> {
+    config: FastSlamConfiguration<T>,
+    particles: Vec<Particle<T>>,
+    rng: SmallRng,
+    last_odometry: Option<Isometry2<T>>,
+}
+
+impl<T> FastSlam<T>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs a filter whose particles all start at the same pose without landmarks, as the initial pose defines the map's frame.
+    ///
+    /// # Arguments
+    /// * `config`: a [`FastSlamConfiguration`], specifying the filter's parameters.
+    /// * `initial_pose`: an [`Isometry2`], the robot's pose in the map's frame.
+    ///
+    /// # Returns
+    /// A new [`FastSlam`].
+    pub fn new(config: FastSlamConfiguration<T>, initial_pose: &Isometry2<T>) -> Self {
+        let weight = T::one() / config.num_particles.max(1).as_();
+        Self {
+            particles: (0..config.num_particles)
+                .map(|_| Particle {
+                    pose: *initial_pose,
+                    weight,
+                    landmarks: Vec::new(),
+                })
+                .collect(),
+            rng: SmallRng::seed_from_u64(config.seed),
+            last_odometry: None,
+            config,
+        }
+    }
+
+    /// Returns the configuration the filter was constructed with.
+    pub fn config(&self) -> &FastSlamConfiguration<T> {
+        &self.config
+    }
+
+    /// Returns the current particles, their weights sum to one.
+    pub fn particles(&self) -> &[Particle<T>] {
+        &self.particles
+    }
+
+    /// Returns the particle with the highest weight, whose path and map are the most likely hypothesis.
+    pub fn best_particle(&self) -> Option<&Particle<T>> {
+        self.particles.iter().max_by(|first, second| {
+            first
+                .weight
+                .partial_cmp(&second.weight)
+                .unwrap_or(core::cmp::Ordering::Equal)
+        })
+    }
+
+    /// Returns the effective sample size of the current particles.
+    pub fn effective_sample_size(&self) -> T {
+        effective_sample_size(
+            &self
+                .particles
+                .iter()
+                .map(|particle| particle.weight)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Updates the filter with an odometry reading, and the landmarks observed from the new pose.
+    /// The first odometry reading only serves as a reference, so its observations are taken at the initial pose.
+    /// Particles are resampled before being propagated, if the previous update left their effective sample size below the threshold.
+    ///
+    /// # Arguments
+    /// * `odometry`: an [`Isometry2`], the robot's odometry reading.
+    /// * `observations`: a slice of [`RangeBearingObservation`], the landmarks observed from the new pose.
+    ///
+    /// # Returns
+    /// Whether the particles were resampled.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Update FastSLAM", skip_all, level = "info")
+    )]
+    pub fn update(
+        &mut self,
+        odometry: &Isometry2<T>,
+        observations: &[RangeBearingObservation<T>],
+    ) -> bool {
+        if self.particles.is_empty() {
+            return false;
+        }
+
+        // Resampling happens before propagation, so the weights always reflect the latest observations
+        let resampled = self.effective_sample_size()
+            <= self.config.resample_threshold * self.particles.len().as_();
+        if resampled {
+            self.resample();
+        }
+
+        let previous_odometry = self.last_odometry.replace(*odometry);
+        let observation_covariance = Matrix2::from_diagonal(&Vector2::new(
+            self.config.range_noise * self.config.range_noise,
+            self.config.bearing_noise * self.config.bearing_noise,
+        ));
+        // A new landmark is considered as likely as a match at the edge of the new landmark gate, as otherwise particles
+        // that drifted away from their landmarks would be favoured over ones that matched them,
+        // with known identities all particles add the same landmarks, so the value is irrelevant
+        let new_landmark_log_likelihood = match self.config.data_association {
+            DataAssociation::Known => T::zero(),
+            DataAssociation::NearestNeighbour { new_landmark_z, .. }
+            | DataAssociation::JointCompatibility { new_landmark_z, .. } => {
+                -chi_square_quantile(2, new_landmark_z) * 0.5.as_()
+                    - (T::two_pi() * self.config.range_noise * self.config.bearing_noise).ln()
+            }
+        };
+
+        let log_likelihoods = self
+            .particles
+            .iter_mut()
+            .map(|particle| {
+                update_particle(
+                    &self.config,
+                    particle,
+                    previous_odometry
+                        .as_ref()
+                        .map(|previous| (previous, odometry)),
+                    observations,
+                    &observation_covariance,
+                    new_landmark_log_likelihood,
+                    &mut self.rng,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Weights are updated relative to the most likely particle, to avoid underflowing
+        let max_log_likelihood = log_likelihoods
+            .iter()
+            .fold(T::min_value().unwrap(), |acc, &log_likelihood| {
+                acc.max(log_likelihood)
+            });
+        for (particle, log_likelihood) in self.particles.iter_mut().zip(log_likelihoods) {
+            particle.weight *= (log_likelihood - max_log_likelihood).exp();
+        }
+        let sum = self
+            .particles
+            .iter()
+            .fold(T::zero(), |acc, particle| acc + particle.weight);
+        let num_particles: T = self.particles.len().as_();
+        for particle in self.particles.iter_mut() {
+            particle.weight = if sum > T::zero() && sum.is_finite() {
+                particle.weight / sum
+            } else {
+                T::one() / num_particles
+            };
+        }
+
+        resampled
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Resample FastSLAM Particles", skip_all, level = "debug")
+    )]
+    fn resample(&mut self) {
+        let weights = self
+            .particles
+            .iter()
+            .map(|particle| particle.weight)
+            .collect::<Vec<_>>();
+        let weight = T::one() / self.particles.len().as_();
+        self.particles = low_variance_resample(&weights, self.particles.len(), &mut self.rng)
+            .into_iter()
+            .map(|particle_idx| Particle {
+                weight,
+                ..self.particles[particle_idx].clone()
+            })
+            .collect();
+    }
+}
+
+/// Returns the logarithm of the density of a zero-mean normal distribution.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Compute Gaussian Log Density", skip_all, level = "trace")
+)]
+fn log_gaussian<T: Copy + RealField>(
+    innovation: &Vector2<T>,
+    covariance: &Matrix2<T>,
+    inverse_covariance: &Matrix2<T>,
+) -> T {
+    let half: T = T::one() / (T::one() + T::one());
+    -innovation.dot(&(inverse_covariance * innovation)) * half
+        - T::two_pi().ln()
+        - covariance.determinant().ln() * half
+}
+
+/// Draws a pose from a normal distribution, falling back to independent axes if the covariance is not positive definite.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Sample FastSLAM Pose", skip_all, level = "trace")
+)]
+fn sample_pose<T>(rng: &mut SmallRng, mean: &Vector3<T>, covariance: &Matrix3<T>) -> Isometry2<T>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+{
+    let factor = covariance
+        .cholesky()
+        .map(|cholesky| cholesky.l())
+        .unwrap_or_else(|| {
+            Matrix3::from_diagonal(
+                &covariance
+                    .diagonal()
+                    .map(|variance| variance.max(T::zero()).sqrt()),
+            )
+        });
+    let sample = mean
+        + factor
+            * Vector3::new(
+                sample_normal(rng, T::one()),
+                sample_normal(rng, T::one()),
+                sample_normal(rng, T::one()),
+            );
+    Isometry2::new(sample.xy(), sample.z)
+}
+
+/// Matches observations with a particle's landmarks.
+/// The pose covariance is that of the FastSLAM 2.0 proposal, and zero for an already sampled pose.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Associate FastSLAM Observations", skip_all, level = "debug")
+)]
+fn associate<T>(
+    data_association: DataAssociation<T>,
+    landmarks: &[Landmark<T>],
+    observations: &[RangeBearingObservation<T>],
+    predictions: &[ObservationPrediction<T>],
+    landmark_covariances: &[Matrix2<T>],
+    pose_covariance: &Matrix3<T>,
+) -> Vec<Association>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    let (gate_z, new_landmark_z) = match data_association {
+        DataAssociation::Known => {
+            return observations
+                .iter()
+                .map(|observation| match observation.id {
+                    Some(id) => landmarks
+                        .iter()
+                        .position(|landmark| landmark.id == Some(id))
+                        .map_or(Association::New, Association::Landmark),
+                    None => Association::Discarded,
+                })
+                .collect();
+        }
+        DataAssociation::NearestNeighbour {
+            gate_z,
+            new_landmark_z,
+        }
+        | DataAssociation::JointCompatibility {
+            gate_z,
+            new_landmark_z,
+        } => (gate_z, new_landmark_z),
+    };
+
+    let pose_covariance_between = |first: usize, second: usize| {
+        predictions[first].pose_jacobian
+            * pose_covariance
+            * predictions[second].pose_jacobian.transpose()
+    };
+    let inverse_covariances = (0..landmarks.len())
+        .map(|landmark_idx| {
+            (pose_covariance_between(landmark_idx, landmark_idx)
+                + landmark_covariances[landmark_idx])
+                .try_inverse()
+        })
+        .collect::<Vec<_>>();
+    let distances = DMatrix::from_fn(
+        observations.len(),
+        landmarks.len(),
+        |observation_idx, landmark_idx| {
+            let innovation =
+                observations[observation_idx].innovation(&predictions[landmark_idx].observation);
+            inverse_covariances[landmark_idx].map_or(T::max_value().unwrap(), |inverse| {
+                innovation.dot(&(inverse * innovation))
+            })
+        },
+    );
+
+    if let DataAssociation::NearestNeighbour { .. } = data_association {
+        return nearest_neighbour(&distances, gate_z, new_landmark_z);
+    }
+
+    // Innovations are correlated only through the pose, as a particle's landmarks are independent
+    joint_compatibility_branch_and_bound(&distances, gate_z, new_landmark_z, |pairings| {
+        let mut innovation = DVector::zeros(2 * pairings.len());
+        let mut covariance = DMatrix::zeros(2 * pairings.len(), 2 * pairings.len());
+        for (first_idx, (observation_idx, landmark_idx)) in pairings.iter().enumerate() {
+            innovation.fixed_rows_mut::<2>(2 * first_idx).copy_from(
+                &observations[*observation_idx].innovation(&predictions[*landmark_idx].observation),
+            );
+            for (second_idx, (_, other_landmark_idx)) in pairings.iter().enumerate() {
+                let mut block = pose_covariance_between(*landmark_idx, *other_landmark_idx);
+                if first_idx == second_idx {
+                    block += landmark_covariances[*landmark_idx];
+                }
+                covariance
+                    .fixed_view_mut::<2, 2>(2 * first_idx, 2 * second_idx)
+                    .copy_from(&block);
+            }
+        }
+
+        covariance
+            .cholesky()
+            .map_or(T::max_value().unwrap(), |cholesky| {
+                innovation.dot(&cholesky.solve(&innovation))
+            })
+    })
+}
+
+/// Computes the FastSLAM 2.0 proposal distribution, refining the motion's Gaussian with each matched observation.
+///
+/// # Returns
+/// The proposal's mean and covariance, along with the logarithm of the matched observations' likelihood,
+/// or [`None`] if no observation was matched with a landmark.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Compute FastSLAM 2.0 Proposal", skip_all, level = "trace")
+)]
+fn proposal<T>(
+    landmarks: &[Landmark<T>],
+    observations: &[RangeBearingObservation<T>],
+    associations: &[Association],
+    motion_mean: &Vector3<T>,
+    motion_covariance: &Matrix3<T>,
+    observation_covariance: &Matrix2<T>,
+) -> Option<(Vector3<T>, Matrix3<T>, T)>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+{
+    let mut mean = *motion_mean;
+    let mut covariance = *motion_covariance;
+    let mut log_likelihood = T::zero();
+    let mut proposed = false;
+    for (observation, association) in observations.iter().zip(associations) {
+        let Association::Landmark(landmark_idx) = association else {
+            continue;
+        };
+        let landmark = &landmarks[*landmark_idx];
+        let prediction =
+            predict_observation(&Isometry2::new(mean.xy(), mean.z), &landmark.position);
+        let innovation_covariance =
+            prediction.pose_jacobian * covariance * prediction.pose_jacobian.transpose()
+                + prediction.landmark_jacobian
+                    * landmark.covariance
+                    * prediction.landmark_jacobian.transpose()
+                + observation_covariance;
+        let Some(inverse_covariance) = innovation_covariance.try_inverse() else {
+            continue;
+        };
+
+        let innovation = observation.innovation(&prediction.observation);
+        log_likelihood += log_gaussian(&innovation, &innovation_covariance, &inverse_covariance);
+        let gain = covariance * prediction.pose_jacobian.transpose() * inverse_covariance;
+        mean += gain * innovation;
+        mean.z = normalize_angle(mean.z);
+        covariance = (Matrix3::identity() - gain * prediction.pose_jacobian) * covariance;
+        covariance = (covariance + covariance.transpose()) * 0.5.as_();
+        proposed = true;
+    }
+
+    proposed.then_some((mean, covariance, log_likelihood))
+}
+
+/// Propagates a single particle, associates and applies the observations to its landmarks.
+///
+/// # Returns
+/// The logarithm of the particle's importance weight.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Update FastSLAM Particle", skip_all, level = "debug")
+)]
+fn update_particle<T>(
+    config: &FastSlamConfiguration<T>,
+    particle: &mut Particle<T>,
+    odometry: Option<(&Isometry2<T>, &Isometry2<T>)>,
+    observations: &[RangeBearingObservation<T>],
+    observation_covariance: &Matrix2<T>,
+    new_landmark_log_likelihood: T,
+    rng: &mut SmallRng,
+) -> T
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    // Observations are associated with the motion's Gaussian before any pose is drawn, so that the gates account for the motion's
+    // uncertainty, otherwise FastSLAM 1.0 would gate each observation around a single noisy draw, and add duplicate landmarks
+    let (mut pose, pose_covariance) = match odometry {
+        Some((previous_odometry, current_odometry)) => {
+            let prediction =
+                config
+                    .motion_model
+                    .predict(&particle.pose, previous_odometry, current_odometry);
+            (prediction.pose, prediction.covariance)
+        }
+        None => (particle.pose, Matrix3::zeros()),
+    };
+
+    let predictions = particle
+        .landmarks
+        .iter()
+        .map(|landmark| predict_observation(&pose, &landmark.position))
+        .collect::<Vec<_>>();
+    let landmark_covariances = predictions
+        .iter()
+        .zip(&particle.landmarks)
+        .map(|(prediction, landmark)| {
+            prediction.landmark_jacobian
+                * landmark.covariance
+                * prediction.landmark_jacobian.transpose()
+                + observation_covariance
+        })
+        .collect::<Vec<_>>();
+    // Observations at a zero or non-finite range or bearing would add a landmark on top of the robot, or at a non-finite position,
+    // so they are left out of the association and discarded
+    let valid_indices = observations
+        .iter()
+        .enumerate()
+        .filter(|(_, observation)| observation.is_valid())
+        .map(|(observation_idx, _)| observation_idx)
+        .collect::<Vec<_>>();
+    let valid_observations = valid_indices
+        .iter()
+        .map(|&observation_idx| observations[observation_idx])
+        .collect::<Vec<_>>();
+    let valid_associations = associate(
+        config.data_association,
+        &particle.landmarks,
+        &valid_observations,
+        &predictions,
+        &landmark_covariances,
+        &pose_covariance,
+    );
+    let mut associations = Vec::from_iter(core::iter::repeat_n(
+        Association::Discarded,
+        observations.len(),
+    ));
+    for (observation_idx, association) in valid_indices.into_iter().zip(valid_associations) {
+        associations[observation_idx] = association;
+    }
+
+    let mut log_likelihood = T::zero();
+    let mut proposed = false;
+    if let Some((previous_odometry, current_odometry)) =
+        odometry.filter(|_| config.variant == FastSlamVariant::FastSlam1)
+    {
+        pose = config
+            .motion_model
+            .sample(&particle.pose, previous_odometry, current_odometry, rng);
+    } else if let Some((previous_odometry, current_odometry)) = odometry {
+        // FastSLAM 2.0 draws the pose from the motion's Gaussian, refined by the matched observations
+        let mean = Vector3::new(
+            pose.translation.x,
+            pose.translation.y,
+            pose.rotation.angle(),
+        );
+        pose = match proposal(
+            &particle.landmarks,
+            observations,
+            &associations,
+            &mean,
+            &pose_covariance,
+            observation_covariance,
+        ) {
+            Some((mean, covariance, observations_log_likelihood)) => {
+                log_likelihood += observations_log_likelihood;
+                proposed = true;
+                sample_pose(rng, &mean, &covariance)
+            }
+            // Without any matched landmark, the proposal is the motion model itself
+            None => {
+                config
+                    .motion_model
+                    .sample(&particle.pose, previous_odometry, current_odometry, rng)
+            }
+        };
+    }
+    particle.pose = pose;
+
+    for (observation, association) in observations.iter().zip(associations) {
+        match association {
+            Association::Landmark(landmark_idx) => {
+                let landmark = &mut particle.landmarks[landmark_idx];
+                let prediction = predict_observation(&pose, &landmark.position);
+                let jacobian = prediction.landmark_jacobian;
+                let innovation_covariance =
+                    jacobian * landmark.covariance * jacobian.transpose() + observation_covariance;
+                let Some(inverse_covariance) = innovation_covariance.try_inverse() else {
+                    continue;
+                };
+
+                let innovation = observation.innovation(&prediction.observation);
+                // FastSLAM 2.0 already weighed the particle by the observations used by its proposal
+                if !proposed {
+                    log_likelihood +=
+                        log_gaussian(&innovation, &innovation_covariance, &inverse_covariance);
+                }
+                let gain = landmark.covariance * jacobian.transpose() * inverse_covariance;
+                landmark.position += gain * innovation;
+                landmark.covariance = (Matrix2::identity() - gain * jacobian) * landmark.covariance;
+                landmark.covariance =
+                    (landmark.covariance + landmark.covariance.transpose()) * 0.5.as_();
+                landmark.observations += 1;
+            }
+            Association::New => {
+                // With known identities, a landmark might be observed more than once in a single update
+                if matches!(config.data_association, DataAssociation::Known)
+                    && particle
+                        .landmarks
+                        .iter()
+                        .any(|landmark| landmark.id == observation.id)
+                {
+                    continue;
+                }
+
+                log_likelihood += new_landmark_log_likelihood;
+                let (position, _, jacobian) = initialize_landmark(&pose, observation);
+                particle.landmarks.push(Landmark {
+                    id: observation.id,
+                    position,
+                    covariance: jacobian * observation_covariance * jacobian.transpose(),
+                    observations: 1,
+                });
+            }
+            Association::Discarded => {}
+        }
+    }
+
+    log_likelihood
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Point2;
+
+    use super::*;
+    use crate::{
+        landmark::simulation::{assert_map, initial_pose, run, simulate},
+        motion_model::{OdometryMotionModel, OdometryNoise},
+    };
+
+    fn config(
+        variant: FastSlamVariant,
+        data_association: DataAssociation<f64>,
+    ) -> FastSlamConfigurationBuilder<f64> {
+        FastSlamConfiguration::builder()
+            .with_variant(variant)
+            .with_num_particles(50)
+            .with_motion_model(OdometryMotionModel::DifferentialDrive(OdometryNoise {
+                alpha1: 0.01,
+                alpha2: 0.0005,
+                alpha3: 0.005,
+                alpha4: 0.0001,
+                alpha5: 0.0,
+            }))
+            .with_observation_noise(0.05, 0.01)
+            .with_data_association(data_association)
+    }
+
+    fn run_fast_slam(fast_slam: &mut FastSlam<f64>, with_ids: bool) -> Isometry2<f64> {
+        run(with_ids, |step| {
+            fast_slam.update(&step.odometry, &step.observations);
+        })
+    }
+
+    fn assert_fast_slam_map(fast_slam: &FastSlam<f64>, true_pose: &Isometry2<f64>) {
+        assert_eq!(fast_slam.particles().len(), 50);
+        let weight_sum = fast_slam
+            .particles()
+            .iter()
+            .map(|particle| particle.weight)
+            .sum::<f64>();
+        assert!((weight_sum - 1.0).abs() < 1e-9);
+
+        let particle = fast_slam.best_particle().unwrap();
+        assert_map(
+            &particle.pose,
+            &particle.landmarks,
+            true_pose,
+            [0.15, 0.06, 0.25],
+        );
+    }
+
+    #[test]
+    fn test_known_association() {
+        for variant in [FastSlamVariant::FastSlam1, FastSlamVariant::FastSlam2] {
+            let mut fast_slam = FastSlam::new(
+                config(variant, DataAssociation::Known).build(),
+                &initial_pose(),
+            );
+            let true_pose = run_fast_slam(&mut fast_slam, true);
+            assert_fast_slam_map(&fast_slam, &true_pose);
+            assert!(fast_slam
+                .best_particle()
+                .unwrap()
+                .landmarks
+                .iter()
+                .all(|landmark| landmark.id.is_some()));
+        }
+    }
+
+    #[test]
+    fn test_unknown_association() {
+        // FastSLAM 1.0's particles drift between observations, which joint compatibility rejects, see its documentation
+        for (variant, data_association) in [
+            (
+                FastSlamVariant::FastSlam1,
+                DataAssociation::NearestNeighbour {
+                    gate_z: 2.326,
+                    new_landmark_z: 4.0,
+                },
+            ),
+            (
+                FastSlamVariant::FastSlam2,
+                DataAssociation::NearestNeighbour {
+                    gate_z: 2.326,
+                    new_landmark_z: 4.0,
+                },
+            ),
+            (
+                FastSlamVariant::FastSlam2,
+                DataAssociation::JointCompatibility {
+                    gate_z: 2.326,
+                    new_landmark_z: 4.0,
+                },
+            ),
+        ] {
+            let mut fast_slam =
+                FastSlam::new(config(variant, data_association).build(), &initial_pose());
+            let true_pose = run_fast_slam(&mut fast_slam, false);
+            assert_fast_slam_map(&fast_slam, &true_pose);
+        }
+    }
+
+    #[test]
+    fn test_determinism() {
+        let steps = simulate(false);
+        let run_with_seed = |seed: u64| {
+            let mut fast_slam = FastSlam::new(
+                config(
+                    FastSlamVariant::FastSlam2,
+                    DataAssociation::NearestNeighbour {
+                        gate_z: 2.326,
+                        new_landmark_z: 4.0,
+                    },
+                )
+                .with_seed(seed)
+                .build(),
+                &Isometry2::identity(),
+            );
+            for step in steps.iter().take(20) {
+                fast_slam.update(&step.odometry, &step.observations);
+            }
+            fast_slam.particles().to_vec()
+        };
+
+        assert_eq!(run_with_seed(3), run_with_seed(3));
+        assert_ne!(run_with_seed(3), run_with_seed(4));
+    }
+
+    #[test]
+    fn test_resample_threshold() {
+        let steps = simulate(true);
+        for (resample_threshold, expected) in [(0.0, false), (1.0, true)] {
+            let mut fast_slam = FastSlam::new(
+                FastSlamConfiguration::builder()
+                    .with_variant(FastSlamVariant::FastSlam1)
+                    .with_num_particles(10)
+                    .with_data_association(DataAssociation::Known)
+                    .with_resample_threshold(resample_threshold)
+                    .build(),
+                &Isometry2::identity(),
+            );
+            for step in steps.iter().skip(1).take(10) {
+                assert_eq!(
+                    fast_slam.update(&step.odometry, &step.observations),
+                    expected
+                );
+            }
+            assert_eq!(fast_slam.particles().len(), 10);
+        }
+
+        // Without resampling, the weights degenerate
+        let mut fast_slam = FastSlam::new(
+            config(FastSlamVariant::FastSlam1, DataAssociation::Known)
+                .with_resample_threshold(0.0)
+                .build(),
+            &Isometry2::identity(),
+        );
+        for step in steps.iter().take(30) {
+            fast_slam.update(&step.odometry, &step.observations);
+        }
+        assert!(fast_slam.effective_sample_size() < 10.0);
+    }
+
+    fn landmark(position: Point2<f64>) -> Landmark<f64> {
+        Landmark {
+            id: None,
+            position,
+            covariance: Matrix2::identity() * 1e-4,
+            observations: 1,
+        }
+    }
+
+    #[test]
+    fn test_log_gaussian() {
+        let covariance = Matrix2::new(4.0, 0.0, 0.0, 1.0);
+        let inverse_covariance = covariance.try_inverse().unwrap();
+        assert!(
+            (log_gaussian(&Vector2::zeros(), &covariance, &inverse_covariance)
+                - (-(2.0 * core::f64::consts::PI).ln() - 0.5 * 4.0f64.ln()))
+            .abs()
+                < 1e-12
+        );
+        assert!(
+            (log_gaussian(&Vector2::new(2.0, 0.0), &covariance, &inverse_covariance)
+                - log_gaussian(&Vector2::new(0.0, 1.0), &covariance, &inverse_covariance))
+            .abs()
+                < 1e-12
+        );
+    }
+
+    #[test]
+    fn test_sample_pose() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mean = Vector3::new(1.0, -2.0, 0.5);
+        let covariance = Matrix3::new(0.04, 0.01, 0.0, 0.01, 0.09, 0.0, 0.0, 0.0, 0.01);
+        let samples = (0..5000)
+            .map(|_| {
+                let pose = sample_pose(&mut rng, &mean, &covariance);
+                Vector3::new(
+                    pose.translation.x,
+                    pose.translation.y,
+                    pose.rotation.angle(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let sample_mean = samples.iter().sum::<Vector3<f64>>() / samples.len() as f64;
+        let sample_covariance = samples
+            .iter()
+            .map(|sample| (sample - sample_mean) * (sample - sample_mean).transpose())
+            .sum::<Matrix3<f64>>()
+            / samples.len() as f64;
+        assert!((sample_mean - mean).norm() < 0.01, "{sample_mean}");
+        assert!(
+            (sample_covariance - covariance).norm() < 0.01,
+            "{sample_covariance}"
+        );
+
+        // Axes without a positive variance are not perturbed
+        let pose = sample_pose(
+            &mut rng,
+            &mean,
+            &Matrix3::from_diagonal(&Vector3::new(0.04, 0.0, -1.0)),
+        );
+        assert_ne!(pose.translation.x, mean.x);
+        assert_eq!(pose.translation.y, mean.y);
+        assert!((pose.rotation.angle() - mean.z).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_associate() {
+        let landmarks = [
+            landmark(Point2::new(2.0, 0.0)),
+            Landmark {
+                id: Some(3),
+                ..landmark(Point2::new(0.0, 2.0))
+            },
+        ];
+        let predictions = landmarks
+            .iter()
+            .map(|landmark| predict_observation(&Isometry2::identity(), &landmark.position))
+            .collect::<Vec<_>>();
+        let landmark_covariances = [Matrix2::from_diagonal(&Vector2::new(0.0025, 0.0001)); 2];
+        let observations = [
+            RangeBearingObservation {
+                range: 2.02,
+                bearing: core::f64::consts::FRAC_PI_2,
+                id: Some(3),
+            },
+            RangeBearingObservation {
+                range: 4.0,
+                bearing: -1.0,
+                id: None,
+            },
+            RangeBearingObservation {
+                range: 1.99,
+                bearing: 0.005,
+                id: Some(7),
+            },
+        ];
+
+        let nearest_neighbour = DataAssociation::NearestNeighbour {
+            gate_z: 2.326,
+            new_landmark_z: 4.0,
+        };
+        assert_eq!(
+            associate(
+                nearest_neighbour,
+                &landmarks,
+                &observations,
+                &predictions,
+                &landmark_covariances,
+                &Matrix3::zeros(),
+            ),
+            [
+                Association::Landmark(1),
+                Association::New,
+                Association::Landmark(0)
+            ]
+        );
+        assert_eq!(
+            associate(
+                DataAssociation::Known,
+                &landmarks,
+                &observations,
+                &predictions,
+                &landmark_covariances,
+                &Matrix3::zeros(),
+            ),
+            [
+                Association::Landmark(1),
+                Association::Discarded,
+                Association::New
+            ]
+        );
+
+        // The pose's uncertainty widens the gates
+        let far_observation = [RangeBearingObservation {
+            range: 2.0,
+            bearing: 0.1,
+            id: None,
+        }];
+        let associate_far = |pose_covariance: &Matrix3<f64>| {
+            associate(
+                nearest_neighbour,
+                &landmarks,
+                &far_observation,
+                &predictions,
+                &landmark_covariances,
+                pose_covariance,
+            )
+        };
+        assert_eq!(associate_far(&Matrix3::zeros()), [Association::New]);
+        assert_eq!(
+            associate_far(&Matrix3::from_diagonal(&Vector3::new(0.01, 0.01, 0.01))),
+            [Association::Landmark(0)]
+        );
+    }
+
+    #[test]
+    fn test_proposal() {
+        let landmarks = [landmark(Point2::new(2.0, 0.0))];
+        let observations = [RangeBearingObservation {
+            range: 1.8,
+            bearing: 0.0,
+            id: None,
+        }];
+        let motion_covariance = Matrix3::from_diagonal(&Vector3::new(0.1, 0.1, 0.01));
+        let observation_covariance = Matrix2::from_diagonal(&Vector2::new(0.0025, 0.0001));
+
+        let (mean, covariance, log_likelihood) = proposal(
+            &landmarks,
+            &observations,
+            &[Association::Landmark(0)],
+            &Vector3::zeros(),
+            &motion_covariance,
+            &observation_covariance,
+        )
+        .unwrap();
+        // The observed range places the robot closer to the landmark than the motion predicted
+        assert!((mean.x - 0.2).abs() < 0.01, "{mean}");
+        assert!(covariance[(0, 0)] < 0.05 * motion_covariance[(0, 0)]);
+        assert!(covariance.trace() < motion_covariance.trace());
+        assert!(log_likelihood.is_finite());
+
+        assert_eq!(
+            proposal(
+                &landmarks,
+                &observations,
+                &[Association::New],
+                &Vector3::zeros(),
+                &motion_covariance,
+                &observation_covariance,
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_update_particle() {
+        let observations = [RangeBearingObservation {
+            range: 1.8,
+            bearing: 0.0,
+            id: None,
+        }];
+        let odometry = (&Isometry2::identity(), &Isometry2::translation(1.0, 0.0));
+        let observation_covariance = Matrix2::from_diagonal(&Vector2::new(0.0025, 0.0001));
+        let particle = Particle {
+            pose: Isometry2::identity(),
+            weight: 1.0,
+            landmarks: Vec::from([landmark(Point2::new(3.0, 0.0))]),
+        };
+
+        let mut rng = SmallRng::seed_from_u64(11);
+        let mut sample_positions = |variant: FastSlamVariant| {
+            let config = config(
+                variant,
+                DataAssociation::NearestNeighbour {
+                    gate_z: 2.326,
+                    new_landmark_z: 4.0,
+                },
+            )
+            .build();
+            (0..500)
+                .map(|_| {
+                    let mut updated = particle.clone();
+                    let log_likelihood = update_particle(
+                        &config,
+                        &mut updated,
+                        Some(odometry),
+                        &observations,
+                        &observation_covariance,
+                        -10.0,
+                        &mut rng,
+                    );
+                    assert!(log_likelihood.is_finite());
+                    assert_eq!(updated.landmarks.len(), 1);
+                    assert_eq!(updated.landmarks[0].observations, 2);
+                    updated.pose.translation.x
+                })
+                .collect::<Vec<_>>()
+        };
+        let mean_and_variance = |samples: &[f64]| {
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            let variance = samples
+                .iter()
+                .map(|sample| (sample - mean) * (sample - mean))
+                .sum::<f64>()
+                / samples.len() as f64;
+            (mean, variance)
+        };
+
+        // FastSLAM 1.0 draws from the motion model, while the 2.0 proposal moves towards where the observation places the robot
+        let (motion_mean, motion_variance) =
+            mean_and_variance(&sample_positions(FastSlamVariant::FastSlam1));
+        let (proposal_mean, proposal_variance) =
+            mean_and_variance(&sample_positions(FastSlamVariant::FastSlam2));
+        assert!((motion_mean - 1.0).abs() < 0.02, "{motion_mean}");
+        assert!(
+            proposal_mean > motion_mean + 0.1 && proposal_mean < 1.2,
+            "{proposal_mean}"
+        );
+        assert!(
+            proposal_variance < 0.5 * motion_variance,
+            "{proposal_variance} {motion_variance}"
+        );
+    }
+
+    #[test]
+    fn test_zero_range() {
+        for variant in [FastSlamVariant::FastSlam1, FastSlamVariant::FastSlam2] {
+            for data_association in [
+                DataAssociation::Known,
+                DataAssociation::NearestNeighbour {
+                    gate_z: 2.326,
+                    new_landmark_z: 4.0,
+                },
+            ] {
+                let mut fast_slam = FastSlam::new(
+                    config(variant, data_association).build(),
+                    &Isometry2::identity(),
+                );
+                let observation = |range: f64, bearing: f64, id: usize| RangeBearingObservation {
+                    range,
+                    bearing,
+                    id: Some(id),
+                };
+
+                // A landmark cannot be added at the robot's position, nor at a non-finite one
+                fast_slam.update(
+                    &Isometry2::identity(),
+                    &[
+                        observation(0.0, 0.0, 0),
+                        observation(f64::NAN, 0.0, 1),
+                        observation(1.0, f64::INFINITY, 2),
+                        observation(1.0, 0.0, 3),
+                    ],
+                );
+                let landmarks = fast_slam.particles()[0].landmarks.clone();
+                assert_eq!(landmarks.len(), 1);
+                assert_eq!(landmarks[0].id, Some(3));
+
+                // Nor corrected by an observation at a zero range
+                fast_slam.update(
+                    &Isometry2::translation(1.0, 0.0),
+                    &[observation(0.0, 0.0, 3)],
+                );
+                for particle in fast_slam.particles() {
+                    assert_eq!(particle.landmarks, landmarks);
+                    assert!(particle.weight.is_finite());
+                    assert!(particle
+                        .pose
+                        .translation
+                        .vector
+                        .iter()
+                        .all(|value| value.is_finite()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_best_particle_nan_weight() {
+        let mut fast_slam = FastSlam::new(
+            config(FastSlamVariant::FastSlam1, DataAssociation::Known).build(),
+            &Isometry2::identity(),
+        );
+        fast_slam.particles[0].weight = f64::NAN;
+        fast_slam.particles[1].weight = 1.0;
+        assert!(fast_slam.best_particle().is_some());
+    }
+}
@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use nalgebra::{Isometry2, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{
+    data_association::DataAssociation,
+    landmark::Landmark,
+    motion_model::{OdometryMotionModel, OdometryNoise},
+    Vec,
+};
+
+/// The variant of FastSLAM, differing in how each particle's new pose is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FastSlamVariant {
+    /// FastSLAM 1.0, poses are drawn from the motion model alone, and weighted by the observations.
+    /// As its particles drift between observations, it is better suited to [`DataAssociation::NearestNeighbour`]
+    /// than to [`DataAssociation::JointCompatibility`], which may reject a drifted particle's matches and add duplicate landmarks.
+    FastSlam1,
+    /// FastSLAM 2.0, poses are drawn from a proposal distribution which also incorporates the observations of known landmarks,
+    /// so fewer particles are needed when the sensor is more accurate than the odometry.
+    FastSlam2,
+}
+
+/// A single hypothesis of the robot's path, along with its own map of independently estimated landmarks.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle<T: RealField> {
+    /// The hypothesised pose of the robot in the map's frame.
+    pub pose: Isometry2<T>,
+    /// The particle's normalised weight.
+    pub weight: T,
+    /// The particle's landmarks, each estimated by its own extended Kalman filter, conditioned on the particle's path.
+    pub landmarks: Vec<Landmark<T>>,
+}
+
+/// A struct specifying configuration options for the [`FastSlam`](crate::fast_slam::FastSlam) suite.
+#[derive(Clone, Debug)]
+pub struct FastSlamConfiguration<T> {
+    /// The variant of FastSLAM, determining how poses are drawn.
+    pub(crate) variant: FastSlamVariant,
+    /// The amount of particles.
+    pub(crate) num_particles: usize,
+    /// The motion model used to propagate particles between odometry readings.
+    pub(crate) motion_model: OdometryMotionModel<T>,
+    /// The standard deviation of the observations' range.
+    pub(crate) range_noise: T,
+    /// The standard deviation of the observations' bearing.
+    pub(crate) bearing_noise: T,
+    /// The method used to match observations with each particle's landmarks.
+    pub(crate) data_association: DataAssociation<T>,
+    /// The particles are resampled when their effective sample size falls below this fraction of the amount of particles.
+    pub(crate) resample_threshold: T,
+    /// The seed of the filter's random number generator.
+    pub(crate) seed: u64,
+}
+
+impl<T: 'static + Copy> FastSlamConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values are tuned for a small differential drive robot observing beacons with unknown identities,
+    /// matching the defaults of [`EkfSlamConfiguration`](crate::ekf_slam::EkfSlamConfiguration).
+    ///
+    /// # Returns
+    /// A [`FastSlamConfigurationBuilder`].
+    pub fn builder() -> FastSlamConfigurationBuilder<T> {
+        FastSlamConfigurationBuilder {
+            _internal: FastSlamConfiguration {
+                variant: FastSlamVariant::FastSlam2,
+                num_particles: 100,
+                motion_model: OdometryMotionModel::DifferentialDrive(OdometryNoise::uniform(
+                    0.05.as_(),
+                )),
+                range_noise: 0.1.as_(),
+                bearing_noise: (2.0f32.to_radians()).as_(),
+                data_association: DataAssociation::NearestNeighbour {
+                    gate_z: 2.326.as_(),
+                    new_landmark_z: 4.0.as_(),
+                },
+                resample_threshold: 0.5.as_(),
+                seed: 0,
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`FastSlamConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct FastSlamConfigurationBuilder<T> {
+    _internal: FastSlamConfiguration<T>,
+}
+
+impl<T: Copy> FastSlamConfigurationBuilder<T> {
+    /// The variant of FastSLAM, determining how each particle's new pose is drawn.
+    ///
+    /// # Arguments
+    /// * `variant`: A [`FastSlamVariant`].
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_variant(&self, variant: FastSlamVariant) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                variant,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The amount of particles, each carrying a full copy of its map.
+    ///
+    /// # Arguments
+    /// * `num_particles`: The amount of particles.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_num_particles(&self, num_particles: usize) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                num_particles,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The motion model used to propagate particles between odometry readings.
+    ///
+    /// # Arguments
+    /// * `motion_model`: An [`OdometryMotionModel`] matching the robot's drive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_motion_model(&self, motion_model: OdometryMotionModel<T>) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                motion_model,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The noise of the range-bearing sensor, both must be positive.
+    ///
+    /// # Arguments
+    /// * `range_noise`: The standard deviation of the range.
+    /// * `bearing_noise`: The standard deviation of the bearing, in radians.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_observation_noise(&self, range_noise: T, bearing_noise: T) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                range_noise,
+                bearing_noise,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The method used to match observations with each particle's landmarks, association is done per particle,
+    /// so that particles with different associations can compete.
+    ///
+    /// # Arguments
+    /// * `data_association`: A [`DataAssociation`], use [`DataAssociation::Known`] if observations carry their landmark's identity,
+    ///   see [`FastSlamVariant::FastSlam1`] for its limitation with unknown identities.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_data_association(&self, data_association: DataAssociation<T>) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                data_association,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The effective sample size below which the particles are resampled, as a fraction of the amount of particles.
+    ///
+    /// # Arguments
+    /// * `resample_threshold`: The fraction, `1` resamples after every update, `0` never resamples.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_resample_threshold(&self, resample_threshold: T) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                resample_threshold,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The seed of the filter's random number generator, identical seeds and inputs produce identical results.
+    ///
+    /// # Arguments
+    /// * `seed`: The seed.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_seed(&self, seed: u64) -> Self {
+        Self {
+            _internal: FastSlamConfiguration {
+                seed,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`FastSlamConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`FastSlamConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> FastSlamConfiguration<T> {
+        self._internal.clone()
+    }
+}
@@ -0,0 +1,352 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry2, Matrix2, Matrix2x3, Point2, RealField, Vector2};
+
+use crate::sampling::normalize_angle;
+
+/// An observation of a landmark, as its range and bearing from the robot.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RangeBearingObservation<T> {
+    /// The distance from the robot to the landmark.
+    pub range: T,
+    /// The angle of the landmark, relative to the robot's heading.
+    pub bearing: T,
+    /// The landmark's identity, if it is known, e.g. from a beacon's signal or a fiducial's code.
+    pub id: Option<usize>,
+}
+
+impl<T: Copy + RealField> RangeBearingObservation<T> {
+    /// Returns the difference between the observation and a predicted observation, with the bearing wrapped into `(-π, π]`.
+    ///
+    /// # Arguments
+    /// * `predicted`: a [`Vector2`], the predicted range and bearing, see [`predict_observation`].
+    ///
+    /// # Returns
+    /// A [`Vector2`], the innovation of the range and bearing.
+    pub fn innovation(&self, predicted: &Vector2<T>) -> Vector2<T> {
+        Vector2::new(
+            self.range - predicted.x,
+            normalize_angle(self.bearing - predicted.y),
+        )
+    }
+
+    /// Checks whether the observation can be used, a landmark observed at a zero range coincides with the robot,
+    /// so its bearing, and the Jacobians of any later prediction of it, are undefined.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.range > T::zero() && self.range.is_finite() && self.bearing.is_finite()
+    }
+}
+
+/// A landmark in the map, estimated as a Gaussian.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Landmark<T: RealField> {
+    /// The landmark's identity, if it was observed with one.
+    pub id: Option<usize>,
+    /// The mean of the landmark's position.
+    pub position: Point2<T>,
+    /// The covariance of the landmark's position.
+    pub covariance: Matrix2<T>,
+    /// The amount of times the landmark was observed, including the observation that added it.
+    pub observations: usize,
+}
+
+/// The range and bearing at which a landmark is expected to be observed, linearised around the robot's pose and the landmark.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObservationPrediction<T: RealField> {
+    /// The predicted range and bearing.
+    pub observation: Vector2<T>,
+    /// The Jacobian of the range and bearing, with respect to the robot's `x`, `y` and heading.
+    pub pose_jacobian: Matrix2x3<T>,
+    /// The Jacobian of the range and bearing, with respect to the landmark's position.
+    pub landmark_jacobian: Matrix2<T>,
+}
+
+/// Predicts the range and bearing at which a landmark would be observed from a pose.
+///
+/// # Arguments
+/// * `pose`: an [`Isometry2`], the robot's pose.
+/// * `landmark`: a [`Point2`], the landmark's position, it must not coincide with the robot's position.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// An [`ObservationPrediction`], containing the predicted observation and its Jacobians.
+pub fn predict_observation<T: Copy + RealField>(
+    pose: &Isometry2<T>,
+    landmark: &Point2<T>,
+) -> ObservationPrediction<T> {
+    let delta = landmark.coords - pose.translation.vector;
+    let squared_range = delta.norm_squared();
+    let range = squared_range.sqrt();
+
+    let landmark_jacobian = Matrix2::new(
+        delta.x / range,
+        delta.y / range,
+        -delta.y / squared_range,
+        delta.x / squared_range,
+    );
+    let mut pose_jacobian = Matrix2x3::zeros();
+    pose_jacobian
+        .fixed_view_mut::<2, 2>(0, 0)
+        .copy_from(&-landmark_jacobian);
+    pose_jacobian[(1, 2)] = -T::one();
+
+    ObservationPrediction {
+        observation: Vector2::new(
+            range,
+            normalize_angle(delta.y.atan2(delta.x) - pose.rotation.angle()),
+        ),
+        pose_jacobian,
+        landmark_jacobian,
+    }
+}
+
+/// Computes the position of a newly observed landmark, the inverse of [`predict_observation`].
+///
+/// # Arguments
+/// * `pose`: an [`Isometry2`], the robot's pose.
+/// * `observation`: a [`RangeBearingObservation`], the landmark's observation.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// A tuple of the landmark's position, its Jacobian with respect to the robot's `x`, `y` and heading,
+/// and its Jacobian with respect to the range and bearing.
+pub fn initialize_landmark<T: Copy + RealField>(
+    pose: &Isometry2<T>,
+    observation: &RangeBearingObservation<T>,
+) -> (Point2<T>, Matrix2x3<T>, Matrix2<T>) {
+    let (sin, cos) = (pose.rotation.angle() + observation.bearing).sin_cos();
+    let range = observation.range;
+
+    (
+        Point2::from(pose.translation.vector + Vector2::new(cos, sin) * range),
+        Matrix2x3::new(
+            T::one(),
+            T::zero(),
+            -range * sin,
+            T::zero(),
+            T::one(),
+            range * cos,
+        ),
+        Matrix2::new(cos, -range * sin, sin, range * cos),
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod simulation {
+    use core::f64::consts::PI;
+
+    use nalgebra::{Isometry2, Point2, Vector2};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::{Landmark, RangeBearingObservation};
+    use crate::{sampling::sample_normal, Vec};
+
+    /// The robot's pose at the start of the simulation.
+    pub(crate) fn initial_pose() -> Isometry2<f64> {
+        Isometry2::new(Vector2::new(3.0, 0.0), PI / 2.0)
+    }
+
+    /// Eight beacons placed on two interleaved circles around the origin.
+    pub(crate) fn beacons() -> Vec<Point2<f64>> {
+        (0..8)
+            .map(|beacon_idx| {
+                let angle = beacon_idx as f64 * PI / 4.0 + 0.3;
+                let radius = if beacon_idx % 2 == 0 { 5.0 } else { 6.5 };
+                Point2::new(radius * angle.cos(), radius * angle.sin())
+            })
+            .collect()
+    }
+
+    pub(crate) struct SimulationStep {
+        pub(crate) pose: Isometry2<f64>,
+        pub(crate) odometry: Isometry2<f64>,
+        pub(crate) observations: Vec<RangeBearingObservation<f64>>,
+    }
+
+    /// Drives twice around a circle of beacons, returning the true poses, the drifting odometry,
+    /// and the noisy observations of the beacons in range.
+    pub(crate) fn simulate(with_ids: bool) -> Vec<SimulationStep> {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let beacons = beacons();
+        let mut pose = initial_pose();
+        let mut odometry = Isometry2::identity();
+        (0..190)
+            .map(|_| {
+                let observations = beacons
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(beacon_idx, beacon)| {
+                        let local = pose.inverse_transform_point(beacon);
+                        let range = local.coords.norm();
+                        (range < 5.0).then(|| RangeBearingObservation {
+                            range: range + sample_normal(&mut rng, 0.05),
+                            bearing: local.y.atan2(local.x) + sample_normal(&mut rng, 0.01),
+                            id: with_ids.then_some(beacon_idx),
+                        })
+                    })
+                    .collect();
+                let step = SimulationStep {
+                    pose,
+                    odometry,
+                    observations,
+                };
+
+                pose *= Isometry2::new(Vector2::new(0.2, 0.0), 0.2 / 3.0);
+                odometry *= Isometry2::new(
+                    Vector2::new(0.2 + sample_normal(&mut rng, 0.01), 0.0),
+                    0.2 / 3.0 + sample_normal(&mut rng, 0.005),
+                );
+                step
+            })
+            .collect()
+    }
+
+    /// Feeds every step of the simulation to a filter, returning the robot's true final pose.
+    pub(crate) fn run<F: FnMut(&SimulationStep)>(with_ids: bool, mut update: F) -> Isometry2<f64> {
+        let mut last_pose = Isometry2::identity();
+        for step in simulate(with_ids) {
+            update(&step);
+            last_pose = step.pose;
+        }
+        last_pose
+    }
+
+    /// Asserts that an estimated pose and map match the simulation, up to a position, heading and landmark tolerance.
+    pub(crate) fn assert_map(
+        pose: &Isometry2<f64>,
+        landmarks: &[Landmark<f64>],
+        true_pose: &Isometry2<f64>,
+        [position_tolerance, heading_tolerance, landmark_tolerance]: [f64; 3],
+    ) {
+        assert!(
+            (pose.translation.vector - true_pose.translation.vector).norm() < position_tolerance,
+            "{pose} != {true_pose}"
+        );
+        assert!(
+            pose.rotation.angle_to(&true_pose.rotation).abs() < heading_tolerance,
+            "{pose} != {true_pose}"
+        );
+
+        assert_eq!(landmarks.len(), 8);
+        for landmark in landmarks {
+            let error = beacons()
+                .iter()
+                .map(|beacon| (beacon - landmark.position).norm())
+                .fold(f64::MAX, f64::min);
+            assert!(error < landmark_tolerance, "{error}");
+            assert!(landmark.observations > 1);
+            if let Some(id) = landmark.id {
+                assert!((beacons()[id] - landmark.position).norm() < landmark_tolerance);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn test_observation_round_trip() {
+        let pose = Isometry2::new(Vector2::new(1.0, -2.0), 2.5);
+        let observation = RangeBearingObservation {
+            range: 4.0,
+            bearing: -2.0,
+            id: None,
+        };
+
+        let (landmark, _, _) = initialize_landmark(&pose, &observation);
+        let prediction = predict_observation(&pose, &landmark);
+        assert!(observation.innovation(&prediction.observation).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_jacobians() {
+        let pose = Vector3::new(1.0, -2.0, 2.5);
+        let landmark = Vector2::new(-3.0, 0.5);
+        let epsilon = 1e-7;
+        let predict = |pose: &Vector3<f64>, landmark: &Vector2<f64>| {
+            predict_observation(&Isometry2::new(pose.xy(), pose.z), &Point2::from(*landmark))
+        };
+        let prediction = predict(&pose, &landmark);
+
+        for axis in 0..3 {
+            let mut perturbed = pose;
+            perturbed[axis] += epsilon;
+            let numerical =
+                (predict(&perturbed, &landmark).observation - prediction.observation) / epsilon;
+            assert!((numerical - prediction.pose_jacobian.column(axis)).norm() < 1e-5);
+        }
+        for axis in 0..2 {
+            let mut perturbed = landmark;
+            perturbed[axis] += epsilon;
+            let numerical =
+                (predict(&pose, &perturbed).observation - prediction.observation) / epsilon;
+            assert!((numerical - prediction.landmark_jacobian.column(axis)).norm() < 1e-5);
+        }
+
+        // The observation Jacobian of the initialisation inverts the landmark Jacobian of the prediction
+        let (_, _, observation_jacobian) = initialize_landmark(
+            &Isometry2::new(pose.xy(), pose.z),
+            &RangeBearingObservation {
+                range: prediction.observation.x,
+                bearing: prediction.observation.y,
+                id: None,
+            },
+        );
+        assert!(
+            (observation_jacobian * prediction.landmark_jacobian - Matrix2::identity()).norm()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let observation = |range: f64, bearing: f64| RangeBearingObservation {
+            range,
+            bearing,
+            id: None,
+        };
+        assert!(observation(1.0, 0.5).is_valid());
+        assert!(!observation(0.0, 0.5).is_valid());
+        assert!(!observation(-1.0, 0.5).is_valid());
+        assert!(!observation(f64::INFINITY, 0.5).is_valid());
+        assert!(!observation(1.0, f64::NAN).is_valid());
+    }
+}
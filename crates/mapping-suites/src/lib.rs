@@ -32,3 +32,50 @@
 extern crate alloc;
 #[cfg(not(feature = "std"))]
 extern crate core;
+
+#[cfg(feature = "std")]
+use std::{
+    boxed::Box,
+    collections::{BinaryHeap, HashMap},
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap as HashMap, BinaryHeap},
+    vec::Vec,
+};
+
+/// An AMCL-style particle filter, localising a robot in a known occupancy grid.
+pub mod amcl;
+
+/// A module containing data association methods, matching landmark observations with mapped landmarks, shared by the landmark SLAM suites.
+pub mod data_association;
+
+/// An extended Kalman filter SLAM suite, mapping point landmarks observed by a range-bearing sensor.
+pub mod ekf_slam;
+
+/// A FastSLAM suite, mapping point landmarks observed by a range-bearing sensor with a Rao-Blackwellised particle filter.
+pub mod fast_slam;
+
+/// A module containing range-bearing landmark observations and their measurement model, shared by the landmark SLAM suites.
+pub mod landmark;
+
+/// A voxelised local map of the robot's surroundings, for scan-to-map registration.
+pub mod local_map;
+
+/// A module containing odometry motion models for planar robots, shared by the particle filter suites.
+pub mod motion_model;
+
+/// A KISS-ICP style LiDAR odometry suite, registering scans to a local voxel map.
+pub mod lidar_odometry;
+
+/// A 2D occupancy grid map, updated by ray casting laser scans.
+pub mod occupancy_grid;
+
+/// A 3D probabilistic occupancy map, stored in an octree.
+pub mod occupancy_octree;
+
+/// A module containing random sampling and resampling functions, shared by the particle filter suites.
+pub mod sampling;
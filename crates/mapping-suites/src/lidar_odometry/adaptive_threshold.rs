@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry3, RealField};
+use num_traits::AsPrimitive;
+
+/// Estimates the correspondence threshold for registration from the deviations between the predicted and registered poses,
+/// so that the threshold grows with the motion model's error instead of being tuned by hand.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveThreshold<T> {
+    initial_threshold: T,
+    min_motion_threshold: T,
+    max_range: T,
+    model_sse: T,
+    num_samples: usize,
+}
+
+impl<T> AdaptiveThreshold<T>
+where
+    T: Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs a threshold estimator without any samples.
+    ///
+    /// # Arguments
+    /// * `initial_threshold`: the threshold used until the first deviation is sampled.
+    /// * `min_motion_threshold`: deviations smaller than this are not sampled, so that standing still does not shrink the threshold.
+    /// * `max_range`: the sensor's range, used to convert rotational deviations into the displacement of the furthest point.
+    ///
+    /// # Returns
+    /// A new [`AdaptiveThreshold`].
+    pub fn new(initial_threshold: T, min_motion_threshold: T, max_range: T) -> Self {
+        Self {
+            initial_threshold,
+            min_motion_threshold,
+            max_range,
+            model_sse: T::zero(),
+            num_samples: 0,
+        }
+    }
+
+    /// Samples the deviation between a predicted pose and the pose found by registration.
+    ///
+    /// # Arguments
+    /// * `deviation`: an [`Isometry3`], the registered pose relative to the predicted pose.
+    pub fn update_model_deviation(&mut self, deviation: &Isometry3<T>) {
+        let two = T::one() + T::one();
+        let rotation_error = two * self.max_range * (deviation.rotation.angle() / two).sin();
+        let model_error = deviation.translation.vector.norm() + rotation_error;
+        if model_error > self.min_motion_threshold {
+            self.model_sse += model_error * model_error;
+            self.num_samples += 1;
+        }
+    }
+
+    /// Returns the current threshold, the root mean square of all sampled deviations.
+    pub fn threshold(&self) -> T {
+        if self.num_samples == 0 {
+            return self.initial_threshold;
+        }
+
+        (self.model_sse / self.num_samples.as_()).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn test_adaptive_threshold() {
+        let mut threshold = AdaptiveThreshold::new(2.0, 0.1, 100.0);
+        assert_eq!(threshold.threshold(), 2.0);
+
+        // Small deviations are ignored
+        threshold.update_model_deviation(&Isometry3::translation(0.05, 0.0, 0.0));
+        assert_eq!(threshold.threshold(), 2.0);
+
+        threshold.update_model_deviation(&Isometry3::translation(0.3, 0.4, 0.0));
+        threshold.update_model_deviation(&Isometry3::translation(0.0, 0.0, 1.0));
+        assert!((threshold.threshold() - 0.625f64.sqrt()).abs() < 1e-12);
+
+        // A rotation displaces the furthest points by the chord of its angle
+        let mut threshold = AdaptiveThreshold::new(2.0, 0.1, 100.0);
+        threshold.update_model_deviation(&Isometry3::new(Vector3::zeros(), Vector3::z() * 0.01));
+        assert!((threshold.threshold() - 200.0 * 0.005f64.sin()).abs() < 1e-9);
+    }
+}
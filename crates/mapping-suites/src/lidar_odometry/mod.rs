@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use mapping_algorithms::point_clouds::{
+    deskew_point_cloud, downsample_point_cloud_voxel_nearest, DeskewError, TimedPose,
+};
+use nalgebra::{Isometry3, Point3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{
+    local_map::{LocalMap, LocalMapConfiguration},
+    Vec,
+};
+
+pub use adaptive_threshold::AdaptiveThreshold;
+pub use types::{LidarOdometryConfiguration, LidarOdometryConfigurationBuilder};
+
+mod adaptive_threshold;
+mod registration;
+mod types;
+
+/// A KISS-ICP style LiDAR odometry suite, registering each scan to a local voxel map.
+///
+/// Each scan is deskewed using a constant velocity motion prior, cropped to the sensor's range and downsampled,
+/// then registered to the local map with robust point-to-point ICP, starting from the motion prior's prediction,
+/// and with a correspondence threshold adapted to the prior's past errors.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct LidarOdometry<T: RealField> {
+    config: LidarOdometryConfiguration<T>,
+    local_map: LocalMap<T>,
+    adaptive_threshold: AdaptiveThreshold<T>,
+    poses: Vec<Isometry3<T>>,
+}
+
+impl<T> LidarOdometry<T>
+where
+    T: AsPrimitive<isize> + AsPrimitive<T> + Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs an odometry suite, starting at the origin with an empty local map.
+    ///
+    /// # Arguments
+    /// * `config`: a [`LidarOdometryConfiguration`], specifying the suite's parameters.
+    ///
+    /// # Returns
+    /// A new [`LidarOdometry`].
+    pub fn new(config: LidarOdometryConfiguration<T>) -> Self {
+        let local_map = LocalMap::new(
+            LocalMapConfiguration::builder()
+                .with_voxel_size(config.voxel_size)
+                .with_max_range(config.max_range)
+                .with_max_points_per_voxel(config.max_points_per_voxel)
+                .build(),
+        );
+        let adaptive_threshold = AdaptiveThreshold::new(
+            config.initial_threshold,
+            config.min_motion_threshold,
+            config.max_range,
+        );
+
+        Self {
+            config,
+            local_map,
+            adaptive_threshold,
+            poses: Vec::new(),
+        }
+    }
+
+    /// Returns the latest pose of the sensor, or the origin if no scans were pushed yet.
+    pub fn pose(&self) -> Isometry3<T> {
+        self.poses
+            .last()
+            .copied()
+            .unwrap_or_else(Isometry3::identity)
+    }
+
+    /// Returns the poses of the sensor at all pushed scans, relative to the first scan.
+    pub fn poses(&self) -> &[Isometry3<T>] {
+        &self.poses
+    }
+
+    /// Returns the local map the scans are registered to.
+    pub fn local_map(&self) -> &LocalMap<T> {
+        &self.local_map
+    }
+
+    /// Returns the motion between the last two scans, the constant velocity motion prior.
+    pub fn velocity(&self) -> Isometry3<T> {
+        match self.poses.as_slice() {
+            [.., previous, current] => previous.inverse() * current,
+            _ => Isometry3::identity(),
+        }
+    }
+
+    /// Pushes a new scan, estimating the sensor's pose at it and adding it to the local map.
+    ///
+    /// # Arguments
+    /// * `points`: a slice of [`Point3`], the scan in the sensor's frame.
+    /// * `timestamps`: optionally, the time each point was measured at, in any units,
+    ///   the scan is assumed to span the time between two scans, and is deskewed to the middle of it.
+    ///
+    /// # Returns
+    /// The sensor's pose at the scan, or a [`DeskewError`] if the amount of timestamps does not match the amount of points.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Push LiDAR Scan", skip_all, level = "info")
+    )]
+    pub fn push_scan(
+        &mut self,
+        points: &[Point3<T>],
+        timestamps: Option<&[T]>,
+    ) -> Result<Isometry3<T>, DeskewError> {
+        let velocity = self.velocity();
+        let deskewed = match timestamps {
+            Some(timestamps) if timestamps.len() != points.len() => {
+                return Err(DeskewError::TimestampLengthMismatch {
+                    expected: points.len(),
+                    actual: timestamps.len(),
+                });
+            }
+            Some(timestamps) if self.config.deskew && self.poses.len() > 1 => {
+                Self::deskew(points, timestamps, velocity)?
+            }
+            _ => points.to_vec(),
+        };
+
+        let cropped = deskewed
+            .into_iter()
+            .filter(|point| {
+                let range = point.coords.norm();
+                range >= self.config.min_range && range <= self.config.max_range
+            })
+            .collect::<Vec<_>>();
+        let half: T = 0.5.as_();
+        let frame = downsample_point_cloud_voxel_nearest::<T, T, 3>(
+            &cropped,
+            self.config.voxel_size * half,
+        )
+        .into_iter()
+        .map(|point_idx| cropped[point_idx])
+        .collect::<Vec<_>>();
+        let source = downsample_point_cloud_voxel_nearest::<T, T, 3>(
+            &frame,
+            self.config.voxel_size * 1.5.as_(),
+        )
+        .into_iter()
+        .map(|point_idx| frame[point_idx])
+        .collect::<Vec<_>>();
+
+        let prediction = self.pose() * velocity;
+        let sigma = self.adaptive_threshold.threshold();
+        let pose = registration::register_scan(
+            &self.local_map,
+            &source,
+            prediction,
+            sigma * 3.0.as_(),
+            sigma / 3.0.as_(),
+            self.config.max_num_iterations,
+            self.config.convergence_criterion,
+        );
+
+        self.adaptive_threshold
+            .update_model_deviation(&(prediction.inverse() * pose));
+        self.local_map.update(&frame, &pose);
+        self.poses.push(pose);
+        Ok(pose)
+    }
+
+    /// Corrects the motion distortion of a scan, assuming the sensor moved by `velocity` at a constant rate between its first and last timestamps.
+    /// The points are moved into the sensor's frame at the middle of the scan.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Deskew LiDAR Scan", skip_all, level = "debug")
+    )]
+    fn deskew(
+        points: &[Point3<T>],
+        timestamps: &[T],
+        velocity: Isometry3<T>,
+    ) -> Result<Vec<Point3<T>>, DeskewError> {
+        let (first, last) = timestamps.iter().fold(
+            (
+                T::max_value().unwrap_or_else(T::one),
+                T::min_value().unwrap_or_else(T::zero),
+            ),
+            |(first, last), &timestamp| (first.min(timestamp), last.max(timestamp)),
+        );
+        // The scan spans exactly the motion between two scans, at a constant velocity
+        deskew_point_cloud(
+            points,
+            timestamps,
+            &TimedPose {
+                time: first,
+                pose: Isometry3::identity(),
+            },
+            &TimedPose {
+                time: last,
+                pose: velocity,
+            },
+            (first + last) * 0.5.as_(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::{PI, TAU};
+    use mapping_algorithms::point_clouds::{generate_point_cloud, interpolate_pose};
+
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    fn world() -> Vec<Point3<f64>> {
+        generate_point_cloud(4000, [-30.0..=30.0, -30.0..=30.0, -3.0..=3.0])
+    }
+
+    fn config() -> LidarOdometryConfigurationBuilder<f64> {
+        LidarOdometryConfiguration::builder()
+            .with_range_limits(1.0, 25.0)
+            .with_voxel_size(0.5)
+    }
+
+    // The sensor moves forward while turning, at a constant velocity of one step per scan
+    fn ground_truth(time: f64) -> Isometry3<f64> {
+        Isometry3::new(
+            Vector3::new(0.6 * time, 0.1 * time, 0.0),
+            Vector3::z() * 0.08 * time,
+        )
+    }
+
+    fn pose_error(estimate: &Isometry3<f64>, truth: &Isometry3<f64>) -> f64 {
+        (estimate.translation.vector - truth.translation.vector).norm()
+            + estimate.rotation.angle_to(&truth.rotation)
+    }
+
+    #[test]
+    fn test_odometry() {
+        let world = world();
+        let mut odometry = LidarOdometry::new(config().build());
+        assert_eq!(odometry.pose(), Isometry3::identity());
+
+        for scan_idx in 0..6 {
+            let truth = ground_truth(scan_idx as f64);
+            let scan = world
+                .iter()
+                .map(|point| truth.inverse_transform_point(point))
+                .collect::<Vec<_>>();
+            let pose = odometry.push_scan(&scan, None).unwrap();
+            assert!(pose_error(&pose, &truth) < 0.01);
+        }
+
+        assert_eq!(odometry.poses().len(), 6);
+        assert!(
+            pose_error(
+                &odometry.velocity(),
+                &(ground_truth(4.0).inverse() * ground_truth(5.0))
+            ) < 0.01
+        );
+        assert!(!odometry.local_map().is_empty());
+    }
+
+    #[test]
+    fn test_deskewed_odometry() {
+        let world = world();
+
+        let mut final_errors = Vec::new();
+        for deskew in [true, false] {
+            let mut odometry = LidarOdometry::new(config().with_deskew(deskew).build());
+
+            let mut final_error = 0.0;
+            for scan_idx in 0..6 {
+                let (start, middle, end) = (
+                    ground_truth(scan_idx as f64 - 0.5),
+                    ground_truth(scan_idx as f64),
+                    ground_truth(scan_idx as f64 + 0.5),
+                );
+                // A rotating sensor measures each point when its beam sweeps past the point's azimuth,
+                // the first scans are taken at once, so the motion prior is known before scans are skewed
+                let timestamps = world
+                    .iter()
+                    .map(|point| {
+                        if scan_idx < 2 {
+                            return 0.5;
+                        }
+                        let local = middle.inverse_transform_point(point);
+                        (local.y.atan2(local.x) + PI) / TAU
+                    })
+                    .collect::<Vec<_>>();
+                let scan = world
+                    .iter()
+                    .zip(timestamps.iter())
+                    .map(|(point, &timestamp)| {
+                        interpolate_pose(&start, &end, timestamp).inverse_transform_point(point)
+                    })
+                    .collect::<Vec<_>>();
+                let pose = odometry.push_scan(&scan, Some(&timestamps)).unwrap();
+                final_error = pose_error(&pose, &middle);
+            }
+            final_errors.push(final_error);
+        }
+
+        assert!(final_errors[0] < 0.01, "{final_errors:?}");
+        assert!(final_errors[0] < final_errors[1], "{final_errors:?}");
+    }
+
+    #[test]
+    fn test_timestamp_mismatch() {
+        let mut odometry = LidarOdometry::new(config().build());
+        assert_eq!(
+            odometry.push_scan(&[Point3::new(1.0, 2.0, 3.0)], Some(&[])),
+            Err(DeskewError::TimestampLengthMismatch {
+                expected: 1,
+                actual: 0
+            })
+        );
+        assert!(odometry.poses().is_empty());
+    }
+}
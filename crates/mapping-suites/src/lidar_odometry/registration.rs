@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry3, Matrix3x6, Matrix6, Point3, RealField, Vector6};
+use num_traits::AsPrimitive;
+
+use crate::{local_map::LocalMap, Vec};
+
+/// Registers a scan to a local map using point-to-point ICP, solved with Gauss-Newton,
+/// with each correspondence weighted by a Geman-McClure kernel to reduce the effect of outliers.
+///
+/// This does not reuse [`icp`](mapping_algorithms::point_clouds::icp), which aligns two point clouds with an unweighted
+/// closed-form solution, pairing every source point with its nearest target point, and stops on the change in MSE.
+/// The odometry instead needs correspondences drawn from the local map's voxels and gated by a distance that the
+/// [`AdaptiveThreshold`](super::AdaptiveThreshold) changes with every scan,
+/// and a robust kernel whose weights are only available to an iteratively reweighted solver.
+///
+/// # Arguments
+/// * `local_map`: a [`LocalMap`], the map to register to.
+/// * `points`: a slice of [`Point3`], the scan in the sensor's frame.
+/// * `initial_guess`: an [`Isometry3`], the predicted pose of the sensor in the map's frame.
+/// * `max_correspondence_distance`: points further than this from their nearest map point are ignored.
+/// * `kernel_scale`: the scale of the Geman-McClure kernel.
+/// * `max_iterations`: the maximum amount of Gauss-Newton iterations.
+/// * `convergence_criterion`: the registration stops once the norm of an update is below this.
+///
+/// # Returns
+/// An [`Isometry3`], the registered pose of the sensor in the map's frame,
+/// or the initial guess if no correspondences were found.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Register Scan To Local Map", skip_all, level = "debug")
+)]
+pub(crate) fn register_scan<T>(
+    local_map: &LocalMap<T>,
+    points: &[Point3<T>],
+    initial_guess: Isometry3<T>,
+    max_correspondence_distance: T,
+    kernel_scale: T,
+    max_iterations: usize,
+    convergence_criterion: T,
+) -> Isometry3<T>
+where
+    T: AsPrimitive<isize> + Copy + RealField,
+{
+    let mut estimate = initial_guess;
+    let mut transformed = points
+        .iter()
+        .map(|point| estimate * point)
+        .collect::<Vec<_>>();
+    let kernel_squared = kernel_scale * kernel_scale;
+
+    for _ in 0..max_iterations {
+        let correspondences = local_map.correspondences(&transformed, max_correspondence_distance);
+        if correspondences.is_empty() {
+            break;
+        }
+
+        let mut hessian = Matrix6::zeros();
+        let mut gradient = Vector6::zeros();
+        for (point_idx, target) in correspondences {
+            let source = transformed[point_idx];
+            let residual = source - target;
+            let mut jacobian = Matrix3x6::zeros();
+            jacobian.fixed_view_mut::<3, 3>(0, 0).fill_with_identity();
+            jacobian
+                .fixed_view_mut::<3, 3>(0, 3)
+                .copy_from(&(-source.coords.cross_matrix()));
+
+            let denominator = kernel_scale + residual.norm_squared();
+            let weight = kernel_squared / (denominator * denominator);
+            let weighted_transpose = jacobian.transpose() * weight;
+            hessian += weighted_transpose * jacobian;
+            gradient += weighted_transpose * residual;
+        }
+
+        let Some(cholesky) = hessian.cholesky() else {
+            log::warn!("Degenerate registration problem, keeping the current estimate");
+            break;
+        };
+        let update = -cholesky.solve(&gradient);
+        let delta = Isometry3::new(
+            update.fixed_rows::<3>(0).into_owned(),
+            update.fixed_rows::<3>(3).into_owned(),
+        );
+        estimate = delta * estimate;
+        transformed
+            .iter_mut()
+            .for_each(|point| *point = delta * *point);
+
+        if update.norm() < convergence_criterion {
+            break;
+        }
+    }
+
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use mapping_algorithms::point_clouds::generate_point_cloud;
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::local_map::LocalMapConfiguration;
+
+    #[test]
+    fn test_register_scan() {
+        let world = generate_point_cloud(2000, [-10.0..=10.0, -10.0..=10.0, -2.0..=2.0]);
+        let mut local_map = LocalMap::new(
+            LocalMapConfiguration::builder()
+                .with_voxel_size(0.5)
+                .with_max_points_per_voxel(100)
+                .build(),
+        );
+        local_map.add_points(&world);
+
+        let pose = Isometry3::new(Vector3::new(0.3, -0.2, 0.05), Vector3::z() * 0.05);
+        let scan = world
+            .iter()
+            .map(|point| pose.inverse_transform_point(point))
+            .collect::<Vec<_>>();
+
+        let registered = register_scan(
+            &local_map,
+            &scan,
+            Isometry3::identity(),
+            1.0,
+            0.3,
+            100,
+            1e-8,
+        );
+        assert!((registered.translation.vector - pose.translation.vector).norm() < 1e-6);
+        assert!(registered.rotation.angle_to(&pose.rotation) < 1e-6);
+
+        // Nothing to register against
+        local_map.clear();
+        let guess = Isometry3::translation(1.0, 0.0, 0.0);
+        assert_eq!(
+            register_scan(&local_map, &scan, guess, 1.0, 0.3, 100, 1e-8),
+            guess
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use num_traits::AsPrimitive;
+
+/// A struct specifying configuration options for the [`LidarOdometry`](crate::lidar_odometry::LidarOdometry) suite.
+#[derive(Clone, Debug)]
+pub struct LidarOdometryConfiguration<T> {
+    /// Points further than this from the sensor are discarded, this is also the local map's radius.
+    pub(crate) max_range: T,
+    /// Points closer than this to the sensor are discarded, usually hitting the vehicle itself.
+    pub(crate) min_range: T,
+    /// The local map's voxel size, scans are downsampled to half of it for the map, and to one and a half of it for registration.
+    pub(crate) voxel_size: T,
+    /// The maximum amount of points stored in each voxel of the local map.
+    pub(crate) max_points_per_voxel: usize,
+    /// The correspondence threshold used before any motion was observed.
+    pub(crate) initial_threshold: T,
+    /// Deviations of the motion prior smaller than this are not used to adapt the correspondence threshold.
+    pub(crate) min_motion_threshold: T,
+    /// The maximum amount of registration iterations for each scan.
+    pub(crate) max_num_iterations: usize,
+    /// The registration stops once the norm of an update is below this.
+    pub(crate) convergence_criterion: T,
+    /// Whether scans with timestamps are deskewed using the constant velocity motion prior.
+    pub(crate) deskew: bool,
+}
+
+impl<T: 'static + Copy> LidarOdometryConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values are tuned for an automotive LiDAR, with a range of 100 metres.
+    ///
+    /// # Returns
+    /// A [`LidarOdometryConfigurationBuilder`].
+    pub fn builder() -> LidarOdometryConfigurationBuilder<T> {
+        LidarOdometryConfigurationBuilder {
+            _internal: LidarOdometryConfiguration {
+                max_range: 100.0.as_(),
+                min_range: 0.0.as_(),
+                voxel_size: 1.0.as_(),
+                max_points_per_voxel: 20,
+                initial_threshold: 2.0.as_(),
+                min_motion_threshold: 0.1.as_(),
+                max_num_iterations: 500,
+                convergence_criterion: 0.0001.as_(),
+                deskew: true,
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`LidarOdometryConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct LidarOdometryConfigurationBuilder<T> {
+    _internal: LidarOdometryConfiguration<T>,
+}
+
+impl<T: Copy> LidarOdometryConfigurationBuilder<T> {
+    /// The range limits of the sensor, points outside of them are discarded.
+    ///
+    /// # Arguments
+    /// * `min_range`: The minimum distance from the sensor.
+    /// * `max_range`: The maximum distance from the sensor, also used as the local map's radius.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_range_limits(&self, min_range: T, max_range: T) -> Self {
+        Self {
+            _internal: LidarOdometryConfiguration {
+                min_range,
+                max_range,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The voxel size of the local map, usually a hundredth of the sensor's range.
+    ///
+    /// # Arguments
+    /// * `voxel_size`: The voxel size.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_voxel_size(&self, voxel_size: T) -> Self {
+        Self {
+            _internal: LidarOdometryConfiguration {
+                voxel_size,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The maximum amount of points stored in each voxel of the local map.
+    ///
+    /// # Arguments
+    /// * `max_points_per_voxel`: The maximum number of points per voxel.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_points_per_voxel(&self, max_points_per_voxel: usize) -> Self {
+        Self {
+            _internal: LidarOdometryConfiguration {
+                max_points_per_voxel,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The parameters of the adaptive correspondence threshold.
+    ///
+    /// # Arguments
+    /// * `initial_threshold`: The threshold used before any motion was observed.
+    /// * `min_motion_threshold`: Deviations of the motion prior smaller than this are ignored.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_adaptive_threshold(&self, initial_threshold: T, min_motion_threshold: T) -> Self {
+        Self {
+            _internal: LidarOdometryConfiguration {
+                initial_threshold,
+                min_motion_threshold,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The stopping conditions of the registration.
+    ///
+    /// # Arguments
+    /// * `max_num_iterations`: The maximum amount of iterations for each scan.
+    /// * `convergence_criterion`: The registration stops once the norm of an update is below this.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_registration_limits(
+        &self,
+        max_num_iterations: usize,
+        convergence_criterion: T,
+    ) -> Self {
+        Self {
+            _internal: LidarOdometryConfiguration {
+                max_num_iterations,
+                convergence_criterion,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Whether scans with timestamps are deskewed before registration.
+    ///
+    /// # Arguments
+    /// * `deskew`: Whether to deskew scans.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_deskew(&self, deskew: bool) -> Self {
+        Self {
+            _internal: LidarOdometryConfiguration {
+                deskew,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`LidarOdometryConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`LidarOdometryConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> LidarOdometryConfiguration<T> {
+        self._internal.clone()
+    }
+}
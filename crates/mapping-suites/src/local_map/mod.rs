@@ -0,0 +1,334 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry3, Point3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{HashMap, Vec};
+
+pub use types::{LocalMapConfiguration, LocalMapConfigurationBuilder};
+
+mod types;
+
+/// A voxelised map of the robot's surroundings, accumulating registered scans for scan-to-map registration.
+///
+/// Each voxel stores a bounded amount of points, voxels far away from the robot are pruned,
+/// and nearest neighbours are found by searching the voxels surrounding a query point.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct LocalMap<T: RealField> {
+    config: LocalMapConfiguration<T>,
+    voxels: HashMap<[isize; 3], Vec<Point3<T>>>,
+}
+
+impl<T> LocalMap<T>
+where
+    T: AsPrimitive<isize> + Copy + RealField,
+{
+    /// Constructs an empty map.
+    ///
+    /// # Arguments
+    /// * `config`: a [`LocalMapConfiguration`], specifying the voxel size and the map's bounds.
+    ///
+    /// # Returns
+    /// An empty [`LocalMap`].
+    pub fn new(config: LocalMapConfiguration<T>) -> Self {
+        Self {
+            config,
+            voxels: HashMap::new(),
+        }
+    }
+
+    /// Returns the configuration the map was constructed with.
+    pub fn config(&self) -> &LocalMapConfiguration<T> {
+        &self.config
+    }
+
+    /// Returns the edge length of the map's voxels.
+    pub fn voxel_size(&self) -> T {
+        self.config.voxel_size
+    }
+
+    /// Returns the amount of points in the map.
+    pub fn len(&self) -> usize {
+        self.voxels.values().map(Vec::len).sum()
+    }
+
+    /// Returns whether the map contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// Returns the amount of occupied voxels in the map.
+    pub fn num_voxels(&self) -> usize {
+        self.voxels.len()
+    }
+
+    /// Removes all points from the map.
+    pub fn clear(&mut self) {
+        self.voxels.clear();
+    }
+
+    /// Returns a copy of all points in the map.
+    pub fn points(&self) -> Vec<Point3<T>> {
+        self.voxels.values().flatten().copied().collect()
+    }
+
+    #[inline]
+    fn voxel_index(&self, point: &Point3<T>) -> [isize; 3] {
+        [0, 1, 2].map(|axis| (point[axis] / self.config.voxel_size).floor().as_())
+    }
+
+    /// Adds points to the map, voxels that are already full discard any new points.
+    ///
+    /// # Arguments
+    /// * `points`: a slice of [`Point3`], the points in the map's frame.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Add Points To Local Map", skip_all, level = "debug")
+    )]
+    pub fn add_points(&mut self, points: &[Point3<T>]) {
+        for point in points {
+            let voxel = self.voxels.entry(self.voxel_index(point)).or_default();
+            if voxel.len() < self.config.max_points_per_voxel.max(1) {
+                voxel.push(*point);
+            }
+        }
+    }
+
+    /// Removes all voxels that are out of range of the robot, and if the map has too many voxels, the furthest voxels as well.
+    /// The distance of each voxel is that of the first point it received.
+    ///
+    /// # Arguments
+    /// * `origin`: a [`Point3`], the robot's current position.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Prune Local Map", skip_all, level = "debug")
+    )]
+    pub fn remove_far_voxels(&mut self, origin: &Point3<T>) {
+        let max_range_squared = self.config.max_range * self.config.max_range;
+        self.voxels
+            .retain(|_, points| (points[0] - origin).norm_squared() <= max_range_squared);
+
+        let Some(max_voxels) = self.config.max_voxels else {
+            return;
+        };
+        if self.voxels.len() <= max_voxels {
+            return;
+        }
+
+        let mut distances = self
+            .voxels
+            .iter()
+            .map(|(index, points)| ((points[0] - origin).norm_squared(), *index))
+            .collect::<Vec<_>>();
+        distances.select_nth_unstable_by(max_voxels, |(first, _), (second, _)| {
+            first
+                .partial_cmp(second)
+                .unwrap_or(core::cmp::Ordering::Equal)
+        });
+        for (_, index) in distances[max_voxels..].iter() {
+            self.voxels.remove(index);
+        }
+    }
+
+    /// Adds a registered scan to the map and prunes the map around the robot's new pose.
+    ///
+    /// # Arguments
+    /// * `points`: a slice of [`Point3`], the scan in the sensor's frame.
+    /// * `pose`: an [`Isometry3`], the sensor's pose in the map's frame.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Update Local Map", skip_all, level = "debug")
+    )]
+    pub fn update(&mut self, points: &[Point3<T>], pose: &Isometry3<T>) {
+        let transformed = points.iter().map(|point| pose * point).collect::<Vec<_>>();
+        self.add_points(&transformed);
+        self.remove_far_voxels(&pose.translation.vector.into());
+    }
+
+    /// Finds the closest map point to a query point, searching the query's voxel and all voxels adjacent to it.
+    /// The result is guaranteed to be the true nearest neighbour if it is closer than the voxel size.
+    ///
+    /// # Arguments
+    /// * `query`: a [`Point3`], the point to search around, in the map's frame.
+    ///
+    /// # Returns
+    /// [`Some`] containing the closest point and its distance from the query, or [`None`] if the surrounding voxels are empty.
+    pub fn nearest_neighbour(&self, query: &Point3<T>) -> Option<(Point3<T>, T)> {
+        let [x, y, z] = self.voxel_index(query);
+        let mut nearest: Option<(Point3<T>, T)> = None;
+        for offset_x in -1..=1 {
+            for offset_y in -1..=1 {
+                for offset_z in -1..=1 {
+                    let Some(points) = self.voxels.get(&[x + offset_x, y + offset_y, z + offset_z])
+                    else {
+                        continue;
+                    };
+                    for point in points {
+                        let distance_squared = (point - query).norm_squared();
+                        if nearest
+                            .is_none_or(|(_, nearest_distance)| distance_squared < nearest_distance)
+                        {
+                            nearest = Some((*point, distance_squared));
+                        }
+                    }
+                }
+            }
+        }
+
+        nearest.map(|(point, distance_squared)| (point, distance_squared.sqrt()))
+    }
+
+    /// Finds correspondences between a scan and the map, for scan-to-map registration.
+    ///
+    /// # Arguments
+    /// * `points`: a slice of [`Point3`], the scan in the map's frame.
+    /// * `max_distance`: the maximum distance between a point and its nearest map point for the pair to be used.
+    ///
+    /// # Returns
+    /// A [`Vec`] of tuples, each containing the index of a scan point and its nearest map point.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Find Local Map Correspondences", skip_all, level = "debug")
+    )]
+    pub fn correspondences(
+        &self,
+        points: &[Point3<T>],
+        max_distance: T,
+    ) -> Vec<(usize, Point3<T>)> {
+        points
+            .iter()
+            .enumerate()
+            .filter_map(|(point_idx, point)| {
+                self.nearest_neighbour(point)
+                    .filter(|(_, distance)| *distance <= max_distance)
+                    .map(|(nearest, _)| (point_idx, nearest))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    fn config() -> LocalMapConfigurationBuilder<f64> {
+        LocalMapConfiguration::builder()
+            .with_voxel_size(1.0)
+            .with_max_range(10.0)
+            .with_max_points_per_voxel(3)
+    }
+
+    #[test]
+    fn test_add_points() {
+        let mut map = LocalMap::new(config().build());
+        assert!(map.is_empty());
+        map.add_points(&[
+            Point3::new(0.1, 0.1, 0.1),
+            Point3::new(0.2, 0.2, 0.2),
+            Point3::new(0.3, 0.3, 0.3),
+            Point3::new(0.4, 0.4, 0.4),
+            Point3::new(-0.1, 0.1, 0.1),
+        ]);
+        // The first voxel is full after three points
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.num_voxels(), 2);
+        assert!(!map.points().contains(&Point3::new(0.4, 0.4, 0.4)));
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_update_and_prune() {
+        let mut map = LocalMap::new(config().build());
+        let points = (0..30)
+            .map(|x| Point3::new(x as f64, 0.5, 0.5))
+            .collect::<Vec<_>>();
+        map.update(&points, &Isometry3::identity());
+        assert_eq!(map.num_voxels(), 10);
+
+        // Moving forward drops the voxels left behind
+        map.update(&points, &Isometry3::translation(15.0, 0.0, 0.0));
+        assert!(map
+            .points()
+            .iter()
+            .all(|point| (point.x - 15.0).abs() <= 10.0));
+        assert_eq!(map.num_voxels(), 14);
+
+        let mut capped = LocalMap::new(config().with_max_voxels(Some(4)).build());
+        capped.update(&points, &Isometry3::translation(5.0, 0.0, 0.0));
+        let mut remaining = capped
+            .points()
+            .iter()
+            .map(|point| point.x)
+            .collect::<Vec<_>>();
+        remaining.sort_by(f64::total_cmp);
+        assert_eq!(remaining, [5.0, 6.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn test_nearest_neighbour() {
+        let mut map = LocalMap::new(config().with_max_points_per_voxel(100).build());
+        let points = (0..200)
+            .map(|idx| {
+                let value = idx as f64;
+                Point3::new(
+                    (value * 0.37) % 5.0,
+                    (value * 0.61) % 5.0,
+                    (value * 0.13) % 5.0,
+                )
+            })
+            .collect::<Vec<_>>();
+        map.add_points(&points);
+
+        for query in [
+            Point3::new(2.5, 2.5, 2.5),
+            Point3::new(0.05, 4.9, 1.0),
+            Point3::new(-0.5, 0.0, 0.0),
+        ] {
+            let (nearest, distance) = map.nearest_neighbour(&query).unwrap();
+            let expected = points
+                .iter()
+                .map(|point| (point - query).norm())
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(distance, expected);
+            assert_eq!((nearest - query).norm(), distance);
+        }
+        assert_eq!(map.nearest_neighbour(&Point3::new(20.0, 0.0, 0.0)), None);
+
+        let scan = [Point3::new(2.5, 2.5, 2.5), Point3::new(20.0, 0.0, 0.0)];
+        let correspondences = map.correspondences(&scan, 0.5);
+        assert_eq!(correspondences.len(), 1);
+        assert_eq!(correspondences[0].0, 0);
+        assert!((correspondences[0].1 - scan[0]).norm() <= 0.5);
+        assert!(map
+            .correspondences(&[scan[0] + Vector3::x() * 0.01], 0.0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use num_traits::AsPrimitive;
+
+/// A struct specifying configuration options for a [`LocalMap`](crate::local_map::LocalMap).
+#[derive(Clone, Debug)]
+pub struct LocalMapConfiguration<T> {
+    /// The edge length of each voxel.
+    pub(crate) voxel_size: T,
+    /// Voxels further than this from the robot are pruned.
+    pub(crate) max_range: T,
+    /// The maximum amount of points stored in each voxel, further points are discarded.
+    pub(crate) max_points_per_voxel: usize,
+    /// The maximum amount of voxels in the map, the furthest voxels are pruned when exceeded.
+    pub(crate) max_voxels: Option<usize>,
+}
+
+impl<T: 'static + Copy> LocalMapConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values are tuned for an automotive LiDAR, with a range of 100 metres.
+    ///
+    /// # Returns
+    /// A [`LocalMapConfigurationBuilder`].
+    pub fn builder() -> LocalMapConfigurationBuilder<T> {
+        LocalMapConfigurationBuilder {
+            _internal: LocalMapConfiguration {
+                voxel_size: 1.0.as_(),
+                max_range: 100.0.as_(),
+                max_points_per_voxel: 20,
+                max_voxels: None,
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`LocalMapConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct LocalMapConfigurationBuilder<T> {
+    _internal: LocalMapConfiguration<T>,
+}
+
+impl<T: Copy> LocalMapConfigurationBuilder<T> {
+    /// The edge length of each voxel, nearest neighbour queries are exact up to this distance.
+    ///
+    /// # Arguments
+    /// * `voxel_size`: The voxel size, must be positive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_voxel_size(&self, voxel_size: T) -> Self {
+        Self {
+            _internal: LocalMapConfiguration {
+                voxel_size,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The radius around the robot in which voxels are kept.
+    ///
+    /// # Arguments
+    /// * `max_range`: The maximum distance from the robot, usually the sensor's range.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_range(&self, max_range: T) -> Self {
+        Self {
+            _internal: LocalMapConfiguration {
+                max_range,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The maximum amount of points stored in each voxel, keeping the map's density bounded.
+    ///
+    /// # Arguments
+    /// * `max_points_per_voxel`: The maximum number of points per voxel.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_points_per_voxel(&self, max_points_per_voxel: usize) -> Self {
+        Self {
+            _internal: LocalMapConfiguration {
+                max_points_per_voxel,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The maximum amount of voxels in the map, bounding its memory regardless of the environment.
+    ///
+    /// # Arguments
+    /// * `max_voxels`: The maximum number of voxels, or [`None`] to only bound the map by its range.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_voxels(&self, max_voxels: Option<usize>) -> Self {
+        Self {
+            _internal: LocalMapConfiguration {
+                max_voxels,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`LocalMapConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`LocalMapConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> LocalMapConfiguration<T> {
+        self._internal.clone()
+    }
+}
@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Isometry2, Matrix3, RealField, Vector2, Vector3};
+use num_traits::AsPrimitive;
+use rand::Rng;
+
+use crate::sampling::{normalize_angle, sample_normal};
+
+/// The noise parameters of an odometry motion model, following the `α` parameters of Probabilistic Robotics.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OdometryNoise<T> {
+    /// The rotational noise caused by rotation.
+    pub alpha1: T,
+    /// The rotational noise caused by translation.
+    pub alpha2: T,
+    /// The translational noise caused by translation.
+    pub alpha3: T,
+    /// The translational noise caused by rotation.
+    pub alpha4: T,
+    /// The strafing noise caused by translation, only used by omnidirectional robots.
+    pub alpha5: T,
+}
+
+impl<T: 'static + Copy> OdometryNoise<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns noise parameters with all `α` equal, a common starting point for tuning.
+    ///
+    /// # Arguments
+    /// * `alpha`: the value of all noise parameters.
+    ///
+    /// # Returns
+    /// A new [`OdometryNoise`].
+    pub fn uniform(alpha: T) -> Self {
+        Self {
+            alpha1: alpha,
+            alpha2: alpha,
+            alpha3: alpha,
+            alpha4: alpha,
+            alpha5: alpha,
+        }
+    }
+}
+
+/// An odometry motion model for a planar robot, propagating a pose by the motion between two odometry readings.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OdometryMotionModel<T> {
+    /// A differential drive robot, whose motion is decomposed into a rotation, a translation and a second rotation.
+    DifferentialDrive(OdometryNoise<T>),
+    /// An omnidirectional robot, whose motion is decomposed into a translation, a strafe and a rotation.
+    Omnidirectional(OdometryNoise<T>),
+}
+
+/// The linearised prediction of an odometry motion model, as used by Kalman filter based suites.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionPrediction<T: RealField> {
+    /// The noiseless propagated pose.
+    pub pose: Isometry2<T>,
+    /// The Jacobian of the propagated `x`, `y` and heading, with respect to the original pose.
+    pub jacobian: Matrix3<T>,
+    /// The covariance of the motion noise, in the propagated pose's `x`, `y` and heading.
+    pub covariance: Matrix3<T>,
+}
+
+impl<T> OdometryMotionModel<T>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+{
+    /// Returns the model's noise parameters.
+    pub fn noise(&self) -> &OdometryNoise<T> {
+        match self {
+            Self::DifferentialDrive(noise) | Self::Omnidirectional(noise) => noise,
+        }
+    }
+
+    /// Decomposes the motion between two odometry readings into the model's three increments,
+    /// the first rotation, translation and second rotation for a differential drive robot,
+    /// or the direction of translation relative to the heading, translation and rotation for an omnidirectional robot.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute Odometry Increments", skip_all, level = "trace")
+    )]
+    fn increments(
+        &self,
+        previous_odometry: &Isometry2<T>,
+        current_odometry: &Isometry2<T>,
+    ) -> [T; 3] {
+        let delta = current_odometry.translation.vector - previous_odometry.translation.vector;
+        let translation = delta.norm();
+        let previous_yaw = previous_odometry.rotation.angle();
+        let rotation = normalize_angle(current_odometry.rotation.angle() - previous_yaw);
+
+        match self {
+            Self::DifferentialDrive(_) => {
+                // Very short motions have no meaningful heading, so all rotation is attributed to the second turn
+                let first_rotation = if translation < 0.01.as_() {
+                    T::zero()
+                } else {
+                    normalize_angle(delta.y.atan2(delta.x) - previous_yaw)
+                };
+                [
+                    first_rotation,
+                    translation,
+                    normalize_angle(rotation - first_rotation),
+                ]
+            }
+            Self::Omnidirectional(_) => {
+                let direction = if translation > T::zero() {
+                    normalize_angle(delta.y.atan2(delta.x) - previous_yaw)
+                } else {
+                    T::zero()
+                };
+                [direction, translation, rotation]
+            }
+        }
+    }
+
+    /// Returns the variances of the model's noise, for the first rotation, translation and second rotation of a differential drive robot,
+    /// or for the translation, strafe and rotation of an omnidirectional robot.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Compute Odometry Noise Variances", skip_all, level = "trace")
+    )]
+    fn variances(&self, increments: &[T; 3]) -> [T; 3] {
+        let noise = self.noise();
+        match self {
+            Self::DifferentialDrive(_) => {
+                let [first_rotation, translation, second_rotation] = *increments;
+                // Driving backwards should not be considered a large rotation
+                let first_rotation = first_rotation
+                    .abs()
+                    .min(normalize_angle(first_rotation - T::pi()).abs());
+                let second_rotation = second_rotation
+                    .abs()
+                    .min(normalize_angle(second_rotation - T::pi()).abs());
+
+                [
+                    noise.alpha1 * first_rotation * first_rotation
+                        + noise.alpha2 * translation * translation,
+                    noise.alpha3 * translation * translation
+                        + noise.alpha4
+                            * (first_rotation * first_rotation + second_rotation * second_rotation),
+                    noise.alpha1 * second_rotation * second_rotation
+                        + noise.alpha2 * translation * translation,
+                ]
+            }
+            Self::Omnidirectional(_) => {
+                let [_, translation, rotation] = *increments;
+                [
+                    noise.alpha3 * translation * translation + noise.alpha1 * rotation * rotation,
+                    noise.alpha1 * rotation * rotation + noise.alpha5 * translation * translation,
+                    noise.alpha4 * rotation * rotation + noise.alpha2 * translation * translation,
+                ]
+            }
+        }
+    }
+
+    /// Draws a new pose, by applying the motion between two odometry readings to a pose, with noise drawn from the model.
+    ///
+    /// # Arguments
+    /// * `pose`: an [`Isometry2`], the pose to propagate, usually a particle's.
+    /// * `previous_odometry`: an [`Isometry2`], the odometry reading at `pose`.
+    /// * `current_odometry`: an [`Isometry2`], the current odometry reading.
+    /// * `rng`: the random number generator to draw the noise from.
+    ///
+    /// # Generics
+    /// * `R`: A random number generator.
+    ///
+    /// # Returns
+    /// An [`Isometry2`], the propagated pose.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Sample Odometry Motion", skip_all, level = "trace")
+    )]
+    pub fn sample<R: Rng + ?Sized>(
+        &self,
+        pose: &Isometry2<T>,
+        previous_odometry: &Isometry2<T>,
+        current_odometry: &Isometry2<T>,
+        rng: &mut R,
+    ) -> Isometry2<T> {
+        let increments = self.increments(previous_odometry, current_odometry);
+        let variances = self.variances(&increments);
+        let yaw = pose.rotation.angle();
+
+        match self {
+            Self::DifferentialDrive(_) => {
+                let [first_rotation, translation, second_rotation] = increments;
+                let first_rotation_hat = first_rotation - sample_normal(rng, variances[0].sqrt());
+                let translation_hat = translation - sample_normal(rng, variances[1].sqrt());
+                let second_rotation_hat = second_rotation - sample_normal(rng, variances[2].sqrt());
+
+                let heading = yaw + first_rotation_hat;
+                Isometry2::new(
+                    pose.translation.vector
+                        + Vector2::new(heading.cos(), heading.sin()) * translation_hat,
+                    yaw + first_rotation_hat + second_rotation_hat,
+                )
+            }
+            Self::Omnidirectional(_) => {
+                let [direction, translation, rotation] = increments;
+                let translation_hat = translation + sample_normal(rng, variances[0].sqrt());
+                let rotation_hat = rotation + sample_normal(rng, variances[2].sqrt());
+                let strafe_hat = sample_normal(rng, variances[1].sqrt());
+
+                let (sin, cos) = (direction + yaw).sin_cos();
+                Isometry2::new(
+                    pose.translation.vector
+                        + Vector2::new(
+                            translation_hat * cos + strafe_hat * sin,
+                            translation_hat * sin - strafe_hat * cos,
+                        ),
+                    yaw + rotation_hat,
+                )
+            }
+        }
+    }
+
+    /// Applies the motion between two odometry readings to a pose without noise,
+    /// linearising the model around it, for use by Kalman filters.
+    ///
+    /// # Arguments
+    /// * `pose`: an [`Isometry2`], the pose to propagate.
+    /// * `previous_odometry`: an [`Isometry2`], the odometry reading at `pose`.
+    /// * `current_odometry`: an [`Isometry2`], the current odometry reading.
+    ///
+    /// # Returns
+    /// A [`MotionPrediction`], containing the propagated pose, its Jacobian and the motion noise's covariance.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Predict Odometry Motion", skip_all, level = "debug")
+    )]
+    pub fn predict(
+        &self,
+        pose: &Isometry2<T>,
+        previous_odometry: &Isometry2<T>,
+        current_odometry: &Isometry2<T>,
+    ) -> MotionPrediction<T> {
+        let increments = self.increments(previous_odometry, current_odometry);
+        let variances = Matrix3::from_diagonal(&Vector3::from(self.variances(&increments)));
+        let yaw = pose.rotation.angle();
+        let translation = increments[1];
+
+        // Both models translate along a direction relative to the heading, and then rotate
+        let (direction, rotation, noise_jacobian) = match self {
+            Self::DifferentialDrive(_) => {
+                let [first_rotation, _, second_rotation] = increments;
+                let (sin, cos) = (yaw + first_rotation).sin_cos();
+                (
+                    first_rotation,
+                    first_rotation + second_rotation,
+                    Matrix3::new(
+                        -translation * sin,
+                        cos,
+                        T::zero(),
+                        translation * cos,
+                        sin,
+                        T::zero(),
+                        T::one(),
+                        T::zero(),
+                        T::one(),
+                    ),
+                )
+            }
+            Self::Omnidirectional(_) => {
+                let [direction, _, rotation] = increments;
+                let (sin, cos) = (yaw + direction).sin_cos();
+                (
+                    direction,
+                    rotation,
+                    Matrix3::new(
+                        cos,
+                        sin,
+                        T::zero(),
+                        sin,
+                        -cos,
+                        T::zero(),
+                        T::zero(),
+                        T::zero(),
+                        T::one(),
+                    ),
+                )
+            }
+        };
+
+        let (sin, cos) = (yaw + direction).sin_cos();
+        let mut jacobian = Matrix3::identity();
+        jacobian[(0, 2)] = -translation * sin;
+        jacobian[(1, 2)] = translation * cos;
+
+        MotionPrediction {
+            pose: Isometry2::new(
+                pose.translation.vector + Vector2::new(cos, sin) * translation,
+                yaw + rotation,
+            ),
+            jacobian,
+            covariance: noise_jacobian * variances * noise_jacobian.transpose(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::Vec;
+
+    fn assert_pose_eq(first: &Isometry2<f64>, second: &Isometry2<f64>, tolerance: f64) {
+        assert!(
+            (first.translation.vector - second.translation.vector).norm() < tolerance,
+            "{first} != {second}"
+        );
+        assert!(
+            first.rotation.angle_to(&second.rotation).abs() < tolerance,
+            "{first} != {second}"
+        );
+    }
+
+    #[test]
+    fn test_noiseless_motion() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let previous = Isometry2::new(Vector2::new(1.0, 2.0), 0.3);
+        // Odometry drifted from the true pose, but the relative motion is still applied in the pose's frame
+        let pose = Isometry2::new(Vector2::new(-1.0, 0.5), 1.2);
+        for motion in [
+            Isometry2::new(Vector2::new(0.5, 0.1), 0.2),
+            Isometry2::new(Vector2::new(-0.4, 0.0), -0.1),
+            Isometry2::new(Vector2::new(0.0, 0.0), 0.5),
+        ] {
+            let current = previous * motion;
+            for model in [
+                OdometryMotionModel::DifferentialDrive(OdometryNoise::uniform(0.0)),
+                OdometryMotionModel::Omnidirectional(OdometryNoise::uniform(0.0)),
+            ] {
+                assert_pose_eq(
+                    &model.sample(&pose, &previous, &current, &mut rng),
+                    &(pose * motion),
+                    1e-9,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_noisy_motion() {
+        let mut rng = SmallRng::seed_from_u64(2);
+        let previous = Isometry2::identity();
+        let current = Isometry2::new(Vector2::new(1.0, 0.0), 0.0);
+        for model in [
+            OdometryMotionModel::DifferentialDrive(OdometryNoise::uniform(0.05)),
+            OdometryMotionModel::Omnidirectional(OdometryNoise::uniform(0.05)),
+        ] {
+            let samples = (0..2000)
+                .map(|_| model.sample(&previous, &previous, &current, &mut rng))
+                .collect::<Vec<_>>();
+            let mean_x = samples.iter().map(|pose| pose.translation.x).sum::<f64>() / 2000.0;
+            let spread_x = (samples
+                .iter()
+                .map(|pose| (pose.translation.x - mean_x).powi(2))
+                .sum::<f64>()
+                / 2000.0)
+                .sqrt();
+            // Heading noise shortens the mean forward motion slightly
+            assert!((mean_x - 1.0).abs() < 0.05);
+            // The translational noise is `sqrt(alpha3) * translation`
+            assert!((spread_x - 0.05f64.sqrt()).abs() < 0.03, "{spread_x}");
+            assert!(samples.iter().any(|pose| pose.rotation.angle() != 0.0));
+        }
+    }
+
+    #[test]
+    fn test_predict() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        let previous = Isometry2::new(Vector2::new(1.0, 2.0), 0.3);
+        let current = previous * Isometry2::new(Vector2::new(0.8, 0.3), 0.4);
+        let pose = Isometry2::new(Vector2::new(-1.0, 0.5), 1.2);
+        for model in [
+            OdometryMotionModel::DifferentialDrive(OdometryNoise::uniform(0.01)),
+            OdometryMotionModel::Omnidirectional(OdometryNoise::uniform(0.01)),
+        ] {
+            let prediction = model.predict(&pose, &previous, &current);
+            assert_pose_eq(&prediction.pose, &(pose * previous.inv_mul(&current)), 1e-9);
+
+            // Only the heading affects the propagated position
+            let epsilon = 1e-6;
+            let perturbed = model.predict(
+                &Isometry2::new(pose.translation.vector, pose.rotation.angle() + epsilon),
+                &previous,
+                &current,
+            );
+            let numerical =
+                (perturbed.pose.translation.vector - prediction.pose.translation.vector) / epsilon;
+            assert!((numerical.x - prediction.jacobian[(0, 2)]).abs() < 1e-4);
+            assert!((numerical.y - prediction.jacobian[(1, 2)]).abs() < 1e-4);
+
+            // The linearised covariance should match the spread of the sampled poses
+            let samples = (0..5000)
+                .map(|_| {
+                    let sample = model.sample(&pose, &previous, &current, &mut rng);
+                    let mut error = Vector3::new(sample.translation.x, sample.translation.y, 0.0)
+                        - Vector3::new(
+                            prediction.pose.translation.x,
+                            prediction.pose.translation.y,
+                            0.0,
+                        );
+                    error.z = prediction.pose.rotation.angle_to(&sample.rotation);
+                    error
+                })
+                .collect::<Vec<_>>();
+            let covariance = samples.iter().fold(Matrix3::zeros(), |acc, error| {
+                acc + error * error.transpose()
+            }) / 5000.0;
+            assert!(
+                (covariance - prediction.covariance).norm() < 0.1 * prediction.covariance.norm(),
+                "{covariance} != {}",
+                prediction.covariance
+            );
+        }
+    }
+}
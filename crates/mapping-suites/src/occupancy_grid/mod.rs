@@ -0,0 +1,459 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use mapping_algorithms::lines::plot_bresenham_line;
+use nalgebra::{Isometry2, Point2, RealField};
+use num_traits::AsPrimitive;
+
+use crate::{HashMap, Vec};
+
+pub use types::{CellState, OccupancyGridConfiguration, OccupancyGridConfigurationBuilder};
+
+mod types;
+
+#[inline]
+pub(crate) fn probability_to_log_odds<T: RealField>(probability: T) -> T {
+    (probability.clone() / (T::one() - probability)).ln()
+}
+
+#[inline]
+pub(crate) fn log_odds_to_probability<T: RealField>(log_odds: T) -> T {
+    T::one() - T::one() / (T::one() + log_odds.exp())
+}
+
+/// A 2D occupancy grid map, storing the log-odds of each cell being occupied.
+///
+/// The grid expands automatically to contain every updated cell, cells that were never updated are unknown.
+/// Cells are indexed by `[column, row]`, with cell `[0, 0]` starting at the configured origin,
+/// so indices may be negative when the grid expands below or left of its origin.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct OccupancyGrid<T: RealField> {
+    config: OccupancyGridConfiguration<T>,
+    hit_log_odds: T,
+    miss_log_odds: T,
+    min_log_odds: T,
+    max_log_odds: T,
+    min_cell: [isize; 2],
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> OccupancyGrid<T>
+where
+    T: AsPrimitive<isize> + AsPrimitive<usize> + Copy + RealField,
+    isize: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs an empty grid, with all cells unknown.
+    ///
+    /// # Arguments
+    /// * `config`: an [`OccupancyGridConfiguration`], specifying the grid's geometry and sensor model.
+    ///
+    /// # Returns
+    /// An empty [`OccupancyGrid`].
+    pub fn new(config: OccupancyGridConfiguration<T>) -> Self {
+        Self {
+            hit_log_odds: probability_to_log_odds(config.hit_probability),
+            miss_log_odds: probability_to_log_odds(config.miss_probability),
+            min_log_odds: probability_to_log_odds(config.min_probability),
+            max_log_odds: probability_to_log_odds(config.max_probability),
+            config,
+            min_cell: [0, 0],
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Returns the configuration the grid was constructed with.
+    pub fn config(&self) -> &OccupancyGridConfiguration<T> {
+        &self.config
+    }
+
+    /// Returns the edge length of each cell.
+    pub fn resolution(&self) -> T {
+        self.config.resolution
+    }
+
+    /// Returns the amount of columns currently stored in the grid.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the amount of rows currently stored in the grid.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the index of the lowest stored cell, the first cell of the exported grids.
+    pub fn min_cell(&self) -> [isize; 2] {
+        self.min_cell
+    }
+
+    /// Returns whether the grid does not store any cells.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns the index of the cell containing a world position.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point2`], the world position.
+    ///
+    /// # Returns
+    /// The cell's `[column, row]` index.
+    #[inline]
+    pub fn world_to_cell(&self, point: &Point2<T>) -> [isize; 2] {
+        [0, 1].map(|axis| {
+            AsPrimitive::<isize>::as_(
+                ((point[axis] - self.config.origin[axis]) / self.config.resolution).floor(),
+            )
+        })
+    }
+
+    /// Returns the world position of a cell's centre.
+    ///
+    /// # Arguments
+    /// * `cell`: the cell's `[column, row]` index.
+    ///
+    /// # Returns
+    /// A [`Point2`], the world position of the cell's centre.
+    #[inline]
+    pub fn cell_to_world(&self, cell: [isize; 2]) -> Point2<T> {
+        let half = T::one() / (T::one() + T::one());
+        Point2::from([0, 1].map(|axis| {
+            self.config.origin[axis]
+                + (AsPrimitive::<T>::as_(cell[axis]) + half) * self.config.resolution
+        }))
+    }
+
+    #[inline]
+    fn cell_offset(&self, cell: [isize; 2]) -> Option<usize> {
+        let column = cell[0] - self.min_cell[0];
+        let row = cell[1] - self.min_cell[1];
+        (column >= 0 && row >= 0 && (column as usize) < self.width && (row as usize) < self.height)
+            .then(|| row as usize * self.width + column as usize)
+    }
+
+    /// Returns the log-odds of a cell being occupied, cells outside of the grid are unknown, with log-odds of zero.
+    ///
+    /// # Arguments
+    /// * `cell`: the cell's `[column, row]` index.
+    ///
+    /// # Returns
+    /// The cell's log-odds.
+    pub fn log_odds(&self, cell: [isize; 2]) -> T {
+        self.cell_offset(cell)
+            .map_or_else(T::zero, |offset| self.cells[offset])
+    }
+
+    /// Returns the probability of a cell being occupied, cells outside of the grid have a probability of `0.5`.
+    ///
+    /// # Arguments
+    /// * `cell`: the cell's `[column, row]` index.
+    ///
+    /// # Returns
+    /// The cell's occupancy probability.
+    pub fn probability(&self, cell: [isize; 2]) -> T {
+        log_odds_to_probability(self.log_odds(cell))
+    }
+
+    /// Classifies a cell using the configured thresholds.
+    ///
+    /// # Arguments
+    /// * `cell`: the cell's `[column, row]` index.
+    ///
+    /// # Returns
+    /// The cell's [`CellState`].
+    pub fn cell_state(&self, cell: [isize; 2]) -> CellState {
+        self.classify(self.probability(cell))
+    }
+
+    #[inline]
+    fn classify(&self, probability: T) -> CellState {
+        if probability > self.config.occupied_threshold {
+            CellState::Occupied
+        } else if probability < self.config.free_threshold {
+            CellState::Free
+        } else {
+            CellState::Unknown
+        }
+    }
+
+    /// Expands the grid so that it contains all cells between two corners, keeping all existing cells.
+    ///
+    /// # Arguments
+    /// * `min_cell`: the lowest cell index to contain.
+    /// * `max_cell`: the highest cell index to contain.
+    pub fn expand_to(&mut self, min_cell: [isize; 2], max_cell: [isize; 2]) {
+        let (new_min, new_max) = if self.is_empty() {
+            (min_cell, max_cell)
+        } else {
+            let current_max = [
+                self.min_cell[0] + self.width as isize - 1,
+                self.min_cell[1] + self.height as isize - 1,
+            ];
+            (
+                [0, 1].map(|axis| self.min_cell[axis].min(min_cell[axis])),
+                [0, 1].map(|axis| current_max[axis].max(max_cell[axis])),
+            )
+        };
+        let new_width = (new_max[0] - new_min[0] + 1) as usize;
+        let new_height = (new_max[1] - new_min[1] + 1) as usize;
+        if new_min == self.min_cell && new_width == self.width && new_height == self.height {
+            return;
+        }
+
+        let mut cells = Vec::from_iter(core::iter::repeat_n(T::zero(), new_width * new_height));
+        let column_offset = (self.min_cell[0] - new_min[0]) as usize;
+        let row_offset = (self.min_cell[1] - new_min[1]) as usize;
+        for (row_idx, row) in self.cells.chunks_exact(self.width.max(1)).enumerate() {
+            let start = (row_idx + row_offset) * new_width + column_offset;
+            cells[start..start + self.width].copy_from_slice(row);
+        }
+
+        self.min_cell = new_min;
+        self.width = new_width;
+        self.height = new_height;
+        self.cells = cells;
+    }
+
+    /// Updates a single cell with an observation, expanding the grid if required.
+    ///
+    /// # Arguments
+    /// * `cell`: the cell's `[column, row]` index.
+    /// * `occupied`: whether the cell was observed as occupied, or as free.
+    pub fn update_cell(&mut self, cell: [isize; 2], occupied: bool) {
+        self.expand_to(cell, cell);
+        self.apply_update(cell, occupied);
+    }
+
+    #[inline]
+    fn apply_update(&mut self, cell: [isize; 2], occupied: bool) {
+        let Some(offset) = self.cell_offset(cell) else {
+            return;
+        };
+        let update = if occupied {
+            self.hit_log_odds
+        } else {
+            self.miss_log_odds
+        };
+        self.cells[offset] = (self.cells[offset] + update)
+            .max(self.min_log_odds)
+            .min(self.max_log_odds);
+    }
+
+    /// Integrates a laser scan, clearing the cells each ray passes through and marking each ray's end cell as occupied.
+    /// Each cell is updated at most once per scan, and a cell that any ray ended in is only marked as occupied.
+    ///
+    /// # Arguments
+    /// * `points`: a slice of [`Point2`], the scan's points in the sensor's frame.
+    /// * `sensor_pose`: an [`Isometry2`], the sensor's pose in the grid's frame.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Integrate Scan Into Occupancy Grid", skip_all, level = "debug")
+    )]
+    pub fn integrate_scan(&mut self, points: &[Point2<T>], sensor_pose: &Isometry2<T>) {
+        let sensor_cell = self.world_to_cell(&sensor_pose.translation.vector.into());
+        let to_line_point =
+            |cell: [isize; 2]| Point2::from(cell.map(|index| AsPrimitive::<T>::as_(index)));
+
+        let mut updates: HashMap<[isize; 2], bool> = HashMap::new();
+        for point in points {
+            let range = point.coords.norm();
+            if !range.is_finite() {
+                continue;
+            }
+            let (end_point, hit) = match self.config.max_range {
+                Some(max_range) if range > max_range => (point * (max_range / range), false),
+                _ => (*point, true),
+            };
+
+            let end_cell = self.world_to_cell(&(sensor_pose * end_point));
+            let line = plot_bresenham_line::<T, isize, 2>(
+                to_line_point(sensor_cell),
+                to_line_point(end_cell),
+            );
+            for cell in line.iter().take(line.len().saturating_sub(1)) {
+                updates.entry([cell.x, cell.y]).or_insert(false);
+            }
+            if hit {
+                updates.insert(end_cell, true);
+            } else {
+                updates.entry(end_cell).or_insert(false);
+            }
+        }
+
+        let Some((min_cell, max_cell)) = updates.keys().fold(None, |bounds, cell| {
+            let (min_cell, max_cell) = bounds.unwrap_or((*cell, *cell));
+            Some((
+                [0, 1].map(|axis| min_cell[axis].min(cell[axis])),
+                [0, 1].map(|axis| max_cell[axis].max(cell[axis])),
+            ))
+        }) else {
+            return;
+        };
+        self.expand_to(min_cell, max_cell);
+        for (cell, occupied) in updates {
+            self.apply_update(cell, occupied);
+        }
+    }
+
+    /// Exports the grid's occupancy probabilities.
+    ///
+    /// # Returns
+    /// A [`Vec`] of probabilities in row-major order, starting at [`OccupancyGrid::min_cell`], with [`OccupancyGrid::width`] columns.
+    pub fn to_probability_grid(&self) -> Vec<T> {
+        self.cells
+            .iter()
+            .map(|log_odds| log_odds_to_probability(*log_odds))
+            .collect()
+    }
+
+    /// Exports the grid as free, occupied and unknown cells, using the configured thresholds.
+    ///
+    /// # Returns
+    /// A [`Vec`] of [`CellState`] in row-major order, starting at [`OccupancyGrid::min_cell`], with [`OccupancyGrid::width`] columns.
+    pub fn to_ternary_grid(&self) -> Vec<CellState> {
+        self.cells
+            .iter()
+            .map(|log_odds| self.classify(log_odds_to_probability(*log_odds)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+
+    use super::*;
+
+    fn config() -> OccupancyGridConfigurationBuilder<f64> {
+        OccupancyGridConfiguration::builder().with_resolution(0.5)
+    }
+
+    #[test]
+    fn test_cell_conversions() {
+        let grid = OccupancyGrid::new(
+            OccupancyGridConfiguration::builder()
+                .with_resolution(0.5)
+                .with_origin(Point2::new(-1.0, 2.0))
+                .build(),
+        );
+        assert_eq!(grid.world_to_cell(&Point2::new(-1.0, 2.0)), [0, 0]);
+        assert_eq!(grid.world_to_cell(&Point2::new(0.3, 1.9)), [2, -1]);
+        assert_eq!(grid.cell_to_world([2, -1]), Point2::new(0.25, 1.75));
+        assert_eq!(grid.probability([100, 100]), 0.5);
+        assert_eq!(grid.cell_state([100, 100]), CellState::Unknown);
+    }
+
+    #[test]
+    fn test_update_and_clamp() {
+        let mut grid = OccupancyGrid::new(config().build());
+        grid.update_cell([-2, 3], true);
+        assert_eq!((grid.width(), grid.height()), (1, 1));
+        assert!((grid.probability([-2, 3]) - 0.7).abs() < 1e-6);
+        assert_eq!(grid.cell_state([-2, 3]), CellState::Occupied);
+
+        for _ in 0..20 {
+            grid.update_cell([-2, 3], true);
+        }
+        assert!((grid.probability([-2, 3]) - 0.97).abs() < 1e-6);
+
+        // The grid expands while keeping existing cells in place
+        grid.update_cell([1, 0], false);
+        assert_eq!(grid.min_cell(), [-2, 0]);
+        assert_eq!((grid.width(), grid.height()), (4, 4));
+        assert!((grid.probability([-2, 3]) - 0.97).abs() < 1e-6);
+        assert!((grid.probability([1, 0]) - 0.4).abs() < 1e-6);
+
+        for _ in 0..20 {
+            grid.update_cell([1, 0], false);
+        }
+        assert!((grid.probability([1, 0]) - 0.12).abs() < 1e-6);
+        assert_eq!(grid.cell_state([1, 0]), CellState::Free);
+    }
+
+    #[test]
+    fn test_integrate_scan() {
+        let mut grid = OccupancyGrid::new(config().build());
+        // A wall two metres ahead of a sensor facing the positive y axis
+        let pose = Isometry2::new(Vector2::new(0.25, 0.25), core::f64::consts::FRAC_PI_2);
+        let scan = (-4..=4)
+            .map(|idx| Point2::new(2.1, idx as f64 * 0.25))
+            .collect::<Vec<_>>();
+        grid.integrate_scan(&scan, &pose);
+
+        let ternary = grid.to_ternary_grid();
+        let probabilities = grid.to_probability_grid();
+        assert_eq!(ternary.len(), grid.width() * grid.height());
+        assert_eq!(probabilities.len(), ternary.len());
+
+        for column in -2..=2 {
+            assert_eq!(grid.cell_state([column, 4]), CellState::Occupied);
+        }
+        assert!((grid.log_odds([0, 0]) - probability_to_log_odds(0.4)).abs() < 1e-6);
+        for row in 1..4 {
+            assert!((grid.probability([0, row]) - 0.4).abs() < 1e-6);
+        }
+        assert_eq!(grid.cell_state([0, 5]), CellState::Unknown);
+
+        let occupied = ternary
+            .iter()
+            .filter(|state| **state == CellState::Occupied)
+            .count();
+        assert_eq!(occupied, 5);
+        assert_eq!(
+            probabilities
+                .iter()
+                .filter(|probability| **probability > 0.5)
+                .count(),
+            occupied
+        );
+    }
+
+    #[test]
+    fn test_max_range() {
+        let mut grid = OccupancyGrid::new(config().with_max_range(Some(1.0)).build());
+        grid.integrate_scan(&[Point2::new(3.1, 0.1)], &Isometry2::identity());
+
+        assert_eq!(grid.width(), 2);
+        assert!((0..2).all(|column| grid.probability([column, 0]) < 0.5));
+        assert_eq!(grid.cell_state([6, 0]), CellState::Unknown);
+    }
+
+    #[test]
+    fn test_far_return() {
+        // The default range keeps a spurious return from allocating a grid reaching it
+        let mut grid = OccupancyGrid::new(config().build());
+        grid.integrate_scan(&[Point2::new(1e6, 0.1)], &Isometry2::identity());
+
+        assert_eq!((grid.width(), grid.height()), (60, 1));
+        assert!(grid.probability([59, 0]) < 0.5);
+        assert_eq!(grid.cell_state([60, 0]), CellState::Unknown);
+    }
+}
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point2, RealField, Scalar};
+use num_traits::AsPrimitive;
+
+/// The state of a single cell in an occupancy grid, after thresholding its occupancy probability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CellState {
+    /// The cell is most likely empty.
+    Free,
+    /// The cell is most likely occupied.
+    Occupied,
+    /// The cell was not observed enough to tell.
+    Unknown,
+}
+
+/// A struct specifying configuration options for an [`OccupancyGrid`](crate::occupancy_grid::OccupancyGrid).
+#[derive(Clone, Debug)]
+pub struct OccupancyGridConfiguration<T: Scalar> {
+    /// The edge length of each cell.
+    pub(crate) resolution: T,
+    /// The world position of the corner of cell `[0, 0]`, all cells are aligned to it.
+    pub(crate) origin: Point2<T>,
+    /// The probability of a cell being occupied, given that a ray ended in it.
+    pub(crate) hit_probability: T,
+    /// The probability of a cell being occupied, given that a ray passed through it.
+    pub(crate) miss_probability: T,
+    /// The minimal occupancy probability a cell can reach, so that it can still change its state quickly.
+    pub(crate) min_probability: T,
+    /// The maximal occupancy probability a cell can reach, so that it can still change its state quickly.
+    pub(crate) max_probability: T,
+    /// Cells with an occupancy probability below this are free.
+    pub(crate) free_threshold: T,
+    /// Cells with an occupancy probability above this are occupied.
+    pub(crate) occupied_threshold: T,
+    /// Rays longer than this are shortened to it, and only clear the cells they pass through.
+    pub(crate) max_range: Option<T>,
+}
+
+impl<T: 'static + Copy + RealField> OccupancyGridConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values are tuned for an indoor laser scanner, with cells of 5 centimetres and a range of 30 metres.
+    ///
+    /// # Returns
+    /// A [`OccupancyGridConfigurationBuilder`].
+    pub fn builder() -> OccupancyGridConfigurationBuilder<T> {
+        OccupancyGridConfigurationBuilder {
+            _internal: OccupancyGridConfiguration {
+                resolution: 0.05.as_(),
+                origin: Point2::origin(),
+                hit_probability: 0.7.as_(),
+                miss_probability: 0.4.as_(),
+                min_probability: 0.12.as_(),
+                max_probability: 0.97.as_(),
+                free_threshold: 0.25.as_(),
+                occupied_threshold: 0.65.as_(),
+                max_range: Some(30.0.as_()),
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`OccupancyGridConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct OccupancyGridConfigurationBuilder<T: Scalar> {
+    _internal: OccupancyGridConfiguration<T>,
+}
+
+impl<T: Copy + Scalar> OccupancyGridConfigurationBuilder<T> {
+    /// The edge length of each cell.
+    ///
+    /// # Arguments
+    /// * `resolution`: The cell size, must be positive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_resolution(&self, resolution: T) -> Self {
+        Self {
+            _internal: OccupancyGridConfiguration {
+                resolution,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The world position of the corner of cell `[0, 0]`, the grid can still expand in any direction from it.
+    ///
+    /// # Arguments
+    /// * `origin`: A [`Point2`], the origin of the grid's cells.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_origin(&self, origin: Point2<T>) -> Self {
+        Self {
+            _internal: OccupancyGridConfiguration {
+                origin,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The inverse sensor model, the occupancy probabilities of cells given that a ray ended in them or passed through them.
+    ///
+    /// # Arguments
+    /// * `hit_probability`: The probability for a ray's end cell, above `0.5`.
+    /// * `miss_probability`: The probability for the cells a ray passed through, below `0.5`.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_sensor_model(&self, hit_probability: T, miss_probability: T) -> Self {
+        Self {
+            _internal: OccupancyGridConfiguration {
+                hit_probability,
+                miss_probability,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The limits of each cell's occupancy probability, keeping the map able to adapt to changes.
+    ///
+    /// # Arguments
+    /// * `min_probability`: The minimal occupancy probability.
+    /// * `max_probability`: The maximal occupancy probability.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_clamping(&self, min_probability: T, max_probability: T) -> Self {
+        Self {
+            _internal: OccupancyGridConfiguration {
+                min_probability,
+                max_probability,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The thresholds used when classifying cells into a [`CellState`].
+    ///
+    /// # Arguments
+    /// * `free_threshold`: Cells below this occupancy probability are free.
+    /// * `occupied_threshold`: Cells above this occupancy probability are occupied.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_thresholds(&self, free_threshold: T, occupied_threshold: T) -> Self {
+        Self {
+            _internal: OccupancyGridConfiguration {
+                free_threshold,
+                occupied_threshold,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// The sensor's maximum range, longer rays usually represent missing returns rather than obstacles.
+    ///
+    /// # Arguments
+    /// * `max_range`: The maximum range, or [`None`] to trust every ray, letting a single spurious far return grow the grid without bound.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_range(&self, max_range: Option<T>) -> Self {
+        Self {
+            _internal: OccupancyGridConfiguration {
+                max_range,
+                ..self._internal.clone()
+            },
+        }
+    }
+
+    /// Generates a [`OccupancyGridConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`OccupancyGridConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> OccupancyGridConfiguration<T> {
+        self._internal.clone()
+    }
+}
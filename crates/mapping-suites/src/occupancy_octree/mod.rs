@@ -0,0 +1,564 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point3, RealField, Vector3};
+use num_traits::AsPrimitive;
+
+use crate::{
+    occupancy_grid::{log_odds_to_probability, probability_to_log_odds, CellState},
+    HashMap, Vec,
+};
+
+use node::OctreeNode;
+pub use types::{OccupancyOctreeConfiguration, OccupancyOctreeConfigurationBuilder, OctreeLeaf};
+
+mod node;
+mod types;
+
+/// A 3D occupancy map stored in an octree, in the spirit of OctoMap.
+///
+/// Each cell stores the log-odds of being occupied, clamped so that homogeneous regions can be pruned into a single node.
+/// Cells that were never observed are unknown, and take no memory.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Debug)]
+pub struct OccupancyOctree<T: RealField> {
+    config: OccupancyOctreeConfiguration<T>,
+    hit_log_odds: T,
+    miss_log_odds: T,
+    min_log_odds: T,
+    max_log_odds: T,
+    root: Option<OctreeNode<T>>,
+}
+
+impl<T> OccupancyOctree<T>
+where
+    T: AsPrimitive<isize> + Copy + RealField,
+    usize: AsPrimitive<T>,
+{
+    /// Constructs an empty octree, with all cells unknown.
+    ///
+    /// # Arguments
+    /// * `config`: an [`OccupancyOctreeConfiguration`], specifying the octree's geometry and sensor model.
+    ///
+    /// # Returns
+    /// An empty [`OccupancyOctree`].
+    pub fn new(config: OccupancyOctreeConfiguration<T>) -> Self {
+        Self {
+            hit_log_odds: probability_to_log_odds(config.hit_probability),
+            miss_log_odds: probability_to_log_odds(config.miss_probability),
+            min_log_odds: probability_to_log_odds(config.min_probability),
+            max_log_odds: probability_to_log_odds(config.max_probability),
+            config,
+            root: None,
+        }
+    }
+
+    /// Returns the configuration the octree was constructed with.
+    pub fn config(&self) -> &OccupancyOctreeConfiguration<T> {
+        &self.config
+    }
+
+    /// Returns the edge length of the smallest cells.
+    pub fn resolution(&self) -> T {
+        self.config.resolution
+    }
+
+    /// Returns whether no cell was observed yet.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the amount of nodes in the octree, including inner nodes.
+    pub fn num_nodes(&self) -> usize {
+        self.root.as_ref().map_or(0, OctreeNode::num_nodes)
+    }
+
+    /// Removes all cells from the octree.
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    #[inline]
+    fn key_offset(&self) -> isize {
+        1 << (self.config.max_depth - 1)
+    }
+
+    #[inline]
+    fn point_to_key(&self, point: &Point3<T>) -> Option<[usize; 3]> {
+        let offset = self.key_offset();
+        let mut key = [0; 3];
+        for (axis, key_element) in key.iter_mut().enumerate() {
+            let scaled = (point[axis] / self.config.resolution).floor();
+            if !scaled.is_finite() {
+                return None;
+            }
+            let index = AsPrimitive::<isize>::as_(scaled) + offset;
+            if index < 0 || index >= 2 * offset {
+                return None;
+            }
+            *key_element = index as usize;
+        }
+        Some(key)
+    }
+
+    #[inline]
+    fn key_to_coordinate(&self, key: usize) -> T {
+        // The lower corner of the cell, computed without negative integers
+        let offset = self.key_offset() as usize;
+        if key >= offset {
+            AsPrimitive::<T>::as_(key - offset) * self.config.resolution
+        } else {
+            -AsPrimitive::<T>::as_(offset - key) * self.config.resolution
+        }
+    }
+
+    #[inline]
+    fn key_to_center(&self, key: &[usize; 3]) -> Point3<T> {
+        let half = self.config.resolution / (T::one() + T::one());
+        Point3::from(key.map(|element| self.key_to_coordinate(element) + half))
+    }
+
+    fn update_key(&mut self, key: &[usize; 3], occupied: bool) {
+        let update = if occupied {
+            self.hit_log_odds
+        } else {
+            self.miss_log_odds
+        };
+        let max_depth = self.config.max_depth;
+        self.root
+            .get_or_insert_with(|| OctreeNode::new(max_depth))
+            .update(key, max_depth, update, self.min_log_odds, self.max_log_odds);
+    }
+
+    /// Updates the cell containing a point with a single observation, pruning the octree where it became homogeneous.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point3`], a position inside the cell.
+    /// * `occupied`: whether the cell was observed as occupied, or as free.
+    ///
+    /// # Returns
+    /// Whether the cell was updated, `false` if the point is outside of the map's extent.
+    pub fn update_cell(&mut self, point: &Point3<T>, occupied: bool) -> bool {
+        let Some(key) = self.point_to_key(point) else {
+            return false;
+        };
+        self.update_key(&key, occupied);
+        true
+    }
+
+    /// Visits the cells along a ray, starting at the origin's cell, using the Amanatides-Woo traversal.
+    /// The traversal stops after `length`, when leaving the map, or when `visit` returns `false`.
+    fn traverse_ray<F: FnMut(&[usize; 3]) -> bool>(
+        &self,
+        origin: &Point3<T>,
+        direction: &Vector3<T>,
+        length: T,
+        mut visit: F,
+    ) {
+        let Some(mut key) = self.point_to_key(origin) else {
+            return;
+        };
+        let resolution = self.config.resolution;
+        // Axes the ray is parallel to are never stepped along, as their next border is beyond the ray's end
+        let never = length + resolution;
+        let mut steps = [0isize; 3];
+        let mut t_max = [never; 3];
+        let mut t_delta = [never; 3];
+        for axis in 0..3 {
+            if direction[axis] == T::zero() {
+                continue;
+            }
+            let lower_border = self.key_to_coordinate(key[axis]);
+            let (step, border) = if direction[axis] > T::zero() {
+                (1, lower_border + resolution)
+            } else {
+                (-1, lower_border)
+            };
+            steps[axis] = step;
+            t_max[axis] = (border - origin[axis]) / direction[axis];
+            t_delta[axis] = resolution / direction[axis].abs();
+        }
+
+        let max_key = 2 * self.key_offset() as usize;
+        if !visit(&key) {
+            return;
+        }
+        loop {
+            let axis = (1..3).fold(0, |min_axis, axis| {
+                if t_max[axis] < t_max[min_axis] {
+                    axis
+                } else {
+                    min_axis
+                }
+            });
+            if t_max[axis] > length {
+                return;
+            }
+            let Some(next) = key[axis]
+                .checked_add_signed(steps[axis])
+                .filter(|next| *next < max_key)
+            else {
+                return;
+            };
+            key[axis] = next;
+            t_max[axis] += t_delta[axis];
+            if !visit(&key) {
+                return;
+            }
+        }
+    }
+
+    /// Inserts a point cloud measured from a sensor, clearing the cells each ray passes through and marking each ray's end cell as occupied.
+    /// Each cell is updated at most once per point cloud, and a cell that any ray ended in is only marked as occupied.
+    ///
+    /// # Arguments
+    /// * `points`: a slice of [`Point3`], the measured points in the map's frame.
+    /// * `sensor_origin`: a [`Point3`], the sensor's position in the map's frame.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument("Insert Point Cloud Into Octree", skip_all, level = "debug")
+    )]
+    pub fn insert_point_cloud(&mut self, points: &[Point3<T>], sensor_origin: &Point3<T>) {
+        let mut updates: HashMap<[usize; 3], bool> = HashMap::new();
+        for point in points {
+            let offset = point - sensor_origin;
+            let range = offset.norm();
+            if !range.is_finite() {
+                continue;
+            }
+            let (length, hit) = match self.config.max_range {
+                Some(max_range) if range > max_range => (max_range, false),
+                _ => (range, true),
+            };
+            let end_key = hit.then(|| self.point_to_key(point)).flatten();
+
+            if range > T::zero() {
+                let direction = offset / range;
+                self.traverse_ray(sensor_origin, &direction, length, |key| {
+                    if Some(*key) == end_key {
+                        return false;
+                    }
+                    updates.entry(*key).or_insert(false);
+                    true
+                });
+            }
+            if let Some(end_key) = end_key {
+                updates.insert(end_key, true);
+            }
+        }
+
+        for (key, occupied) in updates {
+            self.update_key(&key, occupied);
+        }
+    }
+
+    /// Returns the log-odds of the cell containing a point being occupied.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point3`], the position to query.
+    ///
+    /// # Returns
+    /// The cell's log-odds, or [`None`] if the cell is unknown or outside of the map.
+    pub fn log_odds(&self, point: &Point3<T>) -> Option<T> {
+        let key = self.point_to_key(point)?;
+        self.root.as_ref()?.search(&key, self.config.max_depth)
+    }
+
+    /// Returns the probability of the cell containing a point being occupied.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point3`], the position to query.
+    ///
+    /// # Returns
+    /// The cell's occupancy probability, or [`None`] if the cell is unknown or outside of the map.
+    pub fn probability(&self, point: &Point3<T>) -> Option<T> {
+        self.log_odds(point).map(log_odds_to_probability)
+    }
+
+    /// Classifies the cell containing a point, observed cells are either occupied or free.
+    ///
+    /// # Arguments
+    /// * `point`: a [`Point3`], the position to query.
+    ///
+    /// # Returns
+    /// The cell's [`CellState`].
+    pub fn cell_state(&self, point: &Point3<T>) -> CellState {
+        match self.probability(point) {
+            None => CellState::Unknown,
+            Some(probability) if probability > self.config.occupied_threshold => {
+                CellState::Occupied
+            }
+            Some(_) => CellState::Free,
+        }
+    }
+
+    /// Casts a ray through the map, until it reaches an occupied cell.
+    ///
+    /// # Arguments
+    /// * `origin`: a [`Point3`], the ray's origin.
+    /// * `direction`: a [`Vector3`], the ray's direction, does not need to be normalised.
+    /// * `max_range`: the maximum distance to travel along the ray.
+    /// * `ignore_unknown`: whether the ray passes through unknown cells, or stops at the first one.
+    ///
+    /// # Returns
+    /// [`Some`] containing the centre of the first occupied cell along the ray,
+    /// or [`None`] if the ray reached its maximum range, the map's extent, or an unknown cell that was not ignored.
+    pub fn cast_ray(
+        &self,
+        origin: &Point3<T>,
+        direction: &Vector3<T>,
+        max_range: T,
+        ignore_unknown: bool,
+    ) -> Option<Point3<T>> {
+        let direction = direction.try_normalize(T::default_epsilon())?;
+        let mut hit = None;
+        self.traverse_ray(origin, &direction, max_range, |key| {
+            let log_odds = self
+                .root
+                .as_ref()
+                .and_then(|root| root.search(key, self.config.max_depth));
+            match log_odds {
+                Some(log_odds)
+                    if log_odds_to_probability(log_odds) > self.config.occupied_threshold =>
+                {
+                    hit = Some(*key);
+                    false
+                }
+                Some(_) => true,
+                None => ignore_unknown,
+            }
+        });
+
+        hit.map(|key| self.key_to_center(&key))
+    }
+
+    fn leaf_from_key(
+        &self,
+        min_key: &[usize; 3],
+        depth_below: usize,
+        log_odds: T,
+    ) -> OctreeLeaf<T> {
+        let size = AsPrimitive::<T>::as_(1usize << depth_below) * self.config.resolution;
+        let half = size / (T::one() + T::one());
+        OctreeLeaf {
+            center: Point3::from(min_key.map(|element| self.key_to_coordinate(element) + half)),
+            size,
+            log_odds,
+        }
+    }
+
+    /// Returns all leaves of the octree, pruned leaves cover several cells.
+    pub fn leaves(&self) -> Vec<OctreeLeaf<T>> {
+        self.collect_leaves(|_, _| true)
+    }
+
+    /// Returns all leaves whose cubes intersect an axis-aligned bounding box.
+    ///
+    /// # Arguments
+    /// * `min`: a [`Point3`], the box's lowest corner.
+    /// * `max`: a [`Point3`], the box's highest corner.
+    ///
+    /// # Returns
+    /// A [`Vec`] of [`OctreeLeaf`], pruned leaves may extend beyond the box.
+    pub fn leaves_in_bounding_box(&self, min: &Point3<T>, max: &Point3<T>) -> Vec<OctreeLeaf<T>> {
+        self.collect_leaves(|lower, upper| {
+            (0..3).all(|axis| lower[axis] <= max[axis] && upper[axis] >= min[axis])
+        })
+    }
+
+    fn collect_leaves<F: Fn(&Point3<T>, &Point3<T>) -> bool>(
+        &self,
+        intersects: F,
+    ) -> Vec<OctreeLeaf<T>> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+
+        let mut raw_leaves = Vec::new();
+        root.collect_leaves(
+            [0; 3],
+            self.config.max_depth,
+            &mut |min_key, depth_below| {
+                let size = AsPrimitive::<T>::as_(1usize << depth_below) * self.config.resolution;
+                let lower = Point3::from(min_key.map(|element| self.key_to_coordinate(element)));
+                intersects(&lower, &(lower + Vector3::repeat(size)))
+            },
+            &mut raw_leaves,
+        );
+        raw_leaves
+            .into_iter()
+            .map(|(min_key, depth_below, log_odds)| {
+                self.leaf_from_key(&min_key, depth_below, log_odds)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> OccupancyOctreeConfigurationBuilder<f64> {
+        OccupancyOctreeConfiguration::builder()
+            .with_resolution(0.5)
+            .with_max_depth(8)
+    }
+
+    #[test]
+    fn test_update_cell() {
+        let mut octree = OccupancyOctree::new(config().build());
+        assert!(octree.is_empty());
+        assert_eq!(
+            octree.cell_state(&Point3::new(1.0, 1.0, 1.0)),
+            CellState::Unknown
+        );
+
+        assert!(octree.update_cell(&Point3::new(-1.2, 0.3, 2.0), true));
+        assert!((octree.probability(&Point3::new(-1.1, 0.4, 2.2)).unwrap() - 0.7).abs() < 1e-6);
+        assert_eq!(
+            octree.cell_state(&Point3::new(-1.1, 0.4, 2.2)),
+            CellState::Occupied
+        );
+        assert_eq!(
+            octree.cell_state(&Point3::new(-0.9, 0.4, 2.2)),
+            CellState::Unknown
+        );
+        // One node for each level, and the leaf
+        assert_eq!(octree.num_nodes(), 9);
+
+        // The map spans 256 cells of half a metre, centred on the origin
+        assert!(!octree.update_cell(&Point3::new(64.0, 0.0, 0.0), true));
+        assert!(octree.update_cell(&Point3::new(-64.0, 0.0, 0.0), true));
+
+        octree.clear();
+        assert!(octree.is_empty());
+    }
+
+    #[test]
+    fn test_pruning() {
+        let mut octree = OccupancyOctree::new(config().build());
+        let cube = (0..8)
+            .map(|idx| {
+                Point3::new((idx & 1) as f64, ((idx >> 1) & 1) as f64, (idx >> 2) as f64) * 0.5
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..10 {
+            cube.iter().for_each(|point| {
+                octree.update_cell(point, true);
+            });
+        }
+
+        // All eight cells saturated, and were merged into their parent
+        assert_eq!(octree.num_nodes(), 8);
+        let leaves = octree.leaves();
+        assert_eq!(leaves.len(), 1);
+        assert_eq!(leaves[0].size, 1.0);
+        assert_eq!(leaves[0].center, Point3::new(0.5, 0.5, 0.5));
+        assert!((leaves[0].probability() - 0.971).abs() < 1e-6);
+
+        // Updating a single cell expands the pruned node again
+        octree.update_cell(&cube[3], false);
+        assert_eq!(octree.num_nodes(), 16);
+        assert_eq!(octree.leaves().len(), 8);
+        assert_eq!(octree.cell_state(&cube[0]), CellState::Occupied);
+    }
+
+    #[test]
+    fn test_insert_point_cloud() {
+        let mut octree = OccupancyOctree::new(config().build());
+        // A wall four metres ahead of the sensor
+        let wall = (-4..=4)
+            .flat_map(|y| (-4..=4).map(move |z| Point3::new(4.25, y as f64 * 0.5, z as f64 * 0.5)))
+            .collect::<Vec<_>>();
+        let origin = Point3::new(0.25, 0.25, 0.25);
+        octree.insert_point_cloud(&wall, &origin);
+
+        for point in wall.iter() {
+            assert_eq!(octree.cell_state(point), CellState::Occupied);
+        }
+        for x in 0..8 {
+            assert_eq!(
+                octree.cell_state(&Point3::new(x as f64 * 0.5 + 0.25, 0.25, 0.25)),
+                CellState::Free
+            );
+        }
+        assert_eq!(
+            octree.cell_state(&Point3::new(4.75, 0.25, 0.25)),
+            CellState::Unknown
+        );
+
+        // Rays stop at the first occupied cell
+        let hit = octree.cast_ray(&origin, &Vector3::new(1.0, 0.0, 0.0), 10.0, false);
+        assert_eq!(hit, Some(Point3::new(4.25, 0.25, 0.25)));
+        assert_eq!(
+            octree.cast_ray(&origin, &Vector3::new(1.0, 0.0, 0.0), 3.0, false),
+            None
+        );
+        // Rays pointing away only pass through unknown cells
+        assert_eq!(
+            octree.cast_ray(&origin, &Vector3::new(-1.0, 0.0, 0.0), 10.0, true),
+            None
+        );
+        let mut unknown_gap = octree.clone();
+        unknown_gap.clear();
+        unknown_gap.update_cell(&Point3::new(2.25, 0.25, 0.25), true);
+        assert_eq!(
+            unknown_gap.cast_ray(&origin, &Vector3::x(), 10.0, false),
+            None
+        );
+        assert_eq!(
+            unknown_gap.cast_ray(&origin, &Vector3::x(), 10.0, true),
+            Some(Point3::new(2.25, 0.25, 0.25))
+        );
+
+        let in_box = octree
+            .leaves_in_bounding_box(&Point3::new(4.1, -0.1, -0.1), &Point3::new(4.4, 0.1, 0.1));
+        assert_eq!(in_box.len(), 4);
+        assert!(in_box.iter().all(|leaf| leaf.probability() > 0.5));
+        assert!(in_box.len() < octree.leaves().len());
+    }
+
+    #[test]
+    fn test_max_range() {
+        let mut octree = OccupancyOctree::new(config().with_max_range(Some(2.0)).build());
+        octree.insert_point_cloud(
+            &[Point3::new(10.25, 0.25, 0.25)],
+            &Point3::new(0.25, 0.25, 0.25),
+        );
+
+        assert_eq!(
+            octree.cell_state(&Point3::new(2.25, 0.25, 0.25)),
+            CellState::Free
+        );
+        assert_eq!(
+            octree.cell_state(&Point3::new(2.75, 0.25, 0.25)),
+            CellState::Unknown
+        );
+        assert_eq!(
+            octree.cell_state(&Point3::new(10.25, 0.25, 0.25)),
+            CellState::Unknown
+        );
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::RealField;
+
+use crate::{Box, Vec};
+
+/// A single node of the octree, nodes without children are leaves,
+/// which cover a homogeneous cube when they are above the maximum depth.
+#[derive(Clone, Debug)]
+pub(crate) struct OctreeNode<T> {
+    /// The node's log-odds, for inner nodes this is the maximum of their children's log-odds.
+    pub(crate) log_odds: T,
+    /// The node's children, a missing child was never observed.
+    pub(crate) children: Option<Box<[Option<OctreeNode<T>>; 8]>>,
+}
+
+#[inline]
+fn child_index(key: &[usize; 3], depth_below: usize) -> usize {
+    let shift = depth_below - 1;
+    ((key[0] >> shift) & 1) | (((key[1] >> shift) & 1) << 1) | (((key[2] >> shift) & 1) << 2)
+}
+
+impl<T: Copy + RealField> OctreeNode<T> {
+    pub(crate) fn new(depth_below: usize) -> Self {
+        Self {
+            log_odds: T::zero(),
+            children: (depth_below > 0).then(|| Box::new([const { None }; 8])),
+        }
+    }
+
+    /// Applies a log-odds update to the leaf of a key, and prunes all nodes along its path that became homogeneous.
+    pub(crate) fn update(
+        &mut self,
+        key: &[usize; 3],
+        depth_below: usize,
+        update: T,
+        min_log_odds: T,
+        max_log_odds: T,
+    ) {
+        if depth_below == 0 {
+            self.log_odds = (self.log_odds + update).max(min_log_odds).min(max_log_odds);
+            return;
+        }
+
+        // A pruned node is expanded back into identical children
+        let log_odds = self.log_odds;
+        let children = self.children.get_or_insert_with(|| {
+            Box::new(core::array::from_fn(|_| {
+                Some(OctreeNode {
+                    log_odds,
+                    children: None,
+                })
+            }))
+        });
+        children[child_index(key, depth_below)]
+            .get_or_insert_with(|| OctreeNode::new(depth_below - 1))
+            .update(key, depth_below - 1, update, min_log_odds, max_log_odds);
+
+        self.log_odds = children
+            .iter()
+            .flatten()
+            .map(|child| child.log_odds)
+            .reduce(|first, second| first.max(second))
+            .unwrap_or_else(T::zero);
+        let is_homogeneous = children.iter().all(|child| {
+            child
+                .as_ref()
+                .is_some_and(|child| child.children.is_none() && child.log_odds == self.log_odds)
+        });
+        if is_homogeneous {
+            self.children = None;
+        }
+    }
+
+    /// Returns the log-odds of the leaf containing a key, or [`None`] if it was never observed.
+    pub(crate) fn search(&self, key: &[usize; 3], depth_below: usize) -> Option<T> {
+        match &self.children {
+            None => Some(self.log_odds),
+            Some(children) => children[child_index(key, depth_below)]
+                .as_ref()
+                .and_then(|child| child.search(key, depth_below - 1)),
+        }
+    }
+
+    pub(crate) fn num_nodes(&self) -> usize {
+        1 + self.children.as_ref().map_or(0, |children| {
+            children.iter().flatten().map(OctreeNode::num_nodes).sum()
+        })
+    }
+
+    /// Collects all leaves whose cubes are accepted by a filter, skipping whole subtrees that are rejected.
+    /// Each leaf is returned as the lowest key it covers, the amount of levels below it, and its log-odds.
+    pub(crate) fn collect_leaves<F: FnMut(&[usize; 3], usize) -> bool>(
+        &self,
+        min_key: [usize; 3],
+        depth_below: usize,
+        filter: &mut F,
+        leaves: &mut Vec<([usize; 3], usize, T)>,
+    ) {
+        if !filter(&min_key, depth_below) {
+            return;
+        }
+
+        let Some(children) = &self.children else {
+            leaves.push((min_key, depth_below, self.log_odds));
+            return;
+        };
+        let half_size = 1 << (depth_below - 1);
+        for (child_idx, child) in children.iter().enumerate() {
+            if let Some(child) = child {
+                let child_key =
+                    [0, 1, 2].map(|axis| min_key[axis] + ((child_idx >> axis) & 1) * half_size);
+                child.collect_leaves(child_key, depth_below - 1, filter, leaves);
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::{Point3, RealField};
+use num_traits::AsPrimitive;
+
+use crate::occupancy_grid::log_odds_to_probability;
+
+/// A leaf of an [`OccupancyOctree`](crate::occupancy_octree::OccupancyOctree), covering a cube of homogeneous occupancy.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OctreeLeaf<T: RealField> {
+    /// The centre of the leaf's cube.
+    pub center: Point3<T>,
+    /// The edge length of the leaf's cube, larger than the resolution for pruned leaves.
+    pub size: T,
+    /// The log-odds of the cube being occupied.
+    pub log_odds: T,
+}
+
+impl<T: Copy + RealField> OctreeLeaf<T> {
+    /// Returns the probability of the leaf's cube being occupied.
+    pub fn probability(&self) -> T {
+        log_odds_to_probability(self.log_odds)
+    }
+}
+
+/// A struct specifying configuration options for an [`OccupancyOctree`](crate::occupancy_octree::OccupancyOctree).
+#[derive(Clone, Debug)]
+pub struct OccupancyOctreeConfiguration<T> {
+    /// The edge length of the smallest cells.
+    pub(crate) resolution: T,
+    /// The depth of the octree, the map spans `2^max_depth` cells along each axis, centred on the origin.
+    pub(crate) max_depth: usize,
+    /// The probability of a cell being occupied, given that a ray ended in it.
+    pub(crate) hit_probability: T,
+    /// The probability of a cell being occupied, given that a ray passed through it.
+    pub(crate) miss_probability: T,
+    /// The minimal occupancy probability a cell can reach, clamping allows homogeneous regions to be pruned.
+    pub(crate) min_probability: T,
+    /// The maximal occupancy probability a cell can reach, clamping allows homogeneous regions to be pruned.
+    pub(crate) max_probability: T,
+    /// Cells with an occupancy probability above this are occupied, all other observed cells are free.
+    pub(crate) occupied_threshold: T,
+    /// Rays longer than this are shortened to it, and only clear the cells they pass through.
+    pub(crate) max_range: Option<T>,
+}
+
+impl<T: 'static + Copy> OccupancyOctreeConfiguration<T>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Returns a builder for the configuration struct.
+    /// The default values match those of OctoMap, with cells of 10 centimetres.
+    ///
+    /// # Returns
+    /// A [`OccupancyOctreeConfigurationBuilder`].
+    pub fn builder() -> OccupancyOctreeConfigurationBuilder<T> {
+        OccupancyOctreeConfigurationBuilder {
+            _internal: OccupancyOctreeConfiguration {
+                resolution: 0.1.as_(),
+                max_depth: 16,
+                hit_probability: 0.7.as_(),
+                miss_probability: 0.4.as_(),
+                min_probability: 0.1192.as_(),
+                max_probability: 0.971.as_(),
+                occupied_threshold: 0.5.as_(),
+                max_range: None,
+            },
+        }
+    }
+}
+
+/// A Builder-pattern struct for safely constructing a [`OccupancyOctreeConfiguration`] struct.
+#[derive(Clone, Debug)]
+pub struct OccupancyOctreeConfigurationBuilder<T> {
+    _internal: OccupancyOctreeConfiguration<T>,
+}
+
+impl<T: Copy> OccupancyOctreeConfigurationBuilder<T> {
+    /// The edge length of the smallest cells.
+    ///
+    /// # Arguments
+    /// * `resolution`: The cell size, must be positive.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_resolution(&self, resolution: T) -> Self {
+        Self {
+            _internal: OccupancyOctreeConfiguration {
+                resolution,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The depth of the octree, determining the extent of the map.
+    ///
+    /// # Arguments
+    /// * `max_depth`: The amount of levels below the root, between `1` and `30`.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_depth(&self, max_depth: usize) -> Self {
+        Self {
+            _internal: OccupancyOctreeConfiguration {
+                max_depth: max_depth.clamp(1, 30),
+                ..self._internal
+            },
+        }
+    }
+
+    /// The inverse sensor model, the occupancy probabilities of cells given that a ray ended in them or passed through them.
+    ///
+    /// # Arguments
+    /// * `hit_probability`: The probability for a ray's end cell, above `0.5`.
+    /// * `miss_probability`: The probability for the cells a ray passed through, below `0.5`.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_sensor_model(&self, hit_probability: T, miss_probability: T) -> Self {
+        Self {
+            _internal: OccupancyOctreeConfiguration {
+                hit_probability,
+                miss_probability,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The limits of each cell's occupancy probability.
+    ///
+    /// # Arguments
+    /// * `min_probability`: The minimal occupancy probability.
+    /// * `max_probability`: The maximal occupancy probability.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_clamping(&self, min_probability: T, max_probability: T) -> Self {
+        Self {
+            _internal: OccupancyOctreeConfiguration {
+                min_probability,
+                max_probability,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The occupancy probability above which cells are occupied.
+    ///
+    /// # Arguments
+    /// * `occupied_threshold`: The occupancy threshold.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_occupied_threshold(&self, occupied_threshold: T) -> Self {
+        Self {
+            _internal: OccupancyOctreeConfiguration {
+                occupied_threshold,
+                ..self._internal
+            },
+        }
+    }
+
+    /// The sensor's maximum range, longer rays usually represent missing returns rather than obstacles.
+    ///
+    /// # Arguments
+    /// * `max_range`: The maximum range, or [`None`] to trust every ray.
+    ///
+    /// # Returns
+    /// A copy of self, with the updated parameters
+    pub fn with_max_range(&self, max_range: Option<T>) -> Self {
+        Self {
+            _internal: OccupancyOctreeConfiguration {
+                max_range,
+                ..self._internal
+            },
+        }
+    }
+
+    /// Generates a [`OccupancyOctreeConfiguration`] from the struct currently contained by the builder
+    ///
+    /// # Returns
+    /// A [`OccupancyOctreeConfiguration`], note that this does not consume the builder, leaving it intact for another use.
+    pub fn build(&self) -> OccupancyOctreeConfiguration<T> {
+        self._internal.clone()
+    }
+}
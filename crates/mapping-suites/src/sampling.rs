@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MIT
+/*
+ * Copyright (c) [2023 - Present] Emily Matheys <emilymatt96@gmail.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use nalgebra::RealField;
+use num_traits::AsPrimitive;
+use rand::Rng;
+
+use crate::Vec;
+
+/// Draws a sample from a zero-mean normal distribution, using the Box-Muller transform.
+///
+/// # Arguments
+/// * `rng`: the random number generator to draw from.
+/// * `standard_deviation`: the distribution's standard deviation.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `R`: A random number generator.
+///
+/// # Returns
+/// The drawn sample.
+pub fn sample_normal<T, R>(rng: &mut R, standard_deviation: T) -> T
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    R: Rng + ?Sized,
+{
+    if standard_deviation <= T::zero() {
+        return T::zero();
+    }
+
+    // The first uniform sample must not be zero, as its logarithm is taken
+    let first: T = (1.0 - rng.gen::<f32>()).as_();
+    let second: T = rng.gen::<f32>().as_();
+    let two = T::one() + T::one();
+    standard_deviation * (-two * first.ln()).sqrt() * (T::two_pi() * second).cos()
+}
+
+/// Wraps an angle into the range `(-π, π]`.
+///
+/// # Arguments
+/// * `angle`: the angle, in radians.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// The equivalent angle inside `(-π, π]`.
+#[inline]
+pub fn normalize_angle<T: RealField>(angle: T) -> T {
+    angle.clone().sin().atan2(angle.cos())
+}
+
+/// Computes the effective sample size of a set of weights, `1 / Σw²` of the normalised weights,
+/// ranging from `1` when a single particle holds all the weight, to the amount of particles when all weights are equal.
+///
+/// # Arguments
+/// * `weights`: a slice of non-negative weights, does not need to be normalised.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+///
+/// # Returns
+/// The effective sample size, or zero if all weights are zero.
+pub fn effective_sample_size<T: Copy + RealField>(weights: &[T]) -> T {
+    let sum = weights.iter().fold(T::zero(), |acc, &weight| acc + weight);
+    if sum <= T::zero() {
+        return T::zero();
+    }
+
+    let sum_squared = weights.iter().fold(T::zero(), |acc, &weight| {
+        acc + (weight / sum) * (weight / sum)
+    });
+    T::one() / sum_squared
+}
+
+/// Draws indices proportionally to their weights, using low-variance (systematic) resampling,
+/// a single random offset is used for all draws, so that a particle with a normalised weight of `w` is drawn either `⌊w·n⌋` or `⌈w·n⌉` times.
+///
+/// # Arguments
+/// * `weights`: a slice of non-negative weights, does not need to be normalised.
+/// * `num_samples`: the amount of indices to draw.
+/// * `rng`: the random number generator to draw the offset from.
+///
+/// # Generics
+/// * `T`: Either an [`f32`] or [`f64`].
+/// * `R`: A random number generator.
+///
+/// # Returns
+/// A [`Vec`] of the drawn indices, in ascending order, or uniformly spread indices if all weights are zero.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument("Low Variance Resampling", skip_all, level = "debug")
+)]
+pub fn low_variance_resample<T, R>(weights: &[T], num_samples: usize, rng: &mut R) -> Vec<usize>
+where
+    T: Copy + RealField,
+    f32: AsPrimitive<T>,
+    usize: AsPrimitive<T>,
+    R: Rng + ?Sized,
+{
+    if weights.is_empty() {
+        return Vec::new();
+    }
+
+    let sum = weights.iter().fold(T::zero(), |acc, &weight| acc + weight);
+    if sum <= T::zero() {
+        return (0..num_samples)
+            .map(|sample_idx| sample_idx * weights.len() / num_samples)
+            .collect();
+    }
+
+    let step = sum / num_samples.as_();
+    let mut pointer = step * rng.gen::<f32>().as_();
+    let mut cumulative = weights[0];
+    let mut weight_idx = 0;
+    let mut indices = Vec::with_capacity(num_samples);
+    for _ in 0..num_samples {
+        while pointer > cumulative && weight_idx + 1 < weights.len() {
+            weight_idx += 1;
+            cumulative += weights[weight_idx];
+        }
+        indices.push(weight_idx);
+        pointer += step;
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_sample_normal() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let samples = (0..20000)
+            .map(|_| sample_normal(&mut rng, 2.0f64))
+            .collect::<Vec<_>>();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f64>()
+            / samples.len() as f64;
+        assert!(mean.abs() < 0.05);
+        assert!((variance.sqrt() - 2.0).abs() < 0.05);
+        assert_eq!(sample_normal(&mut rng, 0.0f64), 0.0);
+    }
+
+    #[test]
+    fn test_normalize_angle() {
+        assert!(
+            (normalize_angle(3.0 * core::f64::consts::PI) - core::f64::consts::PI).abs() < 1e-12
+        );
+        assert!((normalize_angle(-0.5f64 - core::f64::consts::TAU) + 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_effective_sample_size() {
+        assert!((effective_sample_size(&[1.0f64; 10]) - 10.0).abs() < 1e-9);
+        assert_eq!(effective_sample_size(&[0.0, 3.0f64, 0.0]), 1.0);
+        assert_eq!(effective_sample_size::<f64>(&[0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_low_variance_resample() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let indices = low_variance_resample(&[0.1f64, 0.0, 0.6, 0.3], 10, &mut rng);
+        assert_eq!(indices.len(), 10);
+        let counts = (0..4)
+            .map(|idx| indices.iter().filter(|drawn| **drawn == idx).count())
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [1, 0, 6, 3]);
+        assert!(indices.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        assert_eq!(
+            low_variance_resample(&[0.0f64; 2], 4, &mut rng),
+            [0, 0, 1, 1]
+        );
+        assert!(low_variance_resample::<f64, _>(&[], 4, &mut rng).is_empty());
+    }
+}